@@ -0,0 +1,123 @@
+use std::io::{self, BufWriter, Write};
+
+use anyhow::Result;
+use clap::{Args, ValueEnum};
+use serde::Serialize;
+
+use ddcrate::bracket::{double_elimination, single_elimination, BracketMatch, Slot};
+use ddcrate::PlayerId;
+
+use crate::seed::{seed, SeedList, SeedingArgs};
+
+#[derive(ValueEnum, Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum BracketFormat {
+    #[default]
+    Tsv,
+    Json,
+}
+
+#[derive(Args, Debug)]
+pub struct BracketArgs {
+    #[command(flatten)]
+    seeding: SeedingArgs,
+    /// Generate a double-elimination bracket, rather than single-elimination.
+    #[arg(long)]
+    double: bool,
+    /// Output format.
+    /// tsv lists one game per row; json has the seeded entrants and the games.
+    #[arg(long, value_enum, default_value_t)]
+    format: BracketFormat,
+    /// Skip column headers in output TSV.
+    #[arg(short = 'H', long)]
+    no_headers: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct JsonSeed<'a> {
+    seed: u64,
+    rating: f64,
+    players: &'a [PlayerId],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    team: Option<&'a str>,
+}
+
+#[derive(Debug, Serialize)]
+struct JsonBracket<'a> {
+    seeds: Vec<JsonSeed<'a>>,
+    matches: &'a [BracketMatch],
+}
+
+/// The seed and a description of who plays in a slot:
+/// the entrant's name or comma-separated player IDs, or the game they come from.
+fn describe(slot: &Slot, list: &SeedList) -> (String, String) {
+    match slot {
+        Slot::Seed(n) => {
+            let s = &list.seeded[*n as usize - 1];
+            let label = list.name(s).map(str::to_owned).unwrap_or_else(|| {
+                let ids: Vec<_> = s.entrant.players().iter().map(|p| p.to_string()).collect();
+                ids.join(",")
+            });
+            (n.to_string(), label)
+        }
+        Slot::Winner(id) => (String::default(), format!("winner of {}", id)),
+        Slot::Loser(id) => (String::default(), format!("loser of {}", id)),
+        Slot::Bye => (String::default(), "BYE".to_owned()),
+    }
+}
+
+/// Seed the entrants and print the games of a bracket for them,
+/// either as a TSV with columns game ID, stage, round, and the seed and entrant on each side,
+/// or as JSON.
+pub fn run(args: BracketArgs) -> Result<()> {
+    let Some(list) = seed(args.seeding)? else {
+        return Ok(());
+    };
+    let matches = if args.double {
+        double_elimination(list.seeded.len())
+    } else {
+        single_elimination(list.seeded.len())
+    };
+
+    let mut w = BufWriter::new(io::stdout());
+    match args.format {
+        BracketFormat::Json => {
+            let seeds = list
+                .seeded
+                .iter()
+                .map(|s| JsonSeed {
+                    seed: s.seed,
+                    rating: s.rating,
+                    players: s.entrant.players(),
+                    team: list.name(s),
+                })
+                .collect();
+            let bracket = JsonBracket {
+                seeds,
+                matches: &matches,
+            };
+            serde_json::to_writer_pretty(&mut w, &bracket)?;
+            writeln!(w)?;
+        }
+        BracketFormat::Tsv => {
+            if !args.no_headers {
+                writeln!(w, "match\tstage\tround\ttop_seed\ttop\tbottom_seed\tbottom")?;
+            }
+            for m in matches.iter() {
+                let (top_seed, top) = describe(&m.top, &list);
+                let (bottom_seed, bottom) = describe(&m.bottom, &list);
+                writeln!(
+                    w,
+                    "{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                    m.id,
+                    m.stage.name(),
+                    m.round,
+                    top_seed,
+                    top,
+                    bottom_seed,
+                    bottom
+                )?;
+            }
+        }
+    }
+    Ok(w.flush()?)
+}
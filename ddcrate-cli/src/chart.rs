@@ -0,0 +1,139 @@
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Result};
+use chrono::Duration;
+use clap::Args;
+use plotters::prelude::*;
+
+use ddcrate::elo::Elo;
+use ddcrate::glicko::Glicko;
+use ddcrate::trueskill::TrueSkill;
+use ddcrate::{timeline, Algorithm, PlayerId, PointsSystem};
+
+use crate::{parse_player_db, read_tournaments, Input, InputArgs};
+
+#[derive(Args, Debug)]
+pub struct ChartArgs {
+    #[command(flatten)]
+    input: InputArgs,
+    /// Path to write the SVG to.
+    #[arg(short, long)]
+    output: PathBuf,
+    /// ID of a player whose rating to plot.
+    /// Can be given multiple times.
+    /// If not given, the best-ranked players are plotted.
+    #[arg(short = 'i', long)]
+    player: Vec<PlayerId>,
+    /// Number of best-ranked players to plot, if no players are given.
+    #[arg(short = 'n', long, default_value_t = 10)]
+    top: usize,
+    /// Path to player database, used to label players.
+    #[arg(short, long)]
+    players: Option<PathBuf>,
+    /// Rating algorithm to use.
+    /// Overrides the config file; defaults to points.
+    #[arg(short, long)]
+    algorithm: Option<Algorithm>,
+    /// Width of the chart in pixels.
+    #[arg(long, default_value_t = 1024)]
+    width: u32,
+    /// Height of the chart in pixels.
+    #[arg(long, default_value_t = 768)]
+    height: u32,
+}
+
+/// Plot players' ratings after each tournament date as an SVG line chart.
+pub fn run(args: ChartArgs) -> Result<()> {
+    let Some(input) = Input::new(args.input)? else {
+        return Ok(());
+    };
+    let names = args.players.map(|p| parse_player_db(&p)).transpose()?;
+    let tournaments = read_tournaments(&input.ingest, input.report)?;
+
+    let algorithm = args.algorithm.unwrap_or(input.config.get_algorithm());
+    let tl = match algorithm {
+        Algorithm::Points => timeline(
+            &mut PointsSystem::new(input.config, input.season),
+            &tournaments,
+        ),
+        Algorithm::Elo => timeline(&mut Elo::new(input.config.get_elo().clone()), &tournaments),
+        Algorithm::Glicko => timeline(
+            &mut Glicko::new(input.config.get_glicko().clone()),
+            &tournaments,
+        ),
+        Algorithm::TrueSkill => timeline(
+            &mut TrueSkill::new(input.config.get_trueskill().clone()),
+            &tournaments,
+        ),
+    }?;
+    let (Some(first), Some(last)) = (tl.points().first(), tl.points().last()) else {
+        return Err(anyhow!("No results to plot"));
+    };
+
+    let ids = if args.player.is_empty() {
+        tl.top(args.top)
+    } else {
+        args.player
+    };
+    let series: Vec<_> = ids
+        .into_iter()
+        .map(|id| {
+            let label = names
+                .as_ref()
+                .and_then(|ns| ns.get(&id))
+                .map_or_else(|| id.to_string(), |n| format!("{} ({})", n, id));
+            let points: Vec<_> = tl
+                .player(id)
+                .into_iter()
+                .map(|(dt, rating, _)| (dt, rating.into_inner()))
+                .collect();
+            (label, points)
+        })
+        .collect();
+
+    let ratings = || series.iter().flat_map(|(_, ps)| ps.iter().map(|(_, r)| *r));
+    let y_min = ratings().fold(f64::INFINITY, f64::min);
+    let y_max = ratings().fold(f64::NEG_INFINITY, f64::max);
+    if !y_min.is_finite() {
+        return Err(anyhow!("None of the players have results"));
+    }
+    let y_pad = ((y_max - y_min) * 0.05).max(1.0);
+    // results all on one date would otherwise give an empty range
+    let x_pad = if first.datetime == last.datetime {
+        Duration::days(1)
+    } else {
+        Duration::zero()
+    };
+
+    let root = SVGBackend::new(&args.output, (args.width, args.height)).into_drawing_area();
+    root.fill(&WHITE)?;
+    let mut chart = ChartBuilder::on(&root)
+        .caption(format!("Ratings ({})", algorithm), ("sans-serif", 24))
+        .margin(10)
+        .x_label_area_size(40)
+        .y_label_area_size(60)
+        .build_cartesian_2d(
+            (first.datetime - x_pad)..(last.datetime + x_pad),
+            (y_min - y_pad)..(y_max + y_pad),
+        )?;
+    chart
+        .configure_mesh()
+        .x_label_formatter(&|dt| dt.format("%Y-%m-%d").to_string())
+        .y_desc("rating")
+        .draw()?;
+
+    for (idx, (label, points)) in series.into_iter().enumerate() {
+        let color = Palette99::pick(idx).mix(0.9);
+        chart
+            .draw_series(LineSeries::new(points, color.stroke_width(2)))?
+            .label(label)
+            .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color));
+    }
+    chart
+        .configure_series_labels()
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()?;
+    root.present()?;
+    Ok(())
+}
@@ -0,0 +1,40 @@
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::{ArgGroup, Args};
+
+use ddcrate::Config;
+
+#[derive(Args, Debug)]
+#[command(group(ArgGroup::new("action").required(true)))]
+pub struct ConfigArgs {
+    /// Config file to resolve; otherwise use the defaults.
+    /// In either case, `DDCRATE_*` environment variables override its values.
+    #[arg(short = 'C', long)]
+    config: Option<PathBuf>,
+    /// Print the fully resolved configuration as TOML.
+    #[arg(long, group = "action")]
+    dump: bool,
+    /// Print a JSON Schema for config files, e.g. for editor completion and validation.
+    #[arg(long, group = "action")]
+    schema: bool,
+}
+
+pub fn run(args: ConfigArgs) -> Result<()> {
+    let mut stdout = io::stdout();
+    if args.schema {
+        serde_json::to_writer_pretty(&mut stdout, &Config::json_schema())?;
+        writeln!(stdout)?;
+        return Ok(());
+    }
+    let config = match args.config {
+        Some(p) => Config::read(p)?,
+        None => Config::default(),
+    }
+    .with_env_overrides()?;
+    if args.dump {
+        write!(stdout, "{}", config.to_toml()?)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,149 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use clap::Args;
+use csv::ReaderBuilder;
+
+use ddcrate::{PlayerId, PlayerMap};
+
+#[derive(Args, Debug)]
+pub struct DiffArgs {
+    /// Earlier ranking TSV, as output by the rank subcommand (with headers).
+    old: PathBuf,
+    /// Later ranking TSV, as output by the rank subcommand (with headers).
+    new: PathBuf,
+    /// Skip column headers in output TSV.
+    #[arg(short = 'H', long)]
+    no_headers: bool,
+}
+
+/// A player's row in a ranking TSV.
+#[derive(Debug, Clone)]
+pub struct RankingRow {
+    pub rank: u64,
+    pub rating: f64,
+    pub name: Option<String>,
+}
+
+/// Read a ranking TSV with headers, as output by the rank subcommand.
+/// Requires the `rank`, `rating`, and `player_id` columns; `player_name` is optional.
+pub fn read_ranking(p: &Path) -> Result<PlayerMap<RankingRow>> {
+    let mut rdr = ReaderBuilder::new()
+        .delimiter(b'\t')
+        .flexible(true)
+        .from_reader(BufReader::new(File::open(p)?));
+    let headers = rdr.headers()?.clone();
+    let column = |name: &str| {
+        headers
+            .iter()
+            .position(|h| h == name)
+            .ok_or_else(|| anyhow!("No {} column in {}", name, p.display()))
+    };
+    let rank_idx = column("rank")?;
+    let rating_idx = column("rating")?;
+    let id_idx = column("player_id")?;
+    let name_idx = column("player_name").ok();
+
+    let mut out = HashMap::default();
+    for result in rdr.records() {
+        let record = result?;
+        let field = |idx: usize| {
+            record.get(idx).ok_or_else(|| {
+                anyhow!(
+                    "Missing field in {}, line {}",
+                    p.display(),
+                    record.position().map_or(0, |pos| pos.line())
+                )
+            })
+        };
+        let id: PlayerId = field(id_idx)?.parse()?;
+        let row = RankingRow {
+            rank: field(rank_idx)?.parse()?,
+            rating: field(rating_idx)?.parse()?,
+            name: name_idx
+                .and_then(|idx| record.get(idx))
+                .map(|n| n.to_owned()),
+        };
+        out.insert(id, row);
+    }
+    Ok(out)
+}
+
+/// The value as a string, or an empty string if there is none.
+pub fn fmt_opt<T: ToString>(v: Option<T>) -> String {
+    v.map(|v| v.to_string()).unwrap_or_default()
+}
+
+/// Print a TSV of each player's change in rank and rating; see [write_diff].
+pub fn run(args: DiffArgs) -> Result<()> {
+    let old = read_ranking(&args.old)?;
+    let new = read_ranking(&args.new)?;
+    write_diff(&old, &new, args.no_headers, BufWriter::new(io::stdout()))
+}
+
+/// Write a TSV of each player's change in rank and rating.
+/// Players who moved the most come first,
+/// followed by new entrants (`new`) and those no longer ranked (`dropped`).
+/// A positive rank change means the player moved up.
+pub fn write_diff<W: Write>(
+    old: &PlayerMap<RankingRow>,
+    new: &PlayerMap<RankingRow>,
+    no_headers: bool,
+    mut w: W,
+) -> Result<()> {
+    let ids: BTreeSet<_> = old.keys().chain(new.keys()).copied().collect();
+
+    // (status order, -|rank change|, new rank, id)
+    let mut rows: Vec<_> = ids
+        .into_iter()
+        .map(|id| {
+            let (o, n) = (old.get(&id), new.get(&id));
+            let key = match (o, n) {
+                (Some(o), Some(n)) => (0, -(o.rank as i64 - n.rank as i64).abs(), n.rank),
+                (None, Some(n)) => (1, 0, n.rank),
+                (_, None) => (2, 0, o.map_or(0, |o| o.rank)),
+            };
+            (key, id, o, n)
+        })
+        .collect();
+    rows.sort_unstable_by_key(|(key, id, _, _)| (*key, *id));
+
+    if !no_headers {
+        writeln!(
+            w,
+            "player_id\tplayer_name\told_rank\tnew_rank\trank_change\told_rating\tnew_rating\trating_change\tstatus"
+        )?;
+    }
+    for (_, id, o, n) in rows {
+        let name = n.or(o).and_then(|r| r.name.as_deref()).unwrap_or("");
+        let (rank_change, rating_change) = match (o, n) {
+            (Some(o), Some(n)) => (
+                Some(o.rank as i64 - n.rank as i64),
+                Some(n.rating - o.rating),
+            ),
+            _ => (None, None),
+        };
+        let status = match (o, n) {
+            (None, Some(_)) => "new",
+            (Some(_), None) => "dropped",
+            _ => "",
+        };
+        writeln!(
+            w,
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            id,
+            name,
+            fmt_opt(o.map(|r| r.rank)),
+            fmt_opt(n.map(|r| r.rank)),
+            fmt_opt(rank_change),
+            fmt_opt(o.map(|r| r.rating)),
+            fmt_opt(n.map(|r| r.rating)),
+            fmt_opt(rating_change),
+            status,
+        )?;
+    }
+    Ok(w.flush()?)
+}
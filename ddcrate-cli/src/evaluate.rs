@@ -0,0 +1,81 @@
+use std::io::{self, BufWriter, Write};
+
+use anyhow::{anyhow, Result};
+use clap::Args;
+
+use ddcrate::evaluate::evaluate;
+use ddcrate::Algorithm;
+
+use crate::diff::fmt_opt;
+use crate::{new_system, parse_datetime, read_tournaments, Input, InputArgs};
+
+#[derive(Args, Debug)]
+pub struct EvaluateArgs {
+    #[command(flatten)]
+    input: InputArgs,
+    /// Rating algorithm to use: points, elo, glicko, or trueskill.
+    /// Overrides the config file; defaults to points.
+    #[arg(short, long)]
+    algorithm: Option<Algorithm>,
+    /// Only score tournaments from this datetime, as for --from;
+    /// earlier tournaments are still rated, so that players have ratings by then.
+    #[arg(short, long, value_name = "DATETIME")]
+    score_from: Option<String>,
+    /// Only print the summary line, not every tournament.
+    #[arg(long)]
+    summary: bool,
+    /// Skip column headers in output TSV.
+    #[arg(short = 'H', long)]
+    no_headers: bool,
+}
+
+/// Replay the results, predicting each tournament from the ratings before it,
+/// and print a comment line with the overall Spearman correlation and Brier score,
+/// then a TSV with columns date, level, name, entrants, pairs, spearman, brier.
+pub fn run(args: EvaluateArgs) -> Result<()> {
+    let score_from = args
+        .score_from
+        .map(|s| parse_datetime(&s, false, args.input.timezone.as_ref()).map_err(|e| anyhow!(e)))
+        .transpose()?;
+    let Some(input) = Input::new(args.input)? else {
+        return Ok(());
+    };
+    let algorithm = args.algorithm.unwrap_or(input.config.get_algorithm());
+    let tournaments = read_tournaments(&input.ingest, input.report)?;
+    let mut system = new_system(&input, algorithm);
+    let mut eval = evaluate(system.as_mut(), &tournaments)?;
+    if let Some(dt) = score_from {
+        eval = eval.scored_from(&dt);
+    }
+
+    let mut w = BufWriter::new(io::stdout());
+    writeln!(
+        w,
+        "# {}: {} tournaments, {} pairs, spearman {}, brier {}",
+        algorithm,
+        eval.events.len(),
+        eval.pairs(),
+        fmt_opt(eval.spearman()),
+        fmt_opt(eval.brier())
+    )?;
+    if args.summary {
+        return Ok(w.flush()?);
+    }
+    if !args.no_headers {
+        writeln!(w, "date\tlevel\tname\tentrants\tpairs\tspearman\tbrier")?;
+    }
+    for e in eval.events.iter() {
+        writeln!(
+            w,
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            e.datetime.format("%Y-%m-%d"),
+            e.level,
+            e.name.as_deref().unwrap_or(""),
+            e.entrants,
+            e.pairs,
+            fmt_opt(e.spearman),
+            fmt_opt(e.brier())
+        )?;
+    }
+    Ok(w.flush()?)
+}
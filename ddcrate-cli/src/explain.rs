@@ -0,0 +1,117 @@
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Result};
+use clap::Args;
+
+use ddcrate::{PlayerId, PointsSystem, RankingEngine};
+
+use crate::{parse_player_db, read_tournaments, Input, InputArgs};
+
+#[derive(Args, Debug)]
+pub struct ExplainArgs {
+    /// ID of the player whose results to explain.
+    #[arg(short = 'i', long)]
+    player: PlayerId,
+    #[command(flatten)]
+    options: ExplainOptions,
+}
+
+#[derive(Args, Debug)]
+pub struct PlayerArgs {
+    /// ID of the player whose results to list.
+    player: PlayerId,
+    #[command(flatten)]
+    options: ExplainOptions,
+}
+
+#[derive(Args, Debug)]
+pub struct ExplainOptions {
+    #[command(flatten)]
+    input: InputArgs,
+    /// Skip column headers in output TSV.
+    #[arg(short = 'H', long)]
+    no_headers: bool,
+    /// Path to player database, used to name the player and their partners.
+    #[arg(short, long)]
+    players: Option<PathBuf>,
+}
+
+/// Print a comment line with the player's rank and rating,
+/// then a TSV with a row for each tournament they played.
+/// Teams of more than two give comma-separated partner IDs and names.
+pub fn run(args: ExplainArgs) -> Result<()> {
+    explain(args.player, args.options)
+}
+
+/// As [run], with the player ID given positionally.
+pub fn run_player(args: PlayerArgs) -> Result<()> {
+    explain(args.player, args.options)
+}
+
+fn explain(player: PlayerId, args: ExplainOptions) -> Result<()> {
+    let Some(input) = Input::new(args.input)? else {
+        return Ok(());
+    };
+    let players = args.players.map(|p| parse_player_db(&p)).transpose()?;
+    let name = |id: &PlayerId| {
+        players
+            .as_ref()
+            .and_then(|ps| ps.get(id))
+            .map(|n| n.as_str())
+            .unwrap_or("")
+    };
+
+    let mut engine = RankingEngine::new(PointsSystem::new(input.config, input.season));
+    for t in read_tournaments(&input.ingest, input.report)? {
+        engine.add_tournament(t)?;
+    }
+    engine.advance_to(&input.until);
+    let ranks = engine.current_ranks();
+    let record = engine
+        .current_records()
+        .get(&player)
+        .ok_or_else(|| anyhow!("Player {} has no results", player))?;
+
+    let mut w = BufWriter::new(io::stdout());
+    writeln!(
+        w,
+        "# player {} {}: rank {}, rating {}",
+        player,
+        name(&player),
+        ranks[&player],
+        record.rating
+    )?;
+    if !args.no_headers {
+        writeln!(
+            w,
+            "date\tlevel\ttournament\tlocation\torganizer\tplace\tpartner_id\tpartner_name\tpoint_base\tfinish_factor\tage_factor\tfield_factor\tbonus\tpoints\tcounted"
+        )?;
+    }
+    for (b, counted) in record.counted_history() {
+        let partners = b.entrant.teammates(&player).unwrap_or_default();
+        let partner_ids: Vec<_> = partners.iter().map(|p| p.to_string()).collect();
+        let partner_names: Vec<_> = partners.iter().map(|p| name(p)).collect();
+        writeln!(
+            w,
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            b.datetime.format("%Y-%m-%d"),
+            b.level,
+            b.tournament.name.as_deref().unwrap_or(""),
+            b.tournament.location.as_deref().unwrap_or(""),
+            b.tournament.organizer.as_deref().unwrap_or(""),
+            b.code
+                .map_or_else(|| b.place.to_string(), |c| c.to_string()),
+            partner_ids.join(","),
+            partner_names.join(","),
+            b.point_base,
+            b.finish_factor,
+            b.age_factor,
+            b.field_factor,
+            b.bonus,
+            b.points,
+            counted
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,102 @@
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Result};
+use clap::Args;
+
+use ddcrate::testing::{generate, write_tree, GeneratorConfig};
+use ddcrate::Level;
+
+use crate::parse_datetime;
+
+#[derive(Args, Debug)]
+pub struct GenerateArgs {
+    /// Directory to write the results TSVs into, in a directory per level.
+    #[arg(short, long)]
+    output: PathBuf,
+    /// Number of players in the pool [default: 500].
+    #[arg(short = 'n', long)]
+    players: Option<u64>,
+    /// Date of the first possible tournament [default: 2023-01-01].
+    #[arg(short, long)]
+    from: Option<String>,
+    /// Date of the last possible tournament [default: 2023-12-31].
+    #[arg(short, long)]
+    to: Option<String>,
+    /// Mean number of tournaments each weekend [default: 2].
+    #[arg(short, long)]
+    events_per_week: Option<f64>,
+    /// Level of tournament and its relative frequency, e.g. "small=10".
+    /// Can be given multiple times
+    /// [default: small=10, medium=4, major=1, championship=0.2].
+    #[arg(short, long, value_name = "LEVEL=WEIGHT", value_parser = parse_level_weight)]
+    level: Vec<(Level, f64)>,
+    /// Number of players in each entrant [default: 2].
+    #[arg(long)]
+    team_size: Option<usize>,
+    /// Fewest entrants in a tournament [default: 8].
+    #[arg(long)]
+    min_entrants: Option<usize>,
+    /// Most entrants in a tournament [default: 32].
+    #[arg(long)]
+    max_entrants: Option<usize>,
+    /// Standard deviation of a team's performance around its skill,
+    /// where players' skills have standard deviation 1 [default: 0.5].
+    #[arg(long)]
+    noise: Option<f64>,
+    /// Seed for the random number generator; the same seed gives the same results.
+    #[arg(short, long, default_value_t = 0)]
+    seed: u64,
+}
+
+fn parse_level_weight(s: &str) -> Result<(Level, f64), String> {
+    let (level, weight) = s
+        .split_once('=')
+        .ok_or_else(|| format!("Expected LEVEL=WEIGHT, got {:?}", s))?;
+    let weight = weight
+        .parse()
+        .map_err(|_| format!("Invalid weight {:?}", weight))?;
+    Ok((Level::new(level), weight))
+}
+
+/// Generate a random season of results and write it as a results directory.
+pub fn run(args: GenerateArgs) -> Result<()> {
+    let mut config = GeneratorConfig {
+        seed: args.seed,
+        ..Default::default()
+    };
+    if let Some(n) = args.players {
+        config.players = n;
+    }
+    if let Some(s) = &args.from {
+        config.from = parse_datetime(s, false, None).map_err(|e| anyhow!(e))?;
+    }
+    if let Some(s) = &args.to {
+        config.until = parse_datetime(s, true, None).map_err(|e| anyhow!(e))?;
+    }
+    if let Some(n) = args.events_per_week {
+        config.events_per_week = n;
+    }
+    if !args.level.is_empty() {
+        config.levels = args.level;
+    }
+    if let Some(n) = args.team_size {
+        config.team_size = n;
+    }
+    if let Some(n) = args.min_entrants {
+        config.min_entrants = n;
+    }
+    if let Some(n) = args.max_entrants {
+        config.max_entrants = n;
+    }
+    if let Some(n) = args.noise {
+        config.noise = n;
+    }
+    let tournaments = generate(&config);
+    let paths = write_tree(&tournaments, &args.output)?;
+    eprintln!(
+        "Wrote {} tournaments to {}",
+        paths.len(),
+        args.output.display()
+    );
+    Ok(())
+}
@@ -0,0 +1,105 @@
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::Args;
+
+use ddcrate::h2h::{head_to_head, Tally};
+use ddcrate::PlayerId;
+
+use crate::{parse_player_db, read_tournaments, Input, InputArgs};
+
+#[derive(Args, Debug)]
+pub struct H2hArgs {
+    #[command(flatten)]
+    input: InputArgs,
+    /// Player on the first side.
+    /// Can be given multiple times to compare a team.
+    #[arg(short = 'a', long, required = true)]
+    first: Vec<PlayerId>,
+    /// Player on the second side.
+    /// Can be given multiple times to compare a team.
+    #[arg(short = 'b', long, required = true)]
+    second: Vec<PlayerId>,
+    /// Number of most recent meetings to list.
+    #[arg(short = 'n', long, default_value_t = 5)]
+    recent: usize,
+    /// Skip column headers in output TSV.
+    #[arg(short = 'H', long)]
+    no_headers: bool,
+    /// Path to player database, used to name the players.
+    #[arg(short, long)]
+    players: Option<PathBuf>,
+}
+
+fn tally_line(label: &str, t: &Tally) -> String {
+    format!(
+        "# {}: {} meetings, first ahead {}, second ahead {}, tied {}",
+        label,
+        t.meetings(),
+        t.ahead,
+        t.behind,
+        t.tied
+    )
+}
+
+/// Print comment lines summarising how often each side finished ahead,
+/// overall and at each level, and how many games each side won against the other,
+/// then a TSV of their most recent meetings.
+pub fn run(args: H2hArgs) -> Result<()> {
+    let Some(input) = Input::new(args.input)? else {
+        return Ok(());
+    };
+    let players = args.players.map(|p| parse_player_db(&p)).transpose()?;
+    let side = |ids: &[PlayerId]| {
+        ids.iter()
+            .map(|id| match players.as_ref().and_then(|ps| ps.get(id)) {
+                Some(name) => format!("{} ({})", id, name),
+                None => id.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    let tournaments = read_tournaments(&input.ingest, input.report)?;
+    let h = head_to_head(&tournaments, &args.first, &args.second);
+
+    let mut w = BufWriter::new(io::stdout());
+    writeln!(
+        w,
+        "# first: {}; second: {}",
+        side(&args.first),
+        side(&args.second)
+    )?;
+    writeln!(w, "{}", tally_line("all", &h.total))?;
+    for (level, t) in h.by_level.iter() {
+        writeln!(w, "{}", tally_line(level.name(), t))?;
+    }
+    if h.first_wins + h.second_wins > 0 {
+        writeln!(
+            w,
+            "# games: first won {}, second won {}",
+            h.first_wins, h.second_wins
+        )?;
+    }
+    if !args.no_headers {
+        writeln!(
+            w,
+            "date\tlevel\ttournament\tfirst_place\tsecond_place\tfirst_wins\tsecond_wins"
+        )?;
+    }
+    for m in h.meetings.iter().take(args.recent) {
+        writeln!(
+            w,
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            m.datetime.format("%Y-%m-%d"),
+            m.level,
+            m.tournament.name.as_deref().unwrap_or(""),
+            m.first_place,
+            m.second_place,
+            m.first_wins,
+            m.second_wins
+        )?;
+    }
+    Ok(w.flush()?)
+}
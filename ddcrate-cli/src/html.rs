@@ -0,0 +1,136 @@
+use std::collections::{BTreeSet, HashMap};
+use std::io::{self, Write};
+
+use chrono::{DateTime, Utc};
+
+use ddcrate::{Level, PlayerId, PlayerMap, Tournament};
+
+use crate::{Column, RecordWriter};
+
+const STYLE: &str = "
+body { font-family: sans-serif; margin: 2em auto; max-width: 60em; }
+table { border-collapse: collapse; width: 100%; }
+th, td { padding: 0.3em 0.8em; text-align: left; border-bottom: 1px solid #ddd; }
+th { background: #f4f4f4; }
+tr:nth-child(even) { background: #fafafa; }
+td.num { text-align: right; font-variant-numeric: tabular-nums; }
+.generated { color: #666; font-size: 0.9em; }
+";
+
+/// Show only rows whose levels include the selected one.
+const SCRIPT: &str = "
+document.getElementById('level').addEventListener('change', function (e) {
+  var level = e.target.value;
+  document.querySelectorAll('tbody tr').forEach(function (row) {
+    var levels = row.dataset.levels.split(' ');
+    row.hidden = level !== '' && levels.indexOf(level) === -1;
+  });
+});
+";
+
+/// The levels of tournament in which each player has results.
+pub fn player_levels(tournaments: &[Tournament]) -> PlayerMap<BTreeSet<Level>> {
+    let mut out: PlayerMap<BTreeSet<Level>> = HashMap::default();
+    for t in tournaments {
+        for (_, entrant) in t.results() {
+            for p in entrant.players() {
+                out.entry(*p).or_default().insert(t.level().clone());
+            }
+        }
+    }
+    out
+}
+
+fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+impl<W: Write> RecordWriter<W> {
+    /// Write a standalone HTML page with a leaderboard table,
+    /// which can be filtered to players with results at a given level.
+    ///
+    /// Records should already be sorted.
+    pub fn write_html(
+        &mut self,
+        records: &[(PlayerId, u64)],
+        levels: &PlayerMap<BTreeSet<Level>>,
+        generated: DateTime<Utc>,
+    ) -> io::Result<()> {
+        let all_levels: BTreeSet<_> = levels.values().flatten().collect();
+        let headings: Vec<_> = self
+            .columns
+            .iter()
+            .map(|c| match c {
+                Column::Deviation => format!("<th>{}</th>", self.column_name(*c)),
+                _ => format!("<th>{}</th>", c.title()),
+            })
+            .collect();
+        let rows: Vec<_> = records
+            .iter()
+            .map(|(id, rank)| {
+                let cells: Vec<_> = self
+                    .columns
+                    .iter()
+                    .map(|c| {
+                        let class = if c.is_numeric() { " class=\"num\"" } else { "" };
+                        let value = escape(&self.cell(*c, *id, *rank, true));
+                        format!("<td{}>{}</td>", class, value)
+                    })
+                    .collect();
+                let player_levels: Vec<_> = levels
+                    .get(id)
+                    .into_iter()
+                    .flatten()
+                    .map(|l| escape(l.name()))
+                    .collect();
+                format!(
+                    "<tr data-levels=\"{}\">{}</tr>",
+                    player_levels.join(" "),
+                    cells.concat()
+                )
+            })
+            .collect();
+
+        let w = &mut self.writer;
+        writeln!(w, "<!DOCTYPE html>")?;
+        writeln!(w, "<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">")?;
+        writeln!(w, "<title>DDC rankings</title>")?;
+        writeln!(w, "<style>{}</style>\n</head>\n<body>", STYLE)?;
+        writeln!(w, "<h1>DDC rankings</h1>")?;
+        writeln!(
+            w,
+            "<p class=\"generated\">Generated {}</p>",
+            generated.format("%Y-%m-%d %H:%M UTC")
+        )?;
+
+        writeln!(w, "<label for=\"level\">Level:</label>")?;
+        writeln!(w, "<select id=\"level\">\n<option value=\"\">All</option>")?;
+        for level in all_levels {
+            let name = escape(level.name());
+            writeln!(w, "<option value=\"{}\">{}</option>", name, name)?;
+        }
+        writeln!(w, "</select>")?;
+
+        writeln!(
+            w,
+            "<table>\n<thead><tr>{}</tr></thead>\n<tbody>",
+            headings.concat()
+        )?;
+        for row in rows {
+            writeln!(w, "{}", row)?;
+        }
+        writeln!(w, "</tbody>\n</table>")?;
+        writeln!(w, "<script>{}</script>\n</body>\n</html>", SCRIPT)
+    }
+}
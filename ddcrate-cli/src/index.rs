@@ -0,0 +1,35 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::Args;
+
+use ddcrate::index;
+
+use crate::{Input, InputArgs};
+
+#[derive(Args, Debug)]
+pub struct IndexArgs {
+    #[command(flatten)]
+    input: InputArgs,
+    /// File to write the index to; defaults to .ddcrate-index.tsv in --dir.
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+/// Write a TSV with columns path, datetime, level for every results file,
+/// to be read with --index.
+pub fn run(args: IndexArgs) -> Result<()> {
+    let output = args
+        .output
+        .unwrap_or_else(|| args.input.dir.join(index::DEFAULT_FILE));
+    let Some(input) = Input::new(args.input)? else {
+        return Ok(());
+    };
+    let index = input.ingest.build_index()?;
+    let mut w = BufWriter::new(File::create(output)?);
+    index.write(&mut w)?;
+    w.flush()?;
+    Ok(())
+}
@@ -1,32 +1,155 @@
 use anyhow::{anyhow, Result};
 use chrono::format::Parsed;
-use clap::Parser;
+use clap::{command, Args, FromArgMatches, Subcommand, ValueEnum};
 use csv::ReaderBuilder;
+use glob::Pattern;
 use once_cell_regex::{exports::regex::Captures, regex};
+use std::borrow::Borrow;
+use std::cmp::Ordering;
 use std::fmt::Debug;
 use std::fs::{self, File};
-use std::io::{self, BufReader, BufWriter, Write};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::num::NonZeroUsize;
 use std::path::Path;
-use std::{collections::HashMap, path::PathBuf, str::FromStr};
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    str::FromStr,
+};
+use tempfile::NamedTempFile;
 
-use chrono::{DateTime, Datelike, Utc};
+use chrono::{DateTime, TimeZone, Utc};
 
-use ddcrate::{rank_players, Config, Level, PlayerId, PlayerRecord, ResultIngester};
+use ddcrate::bootstrap::{bootstrap, Interval};
+use ddcrate::elo::Elo;
+use ddcrate::glicko::Glicko;
+use ddcrate::index::Index;
+use ddcrate::trueskill::TrueSkill;
+use ddcrate::{
+    event_counts, last_played, parse_cache, rank_with, ranks_from_ratings, relative_ratings,
+    Algorithm, Checkpoint, Config, Level, NotNan, PlayerId, PlayerMap, PlayerSet, PointsSystem,
+    RankDirection, RankNumbering, RankingEngine, RatingSystem, RelativeRating, ResultIngester,
+    Tournament, Zone,
+};
 
-/// Read a directory of directories of TSV files reporting tournament finishing places,
-/// and print a TSV with columns rank, rating, player ID.
-#[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
-struct Args {
-    /// Directory containing directories of TSV results.
+mod bracket;
+mod chart;
+mod config;
+mod diff;
+mod evaluate;
+mod explain;
+#[cfg(feature = "testing")]
+mod generate;
+mod h2h;
+mod html;
+mod index;
+mod predict;
+mod seed;
+mod serve;
+mod stats;
+mod template;
+mod tune;
+mod validate;
+mod watch;
+mod whatif;
+
+/// Top-level command: ranking arguments are used if no subcommand is given.
+fn cli() -> clap::Command {
+    let cmd = command!()
+        .args_conflicts_with_subcommands(true)
+        .subcommand_negates_reqs(true);
+    Command::augment_subcommands(RankArgs::augment_args(cmd)).about(
+        "Read a directory of directories of TSV files reporting tournament finishing places, \
+        and rank the players. \
+        With no subcommand, prints a TSV with columns rank, rating, player ID.",
+    )
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Print a TSV with columns rank, rating, player ID (the default).
+    Rank(RankArgs),
+    /// Print how each of a player's results contributed to their rating,
+    /// using the points algorithm.
+    Explain(explain::ExplainArgs),
+    /// Print a player's full history of results, as for explain:
+    /// date, level, finish, partners, points, and whether the result is counted.
+    Player(explain::PlayerArgs),
+    /// Check every results file without ranking,
+    /// exiting with an error if any are invalid.
+    Validate(validate::ValidateArgs),
+    /// Write an index of every results file's date and level,
+    /// so that later runs with --index need not find them again.
+    Index(index::IndexArgs),
+    /// Rank players into the --output file, re-ranking whenever the results change.
+    Watch(watch::WatchArgs),
+    /// Serve the points ranking as JSON over HTTP,
+    /// with endpoints /leaderboard, /players/{id}, and /tournaments.
+    Serve(serve::ServeArgs),
+    /// Plot players' rating histories to an SVG.
+    Chart(chart::ChartArgs),
+    /// Replay the results, predicting each tournament's finishing order from the ratings before it,
+    /// and print how accurate the predictions were.
+    Evaluate(evaluate::EvaluateArgs),
+    /// Evaluate the points algorithm as for evaluate with every combination of candidate
+    /// finish_decay, age_decay, and record_length, and print the most accurate config as TOML.
+    Tune(tune::TuneArgs),
+    /// Compare two ranking TSVs, printing each player's change in rank and rating.
+    Diff(diff::DiffArgs),
+    /// Rank players with and without an extra (hypothetical or disputed) tournament,
+    /// printing each player's change in rank and rating as for diff.
+    Whatif(whatif::WhatifArgs),
+    /// Compare two players' or teams' finishes in tournaments where both took part.
+    H2h(h2h::H2hArgs),
+    /// Predict the probability of each side winning a game between two players or teams,
+    /// from their current ratings.
+    Predict(predict::PredictArgs),
+    /// Summarise the distribution of current ratings overall and by level,
+    /// and print a histogram of them.
+    Stats(stats::StatsArgs),
+    /// Seed a list of entrants by their players' combined current ratings,
+    /// printing a TSV with columns seed, rating, player IDs.
+    Seed(seed::SeedArgs),
+    /// Seed a list of entrants as for seed,
+    /// and print the games of a single- or double-elimination bracket for them.
+    Bracket(bracket::BracketArgs),
+    /// Print the configuration resolved from a config file and the defaults,
+    /// e.g. as a starting template.
+    Config(config::ConfigArgs),
+    /// Write a random but realistic season of results to a directory,
+    /// for benchmarking and testing.
+    #[cfg(feature = "testing")]
+    Generate(generate::GenerateArgs),
+}
+
+/// Arguments controlling which results are read and how they are rated.
+#[derive(Args, Debug, Clone)]
+pub struct InputArgs {
+    /// Directory containing directories of TSV results,
+    /// or a zip archive (with a .zip extension) of such a directory.
+    /// With the http feature, this can also be an HTTP(S) URL:
+    /// of a directory listing if it ends with "/", otherwise of a manifest of results files.
+    /// With the cloud feature, this can also be the URL of an S3, GCS or Azure bucket,
+    /// e.g. s3://my-bucket/results.
+    /// With the sheets feature, this can also be the URL of a published or link-shared Google Sheet.
     #[arg(short, long)]
     dir: PathBuf,
-    /// Sort output by player rank
-    #[arg(short, long)]
-    sorted: bool,
+    /// Directory in which to cache results files downloaded from a --dir URL,
+    /// which are read from there rather than downloaded again.
+    #[cfg(feature = "http")]
+    #[arg(long)]
+    cache_dir: Option<PathBuf>,
+    /// Cache parsed results files in this directory (by default, .ddcrate-cache in --dir),
+    /// so that files which have not changed are not parsed again on later runs.
+    #[arg(long, value_name = "DIR")]
+    parse_cache: Option<Option<PathBuf>>,
+    /// Read only the results files listed in this index (by default, .ddcrate-index.tsv in --dir),
+    /// as written by the index subcommand, rather than searching --dir for them.
+    #[arg(long, value_name = "FILE")]
+    index: Option<Option<PathBuf>>,
     /// Only include results from this datetime, as RFC 3339.
     /// Elements can be dropped from the right,
-    /// in which case the parser assumes it's the earliest matching datetime (in UTC).
+    /// in which case the parser assumes it's the earliest matching datetime (in UTC, or --timezone).
     /// For example, valid dates include `2022-06-25T12:00:05+04:00`,
     /// and `2022` (which is interpreted as `2022-01-01T00:00:00+00:00`).
     #[arg(short, long)]
@@ -36,21 +159,59 @@ struct Args {
     /// although truncated datetimes are assumed to be the latest match.
     #[arg(short, long)]
     to: Option<String>,
-    /// Path to TOML config file with algorithm constants.
+    /// Time zone of --from and --to if they have no offset,
+    /// as a UTC offset (e.g. "+02:00") or IANA name (e.g. "Europe/London").
+    #[arg(long, value_name = "ZONE")]
+    timezone: Option<Zone>,
+    /// Season for which results are rated, i.e. from which their age is calculated.
+    /// Defaults to the season containing --to, or the current date.
+    #[arg(long)]
+    season: Option<i32>,
+    /// Path to config file with algorithm constants:
+    /// YAML if its extension is .yaml or .yml, JSON if .json, and TOML otherwise.
+    /// Values can be overridden by `DDCRATE_*` environment variables.
     #[arg(short = 'C', long)]
     config: Option<PathBuf>,
-    /// Ignore results from "small" tournaments.
-    #[arg(short = 'S', long)]
+    /// Ignore results from tournaments of this level (e.g. "small").
+    /// Can be given multiple times.
+    #[arg(short = 'x', long)]
+    exclude_level: Vec<String>,
+    /// Deprecated: use --exclude-level small.
+    #[arg(short = 'S', long, hide = true)]
     no_small: bool,
-    /// Ignore results from "medium" tournaments.
-    #[arg(short = 'E', long)]
+    /// Deprecated: use --exclude-level medium.
+    #[arg(short = 'E', long, hide = true)]
     no_medium: bool,
-    /// Ignore results from "major" tournaments.
-    #[arg(short = 'M', long)]
+    /// Deprecated: use --exclude-level major.
+    #[arg(short = 'M', long, hide = true)]
     no_major: bool,
-    /// Ignore results from "championship" tournaments.
-    #[arg(short = 'C', long)]
+    /// Deprecated: use --exclude-level championship.
+    /// Its old short form, -C, is --config.
+    #[arg(long, hide = true)]
     no_championship: bool,
+    /// Only read results files whose path relative to --dir matches this glob
+    /// (e.g. "small/**/2023-*"). Can be given multiple times.
+    #[arg(long, value_name = "GLOB")]
+    include: Vec<Pattern>,
+    /// Skip results files whose path relative to --dir matches this glob
+    /// (e.g. "*.bak"), even if included. Can be given multiple times.
+    #[arg(long, value_name = "GLOB")]
+    exclude: Vec<Pattern>,
+    /// Fail on malformed rows in results TSVs, rather than skipping them.
+    #[arg(long)]
+    strict: bool,
+    /// Print a summary of the files read and rows skipped to stderr.
+    #[arg(long)]
+    report: bool,
+}
+
+#[derive(Args, Debug, Clone)]
+struct RankArgs {
+    #[command(flatten)]
+    input: InputArgs,
+    /// Sort output by player rank
+    #[arg(short, long)]
+    sorted: bool,
     /// Skip column headers in output TSV.
     #[arg(short = 'H', long)]
     no_headers: bool,
@@ -59,35 +220,615 @@ struct Args {
     /// If not given, the player_name column will be omitted.
     #[arg(short, long)]
     players: Option<PathBuf>,
+    /// Rating algorithm to use: points, elo, glicko (adds an rd column),
+    /// or trueskill (adds a sigma column).
+    /// Overrides the config file; defaults to points.
+    #[arg(short, long)]
+    algorithm: Option<Algorithm>,
+    /// Path to a JSON checkpoint of ranking state (points algorithm only).
+    /// If it exists, ranking resumes from it
+    /// and only results it does not already include are ingested.
+    /// The updated state is written back to it.
+    #[arg(short = 'k', long)]
+    checkpoint: Option<PathBuf>,
+    /// Ranking to use for bonus points during the season of the earliest result
+    /// (points algorithm only),
+    /// rather than ranks calculated from results in that season.
+    /// Either a ranking TSV with headers, or a JSON checkpoint.
+    #[arg(long)]
+    initial_ranks: Option<PathBuf>,
+    /// JSON checkpoint of a previous season's ranking to start from (points algorithm only),
+    /// keeping each counted result with its points multiplied by the config's carry_over factor.
+    /// Ignored when resuming from an existing --checkpoint.
+    #[arg(long)]
+    carry_over: Option<PathBuf>,
+    /// Output format.
+    /// markdown writes a GitHub-flavoured table, with headers even if --no-headers is given.
+    /// html writes a standalone page, always sorted,
+    /// which can be filtered to players with results at a given level.
+    #[arg(long, value_enum, default_value_t)]
+    format: OutputFormat,
+    /// Write output to this file rather than stdout.
+    /// The file is replaced only once ranking has finished,
+    /// so it is never left partially written.
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+    /// Also write output to a file in this directory named by the date ranked to
+    /// (e.g. `2024-05-01.tsv`), unless it is identical to the latest file there.
+    #[arg(long)]
+    snapshot_dir: Option<PathBuf>,
+    /// Previous ranking to compare against, adding rank_change and rating_change columns.
+    /// Either a ranking TSV with headers, or a JSON checkpoint.
+    #[arg(long)]
+    previous: Option<PathBuf>,
+    /// Only output players ranked this or better (including ties).
+    #[arg(short = 'n', long)]
+    limit: Option<u64>,
+    /// Only output this player.
+    /// Can be given multiple times.
+    #[arg(short = 'i', long)]
+    player: Vec<PlayerId>,
+    /// Rank teams rather than players (points algorithm and TSV output only).
+    /// Outputs the team's rank and rating,
+    /// and comma-separated player IDs and names.
+    #[arg(long)]
+    teams: bool,
+    /// Omit provisional players (those with fewer results than the config's min_events),
+    /// and rank the rest without them.
+    /// Otherwise, a provisional column is added if min_events is set.
+    #[arg(long)]
+    exclude_provisional: bool,
+    /// How to number players with equal ratings:
+    /// standard (1, 2, 2, 4), dense (1, 2, 2, 3), or ordinal (1, 2, 3, 4).
+    /// Overrides the config file; defaults to standard.
+    #[arg(long)]
+    numbering: Option<RankNumbering>,
+    /// Whether the highest (descending) or lowest (ascending) rating is rank 1.
+    /// Overrides the config file; defaults to descending.
+    #[arg(long)]
+    direction: Option<RankDirection>,
+    /// Add a schedule_strength column (points algorithm only):
+    /// the mean rank of each player's opponents before their counted tournaments.
+    /// Lower is a stronger schedule.
+    #[arg(long)]
+    schedule: bool,
+    /// Read and rank tournaments this many at a time in date order,
+    /// dropping each chunk once ranked, to bound memory use with very many results files
+    /// (points algorithm only; not with --format html or --report).
+    #[arg(long, value_name = "N")]
+    chunk_size: Option<NonZeroUsize>,
+    /// Add rating_lower and rating_upper columns bounding each player's rating,
+    /// from re-ranking this many resamples of the tournaments, drawn with replacement.
+    /// Players with few results have wide intervals.
+    /// Not with --checkpoint, --carry-over, --initial-ranks, --teams, or --chunk-size.
+    #[arg(long, value_name = "N")]
+    bootstrap: Option<NonZeroUsize>,
+    /// Share of resampled ratings within each --bootstrap interval.
+    #[arg(long, default_value_t = 0.9)]
+    confidence: f64,
+    /// Random seed for --bootstrap resampling.
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+    /// Add percentile and z_score columns,
+    /// giving each player's rating relative to those of every ranked player,
+    /// to compare players between seasons with different point inflation.
+    #[arg(long)]
+    relative: bool,
+    /// Comma-separated columns to write, in order, rather than every available column:
+    /// rank, rating, deviation (rd or sigma), rating_lower, rating_upper, percentile, z_score,
+    /// rank_change, rating_change, provisional, schedule_strength, player_id, player_name,
+    /// events (number of results), and last_active (date of latest result).
+    /// Choosing schedule_strength, percentile, or z_score adds them as for their options.
+    #[arg(long, value_delimiter = ',', hide_possible_values = true)]
+    columns: Option<Vec<Column>>,
+    /// Render the ranking with this minijinja template file rather than as --format,
+    /// e.g. as an HTML snippet, BBCode, or LaTeX.
+    /// The template is given the sorted rows with a value for each column,
+    /// and the ranking's algorithm, season, and date; see the README.
+    #[arg(long, conflicts_with = "format")]
+    template: Option<PathBuf>,
+}
+
+#[derive(ValueEnum, Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum OutputFormat {
+    #[default]
+    Tsv,
+    Html,
+    Markdown,
+}
+
+impl OutputFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Tsv => "tsv",
+            OutputFormat::Html => "html",
+            OutputFormat::Markdown => "md",
+        }
+    }
+}
+
+/// A column of the ranking output.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+#[value(rename_all = "snake_case")]
+enum Column {
+    Rank,
+    Rating,
+    /// Rating uncertainty: rd for glicko, or sigma for trueskill.
+    #[value(alias = "rd", alias = "sigma")]
+    Deviation,
+    RatingLower,
+    RatingUpper,
+    Percentile,
+    ZScore,
+    RankChange,
+    RatingChange,
+    Provisional,
+    ScheduleStrength,
+    PlayerId,
+    PlayerName,
+    /// Number of results.
+    Events,
+    /// Date of the latest result.
+    LastActive,
+}
+
+impl Column {
+    fn name(&self) -> &'static str {
+        match self {
+            Column::Rank => "rank",
+            Column::Rating => "rating",
+            Column::Deviation => "deviation",
+            Column::RatingLower => "rating_lower",
+            Column::RatingUpper => "rating_upper",
+            Column::Percentile => "percentile",
+            Column::ZScore => "z_score",
+            Column::RankChange => "rank_change",
+            Column::RatingChange => "rating_change",
+            Column::Provisional => "provisional",
+            Column::ScheduleStrength => "schedule_strength",
+            Column::PlayerId => "player_id",
+            Column::PlayerName => "player_name",
+            Column::Events => "events",
+            Column::LastActive => "last_active",
+        }
+    }
+
+    /// Heading in HTML output.
+    fn title(&self) -> &'static str {
+        match self {
+            Column::Rank => "Rank",
+            Column::Rating => "Rating",
+            Column::Deviation => "Deviation",
+            Column::RatingLower => "Rating lower",
+            Column::RatingUpper => "Rating upper",
+            Column::Percentile => "Percentile",
+            Column::ZScore => "Z-score",
+            Column::RankChange => "Rank change",
+            Column::RatingChange => "Rating change",
+            Column::Provisional => "Provisional",
+            Column::ScheduleStrength => "Schedule strength",
+            Column::PlayerId => "Player ID",
+            Column::PlayerName => "Player",
+            Column::Events => "Events",
+            Column::LastActive => "Last active",
+        }
+    }
+
+    /// Whether the column's values are right-aligned in HTML output.
+    fn is_numeric(&self) -> bool {
+        !matches!(self, Column::Provisional | Column::PlayerName)
+    }
+
+    /// What is needed for the column to have values.
+    fn requires(&self) -> &'static str {
+        match self {
+            Column::Deviation => "the glicko or trueskill algorithm",
+            Column::RatingLower | Column::RatingUpper => "--bootstrap",
+            Column::RankChange | Column::RatingChange => "--previous",
+            Column::Provisional => "min_events in the config, without --exclude-provisional",
+            Column::ScheduleStrength => "the points algorithm",
+            Column::PlayerName => "--players",
+            Column::Rank
+            | Column::Rating
+            | Column::Percentile
+            | Column::ZScore
+            | Column::PlayerId
+            | Column::Events
+            | Column::LastActive => "nothing",
+        }
+    }
+}
+
+/// Config and ingester resolved from [InputArgs].
+pub struct Input {
+    config: Config,
+    ingest: ResultIngester,
+    /// Start of the ingestion window, if any.
+    from: Option<DateTime<Utc>>,
+    /// End of the ingestion window, or now.
+    until: DateTime<Utc>,
+    /// Season for which results are rated.
+    season: i32,
+    /// Whether to print an ingestion report.
+    report: bool,
+}
+
+impl Input {
+    /// Returns `None` if all levels are excluded.
+    fn new(args: InputArgs) -> Result<Option<Self>> {
+        let config = if let Some(p) = args.config {
+            Config::read(p)?
+        } else {
+            Config::default()
+        }
+        .with_env_overrides()?;
+
+        let parse_cache = args
+            .parse_cache
+            .map(|d| d.unwrap_or_else(|| args.dir.join(parse_cache::DEFAULT_DIR)));
+        let index = args
+            .index
+            .map(|p| p.unwrap_or_else(|| args.dir.join(ddcrate::index::DEFAULT_FILE)))
+            .map(Index::read_file)
+            .transpose()?;
+        let mut ingest = ResultIngester::from_config(args.dir, &config)
+            .parse_cache(parse_cache)
+            .index(index)
+            .strict(args.strict)
+            .include(args.include)
+            .exclude(args.exclude);
+        #[cfg(feature = "http")]
+        {
+            ingest = ingest.cache_dir(args.cache_dir);
+        }
+        let mut season = config.get_seasons().season_of(&Utc::now());
+        let mut until = Utc::now();
+        let mut from = None;
+        if let Some(from_str) = args.from {
+            let dt =
+                parse_datetime(&from_str, false, args.timezone.as_ref()).map_err(|e| anyhow!(e))?;
+            ingest = ingest.from(dt);
+            from = Some(dt);
+        }
+        if let Some(to_str) = args.to {
+            let dt =
+                parse_datetime(&to_str, true, args.timezone.as_ref()).map_err(|e| anyhow!(e))?;
+            ingest = ingest.until(dt);
+            season = config.get_seasons().season_of(&dt);
+            until = dt;
+        }
+        if let Some(s) = args.season {
+            season = s;
+        }
+
+        let mut level_set: HashSet<_> = config.get_levels().keys().cloned().collect();
+        for name in args.exclude_level {
+            level_set.remove(&Level::new(name));
+        }
+        for (excluded, level) in [
+            (args.no_small, Level::SMALL),
+            (args.no_medium, Level::MEDIUM),
+            (args.no_major, Level::MAJOR),
+            (args.no_championship, Level::CHAMPIONSHIP),
+        ] {
+            if excluded {
+                eprintln!("--no-{level} is deprecated; use --exclude-level {level}");
+                level_set.remove(&level);
+            }
+        }
+        if level_set.is_empty() {
+            return Ok(None);
+        }
+        ingest = ingest.levels(level_set);
+
+        Ok(Some(Self {
+            config,
+            ingest,
+            from,
+            until,
+            season,
+            report: args.report,
+        }))
+    }
+}
+
+/// Rating of a player with no results, towards which inactive players decay.
+fn baseline(algorithm: Algorithm, config: &Config) -> f64 {
+    match algorithm {
+        Algorithm::Points => 0.0,
+        Algorithm::Elo => config.get_elo().initial,
+        Algorithm::Glicko => config.get_glicko().initial_rating,
+        Algorithm::TrueSkill => {
+            let ts = config.get_trueskill();
+            ts.mu - ts.confidence * ts.sigma
+        }
+    }
+}
+
+/// Rating system for the given algorithm, with no results yet.
+fn new_system(input: &Input, algorithm: Algorithm) -> Box<dyn RatingSystem> {
+    let config = &input.config;
+    match algorithm {
+        Algorithm::Points => Box::new(PointsSystem::new(config.clone(), input.season)),
+        Algorithm::Elo => Box::new(Elo::new(config.get_elo().clone())),
+        Algorithm::Glicko => Box::new(Glicko::new(config.get_glicko().clone())),
+        Algorithm::TrueSkill => Box::new(TrueSkill::new(config.get_trueskill().clone())),
+    }
+}
+
+/// Rating system for the given algorithm,
+/// having rated the tournaments and been advanced to the end of the input's window.
+fn rated_system<T: Borrow<Tournament>>(
+    input: &Input,
+    algorithm: Algorithm,
+    tournaments: &[T],
+) -> Result<Box<dyn RatingSystem>> {
+    let mut system = new_system(input, algorithm);
+    let mut sorted: Vec<_> = tournaments.iter().map(Borrow::borrow).collect();
+    sorted.sort_by_key(|t| t.datetime());
+    let mut engine = RankingEngine::new(system.as_mut());
+    engine.add_tournaments(&sorted)?;
+    engine.advance_to(&input.until);
+    Ok(system)
+}
+
+/// Ratings of every player with results by the given algorithm,
+/// at the end of the input's window and with any inactivity decay applied.
+fn current_ratings<T: Borrow<Tournament>>(
+    input: &Input,
+    algorithm: Algorithm,
+    tournaments: &[T],
+) -> Result<PlayerMap<NotNan<f64>>> {
+    let config = &input.config;
+    let mut ratings = rated_system(input, algorithm, tournaments)?.ratings();
+    if let Some(ia) = config.get_inactivity() {
+        let last = last_played(tournaments);
+        let baseline = baseline(algorithm, config);
+        ia.apply(&mut ratings, &last, baseline, input.until)?;
+    }
+    Ok(ratings)
+}
+
+/// Ingest tournaments, printing a report to stderr if requested.
+fn read_tournaments(ingester: &ResultIngester, report: bool) -> Result<Vec<Tournament>> {
+    if !report {
+        return Ok(ingester.ingest()?);
+    }
+    let (tournaments, report) = ingester.ingest_with_report()?;
+    let mut w = io::stderr().lock();
+    writeln!(
+        w,
+        "Read {} tournaments from {} files",
+        report.tournaments,
+        report.files_read.len()
+    )?;
+    for m in report.skipped_rows.iter() {
+        writeln!(w, "Skipped row: {}", m)?;
+    }
+    for p in report.unmatched_files.iter() {
+        writeln!(w, "Not a results file: {}", p.display())?;
+    }
+    Ok(tournaments)
 }
 
 pub struct RecordWriter<W: Write> {
     writer: W,
-    records: HashMap<PlayerId, PlayerRecord>,
-    players: Option<HashMap<PlayerId, String>>,
+    ratings: PlayerMap<NotNan<f64>>,
+    /// Column name and values of rating uncertainty.
+    deviations: Option<(&'static str, PlayerMap<f64>)>,
+    players: Option<PlayerMap<String>>,
+    /// Ranks and ratings from a previous ranking.
+    previous: Option<PlayerMap<(u64, f64)>>,
+    /// Players with too few results to be ranked reliably.
+    provisional: Option<PlayerSet>,
+    /// Players' strength of schedule; players without one have an empty cell.
+    schedule: Option<PlayerMap<f64>>,
+    /// Confidence intervals of players' ratings.
+    intervals: Option<PlayerMap<Interval>>,
+    /// Ratings relative to those of every ranked player.
+    relative: Option<PlayerMap<RelativeRating>>,
+    /// Number of results of each player.
+    events: Option<PlayerMap<usize>>,
+    /// Date/time of each player's latest result.
+    last_active: Option<PlayerMap<DateTime<Utc>>>,
+    /// Columns to write, in order.
+    columns: Vec<Column>,
+}
+
+/// Read a previous ranking's ranks and ratings,
+/// from either a JSON checkpoint or a ranking TSV.
+fn read_previous(p: &Path) -> Result<PlayerMap<(u64, f64)>> {
+    if p.extension().is_some_and(|e| e == "json") {
+        let checkpoint = Checkpoint::read(BufReader::new(File::open(p)?))?;
+        return Ok(checkpoint
+            .records
+            .iter()
+            .filter_map(|(pid, rec)| {
+                let rank = checkpoint.ranks.get(pid)?;
+                Some((*pid, (*rank, rec.rating.into_inner())))
+            })
+            .collect());
+    }
+    Ok(diff::read_ranking(p)?
+        .into_iter()
+        .map(|(pid, row)| (pid, (row.rank, row.rating)))
+        .collect())
 }
 
 impl<W: Write> RecordWriter<W> {
-    pub fn write_headers(&mut self) -> io::Result<()> {
-        write!(&mut self.writer, "rank\trating\tplayer_id")?;
+    /// Improvement in rank (positive is up) and change in rating since the previous ranking,
+    /// if the player was in it.
+    fn change(&self, id: PlayerId, rank: u64) -> Option<(i64, f64)> {
+        let (prev_rank, prev_rating) = self.previous.as_ref()?.get(&id)?;
+        Some((
+            *prev_rank as i64 - rank as i64,
+            self.ratings[&id].into_inner() - prev_rating,
+        ))
+    }
+
+    /// Rank and rating changes with arrows, or "new" if the player was not previously ranked.
+    fn change_labels(&self, id: PlayerId, rank: u64) -> (String, String) {
+        let Some((r, d)) = self.change(id, rank) else {
+            return ("new".to_owned(), String::default());
+        };
+        let rank_label = match r.cmp(&0) {
+            Ordering::Greater => format!("▲{}", r),
+            Ordering::Less => format!("▼{}", -r),
+            Ordering::Equal => "–".to_owned(),
+        };
+        (rank_label, format!("{:+.2}", d))
+    }
+
+    /// Every column with values, in the default order.
+    /// Events and last activity are only written if chosen.
+    fn default_columns(&self) -> Vec<Column> {
+        let mut out = vec![Column::Rank, Column::Rating];
+        if self.deviations.is_some() {
+            out.push(Column::Deviation);
+        }
+        if self.intervals.is_some() {
+            out.extend([Column::RatingLower, Column::RatingUpper]);
+        }
+        if self.relative.is_some() {
+            out.extend([Column::Percentile, Column::ZScore]);
+        }
+        if self.previous.is_some() {
+            out.extend([Column::RankChange, Column::RatingChange]);
+        }
+        if self.provisional.is_some() {
+            out.push(Column::Provisional);
+        }
+        if self.schedule.is_some() {
+            out.push(Column::ScheduleStrength);
+        }
+        out.push(Column::PlayerId);
         if self.players.is_some() {
-            write!(&mut self.writer, "\tplayer_name")?;
+            out.push(Column::PlayerName);
         }
-        writeln!(&mut self.writer)
+        out
     }
 
-    pub fn write_record(&mut self, id: PlayerId, rank: u64) -> io::Result<()> {
-        write!(
-            &mut self.writer,
-            "{}\t{}\t{}",
-            rank, self.records[&id].rating, id
-        )?;
-        if let Some(ps) = &self.players {
-            if let Some(name) = ps.get(&id) {
-                write!(&mut self.writer, "\t{}", name)?;
-            }
+    /// Whether there are values for the column.
+    fn has_column(&self, column: Column) -> bool {
+        match column {
+            Column::Rank | Column::Rating | Column::PlayerId => true,
+            Column::Deviation => self.deviations.is_some(),
+            Column::RatingLower | Column::RatingUpper => self.intervals.is_some(),
+            Column::Percentile | Column::ZScore => self.relative.is_some(),
+            Column::RankChange | Column::RatingChange => self.previous.is_some(),
+            Column::Provisional => self.provisional.is_some(),
+            Column::ScheduleStrength => self.schedule.is_some(),
+            Column::PlayerName => self.players.is_some(),
+            Column::Events => self.events.is_some(),
+            Column::LastActive => self.last_active.is_some(),
         }
-        writeln!(&mut self.writer)
+    }
+
+    /// Name of the column in TSV and markdown headers.
+    fn column_name(&self, column: Column) -> &'static str {
+        match (column, &self.deviations) {
+            (Column::Deviation, Some((name, _))) => name,
+            _ => column.name(),
+        }
+    }
+
+    /// A player's value in the column,
+    /// either rounded and labelled for reading (markdown and HTML) or in full (TSV).
+    fn cell(&self, column: Column, id: PlayerId, rank: u64, pretty: bool) -> String {
+        let num = |x: f64, places: usize| {
+            if pretty {
+                format!("{:.*}", places, x)
+            } else {
+                x.to_string()
+            }
+        };
+        let value = match column {
+            Column::Rank => Some(rank.to_string()),
+            Column::Rating => Some(num(self.ratings[&id].into_inner(), 2)),
+            Column::Deviation => self.deviations.as_ref().map(|(_, ds)| num(ds[&id], 2)),
+            Column::RatingLower => self.intervals.as_ref().map(|is| num(is[&id].lower, 2)),
+            Column::RatingUpper => self.intervals.as_ref().map(|is| num(is[&id].upper, 2)),
+            Column::Percentile => self.relative.as_ref().map(|rs| num(rs[&id].percentile, 1)),
+            Column::ZScore => self.relative.as_ref().map(|rs| num(rs[&id].z_score, 2)),
+            Column::RankChange | Column::RatingChange if pretty => {
+                self.previous.as_ref().map(|_| {
+                    let (r, d) = self.change_labels(id, rank);
+                    if column == Column::RankChange {
+                        r
+                    } else {
+                        d
+                    }
+                })
+            }
+            Column::RankChange => self.change(id, rank).map(|(r, _)| r.to_string()),
+            Column::RatingChange => self.change(id, rank).map(|(_, d)| d.to_string()),
+            Column::Provisional => {
+                self.provisional
+                    .as_ref()
+                    .map(|ps| match (ps.contains(&id), pretty) {
+                        (true, true) => "provisional".to_owned(),
+                        (false, true) => String::default(),
+                        (p, false) => p.to_string(),
+                    })
+            }
+            Column::ScheduleStrength => self
+                .schedule
+                .as_ref()
+                .and_then(|ss| ss.get(&id))
+                .map(|s| num(*s, 2)),
+            Column::PlayerId => Some(id.to_string()),
+            Column::PlayerName => self.players.as_ref().and_then(|ps| ps.get(&id)).cloned(),
+            Column::Events => self
+                .events
+                .as_ref()
+                .map(|es| es.get(&id).copied().unwrap_or_default().to_string()),
+            Column::LastActive => self
+                .last_active
+                .as_ref()
+                .and_then(|ls| ls.get(&id))
+                .map(|dt| dt.format("%Y-%m-%d").to_string()),
+        };
+        value.unwrap_or_default()
+    }
+
+    pub fn write_headers(&mut self) -> io::Result<()> {
+        let names: Vec<_> = self.columns.iter().map(|c| self.column_name(*c)).collect();
+        writeln!(&mut self.writer, "{}", names.join("\t"))
+    }
+
+    pub fn write_record(&mut self, id: PlayerId, rank: u64) -> io::Result<()> {
+        let cells: Vec<_> = self
+            .columns
+            .iter()
+            .map(|c| self.cell(*c, id, rank, false))
+            .collect();
+        writeln!(&mut self.writer, "{}", cells.join("\t"))
+    }
+
+    pub fn write_markdown_headers(&mut self) -> io::Result<()> {
+        let names: Vec<_> = self.columns.iter().map(|c| self.column_name(*c)).collect();
+        writeln!(&mut self.writer, "| {} |", names.join(" | "))?;
+        let aligns: Vec<_> = self
+            .columns
+            .iter()
+            .map(|c| {
+                if *c == Column::PlayerName {
+                    "---"
+                } else {
+                    "--:"
+                }
+            })
+            .collect();
+        writeln!(&mut self.writer, "| {} |", aligns.join(" | "))
+    }
+
+    pub fn write_markdown_record(&mut self, id: PlayerId, rank: u64) -> io::Result<()> {
+        let cells: Vec<_> = self
+            .columns
+            .iter()
+            .map(|c| self.cell(*c, id, rank, true).replace('|', "\\|"))
+            .collect();
+        writeln!(&mut self.writer, "| {} |", cells.join(" | "))
     }
 }
 
@@ -116,25 +857,35 @@ const MONTH_DAYS: [i64; 12] = [
     31, // Dec
 ];
 
-fn parse_player_db(p: &Path) -> Result<HashMap<PlayerId, String>> {
-    let f = BufReader::new(File::open(p)?);
+pub fn parse_player_db(p: &Path) -> Result<PlayerMap<String>> {
+    read_player_db(BufReader::new(File::open(p)?))
+}
+
+/// Read a player database, allowing a leading byte order mark and CRLF line endings.
+fn read_player_db<R: Read>(r: R) -> Result<PlayerMap<String>> {
     let mut rdr = ReaderBuilder::new()
         .delimiter(b'\t')
         .comment(Some(b'#'))
-        .from_reader(f);
+        .from_reader(r);
 
     let mut out = HashMap::default();
     for result in rdr.records() {
         let record = result?;
-        let Some(id_str) = record.get(0) else {continue;};
-        let Ok(player) = id_str.parse::<PlayerId>() else {continue;};
-        let Some(name) = record.get(1) else {continue;};
-        out.insert(player, name.to_owned());
+        let Some(id_str) = record.get(0) else {
+            continue;
+        };
+        let Ok(player) = id_str.trim().parse::<PlayerId>() else {
+            continue;
+        };
+        let Some(name) = record.get(1) else {
+            continue;
+        };
+        out.insert(player, name.trim().to_owned());
     }
     Ok(out)
 }
 
-fn parse_datetime(s: &str, up: bool) -> Result<DateTime<Utc>, &'static str> {
+fn parse_datetime(s: &str, up: bool, zone: Option<&Zone>) -> Result<DateTime<Utc>, &'static str> {
     let re = regex!(
         r"(?x)
         (?P<year>\d\d\d\d)
@@ -143,11 +894,13 @@ fn parse_datetime(s: &str, up: bool) -> Result<DateTime<Utc>, &'static str> {
         (T(?P<hour>\d\d)
         (:(?P<min>\d\d)
         (:(?P<sec>\d\d)
-        ((?P<offset>[+-]\d\d:?\d\d)
+        ((?P<offset>Z|[+-]\d\d:?\d\d)
     )?)?)?)?)?)?
     "
     );
-    let Some(cap) = re.captures(s) else {return Err("Could not parse datetime")};
+    let Some(cap) = re.captures(s) else {
+        return Err("Could not parse datetime");
+    };
 
     let mut parsed = Parsed::new();
 
@@ -167,7 +920,7 @@ fn parse_datetime(s: &str, up: bool) -> Result<DateTime<Utc>, &'static str> {
     } else {
         1
     };
-    let day = parse_capture(&cap, "month", n_days);
+    let day = parse_capture(&cap, "day", n_days);
     parsed.set_day(day).map_err(|_| "Invalid day")?;
 
     let hour = parse_capture(&cap, "hour", if up { 23 } else { 0 });
@@ -177,122 +930,548 @@ fn parse_datetime(s: &str, up: bool) -> Result<DateTime<Utc>, &'static str> {
     let sec = parse_capture(&cap, "sec", if up { 59 } else { 0 });
     parsed.set_second(sec).map_err(|_| "Invalid second")?;
 
-    let offset_str = cap
-        .name("offset")
-        .map(|m| m.as_str().replace(':', ""))
-        .unwrap_or("+0000".to_owned());
-
-    let mut chars = offset_str.chars();
-    let sign = chars.next().unwrap();
-    let mut seconds: i64 = 0;
-    let mut buf: [u8; 4] = [0; 4];
-    seconds += chars
-        .next()
-        .unwrap()
-        .encode_utf8(&mut buf)
-        .parse::<i64>()
-        .unwrap()
-        * 60
-        * 60
-        * 10;
-    seconds += chars
-        .next()
-        .unwrap()
-        .encode_utf8(&mut buf)
-        .parse::<i64>()
-        .unwrap()
-        * 60
-        * 60;
-    seconds += chars
-        .next()
-        .unwrap()
-        .encode_utf8(&mut buf)
-        .parse::<i64>()
-        .unwrap()
-        * 60
-        * 10;
-    seconds += chars
-        .next()
-        .unwrap()
-        .encode_utf8(&mut buf)
-        .parse::<i64>()
-        .unwrap()
-        * 60;
-    let _offset = match sign {
-        '-' => -seconds,
-        _ => seconds,
+    let offset = match (cap.name("offset"), zone) {
+        (Some(m), _) => match m.as_str().parse() {
+            Ok(Zone::Fixed(offset)) => offset.local_minus_utc() as i64,
+            _ => return Err("Invalid offset"),
+        },
+        (None, Some(z)) => {
+            let local = parsed
+                .to_naive_datetime_with_offset(0)
+                .map_err(|_| "Invalid datetime")?;
+            return z
+                .to_utc(&local)
+                .ok_or("Datetime does not exist in time zone");
+        }
+        (None, None) => 0,
     };
-
-    parsed.set_offset(seconds).map_err(|_| "Invalid offset")?;
-    let naive = parsed
-        .to_naive_datetime_with_offset(0)
-        .map_err(|_| "Invalid datetime")?;
-    Ok(DateTime::from_utc(naive, Utc))
+    parsed.set_offset(offset).map_err(|_| "Invalid offset")?;
+    let dt = parsed.to_datetime().map_err(|_| "Invalid datetime")?;
+    Ok(dt.with_timezone(&Utc))
 }
 
 fn main() -> Result<()> {
-    let args = Args::parse();
+    let matches = cli().get_matches();
+    if matches.subcommand().is_none() {
+        return rank(RankArgs::from_arg_matches(&matches)?);
+    }
+    match Command::from_arg_matches(&matches)? {
+        Command::Rank(args) => rank(args),
+        Command::Explain(args) => explain::run(args),
+        Command::Player(args) => explain::run_player(args),
+        Command::Validate(args) => validate::run(args),
+        Command::Index(args) => index::run(args),
+        Command::Watch(args) => watch::run(args),
+        Command::Serve(args) => serve::run(args),
+        Command::Chart(args) => chart::run(args),
+        Command::Diff(args) => diff::run(args),
+        Command::Evaluate(args) => evaluate::run(args),
+        Command::Tune(args) => tune::run(args),
+        Command::Whatif(args) => whatif::run(args),
+        Command::H2h(args) => h2h::run(args),
+        Command::Predict(args) => predict::run(args),
+        Command::Stats(args) => stats::run(args),
+        Command::Seed(args) => seed::run(args),
+        Command::Bracket(args) => bracket::run(args),
+        Command::Config(args) => config::run(args),
+        #[cfg(feature = "testing")]
+        Command::Generate(args) => generate::run(args),
+    }
+}
 
-    let config: Config = if let Some(p) = args.config {
-        let contents = fs::read_to_string(p)?;
-        toml::from_str(&contents)?
-    } else {
-        Config::default()
+fn rank(mut args: RankArgs) -> Result<()> {
+    let Some(dir) = args.snapshot_dir.take() else {
+        return match args.output.take() {
+            Some(p) => write_atomic(&p, |f| rank_into(args, f)),
+            None => rank_into(args, io::stdout()),
+        };
+    };
+    let date = match &args.input.to {
+        Some(s) => parse_datetime(s, true, args.input.timezone.as_ref()).map_err(|e| anyhow!(e))?,
+        None => Utc::now(),
+    };
+    let ext = match args.template.as_deref().and_then(|p| p.extension()) {
+        Some(e) => e.to_string_lossy(),
+        None => args.format.extension().into(),
     };
+    let name = format!("{}.{}", date.format("%Y-%m-%d"), ext);
+    let output = args.output.take();
 
-    let mut ingest = ResultIngester::new(args.dir);
-    let mut year = Utc::now().year();
-    if let Some(from_str) = args.from {
-        ingest = ingest.from(parse_datetime(&from_str, false).map_err(|e| anyhow!(e))?);
+    let mut buf = Vec::default();
+    rank_into(args, &mut buf)?;
+    write_snapshot(&dir, &name, &buf)?;
+    match output {
+        Some(p) => write_atomic(&p, |f| Ok(f.write_all(&buf)?)),
+        None => Ok(io::stdout().write_all(&buf)?),
     }
-    if let Some(to_str) = args.to {
-        let dt = parse_datetime(&to_str, true).map_err(|e| anyhow!(e))?;
-        ingest = ingest.until(dt);
-        year = dt.year();
+}
+
+/// Write a file to the snapshot directory,
+/// unless the latest snapshot with the same extension has the same contents.
+fn write_snapshot(dir: &Path, name: &str, contents: &[u8]) -> Result<()> {
+    fs::create_dir_all(dir)?;
+    let ext = Path::new(name).extension();
+    let mut latest: Option<PathBuf> = None;
+    for entry in fs::read_dir(dir)? {
+        let p = entry?.path();
+        if p.extension() != ext || !p.is_file() {
+            continue;
+        }
+        match &latest {
+            Some(l) if *l >= p => (),
+            _ => latest = Some(p),
+        }
+    }
+    if let Some(l) = latest {
+        if fs::read(&l)? == contents {
+            eprintln!("Ranking unchanged since {}", l.display());
+            return Ok(());
+        }
     }
+    write_atomic(&dir.join(name), |f| Ok(f.write_all(contents)?))
+}
 
-    let mut level_set = Level::all();
+/// Write to a temporary file in the same directory as `path`,
+/// then rename it into place, so that `path` is never partially written.
+fn write_atomic<F: FnOnce(&mut NamedTempFile) -> Result<()>>(path: &Path, f: F) -> Result<()> {
+    let dir = match path.parent() {
+        Some(d) if !d.as_os_str().is_empty() => d,
+        _ => Path::new("."),
+    };
+    let mut tmp = NamedTempFile::new_in(dir)?;
+    f(&mut tmp)?;
+    tmp.persist(path)?;
+    Ok(())
+}
 
-    if args.no_small {
-        level_set.remove(&Level::Small);
+/// Rank players, writing the TSV to the given writer.
+fn rank_into<W: Write>(args: RankArgs, w: W) -> Result<()> {
+    let Some(Input {
+        mut config,
+        mut ingest,
+        from,
+        until,
+        season: year,
+        report,
+    }) = Input::new(args.input)?
+    else {
+        return Ok(());
+    };
+
+    if let Some(n) = args.numbering {
+        config = config.numbering(n);
     }
-    if args.no_medium {
-        level_set.remove(&Level::Medium);
+    if let Some(d) = args.direction {
+        config = config.direction(d);
     }
-    if args.no_major {
-        level_set.remove(&Level::Major);
+    let numbering = config.get_numbering();
+    let direction = config.get_direction();
+    let algorithm = args.algorithm.unwrap_or(config.get_algorithm());
+    let checkpoint = match &args.checkpoint {
+        Some(p) if p.exists() => Some(Checkpoint::read(BufReader::new(File::open(p)?))?),
+        _ => None,
+    };
+    if args.checkpoint.is_some() && algorithm != Algorithm::Points {
+        return Err(anyhow!(
+            "Checkpoints are only supported by the points algorithm"
+        ));
     }
-    if args.no_championship {
-        level_set.remove(&Level::Championship);
+    if args.carry_over.is_some() && algorithm != Algorithm::Points {
+        return Err(anyhow!(
+            "Carrying over previous seasons is only supported by the points algorithm"
+        ));
     }
-    if level_set.is_empty() {
-        return Ok(());
+    if args.initial_ranks.is_some() && algorithm != Algorithm::Points {
+        return Err(anyhow!(
+            "Initial ranks are only supported by the points algorithm"
+        ));
+    }
+    let chosen = |c: Column| args.columns.as_ref().is_some_and(|cs| cs.contains(&c));
+    let with_schedule = args.schedule || chosen(Column::ScheduleStrength);
+    let with_relative = args.relative || chosen(Column::Percentile) || chosen(Column::ZScore);
+    let with_events = chosen(Column::Events);
+    let with_last = chosen(Column::LastActive);
+    if with_schedule && algorithm != Algorithm::Points {
+        return Err(anyhow!(
+            "Strength of schedule is only supported by the points algorithm"
+        ));
+    }
+    if args.teams
+        && (algorithm != Algorithm::Points
+            || args.format != OutputFormat::Tsv
+            || args.template.is_some())
+    {
+        return Err(anyhow!(
+            "Team rankings are only supported by the points algorithm with TSV output"
+        ));
+    }
+    if args.teams && args.columns.is_some() {
+        return Err(anyhow!("Columns cannot be chosen for team rankings"));
+    }
+    if args.chunk_size.is_some()
+        && (algorithm != Algorithm::Points || args.format == OutputFormat::Html || report)
+    {
+        return Err(anyhow!(
+            "Chunked ranking is only supported by the points algorithm, without HTML output or a report"
+        ));
+    }
+    if args.bootstrap.is_some()
+        && (args.checkpoint.is_some()
+            || args.carry_over.is_some()
+            || args.initial_ranks.is_some()
+            || args.teams
+            || args.chunk_size.is_some())
+    {
+        return Err(anyhow!(
+            "Bootstrap intervals are not supported with checkpoints, carried-over seasons, \
+            initial ranks, team rankings, or chunked ranking"
+        ));
+    }
+    if !(0.0..=1.0).contains(&args.confidence) {
+        return Err(anyhow!("Confidence must be between 0 and 1"));
+    }
+    if let Some(last) = checkpoint.as_ref().and_then(|c| c.last_datetime) {
+        // the engine skips tournaments at this date/time which the checkpoint includes
+        ingest = ingest.from(from.map_or(last, |f| f.max(last)));
     }
-
-    ingest = ingest.levels(level_set);
 
     let players = args.players.map(|p| parse_player_db(&p)).transpose()?;
+    let previous = args.previous.as_deref().map(read_previous).transpose()?;
+    let initial_ranks: Option<PlayerMap<_>> = args
+        .initial_ranks
+        .as_deref()
+        .map(read_previous)
+        .transpose()?
+        .map(|r| r.into_iter().map(|(pid, (rank, _))| (pid, rank)).collect());
 
-    let tournaments = ingest.ingest()?;
-    let (ranks, records) = rank_players(tournaments.as_slice(), year, &config);
+    // read lazily while ranking if chunked
+    let tournaments = match args.chunk_size {
+        Some(_) => Vec::default(),
+        None => read_tournaments(&ingest, report)?,
+    };
+    let min_events = config.get_min_events();
+    let mut events = if min_events > 0 || with_events {
+        event_counts(&tournaments)
+    } else {
+        HashMap::default()
+    };
+    let inactivity = config.get_inactivity().copied();
+    let mut last = if inactivity.is_some() || with_last {
+        last_played(&tournaments)
+    } else {
+        HashMap::default()
+    };
+    let baseline = baseline(algorithm, &config);
+    let intervals = match args.bootstrap {
+        Some(n) => {
+            let input = Input {
+                config: config.clone(),
+                ingest: ingest.clone(),
+                from,
+                until,
+                season: year,
+                report,
+            };
+            let rate = |sample: &[&Tournament]| current_ratings(&input, algorithm, sample);
+            Some(bootstrap(
+                &tournaments,
+                n.get(),
+                args.confidence,
+                baseline,
+                args.seed,
+                rate,
+            )?)
+        }
+        None => None,
+    };
+    let levels = if args.format == OutputFormat::Html || args.template.is_some() {
+        html::player_levels(&tournaments)
+    } else {
+        HashMap::default()
+    };
+    let mut schedule = None;
+    let (ranks, mut ratings, deviations) = match algorithm {
+        Algorithm::Points => {
+            let seasons = config.get_seasons().clone();
+            let mut engine = match checkpoint {
+                Some(c) => RankingEngine::from_checkpoint(c, config, year),
+                None => {
+                    let mut system = PointsSystem::new(config, year);
+                    if let Some(p) = &args.carry_over {
+                        let previous = Checkpoint::read(BufReader::new(File::open(p)?))?;
+                        system = system.with_previous_season(&previous);
+                    }
+                    RankingEngine::new(system)
+                }
+            };
+            let mut lazy = args.chunk_size.map(|n| (n.get(), ingest.iter().peekable()));
+            if let Some(ranks) = initial_ranks {
+                let first = match lazy.as_mut() {
+                    Some((_, iter)) => match iter.peek() {
+                        Some(Ok(t)) => Some(*t.datetime()),
+                        _ => None,
+                    },
+                    None => tournaments.iter().map(|t| *t.datetime()).min(),
+                };
+                // until the start of the season after the earliest result
+                let until = first
+                    .and_then(|first| seasons.start_of(seasons.season_of(&first) + 1))
+                    .and_then(|d| d.and_hms_opt(0, 0, 0))
+                    .map_or(DateTime::<Utc>::MAX_UTC, |d| Utc.from_utc_datetime(&d));
+                engine = engine.with_initial_ranks(ranks, until);
+            }
+            match lazy {
+                Some((n, iter)) => engine.add_chunked(iter, n)?,
+                None => {
+                    for t in tournaments {
+                        engine.add_tournament(t)?;
+                    }
+                }
+            }
+            engine.advance_to(&until);
+            if min_events > 0 || with_events {
+                // includes results from any checkpoint
+                events = engine
+                    .current_records()
+                    .iter()
+                    .map(|(pid, rec)| (*pid, rec.events()))
+                    .collect();
+            }
+            if inactivity.is_some() || with_last {
+                last = engine
+                    .current_records()
+                    .iter()
+                    .filter_map(|(pid, rec)| Some((*pid, rec.history().last()?.datetime)))
+                    .collect();
+            }
+            if with_schedule {
+                schedule = Some(
+                    engine
+                        .current_records()
+                        .iter()
+                        .filter_map(|(pid, rec)| Some((*pid, rec.strength_of_schedule()?)))
+                        .collect(),
+                );
+            }
+            if let Some(p) = &args.checkpoint {
+                write_atomic(p, |f| {
+                    let mut w = BufWriter::new(f);
+                    engine.checkpoint().write(&mut w)?;
+                    Ok(w.flush()?)
+                })?;
+            }
+            if args.teams {
+                let records = engine.current_team_records();
+                let mut ranks: Vec<_> = engine
+                    .current_team_ranks()
+                    .into_iter()
+                    .filter(|(team, rank)| {
+                        (args.player.is_empty()
+                            || team.players().iter().any(|p| args.player.contains(p)))
+                            && args.limit.is_none_or(|n| *rank <= n)
+                    })
+                    .collect();
+                if args.sorted {
+                    ranks.sort_unstable_by(|(t1, r1), (t2, r2)| (r1, t1).cmp(&(r2, t2)));
+                }
+                let mut w = BufWriter::new(w);
+                if !args.no_headers {
+                    write!(w, "rank\trating\tplayer_ids")?;
+                    if players.is_some() {
+                        write!(w, "\tplayer_names")?;
+                    }
+                    writeln!(w)?;
+                }
+                for (team, rank) in ranks {
+                    let ids: Vec<_> = team.players().iter().map(|p| p.to_string()).collect();
+                    write!(w, "{}\t{}\t{}", rank, records[&team].rating, ids.join(","))?;
+                    if let Some(ps) = &players {
+                        let names: Vec<_> = team
+                            .players()
+                            .iter()
+                            .map(|p| ps.get(p).map(|n| n.as_str()).unwrap_or(""))
+                            .collect();
+                        write!(w, "\t{}", names.join(","))?;
+                    }
+                    writeln!(w)?;
+                }
+                return Ok(w.flush()?);
+            }
+            (engine.current_ranks(), engine.system().ratings(), None)
+        }
+        Algorithm::Elo => {
+            let mut system = Elo::new(config.get_elo().clone());
+            let ranks = rank_with(&mut system, tournaments.as_slice())?;
+            (ranks, system.ratings(), None)
+        }
+        Algorithm::Glicko => {
+            let mut system = Glicko::new(config.get_glicko().clone());
+            let ranks = rank_with(&mut system, tournaments.as_slice())?;
+            let deviations = system
+                .records()
+                .iter()
+                .map(|(pid, rec)| (*pid, rec.rd_at(&until, system.config())))
+                .collect();
+            (ranks, system.ratings(), Some(("rd", deviations)))
+        }
+        Algorithm::TrueSkill => {
+            let mut system = TrueSkill::new(config.get_trueskill().clone());
+            let ranks = rank_with(&mut system, tournaments.as_slice())?;
+            let deviations = system
+                .records()
+                .iter()
+                .map(|(pid, rec)| (*pid, rec.sigma))
+                .collect();
+            (ranks, system.ratings(), Some(("sigma", deviations)))
+        }
+    };
+    let ranks = match inactivity {
+        Some(ia) => {
+            ia.apply(&mut ratings, &last, baseline, until)?;
+            ranks_from_ratings(&ratings, numbering, direction)
+        }
+        // only the points system orders and numbers ranks according to the config
+        None if algorithm != Algorithm::Points => {
+            ranks_from_ratings(&ratings, numbering, direction)
+        }
+        None => ranks,
+    };
+    let provisional: PlayerSet = events
+        .iter()
+        .filter(|(_, n)| **n < min_events)
+        .map(|(pid, _)| *pid)
+        .collect();
+    let ranks = if args.exclude_provisional && !provisional.is_empty() {
+        let established = ratings
+            .iter()
+            .filter(|(pid, _)| !provisional.contains(pid))
+            .map(|(pid, r)| (*pid, *r))
+            .collect();
+        ranks_from_ratings(&established, numbering, direction)
+    } else {
+        ranks
+    };
+    let relative = with_relative.then(|| {
+        let ranked = ratings
+            .iter()
+            .filter(|(pid, _)| ranks.contains_key(pid))
+            .map(|(pid, r)| (*pid, *r))
+            .collect();
+        relative_ratings(&ranked)
+    });
     let mut writer = RecordWriter {
-        writer: BufWriter::new(io::stdout()),
-        records,
+        writer: BufWriter::new(w),
+        ratings,
+        deviations,
         players,
+        previous,
+        provisional: (min_events > 0 && !args.exclude_provisional).then_some(provisional),
+        schedule,
+        intervals,
+        relative,
+        events: with_events.then_some(events),
+        last_active: with_last.then_some(last),
+        columns: Vec::default(),
     };
-    if !args.no_headers {
+    writer.columns = match args.columns {
+        Some(columns) => {
+            if let Some(c) = columns.iter().find(|c| !writer.has_column(**c)) {
+                return Err(anyhow!("The {} column needs {}", c.name(), c.requires()));
+            }
+            columns
+        }
+        None => writer.default_columns(),
+    };
+    let only: HashSet<_> = args.player.into_iter().collect();
+    let mut records: Vec<_> = ranks
+        .into_iter()
+        .filter(|(id, rank)| {
+            (only.is_empty() || only.contains(id)) && args.limit.is_none_or(|n| *rank <= n)
+        })
+        .collect();
+    if let Some(p) = &args.template {
+        records.sort_unstable_by_key(|(pid, rank)| (*rank, *pid));
+        let meta = template::Metadata {
+            algorithm,
+            season: year,
+            until,
+            generated: Utc::now(),
+        };
+        writer.write_template(p, &records, &levels, &meta)?;
+        return Ok(writer.writer.flush()?);
+    }
+    if args.format == OutputFormat::Html {
+        records.sort_unstable_by_key(|(pid, rank)| (*rank, *pid));
+        writer.write_html(&records, &levels, Utc::now())?;
+        return Ok(writer.writer.flush()?);
+    }
+    let markdown = args.format == OutputFormat::Markdown;
+    if markdown {
+        writer.write_markdown_headers()?;
+    } else if !args.no_headers {
         writer.write_headers()?;
     }
     if args.sorted {
-        let mut sorted_ranks: Vec<_> = ranks.into_iter().collect();
-        sorted_ranks.sort_unstable_by_key(|(pid, rank)| (*rank, *pid));
-        sorted_ranks
-            .into_iter()
-            .for_each(|(id, rank)| writer.write_record(id, rank).unwrap());
-    } else {
-        ranks
-            .into_iter()
-            .for_each(|(id, rank)| writer.write_record(id, rank).unwrap());
+        records.sort_unstable_by_key(|(pid, rank)| (*rank, *pid));
+    }
+    for (id, rank) in records {
+        if markdown {
+            writer.write_markdown_record(id, rank)?;
+        } else {
+            writer.write_record(id, rank)?;
+        }
+    }
+    Ok(writer.writer.flush()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deprecated_level_flags() {
+        cli().debug_assert();
+        let matches =
+            cli().get_matches_from(["ddcrate-cli", "-d", "results", "-S", "--no-championship"]);
+        let args = RankArgs::from_arg_matches(&matches).unwrap();
+        assert!(args.input.no_small && args.input.no_championship);
+        assert!(!args.input.no_medium && !args.input.no_major);
+    }
+
+    #[test]
+    fn player_db_bom_and_crlf() {
+        let tsv = "\u{feff}id\tname\r\n1\tAda Lovelace\r\n2\tAlan Turing\r\n";
+        let players = read_player_db(tsv.as_bytes()).unwrap();
+        assert_eq!(players.len(), 2);
+        assert_eq!(players[&1], "Ada Lovelace");
+        assert_eq!(players[&2], "Alan Turing");
+    }
+
+    #[test]
+    fn chosen_columns() {
+        let mut writer = RecordWriter {
+            writer: Vec::default(),
+            ratings: [(1, NotNan::new(2.5).unwrap())].into_iter().collect(),
+            deviations: None,
+            players: Some(PlayerMap::default()),
+            previous: None,
+            provisional: None,
+            schedule: None,
+            intervals: None,
+            relative: None,
+            events: Some([(1, 3)].into_iter().collect()),
+            last_active: None,
+            columns: vec![
+                Column::PlayerId,
+                Column::Events,
+                Column::PlayerName,
+                Column::Rating,
+            ],
+        };
+        assert!(!writer.has_column(Column::Deviation));
+        writer.write_headers().unwrap();
+        writer.write_record(1, 1).unwrap();
+        let tsv = String::from_utf8(writer.writer).unwrap();
+        assert_eq!(tsv, "player_id\tevents\tplayer_name\trating\n1\t3\t\t2.5\n");
     }
-    Ok(())
 }
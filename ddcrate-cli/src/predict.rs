@@ -0,0 +1,83 @@
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Result};
+use clap::Args;
+
+use ddcrate::{Algorithm, PlayerId};
+
+use crate::{parse_player_db, rated_system, read_tournaments, Input, InputArgs};
+
+#[derive(Args, Debug)]
+pub struct PredictArgs {
+    #[command(flatten)]
+    input: InputArgs,
+    /// Player on the first side.
+    /// Can be given multiple times to predict a game between teams.
+    #[arg(short = 'a', long, required = true)]
+    first: Vec<PlayerId>,
+    /// Player on the second side.
+    /// Can be given multiple times to predict a game between teams.
+    #[arg(short = 'b', long, required = true)]
+    second: Vec<PlayerId>,
+    /// Rating algorithm to use: points, elo, glicko, or trueskill.
+    /// Overrides the config file; defaults to points.
+    #[arg(long)]
+    algorithm: Option<Algorithm>,
+    /// Skip column headers in output TSV.
+    #[arg(short = 'H', long)]
+    no_headers: bool,
+    /// Path to player database, used to name the players.
+    #[arg(short, long)]
+    players: Option<PathBuf>,
+}
+
+/// Print a TSV with columns side, comma-separated player IDs, and probability of winning,
+/// and optionally the players' names.
+pub fn run(args: PredictArgs) -> Result<()> {
+    if let Some(p) = args.first.iter().find(|p| args.second.contains(p)) {
+        return Err(anyhow!("Player {} is on both sides", p));
+    }
+    let players = args.players.map(|p| parse_player_db(&p)).transpose()?;
+    let Some(input) = Input::new(args.input)? else {
+        return Ok(());
+    };
+    let algorithm = args.algorithm.unwrap_or(input.config.get_algorithm());
+    let tournaments = read_tournaments(&input.ingest, input.report)?;
+    let system = rated_system(&input, algorithm, &tournaments)?;
+
+    let ratings = system.ratings();
+    let unrated: Vec<_> = args
+        .first
+        .iter()
+        .chain(args.second.iter())
+        .filter(|p| !ratings.contains_key(p))
+        .map(|p| p.to_string())
+        .collect();
+    if !unrated.is_empty() {
+        eprintln!("Players with no rating: {}", unrated.join(", "));
+    }
+    let p = system.win_probability(&args.first, &args.second);
+
+    let mut w = BufWriter::new(io::stdout());
+    if !args.no_headers {
+        write!(w, "side\tplayer_ids\twin_probability")?;
+        if players.is_some() {
+            write!(w, "\tplayer_names")?;
+        }
+        writeln!(w)?;
+    }
+    for (side, ids, prob) in [("first", &args.first, p), ("second", &args.second, 1.0 - p)] {
+        let id_strs: Vec<_> = ids.iter().map(|p| p.to_string()).collect();
+        write!(w, "{}\t{}\t{}", side, id_strs.join(","), prob)?;
+        if let Some(ps) = &players {
+            let names: Vec<_> = ids
+                .iter()
+                .map(|p| ps.get(p).map(|n| n.as_str()).unwrap_or(""))
+                .collect();
+            write!(w, "\t{}", names.join(","))?;
+        }
+        writeln!(w)?;
+    }
+    Ok(w.flush()?)
+}
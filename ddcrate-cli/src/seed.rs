@@ -0,0 +1,217 @@
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Result};
+use clap::Args;
+use csv::ReaderBuilder;
+
+use ddcrate::seeding::{seed_entrants, Seeded};
+use ddcrate::{player_columns, Algorithm, Entrant, PlayerId, PlayerSet};
+
+use crate::{baseline, current_ratings, parse_player_db, read_tournaments, Input, InputArgs};
+
+/// Arguments for seeding a list of entrants.
+#[derive(Args, Debug)]
+pub struct SeedingArgs {
+    #[command(flatten)]
+    input: InputArgs,
+    /// TSV of entrants, one per row.
+    /// If the first row is a header, player IDs are in columns named e.g. "player1" or "p1"
+    /// (or just "player"), and the entrant's name in a "team" or "name" column;
+    /// otherwise, every column is a player ID.
+    #[arg(short, long)]
+    entrants: PathBuf,
+    /// Rating algorithm to use: points, elo, glicko, or trueskill.
+    /// Overrides the config file; defaults to points.
+    #[arg(short, long)]
+    algorithm: Option<Algorithm>,
+}
+
+#[derive(Args, Debug)]
+pub struct SeedArgs {
+    #[command(flatten)]
+    seeding: SeedingArgs,
+    /// Skip column headers in output TSV.
+    #[arg(short = 'H', long)]
+    no_headers: bool,
+    /// Path to player database, used to name the players.
+    #[arg(short, long)]
+    players: Option<PathBuf>,
+}
+
+/// Columns of the entrants TSV: the player IDs, and the entrant's name if any.
+struct EntrantColumns {
+    players: Option<Vec<usize>>,
+    name: Option<usize>,
+}
+
+impl EntrantColumns {
+    /// Columns named in a header row, if any of its fields are not player IDs.
+    fn from_header(record: &csv::StringRecord) -> Option<Result<Self>> {
+        if record
+            .iter()
+            .all(|f| f.trim().is_empty() || f.trim().parse::<PlayerId>().is_ok())
+        {
+            return None;
+        }
+        let players = player_columns(record);
+        if players.is_empty() {
+            return Some(Err(anyhow!("No player columns in entrants header")));
+        }
+        let name = record
+            .iter()
+            .map(|f| f.trim().to_lowercase())
+            .position(|n| n == "team" || n == "name");
+        Some(Ok(Self {
+            players: Some(players),
+            name,
+        }))
+    }
+}
+
+/// Read entrants and their names, one per row; see [SeedArgs::entrants].
+/// Empty player fields are ignored, so that teams can be of different sizes.
+fn read_entrants<R: Read>(r: R) -> Result<Vec<(Entrant, Option<String>)>> {
+    let mut rdr = ReaderBuilder::new()
+        .delimiter(b'\t')
+        .comment(Some(b'#'))
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(r);
+    let mut columns = EntrantColumns {
+        players: None,
+        name: None,
+    };
+    let mut seen = PlayerSet::default();
+    let mut out = Vec::default();
+    for (idx, result) in rdr.records().enumerate() {
+        let record = result?;
+        let line = record.position().map_or(0, |p| p.line());
+        if idx == 0 {
+            if let Some(c) = EntrantColumns::from_header(&record) {
+                columns = c?;
+                continue;
+            }
+        }
+        let fields: Vec<_> = match &columns.players {
+            Some(cols) => cols.iter().filter_map(|c| record.get(*c)).collect(),
+            None => record.iter().collect(),
+        };
+        let mut players = Vec::with_capacity(fields.len());
+        for f in fields.into_iter().map(str::trim).filter(|f| !f.is_empty()) {
+            let p: PlayerId = f
+                .parse()
+                .map_err(|_| anyhow!("Invalid player ID {:?} on line {}", f, line))?;
+            if !seen.insert(p) {
+                return Err(anyhow!("Player {} is entered more than once", p));
+            }
+            players.push(p);
+        }
+        if players.is_empty() {
+            continue;
+        }
+        let name = columns
+            .name
+            .and_then(|c| record.get(c))
+            .map(|n| n.trim().to_owned());
+        out.push((Entrant::from_players(&players)?, name));
+    }
+    Ok(out)
+}
+
+/// Seeded entrants and their names.
+pub struct SeedList {
+    /// Entrants in seed order.
+    pub seeded: Vec<Seeded>,
+    /// Name of each entrant in entry order, if any.
+    names: Vec<Option<String>>,
+}
+
+impl SeedList {
+    pub fn name(&self, s: &Seeded) -> Option<&str> {
+        self.names[s.entry].as_deref()
+    }
+
+    pub fn has_names(&self) -> bool {
+        self.names.iter().any(|n| n.is_some())
+    }
+}
+
+/// Seed the entrants by their players' current ratings.
+/// Returns `None` if all levels are excluded.
+pub fn seed(args: SeedingArgs) -> Result<Option<SeedList>> {
+    let Some(input) = Input::new(args.input)? else {
+        return Ok(None);
+    };
+    let entries = read_entrants(BufReader::new(File::open(&args.entrants)?))?;
+    let (entrants, names): (Vec<_>, Vec<_>) = entries.into_iter().unzip();
+    let algorithm = args.algorithm.unwrap_or(input.config.get_algorithm());
+    let tournaments = read_tournaments(&input.ingest, input.report)?;
+    let ratings = current_ratings(&input, algorithm, &tournaments)?;
+    let seeded = seed_entrants(entrants, &ratings, baseline(algorithm, &input.config));
+    let unrated = seeded.iter().filter(|s| s.unrated).count();
+    if unrated > 0 {
+        eprintln!("{} entrants have unrated players", unrated);
+    }
+    Ok(Some(SeedList { seeded, names }))
+}
+
+/// Print a TSV of entrants in seed order, with their combined rating,
+/// comma-separated player IDs, and optionally their names and players' names.
+pub fn run(args: SeedArgs) -> Result<()> {
+    let players = args.players.map(|p| parse_player_db(&p)).transpose()?;
+    let Some(list) = seed(args.seeding)? else {
+        return Ok(());
+    };
+    let has_names = list.has_names();
+
+    let mut w = BufWriter::new(io::stdout());
+    if !args.no_headers {
+        write!(w, "seed\trating\tplayer_ids")?;
+        if has_names {
+            write!(w, "\tteam")?;
+        }
+        if players.is_some() {
+            write!(w, "\tplayer_names")?;
+        }
+        writeln!(w)?;
+    }
+    for s in list.seeded.iter() {
+        let ids: Vec<_> = s.entrant.players().iter().map(|p| p.to_string()).collect();
+        write!(w, "{}\t{}\t{}", s.seed, s.rating, ids.join(","))?;
+        if has_names {
+            write!(w, "\t{}", list.name(s).unwrap_or(""))?;
+        }
+        if let Some(ps) = &players {
+            let names: Vec<_> = s
+                .entrant
+                .players()
+                .iter()
+                .map(|p| ps.get(p).map(|n| n.as_str()).unwrap_or(""))
+                .collect();
+            write!(w, "\t{}", names.join(","))?;
+        }
+        writeln!(w)?;
+    }
+    Ok(w.flush()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entrants_with_and_without_header() {
+        let plain = read_entrants("1\t2\n3\t4\n5\n".as_bytes()).unwrap();
+        assert_eq!(plain.len(), 3);
+        assert_eq!(plain[2].0, Entrant::Single(5));
+
+        let named = read_entrants("team\tp1\tp2\nAces\t1\t2\nBees\t3\t4\n".as_bytes()).unwrap();
+        assert_eq!(named[1].0, Entrant::from_players(&[3, 4]).unwrap());
+        assert_eq!(named[1].1.as_deref(), Some("Bees"));
+
+        assert!(read_entrants("1\t2\n2\t3\n".as_bytes()).is_err());
+        assert!(read_entrants("team\tseed\nAces\t1\n".as_bytes()).is_err());
+    }
+}
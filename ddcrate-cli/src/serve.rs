@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use clap::Args;
+use serde::Serialize;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use ddcrate::json::JsonTournament;
+use ddcrate::{PlayerId, PlayerMap, PlayerRecord, PointsSystem, RankingEngine, ResultBreakdown};
+
+use crate::{parse_player_db, read_tournaments, Input, InputArgs};
+
+#[derive(Args, Debug)]
+pub struct ServeArgs {
+    #[command(flatten)]
+    input: InputArgs,
+    /// Port to listen on.
+    #[arg(long, default_value_t = 8080)]
+    port: u16,
+    /// Address to listen on.
+    #[arg(long, default_value = "127.0.0.1")]
+    host: String,
+    /// Seconds between re-reading the results.
+    #[arg(long, default_value_t = 300)]
+    interval: u64,
+    /// Path to player database, used to name players.
+    #[arg(short, long)]
+    players: Option<PathBuf>,
+}
+
+/// Ranking state served to clients, using the points algorithm.
+struct State {
+    updated: DateTime<Utc>,
+    ranks: PlayerMap<u64>,
+    records: PlayerMap<PlayerRecord>,
+    tournaments: Vec<JsonTournament>,
+    players: PlayerMap<String>,
+}
+
+impl State {
+    fn read(args: &ServeArgs) -> Result<Self> {
+        let Some(input) = Input::new(args.input.clone())? else {
+            return Err(anyhow!("All levels are excluded"));
+        };
+        let players = match &args.players {
+            Some(p) => parse_player_db(p)?,
+            None => HashMap::default(),
+        };
+        let tournaments = read_tournaments(&input.ingest, input.report)?;
+        let json_tournaments = tournaments.iter().map(JsonTournament::from).collect();
+
+        let mut engine = RankingEngine::new(PointsSystem::new(input.config, input.season));
+        for t in tournaments {
+            engine.add_tournament(t)?;
+        }
+        engine.advance_to(&input.until);
+        Ok(Self {
+            updated: Utc::now(),
+            ranks: engine.current_ranks(),
+            records: engine.current_records().clone(),
+            tournaments: json_tournaments,
+            players,
+        })
+    }
+
+    fn entry(&self, id: PlayerId) -> Option<LeaderboardEntry> {
+        Some(LeaderboardEntry {
+            rank: *self.ranks.get(&id)?,
+            rating: self.records.get(&id)?.rating.into_inner(),
+            player_id: id,
+            player_name: self.players.get(&id).cloned(),
+        })
+    }
+
+    fn leaderboard(&self) -> Vec<LeaderboardEntry> {
+        let mut out: Vec<_> = self.ranks.keys().filter_map(|id| self.entry(*id)).collect();
+        out.sort_unstable_by_key(|e| (e.rank, e.player_id));
+        out
+    }
+
+    fn player(&self, id: PlayerId) -> Option<PlayerDetail> {
+        let entry = self.entry(id)?;
+        let results = self.records[&id]
+            .counted_history()
+            .into_iter()
+            .map(|(b, counted)| CountedResult {
+                breakdown: b.clone(),
+                counted,
+            })
+            .collect();
+        Some(PlayerDetail { entry, results })
+    }
+}
+
+#[derive(Serialize)]
+struct LeaderboardEntry {
+    rank: u64,
+    rating: f64,
+    player_id: PlayerId,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    player_name: Option<String>,
+}
+
+#[derive(Serialize)]
+struct CountedResult {
+    #[serde(flatten)]
+    breakdown: ResultBreakdown,
+    counted: bool,
+}
+
+#[derive(Serialize)]
+struct PlayerDetail {
+    #[serde(flatten)]
+    entry: LeaderboardEntry,
+    results: Vec<CountedResult>,
+}
+
+#[derive(Serialize)]
+struct Envelope<T: Serialize> {
+    updated: DateTime<Utc>,
+    data: T,
+}
+
+/// Serve the points ranking as JSON over HTTP,
+/// re-reading the results every `interval` seconds.
+///
+/// Endpoints (all GET):
+/// - `/leaderboard`: every ranked player, by rank
+/// - `/players/{id}`: a player's rank, rating, and results
+/// - `/tournaments`: every tournament read, in the JSON input format
+///
+/// Responses are objects with the time of the last refresh in `updated`,
+/// and the content in `data`.
+pub fn run(args: ServeArgs) -> Result<()> {
+    let state = Arc::new(RwLock::new(State::read(&args)?));
+    let server = Server::http((args.host.as_str(), args.port)).map_err(|e| anyhow!(e))?;
+    eprintln!("Listening on http://{}:{}", args.host, args.port);
+
+    let interval = Duration::from_secs(args.interval);
+    let refresh_state = Arc::clone(&state);
+    thread::spawn(move || loop {
+        thread::sleep(interval);
+        // keep serving the previous state if the results can't be read
+        match State::read(&args) {
+            Ok(s) => *refresh_state.write().unwrap() = s,
+            Err(e) => eprintln!("Could not refresh rankings: {}", e),
+        }
+    });
+
+    for request in server.incoming_requests() {
+        let response = respond(&request, &state.read().unwrap());
+        if let Err(e) = request.respond(response) {
+            eprintln!("Could not send response: {}", e);
+        }
+    }
+    Ok(())
+}
+
+fn respond(request: &Request, state: &State) -> Response<std::io::Cursor<Vec<u8>>> {
+    if request.method() != &Method::Get {
+        return error_response(405, "Method not allowed");
+    }
+    let path = request.url().split('?').next().unwrap_or("");
+    let segments: Vec<_> = path.split('/').filter(|s| !s.is_empty()).collect();
+    match segments.as_slice() {
+        ["leaderboard"] => json_response(state, state.leaderboard()),
+        ["tournaments"] => json_response(state, &state.tournaments),
+        ["players", id] => {
+            let Ok(id) = id.parse::<PlayerId>() else {
+                return error_response(400, "Invalid player ID");
+            };
+            match state.player(id) {
+                Some(p) => json_response(state, p),
+                None => error_response(404, "Player not found"),
+            }
+        }
+        _ => error_response(404, "Not found"),
+    }
+}
+
+fn json_header() -> Header {
+    Header::from_bytes("Content-Type", "application/json").unwrap()
+}
+
+fn json_response<T: Serialize>(state: &State, data: T) -> Response<std::io::Cursor<Vec<u8>>> {
+    let envelope = Envelope {
+        updated: state.updated,
+        data,
+    };
+    match serde_json::to_string(&envelope) {
+        Ok(s) => Response::from_string(s).with_header(json_header()),
+        Err(e) => error_response(500, &e.to_string()),
+    }
+}
+
+fn error_response(status: u16, message: &str) -> Response<std::io::Cursor<Vec<u8>>> {
+    Response::from_string(serde_json::json!({ "error": message }).to_string())
+        .with_status_code(status)
+        .with_header(json_header())
+}
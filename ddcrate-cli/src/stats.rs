@@ -0,0 +1,144 @@
+use std::collections::BTreeMap;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use clap::Args;
+use plotters::prelude::*;
+
+use ddcrate::stats::{histogram, Bin, Distribution};
+use ddcrate::Algorithm;
+
+use crate::html::player_levels;
+use crate::{current_ratings, read_tournaments, Input, InputArgs};
+
+/// Width of the longest bar of the text histogram, in characters.
+const BAR_WIDTH: usize = 50;
+
+#[derive(Args, Debug)]
+pub struct StatsArgs {
+    #[command(flatten)]
+    input: InputArgs,
+    /// Rating algorithm to use: points, elo, glicko, or trueskill.
+    /// Overrides the config file; defaults to points.
+    #[arg(short, long)]
+    algorithm: Option<Algorithm>,
+    /// Number of bins in the histogram.
+    #[arg(short, long, default_value_t = 10)]
+    bins: usize,
+    /// Also plot the histogram to an SVG at this path.
+    #[arg(long)]
+    svg: Option<PathBuf>,
+    /// Width of the SVG in pixels.
+    #[arg(long, default_value_t = 1024)]
+    width: u32,
+    /// Height of the SVG in pixels.
+    #[arg(long, default_value_t = 768)]
+    height: u32,
+    /// Skip column headers in output TSVs.
+    #[arg(short = 'H', long)]
+    no_headers: bool,
+}
+
+/// Plot the histogram as an SVG bar chart.
+fn plot(path: &Path, size: (u32, u32), algorithm: Algorithm, bins: &[Bin]) -> Result<()> {
+    let (Some(first), Some(last)) = (bins.first(), bins.last()) else {
+        return Err(anyhow!("No ratings to plot"));
+    };
+    let x_pad = ((last.upper - first.lower) * 0.05).max(1.0);
+    let y_max = bins.iter().map(|b| b.count).max().unwrap_or_default();
+
+    let root = SVGBackend::new(path, size).into_drawing_area();
+    root.fill(&WHITE)?;
+    let mut chart = ChartBuilder::on(&root)
+        .caption(
+            format!("Rating distribution ({})", algorithm),
+            ("sans-serif", 24),
+        )
+        .margin(10)
+        .x_label_area_size(40)
+        .y_label_area_size(60)
+        .build_cartesian_2d(
+            (first.lower - x_pad)..(last.upper + x_pad),
+            0..(y_max + y_max / 20 + 1),
+        )?;
+    chart
+        .configure_mesh()
+        .x_desc("rating")
+        .y_desc("players")
+        .draw()?;
+    chart.draw_series(bins.iter().map(|b| {
+        Rectangle::new(
+            [(b.lower, 0), (b.upper, b.count)],
+            Palette99::pick(0).mix(0.7).filled(),
+        )
+    }))?;
+    root.present()?;
+    Ok(())
+}
+
+/// Print a TSV summarising the distribution of current ratings
+/// among all players and among the players with results at each level,
+/// then a TSV histogram of all ratings with a bar for each bin.
+pub fn run(args: StatsArgs) -> Result<()> {
+    let Some(input) = Input::new(args.input)? else {
+        return Ok(());
+    };
+    let algorithm = args.algorithm.unwrap_or(input.config.get_algorithm());
+    let tournaments = read_tournaments(&input.ingest, input.report)?;
+    let ratings = current_ratings(&input, algorithm, &tournaments)?;
+
+    let mut groups = vec![(
+        "all".to_owned(),
+        ratings.values().map(|r| r.into_inner()).collect::<Vec<_>>(),
+    )];
+    let mut by_level: BTreeMap<_, Vec<_>> = BTreeMap::default();
+    for (pid, levels) in player_levels(&tournaments) {
+        let Some(rating) = ratings.get(&pid) else {
+            continue;
+        };
+        for level in levels {
+            by_level.entry(level).or_default().push(rating.into_inner());
+        }
+    }
+    groups.extend(
+        by_level
+            .into_iter()
+            .map(|(level, rs)| (level.name().to_owned(), rs)),
+    );
+
+    let mut w = BufWriter::new(io::stdout());
+    if !args.no_headers {
+        writeln!(
+            w,
+            "level\tcount\tmean\tmin\tlower_quartile\tmedian\tupper_quartile\tmax"
+        )?;
+    }
+    for (name, rs) in groups.iter() {
+        let Some(d) = Distribution::new(rs.iter().copied()) else {
+            continue;
+        };
+        writeln!(
+            w,
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            name, d.count, d.mean, d.min, d.lower_quartile, d.median, d.upper_quartile, d.max
+        )?;
+    }
+
+    let bins = histogram(&groups[0].1, args.bins);
+    writeln!(w)?;
+    if !args.no_headers {
+        writeln!(w, "lower\tupper\tcount\tbar")?;
+    }
+    let most = bins.iter().map(|b| b.count).max().unwrap_or_default();
+    for b in bins.iter() {
+        let bar = "#".repeat((b.count * BAR_WIDTH).div_ceil(most.max(1)));
+        writeln!(w, "{}\t{}\t{}\t{}", b.lower, b.upper, b.count, bar)?;
+    }
+    w.flush()?;
+
+    if let Some(p) = &args.svg {
+        plot(p, (args.width, args.height), algorithm, &bins)?;
+    }
+    Ok(())
+}
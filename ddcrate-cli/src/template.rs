@@ -0,0 +1,163 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use minijinja::{context, Environment, Value};
+
+use ddcrate::{Algorithm, Level, PlayerId, PlayerMap};
+
+use crate::{Column, RecordWriter};
+
+/// Details of the ranking other than its rows, given to templates.
+pub struct Metadata {
+    pub algorithm: Algorithm,
+    pub season: i32,
+    /// End of the ranking window.
+    pub until: DateTime<Utc>,
+    pub generated: DateTime<Utc>,
+}
+
+impl<W: Write> RecordWriter<W> {
+    /// A player's value in the column, unrounded,
+    /// or none where a TSV would have an empty cell.
+    fn value(&self, column: Column, id: PlayerId, rank: u64) -> Value {
+        match column {
+            Column::Rank => Value::from(rank),
+            Column::Rating => Value::from(self.ratings[&id].into_inner()),
+            Column::Deviation => self.deviations.as_ref().map(|(_, ds)| ds[&id]).into(),
+            Column::RatingLower => self.intervals.as_ref().map(|is| is[&id].lower).into(),
+            Column::RatingUpper => self.intervals.as_ref().map(|is| is[&id].upper).into(),
+            Column::Percentile => self.relative.as_ref().map(|rs| rs[&id].percentile).into(),
+            Column::ZScore => self.relative.as_ref().map(|rs| rs[&id].z_score).into(),
+            Column::RankChange => self.change(id, rank).map(|(r, _)| r).into(),
+            Column::RatingChange => self.change(id, rank).map(|(_, d)| d).into(),
+            Column::Provisional => self.provisional.as_ref().map(|ps| ps.contains(&id)).into(),
+            Column::ScheduleStrength => self
+                .schedule
+                .as_ref()
+                .and_then(|ss| ss.get(&id))
+                .copied()
+                .into(),
+            Column::PlayerId => Value::from(id),
+            Column::PlayerName => self
+                .players
+                .as_ref()
+                .and_then(|ps| ps.get(&id))
+                .cloned()
+                .into(),
+            Column::Events => self
+                .events
+                .as_ref()
+                .map(|es| es.get(&id).copied().unwrap_or_default())
+                .into(),
+            Column::LastActive => self
+                .last_active
+                .as_ref()
+                .and_then(|ls| ls.get(&id))
+                .map(|dt| dt.format("%Y-%m-%d").to_string())
+                .into(),
+        }
+    }
+
+    /// Render the ranking with a [minijinja](https://docs.rs/minijinja) template file.
+    ///
+    /// The template is given `rows`, each with a value for every column (named as in TSV headers)
+    /// and the `levels` at which the player has results;
+    /// `columns`, the names of the columns in order;
+    /// and the ranking's `algorithm`, `season`, `date`, and the time it was `generated`.
+    /// Values are HTML-escaped if the template's file name ends in e.g. `.html`.
+    ///
+    /// Records should already be sorted.
+    pub fn write_template(
+        &mut self,
+        path: &Path,
+        records: &[(PlayerId, u64)],
+        levels: &PlayerMap<BTreeSet<Level>>,
+        meta: &Metadata,
+    ) -> Result<()> {
+        let source = fs::read_to_string(path)?;
+        let name = path
+            .file_name()
+            .map_or_else(String::default, |n| n.to_string_lossy().into_owned());
+        let mut env = Environment::new();
+        env.add_template(&name, &source)?;
+
+        let columns: Vec<_> = self.columns.iter().map(|c| self.column_name(*c)).collect();
+        let rows: Vec<_> = records
+            .iter()
+            .map(|(id, rank)| {
+                let mut row: BTreeMap<_, _> = self
+                    .columns
+                    .iter()
+                    .map(|c| (self.column_name(*c), self.value(*c, *id, *rank)))
+                    .collect();
+                let player_levels: Vec<_> = levels
+                    .get(id)
+                    .into_iter()
+                    .flatten()
+                    .map(|l| l.name().to_owned())
+                    .collect();
+                row.insert("levels", Value::from(player_levels));
+                row
+            })
+            .collect();
+        let ctx = context! {
+            rows,
+            columns,
+            algorithm => meta.algorithm.to_string(),
+            season => meta.season,
+            date => meta.until.format("%Y-%m-%d").to_string(),
+            generated => meta.generated.format("%Y-%m-%d %H:%M UTC").to_string(),
+        };
+        let out = env.get_template(&name)?.render(ctx)?;
+        Ok(self.writer.write_all(out.as_bytes())?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use ddcrate::NotNan;
+
+    use super::*;
+
+    #[test]
+    fn html_template_escaped() {
+        let mut file = tempfile::Builder::new().suffix(".html").tempfile().unwrap();
+        let source = "{% for row in rows %}{{ row.rank }} {{ row.player_name }} \
+            {{ row.levels|join(',') }}{% endfor %} {{ date }}";
+        file.write_all(source.as_bytes()).unwrap();
+        let mut writer = RecordWriter {
+            writer: Vec::default(),
+            ratings: [(1, NotNan::new(2.5).unwrap())].into_iter().collect(),
+            deviations: None,
+            players: Some([(1, "A & B".to_owned())].into_iter().collect()),
+            previous: None,
+            provisional: None,
+            schedule: None,
+            intervals: None,
+            relative: None,
+            events: None,
+            last_active: None,
+            columns: vec![Column::Rank, Column::PlayerName],
+        };
+        let levels = [(1, [Level::SMALL, Level::MAJOR].into_iter().collect())]
+            .into_iter()
+            .collect();
+        let dt = Utc.with_ymd_and_hms(2023, 6, 1, 0, 0, 0).unwrap();
+        let meta = Metadata {
+            algorithm: Algorithm::Points,
+            season: 2023,
+            until: dt,
+            generated: dt,
+        };
+        writer
+            .write_template(file.path(), &[(1, 1)], &levels, &meta)
+            .unwrap();
+        let out = String::from_utf8(writer.writer).unwrap();
+        assert_eq!(out, "1 A &amp; B major,small 2023-06-01");
+    }
+}
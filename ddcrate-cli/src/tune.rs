@@ -0,0 +1,95 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Result};
+use clap::Args;
+
+use ddcrate::evaluate::{tune, Grid, Metric};
+
+use crate::diff::fmt_opt;
+use crate::{parse_datetime, read_tournaments, Input, InputArgs};
+
+#[derive(Args, Debug)]
+pub struct TuneArgs {
+    #[command(flatten)]
+    input: InputArgs,
+    /// Candidate values of finish_decay, comma-separated.
+    #[arg(long, value_delimiter = ',', default_values_t = Grid::default().finish_decay)]
+    finish_decay: Vec<f64>,
+    /// Candidate values of age_decay, comma-separated.
+    #[arg(long, value_delimiter = ',', default_values_t = Grid::default().age_decay)]
+    age_decay: Vec<f64>,
+    /// Candidate values of record_length, comma-separated.
+    #[arg(long, value_delimiter = ',', default_values_t = Grid::default().record_length)]
+    record_length: Vec<usize>,
+    /// Measure of accuracy to optimise: brier (lowest) or spearman (highest).
+    #[arg(short, long, default_value_t)]
+    metric: Metric,
+    /// Only score tournaments from this datetime, as for --from;
+    /// earlier tournaments are still rated, so that players have ratings by then.
+    #[arg(short, long, value_name = "DATETIME")]
+    score_from: Option<String>,
+    /// Write a TSV of every combination tried and its scores to this file, best first.
+    #[arg(long)]
+    trials: Option<PathBuf>,
+}
+
+/// Evaluate the points system with every combination of candidate parameters,
+/// and print the config with the best, as TOML with its scores in a comment.
+pub fn run(args: TuneArgs) -> Result<()> {
+    let score_from = args
+        .score_from
+        .map(|s| parse_datetime(&s, false, args.input.timezone.as_ref()).map_err(|e| anyhow!(e)))
+        .transpose()?;
+    let Some(input) = Input::new(args.input)? else {
+        return Ok(());
+    };
+    let grid = Grid {
+        finish_decay: args.finish_decay,
+        age_decay: args.age_decay,
+        record_length: args.record_length,
+    };
+    let tournaments = read_tournaments(&input.ingest, input.report)?;
+    let trials = tune(
+        &input.config,
+        input.season,
+        &grid,
+        args.metric,
+        &tournaments,
+        score_from.as_ref(),
+    )?;
+
+    if let Some(p) = args.trials {
+        let mut w = BufWriter::new(File::create(p)?);
+        writeln!(w, "finish_decay\tage_decay\trecord_length\tspearman\tbrier")?;
+        for t in trials.iter() {
+            writeln!(
+                w,
+                "{}\t{}\t{}\t{}\t{}",
+                t.config.get_finish_decay(),
+                t.config.get_age_decay(),
+                t.config.get_record_length(),
+                fmt_opt(t.evaluation.spearman()),
+                fmt_opt(t.evaluation.brier())
+            )?;
+        }
+        w.flush()?;
+    }
+
+    let Some(best) = trials.first().filter(|t| t.score.is_some()) else {
+        return Err(anyhow!("No tournaments with pairs of entrants to score"));
+    };
+    let mut w = BufWriter::new(io::stdout());
+    writeln!(
+        w,
+        "# best {} of {} configs over {} tournaments: spearman {}, brier {}",
+        args.metric,
+        trials.len(),
+        best.evaluation.events.len(),
+        fmt_opt(best.evaluation.spearman()),
+        fmt_opt(best.evaluation.brier())
+    )?;
+    write!(w, "{}", best.config.to_toml()?)?;
+    Ok(w.flush()?)
+}
@@ -0,0 +1,46 @@
+use std::io::{self, BufWriter, Write};
+
+use anyhow::{anyhow, Result};
+use clap::Args;
+
+use ddcrate::FileCheck;
+
+use crate::{Input, InputArgs};
+
+#[derive(Args, Debug)]
+pub struct ValidateArgs {
+    #[command(flatten)]
+    input: InputArgs,
+    /// Also list files which are not results files.
+    #[arg(short, long)]
+    verbose: bool,
+}
+
+/// Print a TSV with columns status, path, message for every file checked,
+/// failing if any were invalid.
+pub fn run(args: ValidateArgs) -> Result<()> {
+    let Some(input) = Input::new(args.input)? else {
+        return Ok(());
+    };
+    let mut n_invalid = 0;
+    let mut w = BufWriter::new(io::stdout());
+    for (path, check) in input.ingest.validate() {
+        match check {
+            FileCheck::Valid(n) => writeln!(w, "ok\t{}\t{} tournaments", path.display(), n)?,
+            FileCheck::Ignored => {
+                if args.verbose {
+                    writeln!(w, "ignored\t{}\tnot a results file", path.display())?;
+                }
+            }
+            FileCheck::Invalid(e) => {
+                n_invalid += 1;
+                writeln!(w, "error\t{}\t{}", path.display(), e)?;
+            }
+        }
+    }
+    w.flush()?;
+    if n_invalid > 0 {
+        return Err(anyhow!("{} invalid files", n_invalid));
+    }
+    Ok(())
+}
@@ -0,0 +1,45 @@
+use std::sync::mpsc;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use clap::Args;
+use notify::{RecursiveMode, Watcher};
+
+use crate::{rank, RankArgs};
+
+#[derive(Args, Debug)]
+pub struct WatchArgs {
+    // --output is required, and should not be inside the results directory
+    #[command(flatten)]
+    rank: RankArgs,
+    /// Milliseconds to wait for further changes before re-ranking.
+    #[arg(long, default_value_t = 500)]
+    debounce: u64,
+}
+
+/// Rank players, then re-rank whenever the results directory changes.
+/// Errors while ranking are printed, and ranking is retried on the next change.
+pub fn run(args: WatchArgs) -> Result<()> {
+    if args.rank.output.is_none() {
+        return Err(anyhow!("--output is required when watching"));
+    }
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    watcher.watch(&args.rank.input.dir, RecursiveMode::Recursive)?;
+    let debounce = Duration::from_millis(args.debounce);
+
+    loop {
+        if let Err(e) = rank(args.rank.clone()) {
+            eprintln!("Could not rank: {}", e);
+        }
+
+        // wait for a change which isn't just a read
+        loop {
+            let event = rx.recv()??;
+            if !event.kind.is_access() {
+                break;
+            }
+        }
+        while rx.recv_timeout(debounce).is_ok() {}
+    }
+}
@@ -0,0 +1,109 @@
+use std::io::{self, BufWriter};
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Result};
+use clap::Args;
+
+use ddcrate::{ranks_from_ratings, Algorithm, Level, PlayerMap, Tournament};
+
+use crate::diff::{write_diff, RankingRow};
+use crate::{current_ratings, parse_player_db, read_tournaments, Input, InputArgs};
+
+#[derive(Args, Debug)]
+pub struct WhatifArgs {
+    #[command(flatten)]
+    input: InputArgs,
+    /// Results file of the tournament to rank with and without,
+    /// in any format read from --dir.
+    /// It can be a hypothetical tournament anywhere,
+    /// or a disputed one already in --dir.
+    #[arg(short, long)]
+    extra: PathBuf,
+    /// Level of the --extra tournament, unless the file gives its own.
+    /// Defaults to the level whose directory has the same name as the file's directory.
+    #[arg(short, long)]
+    level: Option<String>,
+    /// Rating algorithm to use: points, elo, glicko, or trueskill.
+    /// Overrides the config file; defaults to points.
+    #[arg(short, long)]
+    algorithm: Option<Algorithm>,
+    /// Skip column headers in output TSV.
+    #[arg(short = 'H', long)]
+    no_headers: bool,
+    /// Path to player database, used to name the players.
+    #[arg(short, long)]
+    players: Option<PathBuf>,
+}
+
+/// Whether two tournaments are the same event: at the same date/time and level, with the same results.
+fn same_event(a: &Tournament, b: &Tournament) -> bool {
+    a.datetime() == b.datetime() && a.level() == b.level() && a.results() == b.results()
+}
+
+/// Print a TSV comparing the ranking without the extra tournament (old)
+/// to the ranking with it (new), as for the diff subcommand.
+pub fn run(args: WhatifArgs) -> Result<()> {
+    let players = args.players.map(|p| parse_player_db(&p)).transpose()?;
+    let Some(input) = Input::new(args.input)? else {
+        return Ok(());
+    };
+    let config = &input.config;
+    let level = match args.level {
+        Some(name) => Level::new(name),
+        None => {
+            let dname = args
+                .extra
+                .parent()
+                .and_then(|d| d.file_name())
+                .and_then(|d| d.to_str())
+                .unwrap_or_default();
+            config
+                .get_levels()
+                .keys()
+                .find(|lvl| config.get_level_directory(lvl) == Some(dname))
+                .cloned()
+                .unwrap_or_else(|| Level::new(dname))
+        }
+    };
+    let extra = input.ingest.read_extra(&args.extra, &level)?;
+    if extra.is_empty() {
+        return Err(anyhow!(
+            "No tournaments read from {}: it is not a results file, \
+            or is outside the window or of an excluded level",
+            args.extra.display()
+        ));
+    }
+    let algorithm = args.algorithm.unwrap_or(config.get_algorithm());
+
+    let mut without = read_tournaments(&input.ingest, input.report)?;
+    let before = without.len();
+    without.retain(|t| !extra.iter().any(|e| same_event(t, e)));
+    if without.len() < before {
+        eprintln!(
+            "{} tournaments in --extra are already in --dir",
+            before - without.len()
+        );
+    }
+    let mut with = without.clone();
+    with.extend(extra);
+
+    let ranking = |tournaments: &[Tournament]| {
+        let ratings = current_ratings(&input, algorithm, tournaments)?;
+        let ranks = ranks_from_ratings(&ratings, config.get_numbering(), config.get_direction());
+        let rows = ratings
+            .into_iter()
+            .map(|(id, rating)| {
+                let row = RankingRow {
+                    rank: ranks[&id],
+                    rating: rating.into_inner(),
+                    name: players.as_ref().and_then(|ps| ps.get(&id)).cloned(),
+                };
+                (id, row)
+            })
+            .collect::<PlayerMap<_>>();
+        anyhow::Ok(rows)
+    };
+    let old = ranking(&without)?;
+    let new = ranking(&with)?;
+    write_diff(&old, &new, args.no_headers, BufWriter::new(io::stdout()))
+}
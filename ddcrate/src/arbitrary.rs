@@ -0,0 +1,175 @@
+//! [Arbitrary] implementations for property testing,
+//! e.g. that ratings never become NaN and that ranks are contiguous.
+//!
+//! Generated values are always valid:
+//! teams have distinct players, tournaments have consistent places and no repeated players,
+//! and configs pass [Config::validate].
+//! Player IDs are drawn from a small range, so that players recur between tournaments.
+use chrono::DateTime;
+use proptest::prelude::*;
+
+use crate::{
+    AgeUnit, BonusMode, Config, Entrant, Level, PlayerId, PointSplit, RankDirection, RankNumbering,
+    Team, TiePolicy, Tournament,
+};
+
+/// Largest player ID generated.
+pub const MAX_PLAYER_ID: PlayerId = 64;
+
+/// Most entrants in a generated tournament.
+pub const MAX_ENTRANTS: usize = 16;
+
+impl Arbitrary for Team {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_args: ()) -> Self::Strategy {
+        prop::collection::btree_set(1..=MAX_PLAYER_ID, 2..=4)
+            .prop_map(|ps| {
+                Team::from_players(ps.into_iter().collect()).expect("Players are distinct")
+            })
+            .boxed()
+    }
+}
+
+fn level() -> impl Strategy<Value = Level> {
+    prop_oneof![
+        Just(Level::SMALL),
+        Just(Level::MEDIUM),
+        Just(Level::MAJOR),
+        Just(Level::CHAMPIONSHIP),
+    ]
+}
+
+impl Arbitrary for Tournament {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    /// Singles or doubles tournaments between 2015 and 2030, with some tied places.
+    fn arbitrary_with(_args: ()) -> Self::Strategy {
+        let players = Just((1..=MAX_PLAYER_ID).collect::<Vec<_>>()).prop_shuffle();
+        let ties = prop::collection::vec(prop::bool::weighted(0.1), 2..=MAX_ENTRANTS);
+        // 2015-01-01 to 2030-01-01
+        let timestamp = 1_420_070_400_i64..1_893_456_000;
+        (players, ties, 1_usize..=2, timestamp, level())
+            .prop_map(|(players, ties, team_size, timestamp, level)| {
+                let mut results = Vec::with_capacity(ties.len());
+                let mut place = 0;
+                for (idx, (tied, ids)) in ties.iter().zip(players.chunks(team_size)).enumerate() {
+                    // the first entrant cannot be tied with the one above
+                    if idx == 0 || !tied {
+                        place = idx as u64 + 1;
+                    }
+                    let entrant = Entrant::from_players(ids).expect("Players are distinct");
+                    results.push((place, entrant));
+                }
+                let datetime =
+                    DateTime::from_timestamp(timestamp, 0).expect("Timestamp is in range");
+                Tournament::new(results, datetime, level).expect("Tournament is valid")
+            })
+            .boxed()
+    }
+}
+
+impl Arbitrary for Config {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    /// The default levels, with other points settings varied.
+    fn arbitrary_with(_args: ()) -> Self::Strategy {
+        let decays = (1.0..3.0_f64, 1.0..3.0_f64, 1_usize..=10, 0.0..=1.0_f64);
+        let split = prop_oneof![
+            Just(PointSplit::Even),
+            Just(PointSplit::Full),
+            Just(PointSplit::Rating),
+        ];
+        let bonus_mode = prop_oneof![Just(BonusMode::Player), Just(BonusMode::Team)];
+        let ties = prop_oneof![
+            Just(TiePolicy::Best),
+            Just(TiePolicy::Worst),
+            Just(TiePolicy::Average),
+        ];
+        let numbering = prop_oneof![
+            Just(RankNumbering::Standard),
+            Just(RankNumbering::Dense),
+            Just(RankNumbering::Ordinal),
+        ];
+        let direction = prop_oneof![
+            Just(RankDirection::Descending),
+            Just(RankDirection::Ascending)
+        ];
+        let age_unit = prop_oneof![Just(AgeUnit::Seasons), Just(AgeUnit::Days)];
+        let window_months = prop::option::of(1_u32..=36);
+        (
+            decays,
+            split,
+            bonus_mode,
+            ties,
+            numbering,
+            direction,
+            age_unit,
+            window_months,
+        )
+            .prop_map(
+                |(
+                    (finish_decay, age_decay, record_length, carry_over),
+                    split,
+                    bonus_mode,
+                    ties,
+                    numbering,
+                    direction,
+                    age_unit,
+                    window_months,
+                )| {
+                    Config::default()
+                        .finish_decay(finish_decay)
+                        .age_decay(age_decay)
+                        .record_length(record_length)
+                        .carry_over(carry_over)
+                        .split(split)
+                        .bonus_mode(bonus_mode)
+                        .ties(ties)
+                        .numbering(numbering)
+                        .direction(direction)
+                        .age_unit(age_unit)
+                        .window_months(window_months)
+                },
+            )
+            .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rank_players;
+
+    proptest! {
+        #[test]
+        fn valid_configs(config in any::<Config>()) {
+            prop_assert!(config.validate().is_ok());
+        }
+
+        #[test]
+        fn ratings_finite_and_ranks_contiguous(
+            tournaments in prop::collection::vec(any::<Tournament>(), 1..8),
+            config in any::<Config>(),
+        ) {
+            let numbering = config.get_numbering();
+            let (ranks, records) = rank_players(&tournaments, 2030, &config).unwrap();
+            prop_assert!(records.values().all(|r| r.rating.is_finite()));
+
+            let mut sorted: Vec<_> = ranks.values().copied().collect();
+            sorted.sort_unstable();
+            prop_assert_eq!(sorted.first(), Some(&1));
+            for (idx, w) in sorted.windows(2).enumerate() {
+                let contiguous = match numbering {
+                    RankNumbering::Standard => w[1] == w[0] || w[1] == idx as u64 + 2,
+                    RankNumbering::Dense => w[1] == w[0] || w[1] == w[0] + 1,
+                    RankNumbering::Ordinal => w[1] == idx as u64 + 2,
+                };
+                prop_assert!(contiguous, "ranks {:?} are not contiguous", sorted);
+            }
+        }
+    }
+}
@@ -0,0 +1,125 @@
+//! Confidence intervals for ratings by resampling tournaments.
+//!
+//! Each resample draws as many tournaments as there are, with replacement,
+//! and rates them afresh.
+//! A player's interval holds the central share of their ratings across the resamples.
+//! Where none of a player's tournaments are drawn, they have the rating of a player with no results,
+//! so the intervals of players with few results are wide.
+use std::collections::HashMap;
+
+use ordered_float::NotNan;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::stats::quantile;
+use crate::{PlayerMap, Tournament};
+
+/// Bounds of a rating's confidence interval.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Interval {
+    pub lower: f64,
+    pub upper: f64,
+}
+
+/// Estimate a confidence interval for the rating of every player in the tournaments.
+///
+/// `rate` rates a resample of the tournaments, in no particular order;
+/// players missing from its output have the `unrated` rating.
+/// `confidence` is the share of resampled ratings within each interval, between 0 and 1.
+/// The same `seed` draws the same resamples.
+/// Returns an empty map if there are no tournaments or no samples,
+/// or the first error from rating a resample.
+pub fn bootstrap<F, E>(
+    tournaments: &[Tournament],
+    samples: usize,
+    confidence: f64,
+    unrated: f64,
+    seed: u64,
+    mut rate: F,
+) -> Result<PlayerMap<Interval>, E>
+where
+    F: FnMut(&[&Tournament]) -> Result<PlayerMap<NotNan<f64>>, E>,
+{
+    if tournaments.is_empty() || samples == 0 {
+        return Ok(HashMap::default());
+    }
+    let mut resampled: PlayerMap<Vec<f64>> = HashMap::default();
+    for t in tournaments {
+        for (_, entrant) in t.results() {
+            for p in entrant.players() {
+                resampled.entry(*p).or_default();
+            }
+        }
+    }
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    for _ in 0..samples {
+        let sample: Vec<_> = (0..tournaments.len())
+            .map(|_| &tournaments[rng.gen_range(0..tournaments.len())])
+            .collect();
+        let ratings = rate(&sample)?;
+        for (pid, rs) in resampled.iter_mut() {
+            rs.push(ratings.get(pid).map_or(unrated, |r| r.into_inner()));
+        }
+    }
+
+    let tail = (1.0 - confidence.clamp(0.0, 1.0)) / 2.0;
+    Ok(resampled
+        .into_iter()
+        .map(|(pid, mut rs)| {
+            rs.sort_unstable_by(f64::total_cmp);
+            let interval = Interval {
+                lower: quantile(&rs, tail),
+                upper: quantile(&rs, 1.0 - tail),
+            };
+            (pid, interval)
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::*;
+    use crate::elo::{Elo, EloConfig};
+    use crate::{Entrant, Level, RankingEngine, RankingError, RatingSystem};
+
+    #[test]
+    fn single_event_player_uncertain() {
+        let ts: Vec<_> = (1..=5)
+            .map(|d| {
+                let dt = Utc.with_ymd_and_hms(2022, 6, d, 0, 0, 0).unwrap();
+                let mut results: Vec<_> = (1..=4).map(|p| (p, Entrant::Single(p))).collect();
+                // player 5 only plays once, finishing last
+                if d == 3 {
+                    results.push((5, Entrant::Single(5)));
+                }
+                Tournament::new(results, dt, Level::SMALL).unwrap()
+            })
+            .collect();
+        let rate = |sample: &[&Tournament]| {
+            let mut sorted = sample.to_vec();
+            sorted.sort_by_key(|t| t.datetime());
+            let mut system = Elo::default();
+            RankingEngine::new(&mut system).add_tournaments(&sorted)?;
+            Ok::<_, RankingError>(system.ratings())
+        };
+        let unrated = EloConfig::default().initial;
+        let intervals = bootstrap(&ts, 200, 0.9, unrated, 1, rate).unwrap();
+        assert_eq!(intervals.len(), 5);
+        assert!(intervals.values().all(|i| i.lower <= i.upper));
+
+        let width = |p| intervals[&p].upper - intervals[&p].lower;
+        assert!(width(5) > width(1));
+        // not drawn in some resamples
+        assert_eq!(intervals[&5].upper, unrated);
+        assert!(intervals[&5].lower < unrated);
+
+        assert_eq!(
+            bootstrap(&ts, 200, 0.9, unrated, 1, rate).unwrap(),
+            intervals
+        );
+        assert!(bootstrap(&ts, 0, 0.9, unrated, 1, rate).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,243 @@
+//! Single- and double-elimination brackets for seeded entrants.
+//!
+//! The bracket has room for the smallest power of two at least the number of entrants,
+//! and seeds are placed in the standard order:
+//! if the higher seed always wins, the top two seeds meet in the final,
+//! the top four in the semi-finals, and so on.
+//! The empty places are byes, which go to the top seeds;
+//! games against a bye are not listed, and the entrant goes straight to its next game.
+use serde::Serialize;
+
+/// Part of a bracket.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Stage {
+    /// The main bracket, which every entrant starts in.
+    Winners,
+    /// In double elimination, the bracket of entrants which have lost once.
+    Losers,
+    /// In double elimination, the game between the winners of each bracket.
+    Final,
+}
+
+impl Stage {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Stage::Winners => "winners",
+            Stage::Losers => "losers",
+            Stage::Final => "final",
+        }
+    }
+}
+
+/// Who plays on one side of a game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Slot {
+    /// The entrant with this seed, from 1.
+    Seed(u64),
+    /// The winner of the game with this ID.
+    Winner(usize),
+    /// The loser of the game with this ID.
+    Loser(usize),
+    /// No entrant.
+    Bye,
+}
+
+/// A game in a bracket.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BracketMatch {
+    /// ID of the game, from 1, in the order that games can be played.
+    pub id: usize,
+    pub stage: Stage,
+    /// Round within the stage, from 1.
+    pub round: usize,
+    pub top: Slot,
+    pub bottom: Slot,
+}
+
+/// Seeds in bracket order for a bracket of the given size (a power of two),
+/// where consecutive pairs meet in the first round.
+pub fn seed_order(size: usize) -> Vec<u64> {
+    let mut order = vec![1];
+    while order.len() < size {
+        let n = order.len() as u64 * 2;
+        order = order.iter().flat_map(|s| [*s, n + 1 - s]).collect();
+    }
+    order
+}
+
+#[derive(Debug, Default)]
+struct Builder {
+    matches: Vec<BracketMatch>,
+}
+
+impl Builder {
+    fn push(&mut self, stage: Stage, round: usize, top: Slot, bottom: Slot) -> usize {
+        let id = self.matches.len() + 1;
+        self.matches.push(BracketMatch {
+            id,
+            stage,
+            round,
+            top,
+            bottom,
+        });
+        id
+    }
+
+    /// Add a round in which the winners of consecutive pairs of games play each other.
+    fn pair_winners(&mut self, stage: Stage, round: usize, ids: &[usize]) -> Vec<usize> {
+        ids.chunks(2)
+            .map(|p| self.push(stage, round, Slot::Winner(p[0]), Slot::Winner(p[1])))
+            .collect()
+    }
+
+    /// Add the winners' bracket, returning the IDs of the games in each round.
+    fn winners(&mut self, entrants: usize) -> Vec<Vec<usize>> {
+        let slot = |s: u64| {
+            if s as usize <= entrants {
+                Slot::Seed(s)
+            } else {
+                Slot::Bye
+            }
+        };
+        let order = seed_order(entrants.max(2).next_power_of_two());
+        let first = order
+            .chunks(2)
+            .map(|p| self.push(Stage::Winners, 1, slot(p[0]), slot(p[1])))
+            .collect();
+        let mut rounds: Vec<Vec<usize>> = vec![first];
+        while let Some(prev) = rounds.last().filter(|r| r.len() > 1).cloned() {
+            let next = self.pair_winners(Stage::Winners, rounds.len() + 1, &prev);
+            rounds.push(next);
+        }
+        rounds
+    }
+
+    /// Replace games against a bye with the entrant which advances from them,
+    /// and number the remaining games from 1.
+    fn finish(self) -> Vec<BracketMatch> {
+        // for each game against a bye, who advances from it
+        let mut walkovers: Vec<Option<Slot>> = Vec::with_capacity(self.matches.len());
+        let mut matches = self.matches;
+        for m in matches.iter_mut() {
+            for slot in [&mut m.top, &mut m.bottom] {
+                *slot = match *slot {
+                    Slot::Winner(id) => walkovers[id - 1].unwrap_or(*slot),
+                    Slot::Loser(id) if walkovers[id - 1].is_some() => Slot::Bye,
+                    s => s,
+                };
+            }
+            walkovers.push(match (m.top, m.bottom) {
+                (Slot::Bye, other) | (other, Slot::Bye) => Some(other),
+                _ => None,
+            });
+        }
+        let mut ids = vec![0; matches.len()];
+        let mut out = Vec::with_capacity(matches.len());
+        for (mut m, walkover) in matches.into_iter().zip(walkovers) {
+            if walkover.is_some() {
+                continue;
+            }
+            ids[m.id - 1] = out.len() + 1;
+            m.id = out.len() + 1;
+            out.push(m);
+        }
+        for m in out.iter_mut() {
+            for slot in [&mut m.top, &mut m.bottom] {
+                if let Slot::Winner(id) | Slot::Loser(id) = slot {
+                    *id = ids[*id - 1];
+                }
+            }
+        }
+        out
+    }
+}
+
+/// A single-elimination bracket for the given number of seeded entrants.
+pub fn single_elimination(entrants: usize) -> Vec<BracketMatch> {
+    let mut b = Builder::default();
+    b.winners(entrants);
+    b.finish()
+}
+
+/// A double-elimination bracket for the given number of seeded entrants.
+///
+/// Entrants which lose in the winners' bracket drop into the losers' bracket,
+/// in reverse order in alternate rounds to delay rematches.
+/// The winners of each bracket play a single final.
+pub fn double_elimination(entrants: usize) -> Vec<BracketMatch> {
+    let mut b = Builder::default();
+    let winners = b.winners(entrants);
+    let mut round = 1;
+    let mut losers: Vec<usize> = winners[0]
+        .chunks(2)
+        .filter(|p| p.len() == 2)
+        .map(|p| b.push(Stage::Losers, round, Slot::Loser(p[0]), Slot::Loser(p[1])))
+        .collect();
+    for (idx, wround) in winners.iter().enumerate().skip(1) {
+        round += 1;
+        let mut drops: Vec<_> = wround.iter().map(|id| Slot::Loser(*id)).collect();
+        if idx % 2 == 1 {
+            drops.reverse();
+        }
+        losers = losers
+            .iter()
+            .zip(drops)
+            .map(|(id, drop)| b.push(Stage::Losers, round, Slot::Winner(*id), drop))
+            .collect();
+        if losers.len() > 1 {
+            round += 1;
+            losers = b.pair_winners(Stage::Losers, round, &losers);
+        }
+    }
+    if let Some(wfinal) = winners.last().and_then(|r| r.first()) {
+        let challenger = match losers.first() {
+            Some(id) => Slot::Winner(*id),
+            // only two entrants: the loser of their game gets a rematch
+            None => Slot::Loser(*wfinal),
+        };
+        b.push(Stage::Final, 1, Slot::Winner(*wfinal), challenger);
+    }
+    b.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn standard_seed_order() {
+        assert_eq!(seed_order(8), vec![1, 8, 4, 5, 2, 7, 3, 6]);
+    }
+
+    #[test]
+    fn single_with_byes() {
+        let b = single_elimination(6);
+        // 1 and 2 have byes
+        assert_eq!(b.len(), 5);
+        assert_eq!((b[0].top, b[0].bottom), (Slot::Seed(4), Slot::Seed(5)));
+        assert_eq!((b[2].top, b[2].bottom), (Slot::Seed(1), Slot::Winner(1)));
+        assert_eq!(b[4].round, 3);
+        assert_eq!((b[4].top, b[4].bottom), (Slot::Winner(3), Slot::Winner(4)));
+        assert!(single_elimination(1).is_empty());
+    }
+
+    #[test]
+    fn double() {
+        for n in 2..=16 {
+            let b = double_elimination(n);
+            // n - 1 games in the winners' bracket, n - 2 in the losers', and the final
+            assert_eq!(b.len(), 2 * n - 2, "{} entrants", n);
+            assert_eq!(b.last().unwrap().stage, Stage::Final);
+            for m in b.iter() {
+                for slot in [m.top, m.bottom] {
+                    assert_ne!(slot, Slot::Bye);
+                    if let Slot::Winner(id) | Slot::Loser(id) = slot {
+                        assert!(id < m.id);
+                    }
+                }
+            }
+        }
+    }
+}
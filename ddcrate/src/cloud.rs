@@ -0,0 +1,170 @@
+//! Reading results from an object store bucket (Amazon S3, Google Cloud Storage, or Azure).
+//!
+//! The results root is a URL of a bucket and optionally a prefix within it,
+//! such as `s3://my-bucket/results`, `gs://my-bucket/results`, or `az://my-container/results`.
+//! Every object under the prefix is read as if it were a file in a results directory,
+//! so its key relative to the prefix should start with the level's directory
+//! (e.g. `small/2023-07-10_open.tsv`).
+//!
+//! Credentials and region are configured by the usual environment variables for each store,
+//! such as `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY` and `AWS_REGION`,
+//! `GOOGLE_SERVICE_ACCOUNT`, or `AZURE_STORAGE_ACCOUNT_NAME` and `AZURE_STORAGE_ACCOUNT_KEY`.
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use futures::{StreamExt, TryStreamExt};
+use log::debug;
+use object_store::aws::AmazonS3Builder;
+use object_store::azure::MicrosoftAzureBuilder;
+use object_store::gcp::GoogleCloudStorageBuilder;
+use object_store::path::Path as ObjectPath;
+use object_store::{ObjectStore, ObjectStoreScheme};
+use thiserror::Error;
+use url::Url;
+
+/// URL schemes of buckets.
+const SCHEMES: &[&str] = &["s3", "s3a", "gs", "az", "adl", "azure", "abfs", "abfss"];
+
+/// Maximum number of objects downloaded at once.
+const CONCURRENT_DOWNLOADS: usize = 16;
+
+#[derive(Debug, Error)]
+pub enum CloudError {
+    #[error(transparent)]
+    Store(#[from] object_store::Error),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("Invalid bucket URL {url:?}: {source}")]
+    Url {
+        url: String,
+        source: url::ParseError,
+    },
+    #[error("Unsupported bucket URL {0:?}")]
+    UnsupportedScheme(String),
+}
+
+/// Whether the results root is the URL of a bucket.
+pub fn is_bucket(root: &Path) -> bool {
+    root.to_str()
+        .and_then(|s| Url::parse(s).ok())
+        .is_some_and(|url| SCHEMES.contains(&url.scheme()) && url.host_str().is_some())
+}
+
+/// The store for a bucket URL, configured from the environment, and the prefix within it.
+fn store(url: &Url) -> Result<(Box<dyn ObjectStore>, ObjectPath), CloudError> {
+    let (scheme, prefix) = ObjectStoreScheme::parse(url).map_err(object_store::Error::from)?;
+    let store: Box<dyn ObjectStore> = match scheme {
+        ObjectStoreScheme::AmazonS3 => {
+            Box::new(AmazonS3Builder::from_env().with_url(url.as_str()).build()?)
+        }
+        ObjectStoreScheme::GoogleCloudStorage => Box::new(
+            GoogleCloudStorageBuilder::from_env()
+                .with_url(url.as_str())
+                .build()?,
+        ),
+        ObjectStoreScheme::MicrosoftAzure => Box::new(
+            MicrosoftAzureBuilder::from_env()
+                .with_url(url.as_str())
+                .build()?,
+        ),
+        _ => return Err(CloudError::UnsupportedScheme(url.to_string())),
+    };
+    Ok((store, prefix))
+}
+
+/// Download every object under the prefix, keyed by its key relative to the prefix joined to the root.
+async fn fetch(
+    store: &dyn ObjectStore,
+    prefix: &ObjectPath,
+    root: &Path,
+) -> Result<BTreeMap<PathBuf, Vec<u8>>, CloudError> {
+    let objects: Vec<_> = store.list(Some(prefix)).try_collect().await?;
+    futures::stream::iter(objects)
+        .filter_map(|meta| async move {
+            let rel: PathBuf = meta
+                .location
+                .prefix_match(prefix)?
+                .map(|part| part.as_ref().to_owned())
+                .collect();
+            Some((meta.location, rel))
+        })
+        .map(|(location, rel)| async move {
+            debug!("Downloading {}", location);
+            let bytes = store.get(&location).await?.bytes().await?;
+            Ok((root.join(rel), bytes.to_vec()))
+        })
+        .buffer_unordered(CONCURRENT_DOWNLOADS)
+        .try_collect()
+        .await
+}
+
+/// Download every results file under the root bucket URL,
+/// keyed by its path as if the root were a results directory.
+pub(crate) fn download(root: &Path) -> Result<BTreeMap<PathBuf, Vec<u8>>, CloudError> {
+    let root_str = root.to_string_lossy();
+    let url = Url::parse(&root_str).map_err(|source| CloudError::Url {
+        url: root_str.to_string(),
+        source,
+    })?;
+    let (store, prefix) = store(&url)?;
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?
+        .block_on(fetch(store.as_ref(), &prefix, root))
+}
+
+#[cfg(test)]
+mod tests {
+    use object_store::memory::InMemory;
+
+    use super::*;
+
+    #[test]
+    fn bucket_urls() {
+        assert!(is_bucket(Path::new("s3://bucket/results")));
+        assert!(is_bucket(Path::new("gs://bucket")));
+        assert!(is_bucket(Path::new("az://container/results/")));
+        assert!(!is_bucket(Path::new("https://example.com/results/")));
+        assert!(!is_bucket(Path::new("results/small")));
+    }
+
+    #[test]
+    fn fetch_under_prefix() {
+        let store = InMemory::new();
+        let root = Path::new("s3://bucket/results");
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let files = rt.block_on(async {
+            for (key, contents) in [
+                (
+                    "results/small/2023-07-10_open.tsv",
+                    "place\tp1\tp2\n1\t1\t2\n",
+                ),
+                ("results/major/2023-08-01.tsv", "place\tp1\tp2\n1\t3\t4\n"),
+                ("other/2023-08-01.tsv", ""),
+            ] {
+                store
+                    .put(&ObjectPath::from(key), contents.as_bytes().to_vec().into())
+                    .await
+                    .unwrap();
+            }
+            fetch(&store, &ObjectPath::from("results"), root)
+                .await
+                .unwrap()
+        });
+        let paths: Vec<_> = files.keys().cloned().collect();
+        assert_eq!(
+            paths,
+            vec![
+                root.join("major/2023-08-01.tsv"),
+                root.join("small/2023-07-10_open.tsv"),
+            ]
+        );
+        assert_eq!(
+            files[&root.join("major/2023-08-01.tsv")],
+            b"place\tp1\tp2\n1\t3\t4\n"
+        );
+    }
+}
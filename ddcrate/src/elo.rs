@@ -0,0 +1,278 @@
+//! Elo-style rating, treating every pair of finishing places in a tournament as a game,
+//! or using the tournament's games if they were recorded.
+
+use ordered_float::NotNan;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    collect_ratings, Entrant, Match, PlayerId, PlayerMap, PointsError, RatingSystem, Tournament,
+};
+
+/// The default rating of a player with no results.
+pub const INITIAL_RATING: f64 = 1500.0;
+
+/// The default maximum rating change from a single tournament.
+pub const K_FACTOR: f64 = 32.0;
+
+/// The default rating difference at which the higher-rated team is 10 times as likely to win.
+pub const SCALE: f64 = 400.0;
+
+/// The default largest margin of victory which counts towards a game's weight.
+pub const MARGIN_CAP: u32 = 10;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct EloConfig {
+    /// Rating of a player with no results.
+    pub initial: f64,
+    /// Maximum rating change from a single tournament.
+    pub k: f64,
+    /// Rating difference at which the higher-rated team is 10 times as likely to win.
+    pub scale: f64,
+    /// Extra weight of a game won by `margin_cap` or more points, relative to a game with no margin;
+    /// 0 ignores scores.
+    /// Games are weighted in proportion to their margin of victory, up to the cap.
+    pub margin: f64,
+    /// Largest margin of victory which counts towards a game's weight,
+    /// so that running up the score does not keep gaining rating.
+    pub margin_cap: u32,
+}
+
+impl Default for EloConfig {
+    fn default() -> Self {
+        Self {
+            initial: INITIAL_RATING,
+            k: K_FACTOR,
+            scale: SCALE,
+            margin: 0.0,
+            margin_cap: MARGIN_CAP,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Elo {
+    config: EloConfig,
+    ratings: PlayerMap<f64>,
+}
+
+impl Elo {
+    pub fn new(config: EloConfig) -> Self {
+        Self {
+            config,
+            ratings: Default::default(),
+        }
+    }
+
+    /// Current rating of a player, or the initial rating if they have no results.
+    pub fn rating(&self, player: &PlayerId) -> f64 {
+        *self.ratings.get(player).unwrap_or(&self.config.initial)
+    }
+
+    /// Expected score (between 0 and 1) of a side with rating `a` against a side with rating `b`.
+    pub fn expected_score(&self, a: f64, b: f64) -> f64 {
+        1.0 / (1.0 + 10_f64.powf((b - a) / self.config.scale))
+    }
+
+    /// Mean rating of the entrant's players.
+    fn team_rating(&self, players: &[PlayerId]) -> f64 {
+        players.iter().map(|p| self.rating(p)).sum::<f64>() / players.len() as f64
+    }
+
+    /// Weight of a game's rating change, according to its margin of victory if it has a score.
+    pub fn weight(&self, m: &Match) -> f64 {
+        let cap = self.config.margin_cap.max(1);
+        let margin = m.margin().unwrap_or(0).min(cap);
+        1.0 + self.config.margin * margin as f64 / cap as f64
+    }
+
+    /// Rate the tournament's recorded games, each team's summed (weighted) rating change
+    /// being normalised by its number of games.
+    fn add_matches(&mut self, matches: &[Match]) {
+        let mut deltas: Vec<(&Entrant, f64, usize)> = Vec::default();
+        let mut add = |entrant, diff| match deltas.iter_mut().find(|(e, _, _)| *e == entrant) {
+            Some((_, delta, games)) => {
+                *delta += diff;
+                *games += 1;
+            }
+            None => deltas.push((entrant, diff, 1)),
+        };
+        for m in matches {
+            let expected = self.expected_score(
+                self.team_rating(m.winner.players()),
+                self.team_rating(m.loser.players()),
+            );
+            let diff = self.weight(m) * (1.0 - expected);
+            add(&m.winner, diff);
+            add(&m.loser, -diff);
+        }
+        for (entrant, delta, games) in deltas {
+            let change = self.config.k * delta / games as f64;
+            for p in entrant.players() {
+                let rating = self.rating(p) + change;
+                self.ratings.insert(*p, rating);
+            }
+        }
+    }
+}
+
+impl RatingSystem for Elo {
+    /// Each team's rating is the mean of its players'.
+    /// Every team plays a virtual game against every other team,
+    /// winning if it finished higher and drawing if it tied;
+    /// the summed rating change is normalised by the number of opponents.
+    ///
+    /// If the tournament's games were recorded (see [Tournament::matches]),
+    /// teams play those games instead,
+    /// with games won by larger margins weighted more heavily if `margin` is set.
+    fn add_tournament(
+        &mut self,
+        tournament: &Tournament,
+        _ranks: &PlayerMap<u64>,
+    ) -> Result<(), PointsError> {
+        if !tournament.matches().is_empty() {
+            self.add_matches(tournament.matches());
+            return Ok(());
+        }
+        let results = tournament.results();
+        if results.len() < 2 {
+            return Ok(());
+        }
+        let team_ratings: Vec<_> = results
+            .iter()
+            .map(|(_, entrant)| self.team_rating(entrant.players()))
+            .collect();
+        let mut deltas = vec![0.0; results.len()];
+        for (i, (place_i, _)) in results.iter().enumerate() {
+            for (j, (place_j, _)) in results.iter().enumerate().skip(i + 1) {
+                let actual = match place_i.cmp(place_j) {
+                    std::cmp::Ordering::Less => 1.0,
+                    std::cmp::Ordering::Equal => 0.5,
+                    std::cmp::Ordering::Greater => 0.0,
+                };
+                let diff = actual - self.expected_score(team_ratings[i], team_ratings[j]);
+                deltas[i] += diff;
+                deltas[j] -= diff;
+            }
+        }
+        let n_opponents = (results.len() - 1) as f64;
+        for ((_, entrant), delta) in results.iter().zip(deltas) {
+            let change = self.config.k * delta / n_opponents;
+            for p in entrant.players() {
+                let rating = self.rating(p) + change;
+                self.ratings.insert(*p, rating);
+            }
+        }
+        Ok(())
+    }
+
+    fn ratings(&self) -> PlayerMap<NotNan<f64>> {
+        collect_ratings(self.ratings.iter().map(|(pid, rat)| (*pid, *rat)))
+    }
+
+    /// The expected score of the first team's mean rating against the second's.
+    fn win_probability(&self, first: &[PlayerId], second: &[PlayerId]) -> f64 {
+        self.expected_score(self.team_rating(first), self.team_rating(second))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::*;
+    use crate::{Level, Team};
+
+    #[test]
+    fn winners_gain_losers_lose() {
+        let dt = Utc.with_ymd_and_hms(2022, 6, 1, 0, 0, 0).unwrap();
+        let t = Tournament::new(
+            vec![
+                (1, Team::new(1, 2).unwrap().into()),
+                (2, Team::new(3, 4).unwrap().into()),
+                (3, Team::new(5, 6).unwrap().into()),
+            ],
+            dt,
+            Level::SMALL,
+        )
+        .unwrap();
+        let mut elo = Elo::default();
+        elo.add_tournament(&t, &PlayerMap::default()).unwrap();
+        assert!(elo.rating(&1) > INITIAL_RATING);
+        assert_eq!(elo.rating(&3), INITIAL_RATING);
+        assert!(elo.rating(&6) < INITIAL_RATING);
+        let total: f64 = elo.ratings.values().sum();
+        assert!((total - 6.0 * INITIAL_RATING).abs() < 1e-9);
+    }
+
+    #[test]
+    fn rates_recorded_games() {
+        let dt = Utc.with_ymd_and_hms(2022, 6, 1, 0, 0, 0).unwrap();
+        let game = |round: &str, winner, loser| Match {
+            round: round.to_owned(),
+            winner: Entrant::Single(winner),
+            loser: Entrant::Single(loser),
+            score: None,
+        };
+        // 3 beats 1 in the pool, but 1 finishes higher
+        let matches = vec![
+            game("pool", 3, 1),
+            game("pool", 1, 2),
+            game("pool", 3, 2),
+            game("final", 1, 3),
+        ];
+        let t = Tournament::from_matches(matches, dt, Level::SMALL).unwrap();
+        assert_eq!(t.results()[0], (1, Entrant::Single(1)));
+        let mut elo = Elo::default();
+        elo.add_tournament(&t, &PlayerMap::default()).unwrap();
+        // 1 and 3 each won 1 of their 2 games against each other, and both beat 2
+        assert!((elo.rating(&1) - elo.rating(&3)).abs() < 1e-9);
+        assert!(elo.rating(&1) > INITIAL_RATING);
+        assert!(elo.rating(&2) < INITIAL_RATING);
+    }
+
+    #[test]
+    fn margin_of_victory() {
+        let dt = Utc.with_ymd_and_hms(2022, 6, 1, 0, 0, 0).unwrap();
+        let t = |score| {
+            let m = Match {
+                round: "final".to_owned(),
+                winner: Entrant::Single(1),
+                loser: Entrant::Single(2),
+                score: Some(score),
+            };
+            Tournament::from_matches(vec![m], dt, Level::SMALL).unwrap()
+        };
+        let gain = |config: EloConfig, score| {
+            let mut elo = Elo::new(config);
+            elo.add_tournament(&t(score), &PlayerMap::default())
+                .unwrap();
+            elo.rating(&1) - INITIAL_RATING
+        };
+        let margins = EloConfig {
+            margin: 1.0,
+            ..Default::default()
+        };
+        let close = |a: f64, b: f64| (a - b).abs() < 1e-9;
+        assert!(close(gain(EloConfig::default(), (21, 0)), K_FACTOR / 2.0));
+        assert!(close(gain(margins.clone(), (21, 19)), K_FACTOR / 2.0 * 1.2));
+        // blowouts are capped at double weight
+        assert!(close(gain(margins.clone(), (21, 11)), K_FACTOR));
+        assert!(close(gain(margins, (21, 0)), K_FACTOR));
+    }
+
+    #[test]
+    fn zero_scale_no_panic() {
+        let dt = Utc.with_ymd_and_hms(2022, 6, 1, 0, 0, 0).unwrap();
+        let results = vec![(1, Entrant::Single(1)), (2, Entrant::Single(2))];
+        let t = Tournament::new(results, dt, Level::SMALL).unwrap();
+        let mut elo = Elo::new(EloConfig {
+            scale: 0.0,
+            ..Default::default()
+        });
+        elo.add_tournament(&t, &PlayerMap::default()).unwrap();
+        // expected scores are NaN, so neither player has a rating
+        assert!(elo.ratings().is_empty());
+    }
+}
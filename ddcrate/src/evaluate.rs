@@ -0,0 +1,404 @@
+//! Backtesting a rating system by replaying history.
+//!
+//! Tournaments are replayed in date order,
+//! and before each one, its finishing order is predicted from players' ratings at the time:
+//! each entrant's predicted strength is its expected number of wins against the other entrants,
+//! by [RatingSystem::win_probability].
+//! Predictions are scored by the Spearman rank correlation between predicted and actual finishing order,
+//! and by the Brier score of the win probability of every pair of entrants which did not tie.
+//!
+//! The points system's parameters can be tuned by evaluating every combination of candidate values;
+//! see [tune].
+use std::cmp::{Ordering, Reverse};
+use std::fmt::Display;
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use ordered_float::NotNan;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use thiserror::Error;
+
+use crate::{
+    Config, InvalidConfigValue, Level, PointsSystem, RankingEngine, RankingError, RatingSystem,
+    Tournament,
+};
+
+/// How well a tournament's finishing order was predicted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EventScore {
+    pub datetime: DateTime<Utc>,
+    pub level: Level,
+    pub name: Option<String>,
+    pub entrants: usize,
+    /// Spearman correlation between predicted and actual finishing order,
+    /// or `None` if either has every entrant tied.
+    pub spearman: Option<f64>,
+    /// Number of pairs of entrants which did not tie.
+    pub pairs: usize,
+    /// Sum over those pairs of the squared error of the predicted probability of the higher finisher winning.
+    pub squared_error: f64,
+}
+
+impl EventScore {
+    /// Mean squared error of the pairwise win probabilities, or `None` if there were no pairs.
+    pub fn brier(&self) -> Option<f64> {
+        (self.pairs > 0).then(|| self.squared_error / self.pairs as f64)
+    }
+}
+
+/// Scores of every tournament's predictions.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Evaluation {
+    /// Scores in date order.
+    pub events: Vec<EventScore>,
+}
+
+impl Evaluation {
+    /// Mean Spearman correlation of the events which have one.
+    pub fn spearman(&self) -> Option<f64> {
+        let values: Vec<_> = self.events.iter().filter_map(|e| e.spearman).collect();
+        (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+    }
+
+    /// Total number of pairs of entrants which did not tie.
+    pub fn pairs(&self) -> usize {
+        self.events.iter().map(|e| e.pairs).sum()
+    }
+
+    /// Brier score of every pair in every event, or `None` if there were no pairs.
+    pub fn brier(&self) -> Option<f64> {
+        let pairs = self.pairs();
+        (pairs > 0).then(|| self.events.iter().map(|e| e.squared_error).sum::<f64>() / pairs as f64)
+    }
+
+    /// Only the scores of tournaments from the given date/time,
+    /// e.g. so that players have ratings by the time scoring starts.
+    pub fn scored_from(mut self, datetime: &DateTime<Utc>) -> Self {
+        self.events.retain(|e| e.datetime >= *datetime);
+        self
+    }
+}
+
+/// Difference within which predicted strengths are tied,
+/// so that rounding error does not order evenly matched entrants.
+const TOLERANCE: f64 = 1e-9;
+
+/// Ranks of the values from 1, lowest first,
+/// with tied values (within [TOLERANCE]) sharing the mean of their ranks.
+fn fractional_ranks(values: &[f64]) -> Vec<f64> {
+    let mut order: Vec<_> = (0..values.len()).collect();
+    order.sort_by(|a, b| values[*a].total_cmp(&values[*b]));
+    let mut out = vec![0.0; values.len()];
+    let mut start = 0;
+    while start < order.len() {
+        let mut end = start + 1;
+        while end < order.len() && values[order[end]] - values[order[start]] <= TOLERANCE {
+            end += 1;
+        }
+        // mean of ranks start + 1 to end
+        let rank = (start + end + 1) as f64 / 2.0;
+        for idx in order[start..end].iter() {
+            out[*idx] = rank;
+        }
+        start = end;
+    }
+    out
+}
+
+/// Pearson correlation, or `None` if either sample has no variance.
+fn pearson(x: &[f64], y: &[f64]) -> Option<f64> {
+    let n = x.len() as f64;
+    let mean_x = x.iter().sum::<f64>() / n;
+    let mean_y = y.iter().sum::<f64>() / n;
+    let mut cov = 0.0;
+    let mut var_x = 0.0;
+    let mut var_y = 0.0;
+    for (a, b) in x.iter().zip(y.iter()) {
+        cov += (a - mean_x) * (b - mean_y);
+        var_x += (a - mean_x).powi(2);
+        var_y += (b - mean_y).powi(2);
+    }
+    (var_x > 0.0 && var_y > 0.0).then(|| cov / (var_x * var_y).sqrt())
+}
+
+/// Score the prediction of a tournament's finishing order by the system in its current state.
+pub fn score_event<R: RatingSystem>(system: &R, tournament: &Tournament) -> EventScore {
+    let results = tournament.results();
+    let mut strengths = vec![0.0; results.len()];
+    let mut pairs = 0;
+    let mut squared_error = 0.0;
+    for (i, (place_i, first)) in results.iter().enumerate() {
+        for (j, (place_j, second)) in results.iter().enumerate().skip(i + 1) {
+            let p = system.win_probability(first.players(), second.players());
+            strengths[i] += p;
+            strengths[j] += 1.0 - p;
+            let outcome = match place_i.cmp(place_j) {
+                Ordering::Less => 1.0,
+                Ordering::Greater => 0.0,
+                Ordering::Equal => continue,
+            };
+            pairs += 1;
+            squared_error += (p - outcome).powi(2);
+        }
+    }
+    let spearman = if results.len() < 2 {
+        None
+    } else {
+        let predicted: Vec<_> = strengths.iter().map(|s| -s).collect();
+        let actual: Vec<_> = results.iter().map(|(place, _)| *place as f64).collect();
+        pearson(&fractional_ranks(&predicted), &fractional_ranks(&actual))
+    };
+    EventScore {
+        datetime: *tournament.datetime(),
+        level: tournament.level().clone(),
+        name: tournament.metadata().name.clone(),
+        entrants: results.len(),
+        spearman,
+        pairs,
+        squared_error,
+    }
+}
+
+/// Feed tournaments into a rating system,
+/// scoring the prediction of each one's finishing order from the ratings before it.
+///
+/// Tournaments are processed in date order, regardless of their order in the slice;
+/// tournaments at the same date/time are all predicted before any of them are added.
+/// Returns an error if a tournament cannot be rated.
+pub fn evaluate<R: RatingSystem + ?Sized>(
+    system: &mut R,
+    tournaments: &[Tournament],
+) -> Result<Evaluation, RankingError> {
+    let mut sorted: Vec<_> = tournaments.iter().collect();
+    sorted.sort_by_key(|t| t.datetime());
+    let mut engine = RankingEngine::new(system);
+    let mut events = Vec::with_capacity(sorted.len());
+    for group in sorted.chunk_by(|a, b| a.datetime() == b.datetime()) {
+        engine.advance_to(group[0].datetime());
+        events.extend(group.iter().map(|t| score_event(engine.system(), t)));
+        engine.add_tournaments(group)?;
+    }
+    Ok(Evaluation { events })
+}
+
+/// Measure of predictive accuracy to optimise when tuning.
+#[derive(Debug, Default, Clone, Copy, Hash, Eq, PartialEq)]
+pub enum Metric {
+    /// Lowest Brier score of pairwise win probabilities.
+    #[default]
+    Brier,
+    /// Highest mean Spearman correlation between predicted and actual finishing order.
+    Spearman,
+}
+
+impl Metric {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Metric::Brier => "brier",
+            Metric::Spearman => "spearman",
+        }
+    }
+
+    /// The evaluation's score by this metric, where higher is better.
+    pub fn score(&self, evaluation: &Evaluation) -> Option<f64> {
+        match self {
+            Metric::Brier => evaluation.brier().map(|b| -b),
+            Metric::Spearman => evaluation.spearman(),
+        }
+    }
+}
+
+impl Display for Metric {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("Unknown metric: {0}")]
+pub struct UnknownMetric(String);
+
+impl FromStr for Metric {
+    type Err = UnknownMetric;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "brier" => Ok(Metric::Brier),
+            "spearman" => Ok(Metric::Spearman),
+            _ => Err(UnknownMetric(s.to_owned())),
+        }
+    }
+}
+
+/// Candidate values of the points system's parameters, every combination of which is tried by [tune].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Grid {
+    pub finish_decay: Vec<f64>,
+    pub age_decay: Vec<f64>,
+    pub record_length: Vec<usize>,
+}
+
+impl Default for Grid {
+    fn default() -> Self {
+        Self {
+            finish_decay: vec![1.05, 1.1, 1.15, 1.2, 1.3],
+            age_decay: vec![1.0, 1.1, 1.25, 1.5, 2.0],
+            record_length: vec![5, 8, 10, 15, 20],
+        }
+    }
+}
+
+impl Grid {
+    /// A config for every combination of candidate values, otherwise as `base`.
+    /// Any age half-life is removed, so that the age decay is used.
+    pub fn configs(&self, base: &Config) -> Vec<Config> {
+        let mut out = Vec::with_capacity(
+            self.finish_decay.len() * self.age_decay.len() * self.record_length.len(),
+        );
+        for finish_decay in self.finish_decay.iter() {
+            for age_decay in self.age_decay.iter() {
+                for record_length in self.record_length.iter() {
+                    let config = base
+                        .clone()
+                        .finish_decay(*finish_decay)
+                        .age_decay(*age_decay)
+                        .age_half_life_months(None)
+                        .record_length(*record_length);
+                    out.push(config);
+                }
+            }
+        }
+        out
+    }
+}
+
+/// Error while tuning, as by [tune].
+#[derive(Debug, Error)]
+pub enum TuneError {
+    #[error(transparent)]
+    Invalid(#[from] InvalidConfigValue),
+    #[error(transparent)]
+    Ranking(#[from] RankingError),
+}
+
+/// A config tried by [tune], and how well it predicted results.
+#[derive(Debug, Clone)]
+pub struct Trial {
+    pub config: Config,
+    pub evaluation: Evaluation,
+    /// Score by the metric being optimised, where higher is better.
+    pub score: Option<f64>,
+}
+
+/// Evaluate the points system with every combination of parameters in the grid,
+/// returning the trials best first by the metric;
+/// trials without a score (e.g. because there were no pairs to score) come last.
+///
+/// Only tournaments from `score_from`, if given, are scored.
+/// Every config is validated before any are evaluated.
+/// With the `rayon` feature, configs are evaluated in parallel.
+/// Returns an error if a tournament cannot be rated with any of the configs.
+pub fn tune(
+    base: &Config,
+    season: i32,
+    grid: &Grid,
+    metric: Metric,
+    tournaments: &[Tournament],
+    score_from: Option<&DateTime<Utc>>,
+) -> Result<Vec<Trial>, TuneError> {
+    let configs = grid.configs(base);
+    for c in configs.iter() {
+        c.validate()?;
+    }
+    #[cfg(feature = "rayon")]
+    let configs = configs.into_par_iter();
+    #[cfg(not(feature = "rayon"))]
+    let configs = configs.into_iter();
+    let mut trials: Vec<_> = configs
+        .map(|config| {
+            let mut system = PointsSystem::new(config.clone(), season);
+            let mut evaluation = evaluate(&mut system, tournaments)?;
+            if let Some(dt) = score_from {
+                evaluation = evaluation.scored_from(dt);
+            }
+            let score = metric.score(&evaluation);
+            Ok(Trial {
+                config,
+                evaluation,
+                score,
+            })
+        })
+        .collect::<Result<_, RankingError>>()?;
+    trials.sort_by_key(|t| Reverse(t.score.and_then(|s| NotNan::new(s).ok())));
+    Ok(trials)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::elo::Elo;
+    use crate::Entrant;
+
+    #[test]
+    fn ranks_with_ties() {
+        assert_eq!(
+            fractional_ranks(&[3.0, 1.0, 3.0, 2.0]),
+            vec![3.5, 1.0, 3.5, 2.0]
+        );
+    }
+
+    #[test]
+    fn repeated_results_predicted() {
+        let results = || (1..=4).map(|p| (p, Entrant::Single(p))).collect::<Vec<_>>();
+        let ts: Vec<_> = (1..=3)
+            .map(|d| {
+                let dt = Utc.with_ymd_and_hms(2022, 6, d, 0, 0, 0).unwrap();
+                Tournament::new(results(), dt, Level::SMALL).unwrap()
+            })
+            .collect();
+        let eval = evaluate(&mut Elo::default(), &ts).unwrap();
+        assert_eq!(eval.events.len(), 3);
+        // nothing is known before the first tournament
+        assert_eq!(eval.events[0].spearman, None);
+        assert_eq!(eval.events[0].brier(), Some(0.25));
+        assert_eq!(eval.events[2].spearman, Some(1.0));
+        assert!(eval.events[2].brier().unwrap() < eval.events[1].brier().unwrap());
+        assert_eq!(eval.pairs(), 18);
+        assert_eq!(eval.spearman(), Some(1.0));
+    }
+
+    #[test]
+    fn tune_grid() {
+        let ts: Vec<_> = (1..=6)
+            .map(|d| {
+                let dt = Utc.with_ymd_and_hms(2022, 6, d, 0, 0, 0).unwrap();
+                let mut results: Vec<_> = (1..=6).map(|p| (p, Entrant::Single(p))).collect();
+                // player 1 always wins; the others finish in a different order each time
+                results[1..].rotate_left(d as usize % 5);
+                for (idx, r) in results.iter_mut().enumerate() {
+                    r.0 = idx as u64 + 1;
+                }
+                Tournament::new(results, dt, Level::SMALL).unwrap()
+            })
+            .collect();
+        let grid = Grid {
+            finish_decay: vec![1.1, 1.5],
+            age_decay: vec![1.1],
+            record_length: vec![1, 3],
+        };
+        let base = Config::default();
+        let trials = tune(&base, 2022, &grid, Metric::Brier, &ts, None).unwrap();
+        assert_eq!(trials.len(), 4);
+        assert!(trials.windows(2).all(|w| w[0].score >= w[1].score));
+        assert!(trials[0].score.is_some());
+
+        let bad = Grid {
+            finish_decay: vec![0.5],
+            ..grid
+        };
+        assert!(tune(&base, 2022, &bad, Metric::Brier, &ts, None).is_err());
+    }
+}
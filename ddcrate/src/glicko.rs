@@ -0,0 +1,356 @@
+//! Glicko-2 rating, giving each player a rating deviation (RD) as well as a rating.
+//!
+//! Each tournament is treated as a rating period for the players who took part,
+//! consisting of a game against every other team.
+//! Players' RDs grow with the time since they last played.
+use std::f64::consts::PI;
+
+use chrono::{DateTime, Utc};
+use ordered_float::NotNan;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{collect_ratings, PlayerId, PlayerMap, PointsError, RatingSystem, Tournament};
+
+/// Conversion factor between the Glicko and Glicko-2 scales.
+const SCALE: f64 = 173.7178;
+
+/// Convergence tolerance for the volatility update.
+const EPSILON: f64 = 0.000001;
+
+/// Maximum number of iterations of each loop of the volatility update.
+const MAX_ITERATIONS: usize = 1000;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct GlickoConfig {
+    /// Rating of a player with no results.
+    pub initial_rating: f64,
+    /// Rating deviation of a player with no results; RDs never grow beyond this.
+    pub initial_rd: f64,
+    /// Volatility of a player with no results.
+    pub initial_volatility: f64,
+    /// Constraint on the change in volatility over time;
+    /// if not positive, volatility never changes.
+    pub tau: f64,
+    /// Number of days of inactivity which count as one rating period for RD growth.
+    pub period_days: f64,
+}
+
+impl Default for GlickoConfig {
+    fn default() -> Self {
+        Self {
+            initial_rating: 1500.0,
+            initial_rd: 350.0,
+            initial_volatility: 0.06,
+            tau: 0.5,
+            period_days: 30.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GlickoRecord {
+    pub rating: f64,
+    /// Rating deviation as of the player's last tournament.
+    pub rd: f64,
+    pub volatility: f64,
+    /// Date/time of the player's last tournament.
+    pub last_played: DateTime<Utc>,
+}
+
+impl GlickoRecord {
+    fn new(config: &GlickoConfig, datetime: DateTime<Utc>) -> Self {
+        Self {
+            rating: config.initial_rating,
+            rd: config.initial_rd,
+            volatility: config.initial_volatility,
+            last_played: datetime,
+        }
+    }
+
+    /// Rating deviation at the given date/time, having grown with inactivity since the last tournament.
+    pub fn rd_at(&self, datetime: &DateTime<Utc>, config: &GlickoConfig) -> f64 {
+        let days = (*datetime - self.last_played).num_seconds() as f64 / 86400.0;
+        if days <= 0.0 {
+            return self.rd;
+        }
+        let phi = self.rd / SCALE;
+        let periods = days / config.period_days;
+        let grown = (phi.powi(2) + periods * self.volatility.powi(2)).sqrt() * SCALE;
+        grown.min(config.initial_rd)
+    }
+}
+
+fn g(phi: f64) -> f64 {
+    1.0 / (1.0 + 3.0 * phi.powi(2) / PI.powi(2)).sqrt()
+}
+
+fn expected(mu: f64, mu_opp: f64, phi_opp: f64) -> f64 {
+    1.0 / (1.0 + (-g(phi_opp) * (mu - mu_opp)).exp())
+}
+
+/// New volatility, by the Illinois algorithm from the Glicko-2 paper.
+///
+/// A non-positive (or NaN) `tau` leaves the volatility unchanged, as it does in the limit.
+/// Each loop stops after [MAX_ITERATIONS], in case of pathological inputs.
+fn new_volatility(phi: f64, sigma: f64, v: f64, delta: f64, tau: f64) -> f64 {
+    if tau <= 0.0 || tau.is_nan() {
+        return sigma;
+    }
+    let a = sigma.powi(2).ln();
+    let f = |x: f64| {
+        let ex = x.exp();
+        ex * (delta.powi(2) - phi.powi(2) - v - ex) / (2.0 * (phi.powi(2) + v + ex).powi(2))
+            - (x - a) / tau.powi(2)
+    };
+    let mut big_a = a;
+    let mut big_b = if delta.powi(2) > phi.powi(2) + v {
+        (delta.powi(2) - phi.powi(2) - v).ln()
+    } else {
+        let mut k = 1.0;
+        while f(a - k * tau) < 0.0 && k < MAX_ITERATIONS as f64 {
+            k += 1.0;
+        }
+        a - k * tau
+    };
+    let mut f_a = f(big_a);
+    let mut f_b = f(big_b);
+    for _ in 0..MAX_ITERATIONS {
+        if (big_b - big_a).abs() <= EPSILON {
+            break;
+        }
+        let big_c = big_a + (big_a - big_b) * f_a / (f_b - f_a);
+        let f_c = f(big_c);
+        if f_c * f_b <= 0.0 {
+            big_a = big_b;
+            f_a = f_b;
+        } else {
+            f_a /= 2.0;
+        }
+        big_b = big_c;
+        f_b = f_c;
+    }
+    (big_a / 2.0).exp()
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Glicko {
+    config: GlickoConfig,
+    records: PlayerMap<GlickoRecord>,
+    /// Latest date/time the system has been advanced to.
+    now: Option<DateTime<Utc>>,
+}
+
+impl Glicko {
+    pub fn new(config: GlickoConfig) -> Self {
+        Self {
+            config,
+            records: Default::default(),
+            now: None,
+        }
+    }
+
+    pub fn config(&self) -> &GlickoConfig {
+        &self.config
+    }
+
+    pub fn records(&self) -> &PlayerMap<GlickoRecord> {
+        &self.records
+    }
+
+    pub fn into_records(self) -> PlayerMap<GlickoRecord> {
+        self.records
+    }
+
+    /// Player's record at the given date/time, with inactivity applied to their RD.
+    fn record_at(&self, player: &PlayerId, datetime: &DateTime<Utc>) -> GlickoRecord {
+        match self.records.get(player) {
+            Some(r) => GlickoRecord {
+                rd: r.rd_at(datetime, &self.config),
+                ..*r
+            },
+            None => GlickoRecord::new(&self.config, *datetime),
+        }
+    }
+
+    /// Mean rating and root mean square RD of the players on the Glicko-2 scale,
+    /// with inactivity applied to their RDs up to the given date/time.
+    fn team_at(&self, players: &[PlayerId], datetime: &DateTime<Utc>) -> (f64, f64) {
+        let n = players.len() as f64;
+        let recs: Vec<_> = players
+            .iter()
+            .map(|p| self.record_at(p, datetime))
+            .collect();
+        let mu = recs
+            .iter()
+            .map(|r| (r.rating - self.config.initial_rating) / SCALE)
+            .sum::<f64>()
+            / n;
+        let phi = (recs.iter().map(|r| (r.rd / SCALE).powi(2)).sum::<f64>() / n).sqrt();
+        (mu, phi)
+    }
+}
+
+impl RatingSystem for Glicko {
+    /// A team's rating is the mean of its players' ratings,
+    /// and its RD is the root mean square of its players' RDs.
+    /// Each player is updated using their team's rating against each other team,
+    /// but with their own RD and volatility.
+    fn add_tournament(
+        &mut self,
+        tournament: &Tournament,
+        _ranks: &PlayerMap<u64>,
+    ) -> Result<(), PointsError> {
+        let results = tournament.results();
+        if results.len() < 2 {
+            return Ok(());
+        }
+        let dt = tournament.datetime();
+        let team_records: Vec<_> = results
+            .iter()
+            .map(|(_, entrant)| {
+                entrant
+                    .players()
+                    .iter()
+                    .map(|p| self.record_at(p, dt))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        // (mu, phi) of each team on the Glicko-2 scale
+        let teams: Vec<_> = results
+            .iter()
+            .map(|(_, entrant)| self.team_at(entrant.players(), dt))
+            .collect();
+
+        for (i, ((place_i, _), recs)) in results.iter().zip(team_records.iter()).enumerate() {
+            let (mu_team, _) = teams[i];
+            let mut v_inv = 0.0;
+            let mut score_sum = 0.0;
+            for (j, (place_j, _)) in results.iter().enumerate() {
+                if i == j {
+                    continue;
+                }
+                let (mu_opp, phi_opp) = teams[j];
+                let s = match place_i.cmp(place_j) {
+                    std::cmp::Ordering::Less => 1.0,
+                    std::cmp::Ordering::Equal => 0.5,
+                    std::cmp::Ordering::Greater => 0.0,
+                };
+                let e = expected(mu_team, mu_opp, phi_opp);
+                let g_opp = g(phi_opp);
+                v_inv += g_opp.powi(2) * e * (1.0 - e);
+                score_sum += g_opp * (s - e);
+            }
+            let v = 1.0 / v_inv;
+            let delta = v * score_sum;
+
+            for (player, rec) in results[i].1.players().iter().zip(recs.iter()) {
+                let mu = (rec.rating - self.config.initial_rating) / SCALE;
+                let phi = rec.rd / SCALE;
+                let sigma = new_volatility(phi, rec.volatility, v, delta, self.config.tau);
+                let phi_star = (phi.powi(2) + sigma.powi(2)).sqrt();
+                let phi_new = 1.0 / (1.0 / phi_star.powi(2) + 1.0 / v).sqrt();
+                let mu_new = mu + phi_new.powi(2) * score_sum;
+                self.records.insert(
+                    *player,
+                    GlickoRecord {
+                        rating: mu_new * SCALE + self.config.initial_rating,
+                        rd: (phi_new * SCALE).min(self.config.initial_rd),
+                        volatility: sigma,
+                        last_played: *dt,
+                    },
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn ratings(&self) -> PlayerMap<NotNan<f64>> {
+        collect_ratings(self.records.iter().map(|(pid, rec)| (*pid, rec.rating)))
+    }
+
+    fn advance_to(&mut self, datetime: &DateTime<Utc>) {
+        if self.now.is_none_or(|now| now < *datetime) {
+            self.now = Some(*datetime);
+        }
+    }
+
+    /// The expected score of the first team against the second,
+    /// discounted by both teams' RDs as of the latest date/time the system was advanced to.
+    fn win_probability(&self, first: &[PlayerId], second: &[PlayerId]) -> f64 {
+        let now = self.now.unwrap_or_else(|| {
+            self.records
+                .values()
+                .map(|r| r.last_played)
+                .max()
+                .unwrap_or_default()
+        });
+        let (mu_a, phi_a) = self.team_at(first, &now);
+        let (mu_b, phi_b) = self.team_at(second, &now);
+        expected(mu_a, mu_b, (phi_a.powi(2) + phi_b.powi(2)).sqrt())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::{Entrant, Level, Team};
+
+    #[test]
+    fn rd_shrinks_with_play_and_grows_without() {
+        let dt = Utc.with_ymd_and_hms(2022, 6, 1, 0, 0, 0).unwrap();
+        let t = Tournament::new(
+            vec![
+                (1, Team::new(1, 2).unwrap().into()),
+                (2, Team::new(3, 4).unwrap().into()),
+            ],
+            dt,
+            Level::SMALL,
+        )
+        .unwrap();
+        let mut glicko = Glicko::default();
+        glicko.add_tournament(&t, &PlayerMap::default()).unwrap();
+        let winner = glicko.records()[&1];
+        let loser = glicko.records()[&3];
+        assert!(winner.rating > 1500.0);
+        assert!(loser.rating < 1500.0);
+        assert!(winner.rd < 350.0);
+
+        let later = Utc.with_ymd_and_hms(2023, 6, 1, 0, 0, 0).unwrap();
+        let rd_later = winner.rd_at(&later, glicko.config());
+        assert!(rd_later > winner.rd);
+        assert!(rd_later <= 350.0);
+    }
+
+    #[test]
+    fn predictions_less_certain_with_inactivity() {
+        let dt = Utc.with_ymd_and_hms(2022, 6, 1, 0, 0, 0).unwrap();
+        let t = Tournament::new(
+            vec![(1, Entrant::Single(1)), (2, Entrant::Single(2))],
+            dt,
+            Level::SMALL,
+        )
+        .unwrap();
+        let mut glicko = Glicko::default();
+        glicko.add_tournament(&t, &PlayerMap::default()).unwrap();
+        let p = glicko.win_probability(&[1], &[2]);
+        assert!(p > 0.5);
+        assert!((p + glicko.win_probability(&[2], &[1]) - 1.0).abs() < 1e-9);
+
+        glicko.advance_to(&Utc.with_ymd_and_hms(2024, 6, 1, 0, 0, 0).unwrap());
+        let later = glicko.win_probability(&[1], &[2]);
+        assert!(later > 0.5 && later < p);
+    }
+
+    #[test]
+    fn non_positive_tau() {
+        for tau in [0.0, -0.5, f64::NAN] {
+            assert_eq!(new_volatility(1.0, 0.06, 1.5, 0.1, tau), 0.06);
+        }
+        let changed = new_volatility(1.0, 0.06, 1.5, 0.1, 0.5);
+        assert!(changed.is_finite() && changed != 0.06);
+    }
+}
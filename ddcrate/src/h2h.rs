@@ -0,0 +1,214 @@
+//! Head-to-head records between two players or teams.
+//!
+//! A side is a set of players, which took part in a tournament
+//! if one entrant contained all of them.
+//! Tournaments in which both sides were in the same entrant are not meetings.
+//! Where a tournament's games were recorded, the games played directly between the sides
+//! are also counted.
+use std::cmp::{Ordering, Reverse};
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+
+use crate::{Entrant, Level, PlayerId, Tournament, TournamentMetadata};
+
+/// A tournament in which both sides took part.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Meeting {
+    pub datetime: DateTime<Utc>,
+    pub level: Level,
+    pub tournament: TournamentMetadata,
+    /// Finishing place of the first side.
+    pub first_place: u64,
+    /// Finishing place of the second side.
+    pub second_place: u64,
+    /// Games the first side won against the second, if the tournament's games were recorded.
+    pub first_wins: usize,
+    /// Games the second side won against the first, if the tournament's games were recorded.
+    pub second_wins: usize,
+}
+
+/// Counts of meetings by outcome, from the first side's perspective.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Tally {
+    /// Meetings where the first side finished ahead.
+    pub ahead: usize,
+    /// Meetings where the second side finished ahead.
+    pub behind: usize,
+    /// Meetings where the sides tied.
+    pub tied: usize,
+}
+
+impl Tally {
+    pub fn meetings(&self) -> usize {
+        self.ahead + self.behind + self.tied
+    }
+
+    fn add(&mut self, meeting: &Meeting) {
+        match meeting.first_place.cmp(&meeting.second_place) {
+            Ordering::Less => self.ahead += 1,
+            Ordering::Greater => self.behind += 1,
+            Ordering::Equal => self.tied += 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HeadToHead {
+    /// Outcomes over all meetings.
+    pub total: Tally,
+    /// Outcomes at each level of tournament.
+    pub by_level: BTreeMap<Level, Tally>,
+    /// Games won by the first side against the second, over all meetings.
+    pub first_wins: usize,
+    /// Games won by the second side against the first, over all meetings.
+    pub second_wins: usize,
+    /// Every meeting, most recent first.
+    pub meetings: Vec<Meeting>,
+}
+
+fn place_of(tournament: &Tournament, side: &[PlayerId]) -> Option<(usize, u64)> {
+    tournament
+        .results()
+        .iter()
+        .enumerate()
+        .find(|(_, (_, e))| contains(e, side))
+        .map(|(idx, (place, _))| (idx, *place))
+}
+
+fn contains(entrant: &Entrant, side: &[PlayerId]) -> bool {
+    side.iter().all(|p| entrant.players().contains(p))
+}
+
+/// Number of the tournament's games which the winning side won against the losing side.
+fn wins(tournament: &Tournament, winner: &[PlayerId], loser: &[PlayerId]) -> usize {
+    tournament
+        .matches()
+        .iter()
+        .filter(|m| contains(&m.winner, winner) && contains(&m.loser, loser))
+        .count()
+}
+
+/// Compare two sides' finishes in every tournament where both took part.
+///
+/// Tournaments need not be sorted.
+pub fn head_to_head(
+    tournaments: &[Tournament],
+    first: &[PlayerId],
+    second: &[PlayerId],
+) -> HeadToHead {
+    let mut out = HeadToHead::default();
+    if first.is_empty() || second.is_empty() {
+        return out;
+    }
+    for t in tournaments {
+        let (Some((idx1, place1)), Some((idx2, place2))) =
+            (place_of(t, first), place_of(t, second))
+        else {
+            continue;
+        };
+        if idx1 == idx2 {
+            continue;
+        }
+        let meeting = Meeting {
+            datetime: *t.datetime(),
+            level: t.level().clone(),
+            tournament: t.metadata().clone(),
+            first_place: place1,
+            second_place: place2,
+            first_wins: wins(t, first, second),
+            second_wins: wins(t, second, first),
+        };
+        out.first_wins += meeting.first_wins;
+        out.second_wins += meeting.second_wins;
+        out.total.add(&meeting);
+        out.by_level
+            .entry(meeting.level.clone())
+            .or_default()
+            .add(&meeting);
+        out.meetings.push(meeting);
+    }
+    out.meetings.sort_by_key(|m| Reverse(m.datetime));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::{Match, Team};
+
+    #[test]
+    fn players_and_teams() {
+        let t = |day, results: Vec<(u64, Entrant)>, level| {
+            Tournament::new(
+                results,
+                Utc.with_ymd_and_hms(2022, 6, day, 0, 0, 0).unwrap(),
+                level,
+            )
+            .unwrap()
+        };
+        let ts = vec![
+            t(
+                1,
+                vec![
+                    (1, Team::new(1, 2).unwrap().into()),
+                    (2, Team::new(3, 4).unwrap().into()),
+                ],
+                Level::SMALL,
+            ),
+            t(
+                3,
+                vec![
+                    (1, Team::new(3, 5).unwrap().into()),
+                    (2, Team::new(1, 2).unwrap().into()),
+                ],
+                Level::MAJOR,
+            ),
+            t(
+                2,
+                vec![
+                    (1, Team::new(1, 3).unwrap().into()),
+                    (2, Team::new(2, 4).unwrap().into()),
+                ],
+                Level::SMALL,
+            ),
+        ];
+
+        let h = head_to_head(&ts, &[1], &[3]);
+        assert_eq!(h.total.meetings(), 2);
+        assert_eq!(h.total.ahead, 1);
+        assert_eq!(h.total.behind, 1);
+        assert_eq!(h.by_level[&Level::SMALL].ahead, 1);
+        assert_eq!(h.meetings[0].level, Level::MAJOR);
+
+        let h = head_to_head(&ts, &[1, 2], &[3, 4]);
+        assert_eq!(h.total.meetings(), 1);
+        assert_eq!(h.total.ahead, 1);
+        assert_eq!(h.first_wins + h.second_wins, 0);
+    }
+
+    #[test]
+    fn recorded_games() {
+        let game = |round: &str, winner, loser| Match {
+            round: round.to_owned(),
+            winner: Entrant::Single(winner),
+            loser: Entrant::Single(loser),
+            score: None,
+        };
+        let matches = vec![
+            game("pool", 2, 1),
+            game("pool", 1, 3),
+            game("semi", 1, 3),
+            game("final", 1, 2),
+        ];
+        let dt = Utc.with_ymd_and_hms(2022, 6, 1, 0, 0, 0).unwrap();
+        let ts = vec![Tournament::from_matches(matches, dt, Level::SMALL).unwrap()];
+
+        let h = head_to_head(&ts, &[1], &[2]);
+        assert_eq!(h.total.ahead, 1);
+        assert_eq!((h.first_wins, h.second_wins), (1, 1));
+        assert_eq!(head_to_head(&ts, &[3], &[1]).second_wins, 2);
+    }
+}
@@ -0,0 +1,195 @@
+//! An index of the results files under a root, with each file's date and level.
+//!
+//! With an index, a [ResultIngester](crate::ResultIngester) lists the files in it
+//! instead of walking the results directory,
+//! and skips files outside its date window without opening or even matching their names.
+//! Files added to the directory after the index is built are not read until it is rebuilt.
+//!
+//! The index is a TSV with columns `path` (relative to the root), `datetime` (RFC 3339), and `level`.
+//! JSON results files, which may contain tournaments of several dates and levels,
+//! have neither, and are always read.
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use csv::{ReaderBuilder, WriterBuilder};
+use thiserror::Error;
+
+use crate::Level;
+
+/// Name of the index file, when it is in the results directory.
+pub const DEFAULT_FILE: &str = ".ddcrate-index.tsv";
+
+#[derive(Debug, Error)]
+pub enum IndexError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Csv(#[from] csv::Error),
+    #[error("Invalid datetime in index: {0:?}")]
+    InvalidDate(String),
+}
+
+/// The date and level of a results file, if it has a single one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexEntry {
+    pub datetime: Option<DateTime<Utc>>,
+    pub level: Option<Level>,
+}
+
+impl IndexEntry {
+    /// Whether the file may contain tournaments in the window.
+    pub fn in_window(&self, from: &DateTime<Utc>, until: &DateTime<Utc>) -> bool {
+        self.datetime.is_none_or(|dt| &dt >= from && &dt <= until)
+    }
+}
+
+/// Results files by their path relative to the root.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Index {
+    entries: BTreeMap<PathBuf, IndexEntry>,
+}
+
+impl Index {
+    pub fn insert(&mut self, path: PathBuf, entry: IndexEntry) {
+        self.entries.insert(path, entry);
+    }
+
+    pub fn get(&self, path: &Path) -> Option<&IndexEntry> {
+        self.entries.get(path)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Files in the directory (relative to the root) and its subdirectories, in order.
+    pub fn under<'a>(
+        &'a self,
+        dir: &'a Path,
+    ) -> impl Iterator<Item = (&'a PathBuf, &'a IndexEntry)> + 'a {
+        self.entries
+            .range(dir.to_owned()..)
+            .take_while(move |(p, _)| p.starts_with(dir))
+    }
+
+    pub fn read<R: Read>(r: R) -> Result<Self, IndexError> {
+        let mut rdr = ReaderBuilder::new().delimiter(b'\t').from_reader(r);
+        let mut out = Self::default();
+        for record in rdr.records() {
+            let record = record?;
+            let field = |idx| record.get(idx).filter(|s| !s.is_empty());
+            let Some(path) = field(0) else { continue };
+            let datetime = match field(1) {
+                Some(s) => Some(
+                    DateTime::parse_from_rfc3339(s)
+                        .map_err(|_| IndexError::InvalidDate(s.to_owned()))?
+                        .with_timezone(&Utc),
+                ),
+                None => None,
+            };
+            out.insert(
+                PathBuf::from(path),
+                IndexEntry {
+                    datetime,
+                    level: field(2).map(Level::new),
+                },
+            );
+        }
+        Ok(out)
+    }
+
+    pub fn read_file<P: AsRef<Path>>(path: P) -> Result<Self, IndexError> {
+        Self::read(File::open(path)?)
+    }
+
+    pub fn write<W: Write>(&self, w: W) -> Result<(), IndexError> {
+        let mut wtr = WriterBuilder::new().delimiter(b'\t').from_writer(w);
+        wtr.write_record(["path", "datetime", "level"])?;
+        for (path, entry) in self.entries.iter() {
+            wtr.write_record([
+                path.to_string_lossy().as_ref(),
+                &entry.datetime.map(|dt| dt.to_rfc3339()).unwrap_or_default(),
+                entry.level.as_ref().map_or("", |l| l.name()),
+            ])?;
+        }
+        wtr.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::ResultIngester;
+
+    #[test]
+    fn roundtrip() {
+        let mut index = Index::default();
+        index.insert(
+            PathBuf::from("small/2023-07-10_open.tsv"),
+            IndexEntry {
+                datetime: Some("2023-07-10T00:00:00Z".parse().unwrap()),
+                level: Some(Level::SMALL),
+            },
+        );
+        index.insert(
+            PathBuf::from("major/all.json"),
+            IndexEntry {
+                datetime: None,
+                level: None,
+            },
+        );
+        let mut buf = Vec::default();
+        index.write(&mut buf).unwrap();
+        assert_eq!(
+            String::from_utf8(buf.clone()).unwrap(),
+            "path\tdatetime\tlevel\nmajor/all.json\t\t\nsmall/2023-07-10_open.tsv\t2023-07-10T00:00:00+00:00\tsmall\n"
+        );
+        assert_eq!(Index::read(buf.as_slice()).unwrap(), index);
+    }
+
+    #[test]
+    fn ingest_with_index() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().to_owned();
+        let dir = root.join(Level::SMALL.directory_name());
+        fs::create_dir_all(&dir).unwrap();
+        let tsv = "place\tp1\tp2\n1\t1\t2\n";
+        fs::write(dir.join("2023-07-10_open.tsv"), tsv).unwrap();
+        fs::write(dir.join("2022-07-10_open.tsv"), tsv).unwrap();
+        fs::write(dir.join("undated.tsv"), format!("# level: major\n{}", tsv)).unwrap();
+
+        let ingest = ResultIngester::new(&root);
+        let index = ingest.build_index().unwrap();
+        // files added after the index is built are not read
+        fs::write(dir.join("2023-08-01_new.tsv"), tsv).unwrap();
+        let result = ingest
+            .index(Some(index.clone()))
+            .from("2023-01-01T00:00:00Z".parse().unwrap())
+            .ingest();
+
+        assert_eq!(index.len(), 2);
+        assert_eq!(
+            index.get(Path::new("small/2023-07-10_open.tsv")),
+            Some(&IndexEntry {
+                datetime: Some("2023-07-10T00:00:00Z".parse().unwrap()),
+                level: Some(Level::SMALL),
+            })
+        );
+        let ts = result.unwrap();
+        assert_eq!(ts.len(), 1);
+        assert_eq!(
+            ts[0].datetime(),
+            &"2023-07-10T00:00:00Z".parse::<DateTime<Utc>>().unwrap()
+        );
+    }
+}
@@ -0,0 +1,336 @@
+//! Reading and writing tournaments as JSON.
+//!
+//! A `.json` file contains a single tournament object;
+//! a `.ndjson` file contains any number of them, one per line.
+//!
+//! ```json
+//! {
+//!   "date": "2023-07-10",
+//!   "level": "small",
+//!   "results": [
+//!     {"place": 1, "players": [235476, 529052]},
+//!     {"place": 2, "players": [23342, 4235211978]},
+//!     {"place": 3, "players": [5690845]},
+//!     {"code": "DQ", "players": [4326, 8744]}
+//!   ]
+//! }
+//! ```
+//!
+//! `date` is either an ISO-8601 date (interpreted as midnight) or datetime, or an RFC 3339 datetime.
+//! Dates and datetimes without an offset are in UTC, unless the optional `timezone` is given
+//! as a UTC offset (e.g. `+02:00`) or IANA name (e.g. `Europe/London`).
+//! `level` is optional, defaulting to the level of the directory containing the file.
+//! `players` contains one ID for a singles entrant, or more for a team.
+//! An entrant which was not placed has a result `code` (`DNF`, `DQ`, or `WD`) instead of a `place`.
+//! An entrant which withdrew during the tournament, but keeps its place, has `"withdrawn": true`.
+//! Entrants including the player ID 0 are byes, which are removed
+//! (and the places of the entrants below them adjusted).
+//! `name`, `location`, and `organizer` are optional descriptive fields.
+//!
+//! A tournament can also list its games, e.g.
+//! `{"round": "final", "winner": [235476, 529052], "loser": [23342, 4235211978]}`,
+//! in an optional `matches` array, with an optional `score` of the winner and loser (e.g. `[21, 15]`);
+//! if it has no `results`, finishing positions are derived from the games
+//! (see [placings_from_matches](crate::placings_from_matches)).
+use std::io::Read;
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    close_bye_gaps, placings_from_matches, Entrant, Level, Match, PlayerId, ResultCode,
+    ResultReadError, Tournament, TournamentMetadata, Zone, BYE,
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonTournament {
+    pub date: String,
+    #[serde(default)]
+    pub level: Option<Level>,
+    /// Time zone of `date`, if it has no offset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<Zone>,
+    #[serde(default)]
+    pub results: Vec<JsonResult>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub matches: Vec<JsonMatch>,
+    #[serde(flatten)]
+    pub metadata: TournamentMetadata,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonResult {
+    /// Finishing position, which is ignored if there is a `code`.
+    #[serde(default, skip_serializing_if = "is_unplaced")]
+    pub place: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<ResultCode>,
+    pub players: Vec<PlayerId>,
+    /// Whether the entrant withdrew during the tournament, keeping its place.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub withdrawn: bool,
+}
+
+/// A game between two entrants.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonMatch {
+    pub round: String,
+    pub winner: Vec<PlayerId>,
+    pub loser: Vec<PlayerId>,
+    /// Scores of the winner and loser.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score: Option<(u32, u32)>,
+}
+
+fn is_unplaced(place: &u64) -> bool {
+    *place == 0
+}
+
+/// Parse an RFC 3339 datetime, or an ISO-8601 date as midnight UTC.
+pub fn parse_date(s: &str) -> Option<DateTime<Utc>> {
+    parse_date_in(s, None)
+}
+
+/// Parse an RFC 3339 datetime,
+/// or an ISO-8601 date (as midnight) or datetime without an offset in the given zone, or UTC.
+pub fn parse_date_in(s: &str, zone: Option<&Zone>) -> Option<DateTime<Utc>> {
+    if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+        return Some(dt.with_timezone(&Utc));
+    }
+    let local = match NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S")
+        .or_else(|_| NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M"))
+    {
+        Ok(ndt) => ndt,
+        Err(_) => NaiveDate::parse_from_str(s, "%Y-%m-%d")
+            .ok()?
+            .and_hms_opt(0, 0, 0)?,
+    };
+    match zone {
+        Some(z) => z.to_utc(&local),
+        None => Some(Utc.from_utc_datetime(&local)),
+    }
+}
+
+impl JsonTournament {
+    pub fn datetime(&self) -> Result<DateTime<Utc>, ResultReadError> {
+        parse_date_in(&self.date, self.timezone.as_ref())
+            .ok_or_else(|| ResultReadError::InvalidDate(self.date.clone()))
+    }
+
+    /// Level of the tournament, or the given default if it does not specify one.
+    pub fn level_or<'a>(&'a self, default: &'a Level) -> &'a Level {
+        self.level.as_ref().unwrap_or(default)
+    }
+
+    pub fn into_tournament(self, default_level: &Level) -> Result<Tournament, ResultReadError> {
+        let dt = self.datetime()?;
+        let level = self.level.unwrap_or_else(|| default_level.clone());
+        let mut results = Vec::with_capacity(self.results.len());
+        let mut unplaced = Vec::default();
+        let mut withdrawn = Vec::default();
+        let mut byes = Vec::default();
+        for r in self.results {
+            if r.players.contains(&BYE) {
+                if r.code.is_none() {
+                    byes.push(r.place);
+                }
+                continue;
+            }
+            let entrant = Entrant::from_players(&r.players)?;
+            if r.withdrawn {
+                withdrawn.push(entrant.clone());
+            }
+            match r.code {
+                Some(code) => unplaced.push((code, entrant)),
+                None => results.push((r.place, entrant)),
+            }
+        }
+        close_bye_gaps(&mut results, byes);
+        let matches = self
+            .matches
+            .into_iter()
+            .filter(|m| !m.winner.contains(&BYE) && !m.loser.contains(&BYE))
+            .map(|m| {
+                Ok(Match {
+                    round: m.round,
+                    winner: Entrant::from_players(&m.winner)?,
+                    loser: Entrant::from_players(&m.loser)?,
+                    score: m.score,
+                })
+            })
+            .collect::<Result<Vec<_>, ResultReadError>>()?;
+        if results.is_empty() {
+            results = placings_from_matches(&matches);
+        }
+        Ok(Tournament::new(results, dt, level)?
+            .with_unplaced(unplaced)?
+            .with_withdrawn(withdrawn)
+            .with_matches(matches)
+            .with_metadata(self.metadata))
+    }
+}
+
+impl From<&Tournament> for JsonTournament {
+    fn from(t: &Tournament) -> Self {
+        Self {
+            date: t.datetime().to_rfc3339(),
+            level: Some(t.level().clone()),
+            timezone: None,
+            results: t
+                .results()
+                .iter()
+                .map(|(place, e)| JsonResult {
+                    place: *place,
+                    code: None,
+                    players: e.players().to_vec(),
+                    withdrawn: t.withdrawn().contains(e),
+                })
+                .chain(t.unplaced().iter().map(|(code, e)| JsonResult {
+                    place: 0,
+                    code: Some(*code),
+                    players: e.players().to_vec(),
+                    withdrawn: false,
+                }))
+                .collect(),
+            matches: t
+                .matches()
+                .iter()
+                .map(|m| JsonMatch {
+                    round: m.round.clone(),
+                    winner: m.winner.players().to_vec(),
+                    loser: m.loser.players().to_vec(),
+                    score: m.score,
+                })
+                .collect(),
+            metadata: t.metadata().clone(),
+        }
+    }
+}
+
+/// Read a single tournament.
+pub fn read_json<R: Read>(r: R) -> Result<JsonTournament, ResultReadError> {
+    Ok(serde_json::from_reader(r)?)
+}
+
+/// Read any number of whitespace-separated tournaments.
+pub fn read_ndjson<R: Read>(r: R) -> Result<Vec<JsonTournament>, ResultReadError> {
+    Ok(serde_json::Deserializer::from_reader(r)
+        .into_iter()
+        .collect::<Result<Vec<_>, _>>()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_ndjson_tournaments() {
+        let s = r#"
+{"date": "2023-07-10", "results": [{"place": 1, "players": [1, 2]}, {"place": 2, "players": [3, 4]}]}
+
+{"date": "2023-07-11T12:00:00+01:00", "level": "major", "name": "Worlds", "results": [{"place": 1, "players": [5, 6]}, {"place": 2, "players": [7]}]}
+"#;
+        let ts = read_ndjson(s.as_bytes()).unwrap();
+        assert_eq!(ts.len(), 2);
+        let t0 = ts[0].clone().into_tournament(&Level::SMALL).unwrap();
+        assert_eq!(t0.level(), &Level::SMALL);
+        assert_eq!(t0.results().len(), 2);
+        let t1 = ts[1].clone().into_tournament(&Level::SMALL).unwrap();
+        assert_eq!(t1.level(), &Level::MAJOR);
+        assert_eq!(t1.results()[1].1, Entrant::Single(7));
+        assert_eq!(t1.metadata().name.as_deref(), Some("Worlds"));
+        assert_eq!(
+            t1.datetime(),
+            &Utc.with_ymd_and_hms(2023, 7, 11, 11, 0, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn timezones() {
+        let utc = |h| Utc.with_ymd_and_hms(2023, 7, 10, h, 0, 0).unwrap();
+        assert_eq!(parse_date("2023-07-10"), Some(utc(0)));
+        assert_eq!(parse_date("2023-07-10T14:00"), Some(utc(14)));
+        let plus2: Zone = "+02:00".parse().unwrap();
+        assert_eq!(
+            parse_date_in("2023-07-10T14:00", Some(&plus2)),
+            Some(utc(12))
+        );
+        let london: Zone = "Europe/London".parse().unwrap();
+        assert_eq!(
+            parse_date_in("2023-07-10T14:00", Some(&london)),
+            Some(utc(13))
+        );
+        assert_eq!(
+            parse_date_in("2023-07-10T14:00:00Z", Some(&london)),
+            Some(utc(14))
+        );
+
+        let jt: JsonTournament = serde_json::from_str(
+            r#"{"date": "2023-07-10T09:00", "timezone": "-0500", "results": []}"#,
+        )
+        .unwrap();
+        assert_eq!(jt.datetime().unwrap(), utc(14));
+        assert!(serde_json::from_str::<JsonTournament>(
+            r#"{"date": "2023-07-10", "timezone": "Mars/Olympus", "results": []}"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn byes_removed() {
+        let jt: JsonTournament = serde_json::from_str(
+            r#"{"date": "2023-07-10", "results": [{"place": 1, "players": [1]}, {"place": 2, "players": [0]}, {"place": 3, "players": [2]}]}"#,
+        )
+        .unwrap();
+        let t = jt.into_tournament(&Level::SMALL).unwrap();
+        assert_eq!(
+            t.results(),
+            &[(1, Entrant::Single(1)), (2, Entrant::Single(2))]
+        );
+    }
+
+    #[test]
+    fn placings_from_games() {
+        let jt: JsonTournament = serde_json::from_str(
+            r#"{"date": "2023-07-10", "matches": [{"round": "semi", "winner": [1], "loser": [3]}, {"round": "semi", "winner": [2], "loser": [4]}, {"round": "final", "winner": [2], "loser": [1]}]}"#,
+        )
+        .unwrap();
+        let t = jt.into_tournament(&Level::SMALL).unwrap();
+        assert_eq!(t.matches().len(), 3);
+        assert_eq!(
+            t.results(),
+            &[
+                (1, Entrant::Single(2)),
+                (2, Entrant::Single(1)),
+                (3, Entrant::Single(3)),
+                (3, Entrant::Single(4))
+            ]
+        );
+        let s = serde_json::to_string(&JsonTournament::from(&t)).unwrap();
+        assert!(s.contains(r#"{"round":"final","winner":[2],"loser":[1]}"#));
+    }
+
+    #[test]
+    fn write_tournament() {
+        let t = Tournament::new(
+            vec![(1, Entrant::Single(1)), (2, Entrant::Single(2))],
+            Utc.with_ymd_and_hms(2023, 7, 10, 0, 0, 0).unwrap(),
+            Level::MAJOR,
+        )
+        .unwrap()
+        .with_unplaced(vec![(ResultCode::Dq, Entrant::Single(3))])
+        .unwrap()
+        .with_withdrawn(vec![Entrant::Single(2)]);
+        let s = serde_json::to_string(&JsonTournament::from(&t)).unwrap();
+        assert!(s.contains(r#"{"code":"DQ","players":[3]}"#));
+        let t2 = read_json(s.as_bytes())
+            .unwrap()
+            .into_tournament(&Level::SMALL)
+            .unwrap();
+        assert_eq!(t2.results(), t.results());
+        assert_eq!(t2.unplaced(), t.unplaced());
+        assert_eq!(t2.withdrawn(), t.withdrawn());
+        assert_eq!(t2.datetime(), t.datetime());
+        assert_eq!(t2.level(), t.level());
+    }
+}
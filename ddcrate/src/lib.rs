@@ -1,26 +1,83 @@
-use chrono::{Datelike, NaiveDate, TimeZone};
-use csv::ReaderBuilder;
+use chrono::{Datelike, Duration, FixedOffset, Months, NaiveDate, NaiveDateTime, TimeZone};
+use csv::{ReaderBuilder, StringRecord};
+use flate2::read::GzDecoder;
+use glob::Pattern;
 use log::debug;
 use once_cell::sync::OnceCell;
-use once_cell_regex::regex;
-use serde::Deserialize;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize, Serializer};
 use std::collections::HashSet;
-use std::io::{BufReader, Read};
+use std::fmt::Display;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::str::FromStr;
 use std::{
+    borrow::{Borrow, Cow},
     cmp::Reverse,
-    collections::{BinaryHeap, HashMap},
+    collections::{BTreeMap, BinaryHeap, HashMap},
     fs::File,
+    hash::{BuildHasher, Hash},
     io,
-    path::PathBuf,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
 };
 use thiserror::Error;
 use walkdir::WalkDir;
 
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
 pub use chrono::{DateTime, Utc};
-use ordered_float::NotNan;
+pub use ordered_float::NotNan;
+
+#[cfg(feature = "proptest")]
+pub mod arbitrary;
+#[cfg(feature = "bootstrap")]
+pub mod bootstrap;
+pub mod bracket;
+#[cfg(feature = "cloud")]
+pub mod cloud;
+pub mod elo;
+pub mod evaluate;
+pub mod glicko;
+pub mod h2h;
+pub mod index;
+pub mod json;
+pub mod parse_cache;
+#[cfg(feature = "http")]
+pub mod remote;
+#[cfg(feature = "scripting")]
+pub mod scripting;
+pub mod seeding;
+#[cfg(feature = "sheets")]
+pub mod sheets;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod stats;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod trueskill;
+#[cfg(feature = "xlsx")]
+pub mod xlsx;
+use elo::EloConfig;
+use glicko::GlickoConfig;
+use index::{Index, IndexEntry};
+use parse_cache::{ParseCache, ReadSettings};
+use trueskill::TrueSkillConfig;
 
 pub type PlayerId = u64;
 
+/// Hasher for maps and sets of players, which are built in the hottest loops of ranking.
+///
+/// FxHash is much faster than the standard library's SipHash for integer keys.
+/// It is not resistant to HashDoS attacks, but player IDs come from results files.
+pub type PlayerHasher = rustc_hash::FxBuildHasher;
+
+/// Map keyed by player ID, using [PlayerHasher].
+pub type PlayerMap<V> = HashMap<PlayerId, V, PlayerHasher>;
+
+/// Set of player IDs, using [PlayerHasher].
+pub type PlayerSet = HashSet<PlayerId, PlayerHasher>;
+
 /// The default value of a parameter controlling how the importance of finishing position decays from top to bottom.
 pub const FINISH_DECAY: f64 = 1.1;
 
@@ -30,72 +87,450 @@ pub const AGE_DECAY: f64 = 1.1;
 // The default value of the number of top results analysed for a player's rating.
 pub const RECORD_LENGTH: usize = 10;
 
-/// Pair of DDC players, sorted in ID order.
-#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
+fn default_record_length() -> usize {
+    RECORD_LENGTH
+}
+
+/// Serialize a map in key order, for stable output.
+fn serialize_sorted<S: Serializer, K: Ord + Serialize, V: Serialize>(
+    map: &HashMap<K, V>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_map(map.iter().collect::<BTreeMap<_, _>>())
+}
+
+fn default_age_decay() -> f64 {
+    AGE_DECAY
+}
+
+fn default_factor() -> f64 {
+    1.0
+}
+
+fn default_carry_over() -> f64 {
+    0.5
+}
+
+/// The default maximum points from a single result, which ordinary configs never approach.
+pub const MAX_POINTS: f64 = 1e6;
+
+fn default_max_points() -> f64 {
+    MAX_POINTS
+}
+
+/// The default number of players in each team.
+pub const TEAM_SIZE: usize = 2;
+
+/// Reserved player ID representing a bye, e.g. in a bracket with an odd number of entrants.
+///
+/// Entrants with a bye (or, in TSVs, a `BYE` player) are removed when results are read,
+/// so byes never receive points or appear in rankings.
+pub const BYE: PlayerId = 0;
+
+/// Prefix of environment variables overriding config values.
+pub const ENV_PREFIX: &str = "DDCRATE_";
+
+fn default_team_size() -> usize {
+    TEAM_SIZE
+}
+
+/// Two or more DDC players, sorted in ID order.
+#[derive(Debug, Clone, Hash, Eq, PartialEq, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct Team {
-    early: PlayerId,
-    late: PlayerId,
+    players: Vec<PlayerId>,
 }
 
 impl Team {
     /// Players are sorted by IDs.
     pub fn new(player1: PlayerId, player2: PlayerId) -> Result<Self, RepeatedPlayer> {
         match player1.cmp(&player2) {
-            std::cmp::Ordering::Less => Ok(Self::new_unchecked(player1, player2)),
+            std::cmp::Ordering::Less => Ok(Self::new_unchecked(vec![player1, player2])),
             std::cmp::Ordering::Equal => Err(RepeatedPlayer(player1)),
-            std::cmp::Ordering::Greater => Ok(Self::new_unchecked(player2, player1)),
+            std::cmp::Ordering::Greater => Ok(Self::new_unchecked(vec![player2, player1])),
+        }
+    }
+
+    /// Team of any size from 2 players; players are sorted by IDs.
+    pub fn from_players(mut players: Vec<PlayerId>) -> Result<Self, InvalidTournament> {
+        if players.len() < 2 {
+            return Err(InvalidEntrantSize(players.len()).into());
         }
+        players.sort_unstable();
+        if let Some(w) = players.windows(2).find(|w| w[0] == w[1]) {
+            return Err(RepeatedPlayer(w[0]).into());
+        }
+        Ok(Self::new_unchecked(players))
+    }
+
+    /// Order and uniqueness of player IDs is not checked.
+    pub fn new_unchecked(players: Vec<PlayerId>) -> Self {
+        Self { players }
     }
 
-    /// Order of player IDs is not checked.
-    pub fn new_unchecked(early: PlayerId, late: PlayerId) -> Self {
-        Self { early, late }
+    pub fn players(&self) -> &[PlayerId] {
+        &self.players
     }
 
-    pub fn players(&self) -> [&PlayerId; 2] {
-        [&self.early, &self.late]
+    /// The other players in the team, if the given player is in it.
+    pub fn teammates(&self, player: &PlayerId) -> Option<Vec<&PlayerId>> {
+        if !self.players.contains(player) {
+            return None;
+        }
+        Some(self.players.iter().filter(|p| *p != player).collect())
     }
 }
 
-/// Levels of tournaments, used to determine base points available.
-#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq, Deserialize)]
-#[serde(rename_all = "lowercase")]
-pub enum Level {
-    Small,
-    Medium,
-    Major,
-    Championship,
+/// A single player or a [Team], as entered in a tournament.
+#[derive(Debug, Clone, Hash, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Entrant {
+    Single(PlayerId),
+    Team(Team),
 }
 
-impl Level {
-    /// When parsing results TSVs, the name of directories corresponding to each level.
-    pub fn directory_name(&self) -> &'static str {
+impl Entrant {
+    /// A single player from one ID, or a [Team] from more.
+    pub fn from_players(players: &[PlayerId]) -> Result<Self, InvalidTournament> {
+        match players {
+            [p] => Ok(Self::Single(*p)),
+            _ => Ok(Self::Team(Team::from_players(players.to_vec())?)),
+        }
+    }
+
+    /// Players in the entrant, sorted by ID.
+    pub fn players(&self) -> &[PlayerId] {
+        match self {
+            Self::Single(p) => std::slice::from_ref(p),
+            Self::Team(t) => t.players(),
+        }
+    }
+
+    /// The other players in the entrant, if the given player is in it.
+    pub fn teammates(&self, player: &PlayerId) -> Option<Vec<&PlayerId>> {
         match self {
-            Level::Small => "small",
-            Level::Medium => "medium",
-            Level::Major => "major",
-            Level::Championship => "championship",
+            Self::Single(p) => (p == player).then(Vec::default),
+            Self::Team(t) => t.teammates(player),
         }
     }
+}
+
+impl From<Team> for Entrant {
+    fn from(value: Team) -> Self {
+        Self::Team(value)
+    }
+}
+
+/// Levels of tournaments, used to determine base points available.
+///
+/// The standard levels are available as associated constants;
+/// any others can be defined in the [Config].
+#[derive(
+    Debug, Clone, Hash, Eq, PartialEq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema,
+)]
+#[serde(transparent)]
+pub struct Level(Cow<'static, str>);
+
+impl Level {
+    pub const SMALL: Self = Self(Cow::Borrowed("small"));
+    pub const MEDIUM: Self = Self(Cow::Borrowed("medium"));
+    pub const MAJOR: Self = Self(Cow::Borrowed("major"));
+    pub const CHAMPIONSHIP: Self = Self(Cow::Borrowed("championship"));
+
+    pub fn new<S: Into<String>>(name: S) -> Self {
+        Self(Cow::Owned(name.into()))
+    }
+
+    pub fn name(&self) -> &str {
+        &self.0
+    }
 
-    pub fn all() -> HashSet<Self> {
+    /// When parsing results TSVs, the default name of the directory corresponding to this level.
+    pub fn directory_name(&self) -> &str {
+        &self.0
+    }
+
+    /// The standard levels.
+    pub fn defaults() -> HashSet<Self> {
         let mut out = HashSet::with_capacity(4);
-        out.insert(Self::Small);
-        out.insert(Self::Medium);
-        out.insert(Self::Major);
-        out.insert(Self::Championship);
+        out.insert(Self::SMALL);
+        out.insert(Self::MEDIUM);
+        out.insert(Self::MAJOR);
+        out.insert(Self::CHAMPIONSHIP);
         out
     }
 }
 
+impl Display for Level {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Settings for a level of tournament.
+///
+/// In TOML, this can be given either as a number (the point base)
+/// or a table with `points` and optionally `directory`, `team_size`, and `finish` keys.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "LevelConfigRepr")]
+pub struct LevelConfig {
+    /// Base points available.
+    pub points: f64,
+    /// Name of the directory containing results of this level;
+    /// defaults to the level's name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub directory: Option<String>,
+    /// Number of player columns in results TSVs of this level.
+    pub team_size: usize,
+    /// Proportion of the point base for each finishing position, starting from 1st.
+    /// Positions beyond the table use the finish decay formula.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub finish: Vec<f64>,
+}
+
+impl LevelConfig {
+    pub const fn new(points: f64) -> Self {
+        Self {
+            points,
+            directory: None,
+            team_size: TEAM_SIZE,
+            finish: Vec::new(),
+        }
+    }
+
+    /// Multiplier from finishing position:
+    /// from the finish table if it covers the place, otherwise `1 / finish_decay ^ place`.
+    pub fn finish_factor(&self, finish_decay: f64, place: u64) -> f64 {
+        match place
+            .checked_sub(1)
+            .and_then(|idx| self.finish.get(idx as usize))
+        {
+            Some(f) => *f,
+            // places beyond i32 are too low to score anyway
+            None => 1.0 / finish_decay.powi(place.min(i32::MAX as u64) as i32),
+        }
+    }
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(untagged)]
+enum LevelConfigRepr {
+    Points(f64),
+    Table {
+        points: f64,
+        directory: Option<String>,
+        #[serde(default = "default_team_size")]
+        team_size: usize,
+        #[serde(default)]
+        finish: Vec<f64>,
+    },
+}
+
+impl JsonSchema for LevelConfig {
+    fn schema_name() -> String {
+        "LevelConfig".to_owned()
+    }
+
+    fn json_schema(gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        LevelConfigRepr::json_schema(gen)
+    }
+}
+
+impl From<LevelConfigRepr> for LevelConfig {
+    fn from(value: LevelConfigRepr) -> Self {
+        match value {
+            LevelConfigRepr::Points(points) => Self::new(points),
+            LevelConfigRepr::Table {
+                points,
+                directory,
+                team_size,
+                finish,
+            } => Self {
+                points,
+                directory,
+                team_size,
+                finish,
+            },
+        }
+    }
+}
+
+/// A game between two entrants in a tournament, from round-level results.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Match {
+    /// Name of the round, e.g. `pool A` or `final`.
+    pub round: String,
+    pub winner: Entrant,
+    pub loser: Entrant,
+    /// Scores of the winner and loser, if recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score: Option<(u32, u32)>,
+}
+
+impl Match {
+    /// Margin of victory, if the score was recorded.
+    pub fn margin(&self) -> Option<u32> {
+        self.score.map(|(w, l)| w.saturating_sub(l))
+    }
+
+    /// Whether the game was in a pool (its round's name starts with `pool`, case-insensitive),
+    /// rather than a bracket.
+    pub fn is_pool(&self) -> bool {
+        self.round
+            .get(..4)
+            .is_some_and(|r| r.eq_ignore_ascii_case("pool"))
+    }
+}
+
+/// Entrant; its last bracket round, whether it won that game, and its number of placement wins;
+/// and its number of pool wins.
+type MatchRecord<'a> = (&'a Entrant, Option<(usize, bool, usize)>, usize);
+
+/// Finishing positions derived from a tournament's games.
+///
+/// Entrants which played in the bracket finish above those which only played in pools.
+/// Bracket rounds are ordered by their first game, and entrants which reached a later round
+/// finish higher; of those whose last game was in the same round, the winners of that game
+/// finish higher (e.g. the winner of the final, then its loser, then the losers of the semi-finals).
+/// A bracket game between two entrants which have both already lost in the bracket,
+/// such as a third-place game, is a placement game, wherever it is listed:
+/// it does not advance them, but among those knocked out in the same round,
+/// entrants with more placement wins finish higher.
+/// Entrants which only played in pools are ordered by their number of pool wins.
+/// Otherwise, entrants are tied.
+pub fn placings_from_matches(matches: &[Match]) -> Placings {
+    fn record_of<'a>(e: &'a Entrant, records: &mut Vec<MatchRecord<'a>>) -> usize {
+        match records.iter().position(|(r, _, _)| *r == e) {
+            Some(idx) => idx,
+            None => {
+                records.push((e, None, 0));
+                records.len() - 1
+            }
+        }
+    }
+    let knocked_out = |r: &MatchRecord| matches!(r.1, Some((_, false, _)));
+    let mut rounds: Vec<&str> = Vec::default();
+    let mut records: Vec<MatchRecord> = Vec::default();
+    for m in matches {
+        let winner = record_of(&m.winner, &mut records);
+        let loser = record_of(&m.loser, &mut records);
+        if m.is_pool() {
+            records[winner].2 += 1;
+            continue;
+        }
+        if knocked_out(&records[winner]) && knocked_out(&records[loser]) {
+            if let Some((_, _, placement_wins)) = records[winner].1.as_mut() {
+                *placement_wins += 1;
+            }
+            continue;
+        }
+        let round = match rounds.iter().position(|r| *r == m.round) {
+            Some(idx) => idx,
+            None => {
+                rounds.push(&m.round);
+                rounds.len() - 1
+            }
+        };
+        for (idx, won) in [(winner, true), (loser, false)] {
+            if records[idx].1.is_none_or(|(r, _, _)| r <= round) {
+                records[idx].1 = Some((round, won, 0));
+            }
+        }
+    }
+    let key = |(_, bracket, pool_wins): &MatchRecord| {
+        Reverse((*bracket, bracket.is_none().then_some(*pool_wins)))
+    };
+    records.sort_by_key(key);
+    let mut out = Vec::with_capacity(records.len());
+    for (idx, record) in records.iter().enumerate() {
+        let place = match out.last() {
+            Some((prev_place, _)) if key(&records[idx - 1]) == key(record) => *prev_place,
+            _ => idx as u64 + 1,
+        };
+        out.push((place, record.0.clone()));
+    }
+    out
+}
+
 #[derive(Debug, Clone)]
 pub struct Tournament {
-    /// Pairs of finishing position and team.
-    results: Vec<(u64, Team)>,
+    /// Pairs of finishing position and entrant.
+    results: Vec<(u64, Entrant)>,
+    /// Entrants which were not placed, with their result codes.
+    unplaced: Vec<(ResultCode, Entrant)>,
+    /// Placed entrants which withdrew during the tournament.
+    withdrawn: Vec<Entrant>,
+    /// Games between entrants, if round-level results were given.
+    matches: Vec<Match>,
     /// Tournament finish date/time.
     datetime: DateTime<Utc>,
     /// Level of tournament.
     level: Level,
+    /// Descriptive information, not used in ranking.
+    metadata: TournamentMetadata,
+}
+
+/// Descriptive information about a tournament.
+///
+/// For TSV results, this can be given as `# key: value` comment lines at the top of the file,
+/// or in a TOML file alongside it with the same name but a `.toml` extension,
+/// which takes precedence.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TournamentMetadata {
+    pub name: Option<String>,
+    pub location: Option<String>,
+    #[serde(alias = "organiser")]
+    pub organizer: Option<String>,
+}
+
+impl TournamentMetadata {
+    /// Parse `# key: value` lines from the start of a results file.
+    /// Unknown keys are ignored.
+    pub fn from_frontmatter(contents: &str) -> Self {
+        let mut out = Self::default();
+        for line in strip_bom(contents).lines() {
+            let Some(comment) = line.strip_prefix('#') else {
+                break;
+            };
+            let Some((key, value)) = comment.split_once(':') else {
+                continue;
+            };
+            let value = Some(value.trim().to_owned());
+            match key.trim().to_lowercase().as_str() {
+                "name" => out.name = value,
+                "location" => out.location = value,
+                "organizer" | "organiser" => out.organizer = value,
+                _ => (),
+            }
+        }
+        out
+    }
+
+    /// Read the TOML sidecar of the given results file, if it exists.
+    pub fn read_sidecar(results_path: &Path) -> Result<Option<Self>, ResultReadError> {
+        Self::read_sidecar_from(results_path, &Files::default())
+    }
+
+    fn read_sidecar_from(
+        results_path: &Path,
+        files: &Files,
+    ) -> Result<Option<Self>, ResultReadError> {
+        let path = sidecar_path(results_path);
+        if !files.is_file(&path) {
+            return Ok(None);
+        }
+        let contents = files.read_to_string(&path)?;
+        Ok(Some(toml::from_str(&contents)?))
+    }
+
+    /// Fill any missing fields from another.
+    pub fn or(self, other: Self) -> Self {
+        Self {
+            name: self.name.or(other.name),
+            location: self.location.or(other.location),
+            organizer: self.organizer.or(other.organizer),
+        }
+    }
 }
 
 #[derive(Debug, Error)]
@@ -106,443 +541,6604 @@ pub struct RepeatedPlayer(PlayerId);
 #[error("Ranks are inconsistent")]
 pub struct InconsistentRanks();
 
+#[derive(Debug, Error)]
+#[error("Invalid number of players in entrant: {0}")]
+pub struct InvalidEntrantSize(usize);
+
 #[derive(Debug, Error)]
 pub enum InvalidTournament {
     #[error(transparent)]
     RepeatedPlayer(#[from] RepeatedPlayer),
     #[error(transparent)]
     InconsistentRanks(#[from] InconsistentRanks),
+    #[error(transparent)]
+    InvalidEntrantSize(#[from] InvalidEntrantSize),
 }
 
-#[derive(Debug, Clone, Deserialize)]
-pub struct Config {
-    finish_decay: f64,
-    age_decay: f64,
-    record_length: usize,
-    levels: HashMap<Level, f64>,
+/// A tournament whose points cannot be calculated with a config.
+#[derive(Debug, Error)]
+pub enum PointsError {
+    #[error("Tournament level {0:?} is not in the config")]
+    UnknownLevel(Level),
+    #[error("Points for player {0} are NaN")]
+    NaN(PlayerId),
+    #[cfg(feature = "scripting")]
+    #[error(transparent)]
+    Formula(#[from] scripting::FormulaError),
 }
 
-impl Config {
-    pub fn new(
-        finish_decay: f64,
-        age_decay: f64,
-        record_length: usize,
-        levels: &HashMap<Level, f64>,
-    ) -> Self {
-        let default_levels = default_levels();
-        let mut lvls = HashMap::with_capacity(default_levels.len());
-        for (lvl, pb) in default_levels.iter() {
-            let val = *levels.get(lvl).unwrap_or(pb);
-            lvls.insert(*lvl, val);
-        }
-        Self {
-            finish_decay,
-            age_decay,
-            record_length,
-            levels: lvls,
+/// How a team's points are shared between its players.
+#[derive(Debug, Default, Clone, Copy, Hash, Eq, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum PointSplit {
+    /// Each player receives an equal share.
+    #[default]
+    Even,
+    /// Each player receives the team's full points.
+    Full,
+    /// Each player receives a share in proportion to their rating before the tournament.
+    /// If no player in the team has a positive rating, the shares are equal.
+    Rating,
+}
+
+impl PointSplit {
+    /// Proportion of the team's points received by one of its players,
+    /// given players' ratings before the tournament.
+    pub fn share(
+        &self,
+        player: &PlayerId,
+        team: &[PlayerId],
+        ratings: &PlayerMap<NotNan<f64>>,
+    ) -> f64 {
+        let even = 1.0 / team.len() as f64;
+        match self {
+            PointSplit::Even => even,
+            PointSplit::Full => 1.0,
+            PointSplit::Rating => {
+                let rating = |p| ratings.get(p).map_or(0.0, |r| r.into_inner().max(0.0));
+                let total: f64 = team.iter().map(rating).sum();
+                if total > 0.0 {
+                    rating(player) / total
+                } else {
+                    even
+                }
+            }
         }
     }
+}
 
-    pub fn finish_decay(mut self, finish_decay: f64) -> Self {
-        self.finish_decay = finish_decay;
-        self
-    }
+/// How bonus points are awarded for finishing above ranked players.
+///
+/// In either case, bonuses are only awarded to entrants which finished strictly above,
+/// not those tied with the ranked players.
+#[derive(Debug, Default, Clone, Copy, Hash, Eq, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum BonusMode {
+    /// A bonus for every ranked player finished above.
+    #[default]
+    Player,
+    /// A bonus for every entrant finished above containing a ranked player,
+    /// according to its highest-ranked player.
+    Team,
+}
 
-    pub fn age_decay(mut self, age_decay: f64) -> Self {
-        self.age_decay = age_decay;
-        self
+/// Decay of inactive players' ratings, applied at ranking time.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct InactivityConfig {
+    /// Length of a period of inactivity, in days.
+    pub days: u32,
+    /// Proportion of a player's rating (above the baseline) retained for each full period
+    /// since their latest result.
+    pub factor: f64,
+}
+
+impl InactivityConfig {
+    /// Decay a rating towards a baseline (e.g. the rating of a player with no results),
+    /// given the time since the player's latest result.
+    pub fn decay(&self, rating: f64, baseline: f64, idle: Duration) -> f64 {
+        if self.days == 0 {
+            return rating;
+        }
+        let periods = (idle.num_days().max(0) / self.days as i64) as i32;
+        baseline + (rating - baseline) * self.factor.powi(periods)
     }
 
-    pub fn record_length(mut self, record_length: usize) -> Self {
-        self.record_length = record_length;
-        self
+    /// Decay every player's rating, given when each last played.
+    /// Players without a latest result are not decayed.
+    ///
+    /// Returns an error if a rating would decay to NaN (e.g. an infinite rating with a factor of 0),
+    /// leaving the remaining ratings undecayed.
+    pub fn apply(
+        &self,
+        ratings: &mut PlayerMap<NotNan<f64>>,
+        last_played: &PlayerMap<DateTime<Utc>>,
+        baseline: f64,
+        at: DateTime<Utc>,
+    ) -> Result<(), DecayedToNaN> {
+        for (pid, rating) in ratings.iter_mut() {
+            if let Some(last) = last_played.get(pid) {
+                let decayed = self.decay(rating.into_inner(), baseline, at - *last);
+                *rating = NotNan::new(decayed).map_err(|_| DecayedToNaN(*pid))?;
+            }
+        }
+        Ok(())
     }
+}
 
-    pub fn level(mut self, level: Level, point_base: f64) -> Self {
-        self.levels.insert(level, point_base);
-        self
+/// A player's rating which decays to NaN with an [InactivityConfig].
+#[derive(Debug, Error)]
+#[error("Rating of player {0} decays to NaN")]
+pub struct DecayedToNaN(pub PlayerId);
+
+/// Multiplier of a tournament's points based on the strength of its field,
+/// so that a tournament attended by many highly-ranked players is worth more.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct FieldStrengthConfig {
+    /// Players ranked at or above this before the tournament count towards its strength.
+    pub top: u64,
+    /// Increase in the multiplier for each such player.
+    pub per_player: f64,
+    /// Maximum multiplier.
+    #[serde(default)]
+    pub max: Option<f64>,
+}
+
+impl FieldStrengthConfig {
+    /// Multiplier for a tournament with the given players, given their ranks before it.
+    pub fn factor<'a>(
+        &self,
+        players: impl IntoIterator<Item = &'a PlayerId>,
+        ranks: &PlayerMap<u64>,
+    ) -> f64 {
+        let n_top = players
+            .into_iter()
+            .filter(|p| ranks.get(p).is_some_and(|r| *r <= self.top))
+            .count();
+        let factor = 1.0 + self.per_player * n_top as f64;
+        self.max.map_or(factor, |m| factor.min(m))
     }
 }
 
-const LEVEL_PAIRS: [(Level, f64); 4] = [
-    (Level::Small, 50.0),
-    (Level::Medium, 125.0),
-    (Level::Major, 200.0),
-    (Level::Championship, 250.0),
-];
+/// How a tournament's point base grows with its number of entrants.
+#[derive(Debug, Default, Clone, Copy, Hash, Eq, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SizeScale {
+    /// Proportional to the number of entrants.
+    #[default]
+    Linear,
+    /// Proportional to the logarithm of one more than the number of entrants.
+    Log,
+}
 
-fn level_init() -> HashMap<Level, f64> {
-    LEVEL_PAIRS.into_iter().collect()
+/// Scaling of a tournament's point base by its number of entrants,
+/// so that a large tournament is worth more than a small one of the same level.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct SizeScalingConfig {
+    #[serde(default)]
+    pub scale: SizeScale,
+    /// Number of entrants at which the point base is unscaled.
+    pub reference: usize,
+    /// Maximum multiplier.
+    #[serde(default)]
+    pub max: Option<f64>,
 }
 
-static LEVELS: OnceCell<HashMap<Level, f64>> = OnceCell::new();
+impl SizeScalingConfig {
+    /// Multiplier of the point base for a tournament with this many entrants.
+    pub fn factor(&self, n_entrants: usize) -> f64 {
+        let factor = match self.scale {
+            SizeScale::Linear => n_entrants as f64 / self.reference as f64,
+            SizeScale::Log => (n_entrants as f64).ln_1p() / (self.reference as f64).ln_1p(),
+        };
+        self.max.map_or(factor, |m| factor.min(m))
+    }
+}
 
-pub fn default_levels() -> &'static HashMap<Level, f64> {
-    LEVELS.get_or_init(level_init)
+/// When each season starts, used to calculate the age of results.
+///
+/// By default, seasons are calendar years.
+/// If `starts` is given, each season starts on one of those dates,
+/// and is numbered by its start date's year;
+/// otherwise, every season starts on the same `month` and `day`,
+/// and is numbered by the year in which it starts.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct SeasonConfig {
+    pub month: u32,
+    pub day: u32,
+    pub starts: Vec<NaiveDate>,
 }
 
-impl Default for Config {
+impl Default for SeasonConfig {
     fn default() -> Self {
         Self {
-            finish_decay: FINISH_DECAY,
-            age_decay: AGE_DECAY,
-            record_length: RECORD_LENGTH,
-            levels: default_levels().clone(),
+            month: 1,
+            day: 1,
+            starts: Vec::default(),
         }
     }
 }
 
-impl Tournament {
-    pub fn new(
-        mut results: Vec<(u64, Team)>,
-        datetime: DateTime<Utc>,
-        level: Level,
-    ) -> Result<Self, InvalidTournament> {
-        let mut prev_place: u64 = 0;
-        let mut increment: u64 = 1;
-        results.sort_unstable_by_key(|p| p.0);
-        let mut players = HashSet::with_capacity(results.len() * 4);
-        for (place, team) in results.iter() {
-            for player in team.players() {
-                if players.contains(player) {
-                    return Err(RepeatedPlayer(*player).into());
-                }
-                players.insert(*player);
-            }
-            if &prev_place == place {
-                increment += 1;
-            } else if place != &(prev_place + increment) {
-                return Err(InconsistentRanks().into());
-            } else {
-                increment = 1;
-            }
-            prev_place += increment;
+impl SeasonConfig {
+    /// The season containing the given date/time.
+    ///
+    /// With explicit start dates, dates before the first start are in the preceding season.
+    pub fn season_of(&self, datetime: &DateTime<Utc>) -> i32 {
+        let date = datetime.date_naive();
+        if !self.starts.is_empty() {
+            return match self.starts.iter().filter(|s| **s <= date).max() {
+                Some(start) => start.year(),
+                None => self.starts.iter().min().unwrap().year() - 1,
+            };
         }
-        Ok(Self::new_unchecked(results, datetime, level))
-    }
-
-    pub fn new_unchecked(results: Vec<(u64, Team)>, datetime: DateTime<Utc>, level: Level) -> Self {
-        Self {
-            results,
-            datetime,
-            level,
+        match self.start_of(date.year()) {
+            Some(s) if date < s => date.year() - 1,
+            _ => date.year(),
         }
     }
 
-    pub fn points(
-        &self,
-        current_season: i32,
-        initial_ranks: &HashMap<PlayerId, u64>,
-        config: &Config,
-    ) -> HashMap<PlayerId, NotNan<f64>> {
-        if self.results.is_empty() {
+    /// The first day of the given season, if it is known.
+    pub fn start_of(&self, season: i32) -> Option<NaiveDate> {
+        if !self.starts.is_empty() {
             return self
-                .results
+                .starts
                 .iter()
-                .flat_map(|(_, t)| t.players())
-                .map(|p| (*p, NotNan::new(0.0).unwrap()))
-                .collect();
-        }
-        let mut out = HashMap::with_capacity(self.results.len() * 2);
-        let mut bonus: f64 = 0.0;
-        let age = (current_season - self.datetime.year()) as f64;
-        let mut bonus_update: f64 = 0.0;
-        let mut prev_place = self.results.last().unwrap().0 + 1;
-        let point_base = config.levels[&self.level];
-        for (place, team) in self.results.iter().rev() {
-            for player in team.players() {
-                let mut points = point_base * (1.0 / FINISH_DECAY.powi(*place as i32));
-                points *= 1.0 / AGE_DECAY.powf(age);
-                points += bonus;
-                out.insert(*player, NotNan::new(points / 2.0).unwrap());
-                bonus_update += bonus_points(*initial_ranks.get(player).unwrap_or(&201));
-            }
-            if place != &prev_place {
-                bonus += bonus_update;
-                bonus_update = 0.0;
-                prev_place = *place;
-            }
+                .filter(|s| s.year() == season)
+                .min()
+                .copied();
         }
-        out
+        // fall back to the end of the month for e.g. Feb 29th
+        NaiveDate::from_ymd_opt(season, self.month, self.day).or_else(|| {
+            NaiveDate::from_ymd_opt(season, self.month + 1, 1).and_then(|d| d.pred_opt())
+        })
     }
 }
 
-fn bonus_points(rank: u64) -> f64 {
-    let bpoints = vec![
-        (5, 10.0),
-        (10, 7.5),
-        (20, 5.0),
-        (50, 2.5),
-        (100, 1.0),
-        (200, 0.5),
-    ];
+/// Columns of results TSVs containing the tournament's date or level,
+/// counting from 1 (the finishing position).
+///
+/// The value is taken from the first row in which it is valid.
+/// `# date:` and `# level:` header lines take precedence over these columns,
+/// which take precedence over the date in the file name and the level of its directory.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct TsvColumns {
+    /// Column containing an ISO-8601 date or RFC 3339 datetime.
+    pub date: Option<usize>,
+    /// Column containing the name of the level.
+    pub level: Option<usize>,
+}
 
-    for (max_rank, points) in bpoints {
-        if rank <= max_rank {
-            return points;
-        }
-    }
-    0.0
+/// How the age of a result is measured for age decay.
+#[derive(Debug, Default, Clone, Copy, Hash, Eq, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum AgeUnit {
+    /// The number of seasons between the result's season and the current season.
+    #[default]
+    Seasons,
+    /// Fractional years (of 365.25 days) from the result to the start of the current season,
+    /// so that results from just before the start of the season are barely decayed.
+    /// Results in the current season have age 0.
+    /// Falls back to seasons if the start of the current season is not known.
+    Days,
 }
 
-#[derive(Debug, Clone)]
-pub struct PlayerRecord {
-    pub id: PlayerId,
-    points: BinaryHeap<Reverse<NotNan<f64>>>,
-    pub rating: NotNan<f64>,
+/// How finishing-place points are given to entrants tied for a place.
+///
+/// Tied entrants share a place, and the next entrant's place skips those taken by the tie
+/// (e.g. 1, 2, 2, 4): the tie spans places 2 and 3.
+#[derive(Debug, Default, Clone, Copy, Hash, Eq, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum TiePolicy {
+    /// Each tied entrant receives the points for the best place of the tie.
+    #[default]
+    Best,
+    /// Each tied entrant receives the points for the worst place of the tie.
+    Worst,
+    /// Each tied entrant receives the mean of the points for the places spanned by the tie.
+    Average,
 }
 
-impl PlayerRecord {
-    pub fn new(id: PlayerId, record_length: usize) -> Self {
-        Self {
-            id,
-            points: BinaryHeap::with_capacity(record_length + 1),
-            rating: NotNan::new(0.0).unwrap(),
+impl TiePolicy {
+    /// Multiplier from finishing position for an entrant tied with `n_tied - 1` others,
+    /// given the multiplier for each untied place.
+    pub fn finish_factor<F: Fn(u64) -> f64>(&self, factor: F, place: u64, n_tied: usize) -> f64 {
+        let n_tied = n_tied.max(1) as u64;
+        match self {
+            TiePolicy::Best => factor(place),
+            TiePolicy::Worst => factor(place.saturating_add(n_tied - 1)),
+            TiePolicy::Average => {
+                (place..place.saturating_add(n_tied))
+                    .map(factor)
+                    .sum::<f64>()
+                    / n_tied as f64
+            }
         }
     }
+}
 
-    pub fn new_with_points(id: PlayerId, record_length: usize, points: &[f64]) -> Self {
-        let mut player = Self::new(id, record_length);
-        for p in points.iter() {
-            player.add_result(NotNan::new(*p).unwrap());
+/// Code given in place of an entrant's finishing position, when it was not placed.
+#[derive(
+    Debug, Clone, Copy, Hash, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize, JsonSchema,
+)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum ResultCode {
+    /// Did not finish.
+    Dnf,
+    /// Disqualified.
+    Dq,
+    /// Withdrew or forfeited.
+    Wd,
+}
+
+#[derive(Debug, Error)]
+#[error("Unknown result code: {0}")]
+pub struct UnknownResultCode(String);
+
+impl FromStr for ResultCode {
+    type Err = UnknownResultCode;
+
+    /// Case-insensitive; `DSQ` is also accepted for disqualifications.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_uppercase().as_str() {
+            "DNF" => Ok(ResultCode::Dnf),
+            "DQ" | "DSQ" => Ok(ResultCode::Dq),
+            "WD" => Ok(ResultCode::Wd),
+            _ => Err(UnknownResultCode(s.to_owned())),
         }
-        player
     }
+}
 
-    pub fn add_result(&mut self, points: NotNan<f64>) -> (bool, NotNan<f64>) {
-        let p = Reverse(points);
-        if self.points.len() < RECORD_LENGTH {
-            self.rating += points;
-            self.points.push(p);
-            return (points != 0.0, self.rating);
+impl Display for ResultCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            ResultCode::Dnf => "DNF",
+            ResultCode::Dq => "DQ",
+            ResultCode::Wd => "WD",
+        })
+    }
+}
+
+/// How the points system rates an entrant with a [ResultCode].
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum CodePolicy {
+    /// The entrant's players get a result of 0 points, which counts towards their records.
+    Zero,
+    /// The entrant is ignored, as if it had not entered.
+    Omit,
+    /// The entrant is placed below every placed entrant, tied with any others placed last.
+    Last,
+}
+
+/// How the points system rates entrants with each [ResultCode].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct CodePolicies {
+    pub dnf: CodePolicy,
+    pub dq: CodePolicy,
+    pub wd: CodePolicy,
+}
+
+impl Default for CodePolicies {
+    fn default() -> Self {
+        Self {
+            dnf: CodePolicy::Last,
+            dq: CodePolicy::Zero,
+            wd: CodePolicy::Omit,
         }
+    }
+}
 
-        self.points.push(p);
-        let removed = self.points.pop().unwrap().0;
-        if removed == points {
-            (false, self.rating)
-        } else {
-            self.rating = self.rating - removed + points;
-            (true, self.rating)
+impl CodePolicies {
+    pub fn get(&self, code: ResultCode) -> CodePolicy {
+        match code {
+            ResultCode::Dnf => self.dnf,
+            ResultCode::Dq => self.dq,
+            ResultCode::Wd => self.wd,
         }
     }
 }
 
-fn records_to_update_ranks(
-    records: &HashMap<PlayerId, PlayerRecord>,
-    into: &mut HashMap<PlayerId, u64>,
-) {
-    into.clear();
-    let mut pid_scores: Vec<_> = records
-        .iter()
-        .map(|(pid, rec)| (*pid, rec.rating))
-        .collect();
-    pid_scores.sort_unstable_by_key(|(_, rat)| *rat);
-    let mut prev_rank = 0;
-    let mut rank_incr = 1;
-    let mut prev_score = NotNan::new(-1.0).unwrap();
+/// How the points system rates an entrant which withdrew during a tournament,
+/// but still has a finishing position.
+///
+/// In every case, the entrant keeps its place, so the places of other entrants are unaffected.
+#[derive(Debug, Default, Clone, Copy, Hash, Eq, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum WithdrawnPolicy {
+    /// The entrant's points are multiplied by [WithdrawnConfig::factor].
+    #[default]
+    Reduced,
+    /// The entrant's players get a result of 0 points, which counts towards their records.
+    Zero,
+    /// The entrant's points are as if it had not withdrawn.
+    Keep,
+}
 
-    for (pid, score) in pid_scores {
-        if score == prev_score {
-            rank_incr += 1;
-        } else {
-            prev_rank += rank_incr;
-            rank_incr = 1;
+/// How entrants which withdrew during a tournament are rated.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct WithdrawnConfig {
+    pub policy: WithdrawnPolicy,
+    /// Multiplier of withdrawn entrants' points, with the `reduced` policy.
+    pub factor: f64,
+}
 
-            prev_score = score;
+impl Default for WithdrawnConfig {
+    fn default() -> Self {
+        Self {
+            policy: Default::default(),
+            factor: 0.5,
         }
+    }
+}
 
-        into.insert(pid, prev_rank);
+impl WithdrawnConfig {
+    /// Multiplier of a withdrawn entrant's points.
+    pub fn points_factor(&self) -> f64 {
+        match self.policy {
+            WithdrawnPolicy::Reduced => self.factor,
+            WithdrawnPolicy::Zero => 0.0,
+            WithdrawnPolicy::Keep => 1.0,
+        }
     }
 }
 
-/// Tournaments must be pre-sorted.
-pub fn rank_players(
-    tournaments: &[Tournament],
-    current_season: i32,
-    config: &Config,
-) -> (HashMap<PlayerId, u64>, HashMap<PlayerId, PlayerRecord>) {
-    let mut prev_dt = DateTime::<Utc>::MIN_UTC;
-    let mut ranks: HashMap<PlayerId, u64> = Default::default();
-    let mut records: HashMap<PlayerId, PlayerRecord> = Default::default();
-    let mut needs_updating = true;
-    for t in tournaments.iter() {
-        for (pid, pts) in t.points(current_season, &ranks, config).iter() {
-            let record = records
-                .entry(*pid)
-                .or_insert_with(|| PlayerRecord::new(*pid, config.record_length));
-            record.add_result(*pts);
+/// How ranks are numbered when players have equal ratings.
+#[derive(Debug, Default, Clone, Copy, Hash, Eq, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum RankNumbering {
+    /// Standard competition ranking: tied players share a rank, and ranks after them are skipped
+    /// (1, 2, 2, 4).
+    #[default]
+    Standard,
+    /// Tied players share a rank, and no ranks are skipped (1, 2, 2, 3).
+    Dense,
+    /// Every player has a distinct rank; ties are broken by ID (1, 2, 3, 4).
+    Ordinal,
+}
+
+#[derive(Debug, Error)]
+#[error("Unknown rank numbering: {0}")]
+pub struct UnknownRankNumbering(String);
+
+impl FromStr for RankNumbering {
+    type Err = UnknownRankNumbering;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "standard" => Ok(RankNumbering::Standard),
+            "dense" => Ok(RankNumbering::Dense),
+            "ordinal" => Ok(RankNumbering::Ordinal),
+            _ => Err(UnknownRankNumbering(s.to_owned())),
         }
-        match prev_dt.cmp(&t.datetime) {
-            std::cmp::Ordering::Less => {
-                records_to_update_ranks(&records, &mut ranks);
-                prev_dt = t.datetime;
-                needs_updating = false;
-            }
-            std::cmp::Ordering::Equal => {
-                needs_updating = true;
-            }
-            std::cmp::Ordering::Greater => panic!("Tournaments were not ordered"),
+    }
+}
+
+/// Which end of the ratings is ranked first.
+#[derive(Debug, Default, Clone, Copy, Hash, Eq, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum RankDirection {
+    /// The highest rating is rank 1.
+    #[default]
+    Descending,
+    /// The lowest rating is rank 1.
+    Ascending,
+}
+
+#[derive(Debug, Error)]
+#[error("Unknown rank direction: {0}")]
+pub struct UnknownRankDirection(String);
+
+impl FromStr for RankDirection {
+    type Err = UnknownRankDirection;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "descending" => Ok(RankDirection::Descending),
+            "ascending" => Ok(RankDirection::Ascending),
+            _ => Err(UnknownRankDirection(s.to_owned())),
+        }
+    }
+}
+
+/// Rating systems available for ranking players.
+#[derive(Debug, Default, Clone, Copy, Hash, Eq, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Algorithm {
+    /// WFDF points system: sum of best results.
+    #[default]
+    Points,
+    /// Elo-style pairwise comparisons between finishing places.
+    Elo,
+    /// Glicko-2 pairwise comparisons, with rating deviation.
+    Glicko,
+    /// TrueSkill-style team rating, with skill uncertainty.
+    TrueSkill,
+}
+
+impl Algorithm {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Algorithm::Points => "points",
+            Algorithm::Elo => "elo",
+            Algorithm::Glicko => "glicko",
+            Algorithm::TrueSkill => "trueskill",
         }
     }
-    if needs_updating {
-        records_to_update_ranks(&records, &mut ranks);
+}
+
+impl Display for Algorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
     }
-    (ranks, records)
 }
 
 #[derive(Debug, Error)]
-pub enum ResultReadError {
-    #[error(transparent)]
-    InvalidTournament(#[from] InvalidTournament),
-    #[error(transparent)]
-    Io(#[from] io::Error),
+#[error("Unknown algorithm: {0}")]
+pub struct UnknownAlgorithm(String);
+
+impl FromStr for Algorithm {
+    type Err = UnknownAlgorithm;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "points" => Ok(Algorithm::Points),
+            "elo" => Ok(Algorithm::Elo),
+            "glicko" => Ok(Algorithm::Glicko),
+            "trueskill" => Ok(Algorithm::TrueSkill),
+            _ => Err(UnknownAlgorithm(s.to_owned())),
+        }
+    }
 }
 
-pub struct ResultIngester {
-    root: PathBuf,
-    levels: HashSet<Level>,
-    from: DateTime<Utc>,
-    until: DateTime<Utc>,
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Config {
+    /// Rating system used by default.
+    #[serde(default)]
+    algorithm: Algorithm,
+    finish_decay: f64,
+    #[serde(default = "default_age_decay")]
+    age_decay: f64,
+    /// If given, the number of months over which a result's points halve,
+    /// used in place of `age_decay`.
+    #[serde(default)]
+    age_half_life_months: Option<f64>,
+    record_length: usize,
+    #[serde(serialize_with = "serialize_sorted")]
+    levels: HashMap<Level, LevelConfig>,
+    /// Pairs of maximum initial rank and the bonus points awarded for beating a player of that rank.
+    #[serde(default = "default_bonus")]
+    bonus: Vec<(u64, f64)>,
+    /// Whether bonuses are per ranked player or per entrant finished above.
+    #[serde(default)]
+    bonus_mode: BonusMode,
+    /// How a team's points are shared between its players.
+    #[serde(default)]
+    split: PointSplit,
+    /// Players with fewer results than this are provisional.
+    #[serde(default)]
+    min_events: usize,
+    /// Decay of inactive players' ratings.
+    #[serde(default)]
+    inactivity: Option<InactivityConfig>,
+    /// If given, only results from this many months before the current time count.
+    #[serde(default)]
+    window_months: Option<u32>,
+    /// If given, tournaments' points are multiplied according to the strength of their field.
+    #[serde(default)]
+    field_strength: Option<FieldStrengthConfig>,
+    /// If given, tournaments' point bases are scaled by their number of entrants.
+    #[serde(default)]
+    size_scaling: Option<SizeScalingConfig>,
+    /// Proportion of each counted result's points kept when carried over from a previous season.
+    #[serde(default = "default_carry_over")]
+    carry_over: f64,
+    /// Points from a single result (before a team's points are split) are capped at this,
+    /// so that extreme settings (e.g. a tiny finish decay) give large but finite ratings.
+    #[serde(default = "default_max_points")]
+    max_points: f64,
+    /// When each season starts.
+    #[serde(default)]
+    seasons: SeasonConfig,
+    /// Format of the date in results TSVs' file names.
+    #[serde(default)]
+    #[schemars(with = "String")]
+    filename_date: FilenameDate,
+    /// Columns of results TSVs containing the tournament's date or level.
+    #[serde(default)]
+    columns: TsvColumns,
+    /// How the age of results is measured.
+    #[serde(default)]
+    age_unit: AgeUnit,
+    /// How finishing-place points are given to tied entrants.
+    #[serde(default)]
+    ties: TiePolicy,
+    /// How entrants with result codes (e.g. `DNF`) rather than places are rated.
+    #[serde(default)]
+    codes: CodePolicies,
+    /// How entrants which withdrew during a tournament are rated.
+    #[serde(default)]
+    withdrawn: WithdrawnConfig,
+    /// If given, a script calculating each result's points in place of the decay formula.
+    #[cfg(feature = "scripting")]
+    #[serde(default)]
+    #[schemars(with = "Option<String>")]
+    formula: Option<scripting::PointFormula>,
+    /// How ranks are numbered when ratings are equal,
+    /// both in output and for calculating bonus points.
+    #[serde(default)]
+    numbering: RankNumbering,
+    /// Whether the highest or lowest rating is rank 1,
+    /// both in output and for calculating bonus points.
+    #[serde(default)]
+    direction: RankDirection,
+    /// Parameters for the Elo rating system.
+    #[serde(default)]
+    elo: EloConfig,
+    /// Parameters for the Glicko-2 rating system.
+    #[serde(default)]
+    glicko: GlickoConfig,
+    /// Parameters for the TrueSkill rating system.
+    #[serde(default)]
+    trueskill: TrueSkillConfig,
 }
 
-impl ResultIngester {
-    pub fn new<P: Into<PathBuf>>(root: P) -> Self {
+/// A config value which would produce meaningless ratings.
+#[derive(Debug, Error)]
+#[error("Invalid config value for `{key}`: {reason}")]
+pub struct InvalidConfigValue {
+    /// TOML key of the value.
+    pub key: String,
+    pub reason: String,
+}
+
+impl InvalidConfigValue {
+    fn new<K: Into<String>, R: Into<String>>(key: K, reason: R) -> Self {
         Self {
-            root: root.into(),
-            levels: Level::all(),
-            from: DateTime::<Utc>::MIN_UTC,
-            until: DateTime::<Utc>::MAX_UTC,
+            key: key.into(),
+            reason: reason.into(),
         }
     }
+}
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Toml(#[from] toml::de::Error),
+    #[error(transparent)]
+    Yaml(#[from] serde_yaml::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error(transparent)]
+    Invalid(#[from] InvalidConfigValue),
+    #[error(transparent)]
+    Serialize(#[from] toml::ser::Error),
+    #[error("Config schema is not an object")]
+    Schema,
+}
+
+impl Config {
+    /// Parse a config from TOML, checking that its values are valid.
+    pub fn from_toml(s: &str) -> Result<Self, ConfigError> {
+        let config: Self = toml::from_str(s)?;
+        config.validate()?;
+        Ok(config)
+    }
 
-    pub fn levels(mut self, levels: HashSet<Level>) -> Self {
-        self.levels = levels;
-        self
+    /// Parse a config from YAML, checking that its values are valid.
+    pub fn from_yaml(s: &str) -> Result<Self, ConfigError> {
+        let config: Self = serde_yaml::from_str(s)?;
+        config.validate()?;
+        Ok(config)
     }
 
-    pub fn from(mut self, from: DateTime<Utc>) -> Self {
-        self.from = from;
-        self
+    /// Parse a config from JSON, checking that its values are valid.
+    pub fn from_json(s: &str) -> Result<Self, ConfigError> {
+        let config: Self = serde_json::from_str(s)?;
+        config.validate()?;
+        Ok(config)
     }
 
-    pub fn until(mut self, until: DateTime<Utc>) -> Self {
-        self.until = until;
-        self
+    /// Read a config file, checking that its values are valid.
+    ///
+    /// Files with a `.yaml`/`.yml` or `.json` extension are parsed as YAML or JSON respectively;
+    /// anything else as TOML.
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)?;
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("yaml" | "yml") => Self::from_yaml(&contents),
+            Some("json") => Self::from_json(&contents),
+            _ => Self::from_toml(&contents),
+        }
     }
 
-    pub fn ingest_level(&self, level: Level) -> Result<Vec<Tournament>, ResultReadError> {
-        let mut out = Vec::default();
-        let dname = level.directory_name();
-        let mut d = self.root.clone();
-        d.push(dname);
-        if !d.is_dir() {
-            return Ok(out);
+    /// Override values from `DDCRATE_*` environment variables,
+    /// checking that the resulting values are valid.
+    ///
+    /// See [Config::with_overrides] for how variables are interpreted.
+    pub fn with_env_overrides(self) -> Result<Self, ConfigError> {
+        self.with_overrides(std::env::vars())
+    }
+
+    /// Override values from `DDCRATE_*` variables,
+    /// checking that the resulting values are valid.
+    ///
+    /// The rest of the variable name is the lowercased config key,
+    /// with `__` separating the keys of nested tables:
+    /// e.g. `DDCRATE_RECORD_LENGTH=12` or `DDCRATE_ELO__K=24`.
+    /// Values are parsed as TOML values, falling back to strings,
+    /// so that e.g. `DDCRATE_TIES=average` needs no quotes.
+    /// Variables without the prefix are ignored,
+    /// as are (with a warning) those which do not name a config key, e.g. `DDCRATE_HOME`.
+    pub fn with_overrides<I, K, V>(self, vars: I) -> Result<Self, ConfigError>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        let mut vars: Vec<_> = vars
+            .into_iter()
+            .filter_map(|(k, v)| {
+                let key = k.as_ref().strip_prefix(ENV_PREFIX)?.to_lowercase();
+                Some((key, v.as_ref().to_owned()))
+            })
+            .collect();
+        if vars.is_empty() {
+            return Ok(self);
         }
-        let tsv_re = regex!(r"(?P<date>\d\d\d\d-\d\d-\d\d).*\.tsv");
-        for entry in WalkDir::new(d).follow_links(true) {
-            // todo: parallelise reading
-            let e = entry.map_err(|e| {
-                e.into_io_error().unwrap_or(io::Error::new(
-                    io::ErrorKind::Other,
-                    "Error reading directories",
-                ))
-            })?;
-            if !e.file_type().is_file() {
-                continue;
-            }
-            let fname = e.file_name().to_str().expect("Non UTF-8 file name");
-            let Some(cap) = tsv_re.captures(fname) else {continue};
-            let date_str = &cap["date"];
-            let date = NaiveDate::parse_from_str(date_str, "%Y-%m-%d").unwrap();
-            let dt = Utc
-                .with_ymd_and_hms(date.year(), date.month(), date.day(), 0, 0, 0)
-                .unwrap();
+        vars.sort_unstable();
 
-            if dt < self.from || dt > self.until {
+        let schema = Self::json_schema();
+        let known = &schema
+            .schema
+            .object
+            .as_ref()
+            .ok_or(ConfigError::Schema)?
+            .properties;
+
+        let mut table = toml::Value::try_from(&self)?;
+        for (key, raw) in vars {
+            let var = format!("{}{}", ENV_PREFIX, key.to_uppercase());
+            let path: Vec<_> = key.split("__").collect();
+            if !known.contains_key(path[0]) {
+                log::warn!("Ignoring {}, which is not a config key", var);
                 continue;
             }
+            let value = toml::from_str::<toml::Table>(&format!("v = {}", raw))
+                .ok()
+                .and_then(|mut t| t.remove("v"))
+                .unwrap_or(toml::Value::String(raw));
 
-            let rd = BufReader::new(File::open(e.path())?);
-            let ranks = parse_ranks(rd)?;
-            out.push(Tournament::new(ranks, dt, level)?);
+            let mut target = &mut table;
+            for k in &path[..path.len() - 1] {
+                let toml::Value::Table(t) = target else {
+                    return Err(InvalidConfigValue::new(var, "not a table").into());
+                };
+                target = t
+                    .entry(k.to_string())
+                    .or_insert_with(|| toml::Value::Table(Default::default()));
+            }
+            let toml::Value::Table(t) = target else {
+                return Err(InvalidConfigValue::new(var, "not a table").into());
+            };
+            t.insert(path[path.len() - 1].to_owned(), value);
         }
-        Ok(out)
+
+        let config: Self = table.try_into()?;
+        config.validate()?;
+        Ok(config)
     }
 
-    pub fn ingest(&self) -> Result<Vec<Tournament>, ResultReadError> {
-        let mut out = Vec::default();
-        for level in self.levels.iter() {
-            let mut v = self.ingest_level(*level)?;
-            out.append(&mut v);
-        }
-        Ok(out)
+    /// A JSON Schema describing config files, e.g. for editor completion and validation.
+    pub fn json_schema() -> schemars::schema::RootSchema {
+        schemars::schema_for!(Config)
     }
-}
 
-pub fn parse_ranks<R: Read>(r: R) -> Result<Vec<(u64, Team)>, ResultReadError> {
-    let mut ranks = Vec::default();
-    let mut rdr = ReaderBuilder::new()
-        .delimiter(b'\t')
-        .comment(Some(b'#'))
-        .from_reader(r);
+    /// Write the config as TOML, including any default values.
+    pub fn to_toml(&self) -> Result<String, toml::ser::Error> {
+        toml::to_string(self)
+    }
 
-    for result in rdr.records() {
-        let record =
-            result.map_err(|_| io::Error::new(io::ErrorKind::Other, "Could not parse TSV"))?;
-        let Some(rank_str) = record.get(0) else {continue};
-        let Ok(rank) = rank_str.parse::<u64>() else {
-            debug!("Could not parse '{}' as rank, skipping", rank_str);
-            continue;
-        };
-        let Some(p1_str) = record.get(1) else {
-            debug!("No player 1 field, skipping");
-            continue;
-        };
-        let Ok(p1) = p1_str.parse::<PlayerId>() else {
-            debug!("Could not parse '{}' as player ID, skipping", p1_str);
-            continue;
-        };
-        let Some(p2_str) = record.get(2) else {
-            debug!("No player 2 field, skipping");
-            continue;
+    /// Check for values which would produce meaningless ratings,
+    /// returning the first found.
+    pub fn validate(&self) -> Result<(), InvalidConfigValue> {
+        let at_least = |key: &str, value: f64, min: f64| {
+            if value.is_finite() && value >= min {
+                Ok(())
+            } else {
+                Err(InvalidConfigValue::new(
+                    key,
+                    format!("must be at least {}, got {}", min, value),
+                ))
+            }
         };
-        let Ok(p2) = p2_str.parse::<PlayerId>() else {
-            debug!("Could not parse '{}' as player ID, skipping", p2_str);
-            continue;
+        let positive = |key: &str, value: f64| {
+            if value.is_finite() && value > 0.0 {
+                Ok(())
+            } else {
+                Err(InvalidConfigValue::new(
+                    key,
+                    format!("must be positive, got {}", value),
+                ))
+            }
         };
-        ranks.push((
-            rank,
-            Team::new(p1, p2).map_err(|e| ResultReadError::from(InvalidTournament::from(e)))?,
-        ));
+
+        at_least("finish_decay", self.finish_decay, 1.0)?;
+        at_least("age_decay", self.age_decay, 1.0)?;
+        if let Some(months) = self.age_half_life_months {
+            positive("age_half_life_months", months)?;
+        }
+        if self.record_length == 0 {
+            return Err(InvalidConfigValue::new(
+                "record_length",
+                "must be at least 1",
+            ));
+        }
+        if self.levels.is_empty() {
+            return Err(InvalidConfigValue::new("levels", "must not be empty"));
+        }
+        let mut levels: Vec<_> = self.levels.iter().collect();
+        levels.sort_unstable_by_key(|(l, _)| l.name());
+        for (level, lc) in levels {
+            at_least(&format!("levels.{}.points", level), lc.points, 0.0)?;
+            if lc.team_size == 0 {
+                return Err(InvalidConfigValue::new(
+                    format!("levels.{}.team_size", level),
+                    "must be at least 1",
+                ));
+            }
+            for (idx, f) in lc.finish.iter().enumerate() {
+                at_least(&format!("levels.{}.finish[{}]", level, idx), *f, 0.0)?;
+            }
+        }
+        at_least("carry_over", self.carry_over, 0.0)?;
+        positive("elo.k", self.elo.k)?;
+        positive("elo.scale", self.elo.scale)?;
+        at_least("elo.margin", self.elo.margin, 0.0)?;
+        if self.elo.margin_cap == 0 {
+            return Err(InvalidConfigValue::new(
+                "elo.margin_cap",
+                "must be at least 1",
+            ));
+        }
+        positive("glicko.initial_rd", self.glicko.initial_rd)?;
+        positive("glicko.tau", self.glicko.tau)?;
+        positive("glicko.period_days", self.glicko.period_days)?;
+        positive("trueskill.sigma", self.trueskill.sigma)?;
+        positive("trueskill.beta", self.trueskill.beta)?;
+        positive("trueskill.kappa", self.trueskill.kappa)?;
+        positive("max_points", self.max_points)?;
+        if self.window_months == Some(0) {
+            return Err(InvalidConfigValue::new(
+                "window_months",
+                "must be at least 1",
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.withdrawn.factor) {
+            return Err(InvalidConfigValue::new(
+                "withdrawn.factor",
+                format!("must be between 0 and 1, got {}", self.withdrawn.factor),
+            ));
+        }
+        if let Some(ia) = &self.inactivity {
+            positive("inactivity.days", ia.days.into())?;
+            if !(0.0..=1.0).contains(&ia.factor) {
+                return Err(InvalidConfigValue::new(
+                    "inactivity.factor",
+                    format!("must be between 0 and 1, got {}", ia.factor),
+                ));
+            }
+        }
+        if let Some(fs) = &self.field_strength {
+            at_least("field_strength.per_player", fs.per_player, 0.0)?;
+            if let Some(max) = fs.max {
+                positive("field_strength.max", max)?;
+            }
+        }
+        if let Some(ss) = &self.size_scaling {
+            if ss.reference == 0 {
+                return Err(InvalidConfigValue::new(
+                    "size_scaling.reference",
+                    "must be at least 1",
+                ));
+            }
+            if let Some(max) = ss.max {
+                positive("size_scaling.max", max)?;
+            }
+        }
+        if !(1..=12).contains(&self.seasons.month) {
+            return Err(InvalidConfigValue::new(
+                "seasons.month",
+                format!("must be between 1 and 12, got {}", self.seasons.month),
+            ));
+        }
+        if !(1..=31).contains(&self.seasons.day) {
+            return Err(InvalidConfigValue::new(
+                "seasons.day",
+                format!("must be between 1 and 31, got {}", self.seasons.day),
+            ));
+        }
+        Ok(())
     }
-    Ok(ranks)
-}
 
-#[cfg(test)]
-mod tests {
-    use std::fs;
+    pub fn new(
+        finish_decay: f64,
+        age_decay: f64,
+        record_length: usize,
+        levels: &HashMap<Level, f64>,
+    ) -> Self {
+        let mut lvls = default_levels().clone();
+        for (lvl, pb) in levels.iter() {
+            lvls.insert(lvl.clone(), LevelConfig::new(*pb));
+        }
+        Self {
+            algorithm: Default::default(),
+            finish_decay,
+            age_decay,
+            age_half_life_months: None,
+            record_length,
+            levels: lvls,
+            bonus: default_bonus(),
+            bonus_mode: Default::default(),
+            split: Default::default(),
+            min_events: 0,
+            inactivity: None,
+            field_strength: None,
+            size_scaling: None,
+            carry_over: default_carry_over(),
+            max_points: MAX_POINTS,
+            window_months: None,
+            seasons: Default::default(),
+            filename_date: Default::default(),
+            columns: Default::default(),
+            age_unit: Default::default(),
+            ties: Default::default(),
+            codes: Default::default(),
+            withdrawn: Default::default(),
+            #[cfg(feature = "scripting")]
+            formula: None,
+            numbering: Default::default(),
+            direction: Default::default(),
+            elo: Default::default(),
+            glicko: Default::default(),
+            trueskill: Default::default(),
+        }
+    }
 
-    use super::*;
+    pub fn algorithm(mut self, algorithm: Algorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
 
-    fn data_dir() -> PathBuf {
-        let mut d = PathBuf::from(PathBuf::from(env!("CARGO_MANIFEST_DIR")).parent().unwrap());
-        d.push("example_data");
-        d
+    pub fn finish_decay(mut self, finish_decay: f64) -> Self {
+        self.finish_decay = finish_decay;
+        self
     }
 
-    #[test]
-    fn config_deser() {
-        let mut path = data_dir();
-        path.push("default_config.toml");
-        let contents = fs::read_to_string(path).expect("Could not read");
-        let config: Config = toml::from_str(&contents).expect("Could not parse");
-        assert_eq!(config.finish_decay, 1.1);
-        assert_eq!(config.age_decay, 1.1);
-        assert_eq!(config.record_length, 10);
-        assert_eq!(config.levels[&Level::Small], 50.0);
-        assert_eq!(config.levels[&Level::Medium], 125.0);
-        assert_eq!(config.levels[&Level::Major], 200.0);
-        assert_eq!(config.levels[&Level::Championship], 250.0);
+    pub fn age_decay(mut self, age_decay: f64) -> Self {
+        self.age_decay = age_decay;
+        self
+    }
+
+    /// Number of months over which a result's points halve, used in place of the age decay.
+    pub fn age_half_life_months(mut self, months: Option<f64>) -> Self {
+        self.age_half_life_months = months;
+        self
+    }
+
+    pub fn record_length(mut self, record_length: usize) -> Self {
+        self.record_length = record_length;
+        self
+    }
+
+    /// Add a level or change its point base.
+    pub fn level(mut self, level: Level, point_base: f64) -> Self {
+        self.levels
+            .entry(level)
+            .and_modify(|c| c.points = point_base)
+            .or_insert_with(|| LevelConfig::new(point_base));
+        self
+    }
+
+    /// Set the directory containing results for a level, which must already be defined.
+    pub fn level_directory<S: Into<String>>(mut self, level: &Level, directory: S) -> Self {
+        if let Some(c) = self.levels.get_mut(level) {
+            c.directory = Some(directory.into());
+        }
+        self
+    }
+
+    /// Number of player columns in the given level's results.
+    pub fn level_team_size(mut self, level: &Level, team_size: usize) -> Self {
+        if let Some(c) = self.levels.get_mut(level) {
+            c.team_size = team_size;
+        }
+        self
+    }
+
+    pub fn split(mut self, split: PointSplit) -> Self {
+        self.split = split;
+        self
+    }
+
+    /// Players with fewer results than this are provisional.
+    pub fn min_events(mut self, min_events: usize) -> Self {
+        self.min_events = min_events;
+        self
+    }
+
+    pub fn inactivity(mut self, inactivity: Option<InactivityConfig>) -> Self {
+        self.inactivity = inactivity;
+        self
+    }
+
+    pub fn field_strength(mut self, field_strength: Option<FieldStrengthConfig>) -> Self {
+        self.field_strength = field_strength;
+        self
+    }
+
+    pub fn size_scaling(mut self, size_scaling: Option<SizeScalingConfig>) -> Self {
+        self.size_scaling = size_scaling;
+        self
+    }
+
+    pub fn carry_over(mut self, carry_over: f64) -> Self {
+        self.carry_over = carry_over;
+        self
+    }
+
+    /// Cap the points from a single result.
+    pub fn max_points(mut self, max_points: f64) -> Self {
+        self.max_points = max_points;
+        self
+    }
+
+    /// Only count results from this many months before the current time.
+    pub fn window_months(mut self, window_months: Option<u32>) -> Self {
+        self.window_months = window_months;
+        self
+    }
+
+    pub fn seasons(mut self, seasons: SeasonConfig) -> Self {
+        self.seasons = seasons;
+        self
+    }
+
+    pub fn filename_date(mut self, filename_date: FilenameDate) -> Self {
+        self.filename_date = filename_date;
+        self
+    }
+
+    pub fn columns(mut self, columns: TsvColumns) -> Self {
+        self.columns = columns;
+        self
+    }
+
+    pub fn age_unit(mut self, age_unit: AgeUnit) -> Self {
+        self.age_unit = age_unit;
+        self
+    }
+
+    pub fn ties(mut self, ties: TiePolicy) -> Self {
+        self.ties = ties;
+        self
+    }
+
+    pub fn codes(mut self, codes: CodePolicies) -> Self {
+        self.codes = codes;
+        self
+    }
+
+    pub fn withdrawn(mut self, withdrawn: WithdrawnConfig) -> Self {
+        self.withdrawn = withdrawn;
+        self
+    }
+
+    #[cfg(feature = "scripting")]
+    pub fn formula(mut self, formula: Option<scripting::PointFormula>) -> Self {
+        self.formula = formula;
+        self
+    }
+
+    pub fn numbering(mut self, numbering: RankNumbering) -> Self {
+        self.numbering = numbering;
+        self
+    }
+
+    pub fn direction(mut self, direction: RankDirection) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    pub fn elo(mut self, elo: EloConfig) -> Self {
+        self.elo = elo;
+        self
+    }
+
+    pub fn glicko(mut self, glicko: GlickoConfig) -> Self {
+        self.glicko = glicko;
+        self
+    }
+
+    pub fn trueskill(mut self, trueskill: TrueSkillConfig) -> Self {
+        self.trueskill = trueskill;
+        self
+    }
+
+    /// Pairs of maximum initial rank and bonus points.
+    /// A player's bonus is taken from the entry with the lowest maximum rank which is not below theirs.
+    pub fn bonus(mut self, bonus: Vec<(u64, f64)>) -> Self {
+        self.bonus = bonus;
+        self
+    }
+
+    pub fn bonus_mode(mut self, bonus_mode: BonusMode) -> Self {
+        self.bonus_mode = bonus_mode;
+        self
+    }
+
+    /// Rating system used by default.
+    pub fn get_algorithm(&self) -> Algorithm {
+        self.algorithm
+    }
+
+    /// Parameter controlling how the importance of finishing position decays from top to bottom.
+    pub fn get_finish_decay(&self) -> f64 {
+        self.finish_decay
+    }
+
+    /// Parameter controlling how the importance of result age decays across seasons;
+    /// derived from the half-life, if one is given.
+    pub fn get_age_decay(&self) -> f64 {
+        match self.age_half_life_months {
+            // ages are in (roughly) years
+            Some(months) => 2.0_f64.powf(12.0 / months),
+            None => self.age_decay,
+        }
+    }
+
+    pub fn get_age_half_life_months(&self) -> Option<f64> {
+        self.age_half_life_months
+    }
+
+    /// Number of top results analysed for a player's rating.
+    pub fn get_record_length(&self) -> usize {
+        self.record_length
+    }
+
+    /// Base points for the given level of tournament.
+    pub fn get_level(&self, level: &Level) -> Option<f64> {
+        self.levels.get(level).map(|c| c.points)
+    }
+
+    /// All defined levels and their settings.
+    pub fn get_levels(&self) -> &HashMap<Level, LevelConfig> {
+        &self.levels
+    }
+
+    /// Name of the directory containing results for the given level, if it is defined.
+    pub fn get_level_directory(&self, level: &Level) -> Option<&str> {
+        self.levels
+            .get_key_value(level)
+            .map(|(lvl, c)| c.directory.as_deref().unwrap_or(lvl.directory_name()))
+    }
+
+    /// Number of player columns in the given level's results.
+    pub fn get_level_team_size(&self, level: &Level) -> Option<usize> {
+        self.levels.get(level).map(|c| c.team_size)
+    }
+
+    /// How a team's points are shared between its players.
+    pub fn get_split(&self) -> PointSplit {
+        self.split
+    }
+
+    pub fn get_min_events(&self) -> usize {
+        self.min_events
+    }
+
+    pub fn get_inactivity(&self) -> Option<&InactivityConfig> {
+        self.inactivity.as_ref()
+    }
+
+    pub fn get_field_strength(&self) -> Option<&FieldStrengthConfig> {
+        self.field_strength.as_ref()
+    }
+
+    pub fn get_size_scaling(&self) -> Option<&SizeScalingConfig> {
+        self.size_scaling.as_ref()
+    }
+
+    pub fn get_carry_over(&self) -> f64 {
+        self.carry_over
+    }
+
+    pub fn get_max_points(&self) -> f64 {
+        self.max_points
+    }
+
+    /// Points capped at [Config::get_max_points], including infinite points;
+    /// NaN is unchanged.
+    pub fn clamp_points(&self, points: f64) -> f64 {
+        // unlike f64::clamp, does not panic if max_points is NaN
+        if points > self.max_points {
+            self.max_points
+        } else if points < -self.max_points {
+            -self.max_points
+        } else {
+            points
+        }
+    }
+
+    pub fn get_window_months(&self) -> Option<u32> {
+        self.window_months
+    }
+
+    pub fn get_seasons(&self) -> &SeasonConfig {
+        &self.seasons
+    }
+
+    pub fn get_filename_date(&self) -> &FilenameDate {
+        &self.filename_date
+    }
+
+    pub fn get_columns(&self) -> &TsvColumns {
+        &self.columns
+    }
+
+    pub fn get_age_unit(&self) -> AgeUnit {
+        self.age_unit
+    }
+
+    /// Age of a result at the given date/time, for age decay in the current season.
+    pub fn age_of(&self, current_season: i32, datetime: &DateTime<Utc>) -> f64 {
+        let seasons = (current_season - self.seasons.season_of(datetime)) as f64;
+        match self.age_unit {
+            AgeUnit::Seasons => seasons,
+            AgeUnit::Days => match self.seasons.start_of(current_season) {
+                Some(start) => {
+                    let days = (start - datetime.date_naive()).num_days().max(0);
+                    days as f64 / 365.25
+                }
+                None => seasons,
+            },
+        }
+    }
+
+    pub fn get_ties(&self) -> TiePolicy {
+        self.ties
+    }
+
+    pub fn get_codes(&self) -> CodePolicies {
+        self.codes
+    }
+
+    pub fn get_withdrawn(&self) -> WithdrawnConfig {
+        self.withdrawn
+    }
+
+    #[cfg(feature = "scripting")]
+    pub fn get_formula(&self) -> Option<&scripting::PointFormula> {
+        self.formula.as_ref()
+    }
+
+    pub fn get_numbering(&self) -> RankNumbering {
+        self.numbering
+    }
+
+    pub fn get_direction(&self) -> RankDirection {
+        self.direction
+    }
+
+    /// Pairs of maximum initial rank and bonus points.
+    pub fn get_bonus(&self) -> &[(u64, f64)] {
+        &self.bonus
+    }
+
+    pub fn get_bonus_mode(&self) -> BonusMode {
+        self.bonus_mode
+    }
+
+    /// Parameters for the Elo rating system.
+    pub fn get_elo(&self) -> &EloConfig {
+        &self.elo
+    }
+
+    /// Parameters for the Glicko-2 rating system.
+    pub fn get_glicko(&self) -> &GlickoConfig {
+        &self.glicko
+    }
+
+    /// Parameters for the TrueSkill rating system.
+    pub fn get_trueskill(&self) -> &TrueSkillConfig {
+        &self.trueskill
+    }
+}
+
+const LEVEL_PAIRS: [(Level, LevelConfig); 4] = [
+    (Level::SMALL, LevelConfig::new(50.0)),
+    (Level::MEDIUM, LevelConfig::new(125.0)),
+    (Level::MAJOR, LevelConfig::new(200.0)),
+    (Level::CHAMPIONSHIP, LevelConfig::new(250.0)),
+];
+
+fn level_init() -> HashMap<Level, LevelConfig> {
+    LEVEL_PAIRS.into_iter().collect()
+}
+
+static LEVELS: OnceCell<HashMap<Level, LevelConfig>> = OnceCell::new();
+
+pub fn default_levels() -> &'static HashMap<Level, LevelConfig> {
+    LEVELS.get_or_init(level_init)
+}
+
+const BONUS_PAIRS: [(u64, f64); 6] = [
+    (5, 10.0),
+    (10, 7.5),
+    (20, 5.0),
+    (50, 2.5),
+    (100, 1.0),
+    (200, 0.5),
+];
+
+pub fn default_bonus() -> Vec<(u64, f64)> {
+    BONUS_PAIRS.to_vec()
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            algorithm: Default::default(),
+            finish_decay: FINISH_DECAY,
+            age_decay: AGE_DECAY,
+            age_half_life_months: None,
+            record_length: RECORD_LENGTH,
+            levels: default_levels().clone(),
+            bonus: default_bonus(),
+            bonus_mode: Default::default(),
+            split: Default::default(),
+            min_events: 0,
+            inactivity: None,
+            field_strength: None,
+            size_scaling: None,
+            carry_over: default_carry_over(),
+            max_points: MAX_POINTS,
+            window_months: None,
+            seasons: Default::default(),
+            filename_date: Default::default(),
+            columns: Default::default(),
+            age_unit: Default::default(),
+            ties: Default::default(),
+            codes: Default::default(),
+            withdrawn: Default::default(),
+            #[cfg(feature = "scripting")]
+            formula: None,
+            numbering: Default::default(),
+            direction: Default::default(),
+            elo: Default::default(),
+            glicko: Default::default(),
+            trueskill: Default::default(),
+        }
+    }
+}
+
+impl Tournament {
+    pub fn new(
+        mut results: Vec<(u64, Entrant)>,
+        datetime: DateTime<Utc>,
+        level: Level,
+    ) -> Result<Self, InvalidTournament> {
+        let mut prev_place: u64 = 0;
+        results.sort_unstable_by_key(|p| p.0);
+        let mut players = HashSet::with_capacity(results.len() * 4);
+        for (idx, (place, entrant)) in results.iter().enumerate() {
+            for player in entrant.players() {
+                if players.contains(player) {
+                    return Err(RepeatedPlayer(*player).into());
+                }
+                players.insert(*player);
+            }
+            // tied entrants share a place;
+            // otherwise, the place is one more than the number of entrants above
+            if *place == 0 || (place != &prev_place && place != &(idx as u64 + 1)) {
+                return Err(InconsistentRanks().into());
+            }
+            prev_place = *place;
+        }
+        Ok(Self::new_unchecked(results, datetime, level))
+    }
+
+    pub fn new_unchecked(
+        results: Vec<(u64, Entrant)>,
+        datetime: DateTime<Utc>,
+        level: Level,
+    ) -> Self {
+        Self {
+            results,
+            unplaced: Vec::default(),
+            withdrawn: Vec::default(),
+            matches: Vec::default(),
+            datetime,
+            level,
+            metadata: Default::default(),
+        }
+    }
+
+    /// Add entrants which were not placed, e.g. because they were disqualified.
+    pub fn with_unplaced(
+        mut self,
+        unplaced: Vec<(ResultCode, Entrant)>,
+    ) -> Result<Self, InvalidTournament> {
+        let mut players: HashSet<_> = self.results.iter().flat_map(|(_, e)| e.players()).collect();
+        for player in unplaced.iter().flat_map(|(_, e)| e.players()) {
+            if !players.insert(player) {
+                return Err(RepeatedPlayer(*player).into());
+            }
+        }
+        self.unplaced = unplaced;
+        Ok(self)
+    }
+
+    /// Mark placed entrants as having withdrawn during the tournament.
+    /// Entrants which are not placed are ignored.
+    pub fn with_withdrawn(mut self, withdrawn: Vec<Entrant>) -> Self {
+        self.withdrawn = withdrawn
+            .into_iter()
+            .filter(|w| self.results.iter().any(|(_, e)| e == w))
+            .collect();
+        self
+    }
+
+    /// A tournament whose finishing positions are derived from its games;
+    /// see [placings_from_matches].
+    pub fn from_matches(
+        matches: Vec<Match>,
+        datetime: DateTime<Utc>,
+        level: Level,
+    ) -> Result<Self, InvalidTournament> {
+        let results = placings_from_matches(&matches);
+        Ok(Self::new(results, datetime, level)?.with_matches(matches))
+    }
+
+    /// Record the games between entrants, e.g. for head-to-head statistics.
+    /// The finishing positions are unchanged.
+    pub fn with_matches(mut self, matches: Vec<Match>) -> Self {
+        self.matches = matches;
+        self
+    }
+
+    pub fn with_metadata(mut self, metadata: TournamentMetadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    /// Pairs of finishing position and entrant, sorted by position.
+    pub fn results(&self) -> &[(u64, Entrant)] {
+        &self.results
+    }
+
+    /// Entrants which were not placed, with their result codes.
+    pub fn unplaced(&self) -> &[(ResultCode, Entrant)] {
+        &self.unplaced
+    }
+
+    /// Placed entrants which withdrew during the tournament.
+    pub fn withdrawn(&self) -> &[Entrant] {
+        &self.withdrawn
+    }
+
+    /// Games between entrants, if round-level results were given.
+    pub fn matches(&self) -> &[Match] {
+        &self.matches
+    }
+
+    /// Tournament finish date/time.
+    pub fn datetime(&self) -> &DateTime<Utc> {
+        &self.datetime
+    }
+
+    /// Level of tournament.
+    pub fn level(&self) -> &Level {
+        &self.level
+    }
+
+    /// Descriptive information about the tournament.
+    pub fn metadata(&self) -> &TournamentMetadata {
+        &self.metadata
+    }
+
+    pub fn points(
+        &self,
+        current_season: i32,
+        initial_ranks: &PlayerMap<u64>,
+        config: &Config,
+    ) -> Result<PlayerMap<NotNan<f64>>, PointsError> {
+        Ok(self
+            .points_breakdown(current_season, initial_ranks, config)?
+            .into_iter()
+            .map(|(pid, b)| (pid, b.points))
+            .collect())
+    }
+
+    /// As [Tournament::points], but retaining how each player's points were calculated.
+    pub fn points_breakdown(
+        &self,
+        current_season: i32,
+        initial_ranks: &PlayerMap<u64>,
+        config: &Config,
+    ) -> Result<PlayerMap<ResultBreakdown>, PointsError> {
+        self.points_breakdown_with_ratings(
+            current_season,
+            initial_ranks,
+            &HashMap::default(),
+            config,
+        )
+    }
+
+    /// As [Tournament::points_breakdown], but with players' ratings before the tournament,
+    /// which are needed to split a team's points by [PointSplit::Rating].
+    ///
+    /// Unplaced entrants are rated according to the config's [CodePolicies].
+    pub fn points_breakdown_with_ratings(
+        &self,
+        current_season: i32,
+        initial_ranks: &PlayerMap<u64>,
+        initial_ratings: &PlayerMap<NotNan<f64>>,
+        config: &Config,
+    ) -> Result<PlayerMap<ResultBreakdown>, PointsError> {
+        let mut out =
+            PlayerMap::with_capacity_and_hasher(self.results.len() * 2, PlayerHasher::default());
+        // unplaced entrants are below every placed entrant
+        let below_placed = self.results.len() as u64 + 1;
+        let mut results = Cow::Borrowed(self.results.as_slice());
+        let mut placed_last = Vec::default();
+        for (code, entrant) in self.unplaced.iter() {
+            match config.codes.get(*code) {
+                CodePolicy::Zero => {
+                    for player in entrant.players() {
+                        out.insert(
+                            *player,
+                            ResultBreakdown {
+                                datetime: self.datetime,
+                                level: self.level.clone(),
+                                tournament: self.metadata.clone(),
+                                place: below_placed,
+                                code: Some(*code),
+                                entrant: entrant.clone(),
+                                point_base: 0.0,
+                                finish_factor: 0.0,
+                                age_factor: 1.0,
+                                field_factor: 1.0,
+                                carry_factor: 1.0,
+                                withdrawn_factor: 1.0,
+                                bonus: 0.0,
+                                mean_opponent_rank: None,
+                                points: NotNan::default(),
+                            },
+                        );
+                    }
+                }
+                CodePolicy::Omit => (),
+                CodePolicy::Last => {
+                    results.to_mut().push((below_placed, entrant.clone()));
+                    placed_last.push((*code, entrant));
+                }
+            }
+        }
+        let Some((last_place, _)) = results.last() else {
+            return Ok(out);
+        };
+        let mut bonus: f64 = 0.0;
+        let age = config.age_of(current_season, &self.datetime);
+        let mut bonus_update: f64 = 0.0;
+        let mut prev_place = last_place.saturating_add(1);
+        let level_config = config
+            .levels
+            .get(&self.level)
+            .ok_or_else(|| PointsError::UnknownLevel(self.level.clone()))?;
+        let point_base =
+            level_config.points * config.size_scaling.map_or(1.0, |s| s.factor(results.len()));
+        let age_factor = 1.0 / config.get_age_decay().powf(age);
+        let field_factor = config.field_strength.map_or(1.0, |fs| {
+            fs.factor(results.iter().flat_map(|(_, e)| e.players()), initial_ranks)
+        });
+        let mut n_tied: HashMap<u64, usize> = HashMap::default();
+        // sum and count of all ranked players' initial ranks, to find opponents' mean rank
+        let mut rank_sum = 0;
+        let mut n_ranked = 0;
+        for (place, entrant) in results.iter() {
+            *n_tied.entry(*place).or_default() += 1;
+            for rank in entrant
+                .players()
+                .iter()
+                .filter_map(|p| initial_ranks.get(p))
+            {
+                rank_sum += rank;
+                n_ranked += 1;
+            }
+        }
+        for (place, entrant) in results.iter().rev() {
+            // only entrants below this place, not those tied with it, count towards the bonus
+            if place != &prev_place {
+                bonus += bonus_update;
+                bonus_update = 0.0;
+                prev_place = *place;
+            }
+            #[allow(unused_mut)]
+            let (mut point_base, mut finish_factor, mut age_factor) = (
+                point_base,
+                config.ties.finish_factor(
+                    |p| level_config.finish_factor(config.finish_decay, p),
+                    *place,
+                    n_tied[place],
+                ),
+                age_factor,
+            );
+            #[cfg(feature = "scripting")]
+            if let Some(formula) = &config.formula {
+                let p = formula.points(*place, point_base, age, results.len())?;
+                (point_base, finish_factor, age_factor) = (p, 1.0, 1.0);
+            }
+            let (own_sum, own_ranked) = entrant
+                .players()
+                .iter()
+                .filter_map(|p| initial_ranks.get(p))
+                .fold((0, 0), |(s, n), r| (s + r, n + 1));
+            let mean_opponent_rank = (n_ranked > own_ranked)
+                .then(|| (rank_sum - own_sum) as f64 / (n_ranked - own_ranked) as f64);
+            let withdrawn_factor = if self.withdrawn.contains(entrant) {
+                config.withdrawn.points_factor()
+            } else {
+                1.0
+            };
+            for player in entrant.players() {
+                let mut breakdown = ResultBreakdown {
+                    datetime: self.datetime,
+                    level: self.level.clone(),
+                    tournament: self.metadata.clone(),
+                    place: *place,
+                    code: None,
+                    entrant: entrant.clone(),
+                    point_base,
+                    finish_factor,
+                    age_factor,
+                    field_factor,
+                    carry_factor: 1.0,
+                    withdrawn_factor,
+                    bonus,
+                    mean_opponent_rank,
+                    points: NotNan::default(),
+                };
+                let share = config
+                    .split
+                    .share(player, entrant.players(), initial_ratings);
+                breakdown.points = NotNan::new(breakdown.entrant_points(config) * share)
+                    .map_err(|_| PointsError::NaN(*player))?;
+                out.insert(*player, breakdown);
+            }
+            let player_bonuses = entrant
+                .players()
+                .iter()
+                .filter_map(|p| initial_ranks.get(p))
+                .map(|rank| bonus_points(*rank, &config.bonus));
+            bonus_update += match config.bonus_mode {
+                BonusMode::Player => player_bonuses.sum(),
+                BonusMode::Team => player_bonuses.fold(0.0, f64::max),
+            };
+        }
+        for (code, entrant) in placed_last {
+            for player in entrant.players() {
+                if let Some(b) = out.get_mut(player) {
+                    b.code = Some(code);
+                }
+            }
+        }
+        Ok(out)
+    }
+}
+
+/// How a player's points from one tournament were calculated.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResultBreakdown {
+    /// Tournament finish date/time.
+    pub datetime: DateTime<Utc>,
+    /// Level of tournament.
+    pub level: Level,
+    /// Descriptive information about the tournament.
+    #[serde(default)]
+    pub tournament: TournamentMetadata,
+    /// Finishing position.
+    pub place: u64,
+    /// The entrant's result code, if it was not placed,
+    /// in which case its place is below every placed entrant.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<ResultCode>,
+    /// The player's entrant: themselves alone, or their team.
+    #[serde(alias = "team")]
+    pub entrant: Entrant,
+    /// Base points for the tournament's level,
+    /// scaled by its number of entrants if the config's [SizeScalingConfig] is given.
+    /// If the config has a point formula (with the `scripting` feature),
+    /// this is instead the formula's result, and the finish and age factors are 1.
+    pub point_base: f64,
+    /// Multiplier from finishing position: `1 / finish_decay ^ place`
+    /// or from the level's finish table (see [LevelConfig::finish]),
+    /// adjusted for ties according to the config's [TiePolicy].
+    pub finish_factor: f64,
+    /// Multiplier from the age of the result: `1 / age_decay ^ age`,
+    /// where age is measured according to the config's [AgeUnit].
+    pub age_factor: f64,
+    /// Multiplier from the strength of the tournament's field; see [FieldStrengthConfig].
+    #[serde(default = "default_factor")]
+    pub field_factor: f64,
+    /// Multiplier applied to the points when carried over from previous seasons.
+    #[serde(default = "default_factor")]
+    pub carry_factor: f64,
+    /// Multiplier applied to the points if the entrant withdrew during the tournament;
+    /// see [WithdrawnConfig].
+    #[serde(default = "default_factor")]
+    pub withdrawn_factor: f64,
+    /// Bonus points for finishing above ranked players.
+    pub bonus: f64,
+    /// Mean rank of the player's ranked opponents before the tournament,
+    /// if any were ranked.
+    #[serde(default)]
+    pub mean_opponent_rank: Option<f64>,
+    /// Points awarded to the player;
+    /// a team's points are shared between its players according to the config's [PointSplit].
+    pub points: NotNan<f64>,
+}
+
+impl ResultBreakdown {
+    /// Points awarded to the player's whole entrant, before they are shared between its players.
+    pub fn entrant_points(&self, config: &Config) -> f64 {
+        let points = config.clamp_points(
+            self.point_base * self.finish_factor * self.age_factor * self.field_factor + self.bonus,
+        );
+        points * self.carry_factor * self.withdrawn_factor
+    }
+}
+
+fn bonus_points(rank: u64, table: &[(u64, f64)]) -> f64 {
+    table
+        .iter()
+        .filter(|(max_rank, _)| rank <= *max_rank)
+        .min_by_key(|(max_rank, _)| *max_rank)
+        .map(|(_, points)| *points)
+        .unwrap_or(0.0)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlayerRecord {
+    pub id: PlayerId,
+    points: BinaryHeap<Reverse<NotNan<f64>>>,
+    pub rating: NotNan<f64>,
+    /// Maximum number of results counted towards the rating.
+    #[serde(default = "default_record_length")]
+    record_length: usize,
+    /// Breakdowns of results added with [PlayerRecord::add_breakdown], in the order they were added.
+    #[serde(default)]
+    history: Vec<ResultBreakdown>,
+    /// Number of results added with [PlayerRecord::add_breakdown],
+    /// including any since dropped from the history by [PlayerRecord::trim_history].
+    #[serde(default)]
+    events: usize,
+}
+
+impl PlayerRecord {
+    pub fn new(id: PlayerId, record_length: usize) -> Self {
+        Self {
+            id,
+            points: BinaryHeap::with_capacity(record_length + 1),
+            rating: NotNan::new(0.0).unwrap(),
+            record_length,
+            history: Vec::default(),
+            events: 0,
+        }
+    }
+
+    /// A record with the given results' points; NaN points are ignored.
+    pub fn new_with_points(id: PlayerId, record_length: usize, points: &[f64]) -> Self {
+        let mut player = Self::new(id, record_length);
+        for p in points.iter().filter_map(|p| NotNan::new(*p).ok()) {
+            player.add_result(p);
+        }
+        player
+    }
+
+    /// Maximum number of results counted towards the rating.
+    pub fn record_length(&self) -> usize {
+        self.record_length
+    }
+
+    pub fn add_result(&mut self, points: NotNan<f64>) -> (bool, NotNan<f64>) {
+        let p = Reverse(points);
+        if self.points.len() < self.record_length {
+            self.rating += points;
+            self.points.push(p);
+            return (points != 0.0, self.rating);
+        }
+
+        self.points.push(p);
+        let removed = self.points.pop().unwrap().0;
+        if removed == points {
+            (false, self.rating)
+        } else {
+            self.rating = self.rating - removed + points;
+            (true, self.rating)
+        }
+    }
+
+    /// Add a result, retaining its breakdown in the player's history.
+    pub fn add_breakdown(&mut self, breakdown: ResultBreakdown) -> (bool, NotNan<f64>) {
+        let points = breakdown.points;
+        self.history.push(breakdown);
+        self.events += 1;
+        self.add_result(points)
+    }
+
+    /// Breakdowns of results added with [PlayerRecord::add_breakdown].
+    pub fn history(&self) -> &[ResultBreakdown] {
+        &self.history
+    }
+
+    /// Number of results added with [PlayerRecord::add_breakdown] and not since expired,
+    /// even if they are no longer in the history.
+    pub fn events(&self) -> usize {
+        self.events.max(self.history.len())
+    }
+
+    /// Drop results which do not count towards the rating from the history,
+    /// except the latest, so that it holds at most `record_length + 1` results.
+    ///
+    /// [PlayerRecord::events] still counts the dropped results,
+    /// but they are no longer explained, and cannot count again if others expire.
+    pub fn trim_history(&mut self) {
+        self.events = self.events();
+        let counted: Vec<_> = self.counted_history().into_iter().map(|(_, c)| c).collect();
+        let latest = counted.len().saturating_sub(1);
+        self.history = std::mem::take(&mut self.history)
+            .into_iter()
+            .zip(counted)
+            .enumerate()
+            .filter(|(idx, (_, counted))| *counted || *idx == latest)
+            .map(|(_, (b, _))| b)
+            .collect();
+    }
+
+    /// Remove results from before the cutoff, recalculating the rating from those remaining.
+    ///
+    /// The rating is rebuilt from the history, so records with fewer results in their history
+    /// than count towards their rating (i.e. some were added with [PlayerRecord::add_result])
+    /// are left unchanged.
+    /// Returns whether any results were removed.
+    pub fn expire_before(&mut self, cutoff: &DateTime<Utc>) -> bool {
+        let total = self.history.len();
+        if total < self.points.len() {
+            return false;
+        }
+        self.history.retain(|b| b.datetime >= *cutoff);
+        if self.history.len() == total {
+            return false;
+        }
+        self.events -= (total - self.history.len()).min(self.events);
+        self.points.clear();
+        self.rating = NotNan::new(0.0).unwrap();
+        let points: Vec<_> = self.history.iter().map(|b| b.points).collect();
+        for p in points {
+            self.add_result(p);
+        }
+        true
+    }
+
+    /// A record containing only this record's counted results,
+    /// with their points multiplied by `factor`,
+    /// e.g. to start a new season from a discounted previous rating.
+    pub fn carry_over(&self, factor: f64) -> Self {
+        let mut out = Self::new(self.id, self.record_length);
+        if self.history.len() < self.points.len() {
+            for Reverse(p) in self.points.iter() {
+                out.add_result(*p * factor);
+            }
+            return out;
+        }
+        for (b, counted) in self.counted_history() {
+            if counted {
+                let mut b = b.clone();
+                b.points *= factor;
+                b.carry_factor *= factor;
+                out.add_breakdown(b);
+            }
+        }
+        out
+    }
+
+    /// Breakdowns of results, with whether each currently counts towards the rating.
+    ///
+    /// Where results with equal points compete for the last counted place,
+    /// the earliest-added counts.
+    pub fn counted_history(&self) -> Vec<(&ResultBreakdown, bool)> {
+        let mut idxs: Vec<_> = (0..self.history.len()).collect();
+        idxs.sort_by_key(|idx| Reverse(self.history[*idx].points));
+        let mut counted = vec![false; self.history.len()];
+        for idx in idxs.into_iter().take(self.points.len()) {
+            counted[idx] = true;
+        }
+        self.history.iter().zip(counted).collect()
+    }
+
+    /// Strength of schedule: the mean, over counted results with any ranked opponents,
+    /// of the opponents' mean rank before the tournament.
+    /// Lower is a stronger schedule.
+    pub fn strength_of_schedule(&self) -> Option<f64> {
+        let means: Vec<_> = self
+            .counted_history()
+            .into_iter()
+            .filter(|(_, counted)| *counted)
+            .filter_map(|(b, _)| b.mean_opponent_rank)
+            .collect();
+        (!means.is_empty()).then(|| means.iter().sum::<f64>() / means.len() as f64)
+    }
+
+    /// Statistics of the player's results with each partner, from their history.
+    ///
+    /// Every teammate in a team of more than two counts as a partner.
+    /// Sorted by number of events together (most first), then partner ID.
+    pub fn partner_stats(&self) -> Vec<PartnerStats> {
+        // partner -> (events, sum of places, points)
+        let mut totals: PlayerMap<(usize, u64, f64)> = HashMap::default();
+        for b in self.history.iter() {
+            for partner in b.entrant.teammates(&self.id).unwrap_or_default() {
+                let t = totals.entry(*partner).or_default();
+                t.0 += 1;
+                t.1 += b.place;
+                t.2 += b.points.into_inner();
+            }
+        }
+        let mut out: Vec<_> = totals
+            .into_iter()
+            .map(|(partner, (events, places, points))| PartnerStats {
+                partner,
+                events,
+                mean_place: places as f64 / events as f64,
+                points,
+            })
+            .collect();
+        out.sort_unstable_by_key(|s| (Reverse(s.events), s.partner));
+        out
+    }
+}
+
+/// A player's results with one partner.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PartnerStats {
+    pub partner: PlayerId,
+    /// Number of tournaments played together.
+    pub events: usize,
+    /// Mean finishing place in those tournaments.
+    pub mean_place: f64,
+    /// Total points the player earned in those tournaments, whether or not they are counted.
+    pub points: f64,
+}
+
+/// Collect ratings into a map, leaving out any which are NaN
+/// (e.g. from degenerate algorithm parameters) with a warning.
+pub(crate) fn collect_ratings<I: IntoIterator<Item = (PlayerId, f64)>>(
+    ratings: I,
+) -> PlayerMap<NotNan<f64>> {
+    ratings
+        .into_iter()
+        .filter_map(|(pid, r)| match NotNan::new(r) {
+            Ok(r) => Some((pid, r)),
+            Err(_) => {
+                log::warn!("Rating of player {} is not a number; leaving them out", pid);
+                None
+            }
+        })
+        .collect()
+}
+
+fn ratings_to_ranks<K: Hash + Eq + Ord + Clone, S: BuildHasher>(
+    ratings: &HashMap<K, NotNan<f64>, S>,
+    into: &mut HashMap<K, u64, S>,
+    numbering: RankNumbering,
+    direction: RankDirection,
+) {
+    into.clear();
+    let mut pid_scores: Vec<_> = ratings
+        .iter()
+        .map(|(pid, rat)| (pid.clone(), *rat))
+        .collect();
+    pid_scores.sort_unstable_by(|(p1, r1), (p2, r2)| match direction {
+        RankDirection::Descending => r2.cmp(r1).then_with(|| p1.cmp(p2)),
+        RankDirection::Ascending => (r1, p1).cmp(&(r2, p2)),
+    });
+    let mut prev_rank = 0;
+    let mut prev_score = None;
+
+    for (idx, (pid, score)) in pid_scores.into_iter().enumerate() {
+        if prev_score != Some(score) || numbering == RankNumbering::Ordinal {
+            prev_rank = match numbering {
+                RankNumbering::Dense => prev_rank + 1,
+                RankNumbering::Standard | RankNumbering::Ordinal => idx as u64 + 1,
+            };
+            prev_score = Some(score);
+        }
+        into.insert(pid, prev_rank);
+    }
+}
+
+/// A method of rating players based on tournament results.
+pub trait RatingSystem {
+    /// Incorporate the results of a tournament.
+    /// Tournaments are added in chronological order;
+    /// `ranks` are players' ranks based on all results before this tournament's date.
+    ///
+    /// Returns an error, without adding the tournament, if it cannot be rated.
+    fn add_tournament(
+        &mut self,
+        tournament: &Tournament,
+        ranks: &PlayerMap<u64>,
+    ) -> Result<(), PointsError>;
+
+    /// Incorporate the results of several tournaments at the same date/time, in order.
+    ///
+    /// By default they are added one at a time, stopping at the first which cannot be rated;
+    /// systems may override this to process them together, e.g. in parallel.
+    fn add_simultaneous(
+        &mut self,
+        tournaments: &[&Tournament],
+        ranks: &PlayerMap<u64>,
+    ) -> Result<(), PointsError> {
+        for t in tournaments {
+            self.add_tournament(t, ranks)?;
+        }
+        Ok(())
+    }
+
+    /// Current rating of every player seen so far.
+    ///
+    /// Players whose rating is not a number are left out.
+    fn ratings(&self) -> PlayerMap<NotNan<f64>>;
+
+    /// Move the system's notion of the current time forward,
+    /// before ranks are calculated at that time (e.g. to expire old results).
+    fn advance_to(&mut self, _datetime: &DateTime<Utc>) {}
+
+    /// How ranks are numbered when ratings are equal.
+    fn numbering(&self) -> RankNumbering {
+        RankNumbering::default()
+    }
+
+    /// Whether the highest or lowest rating is rank 1.
+    fn direction(&self) -> RankDirection {
+        RankDirection::default()
+    }
+
+    /// Probability that a team of the `first` players beats a team of the `second` players
+    /// in a single game, given the results so far.
+    ///
+    /// By default, each side's chance is its share of the two teams' summed ratings
+    /// (taking the lower rating as better if ranks are [RankDirection::Ascending]),
+    /// and evenly matched if neither side has a positive rating.
+    fn win_probability(&self, first: &[PlayerId], second: &[PlayerId]) -> f64 {
+        let ratings = self.ratings();
+        let total = |players: &[PlayerId]| {
+            players
+                .iter()
+                .filter_map(|p| ratings.get(p))
+                .map(|r| r.into_inner())
+                .sum::<f64>()
+        };
+        rating_share(total(first), total(second), self.direction())
+    }
+}
+
+/// Probability of a side winning as its share of two sides' (non-negative) ratings;
+/// see [RatingSystem::win_probability].
+fn rating_share(first: f64, second: f64, direction: RankDirection) -> f64 {
+    let (mut a, mut b) = (first.max(0.0), second.max(0.0));
+    if direction == RankDirection::Ascending {
+        std::mem::swap(&mut a, &mut b);
+    }
+    if a + b > 0.0 {
+        a / (a + b)
+    } else {
+        0.5
+    }
+}
+
+/// The WFDF rating system, where a player's rating is the sum of their best results' points.
+///
+/// Teams of two or more players are also rated,
+/// as the sum of the team's best results' points, which are never split.
+#[derive(Debug, Clone)]
+pub struct PointsSystem {
+    config: Config,
+    current_season: i32,
+    records: PlayerMap<PlayerRecord>,
+    /// Records of teams, whose IDs are the team's lowest player ID.
+    team_records: HashMap<Team, PlayerRecord>,
+}
+
+impl PointsSystem {
+    /// Results' ages are calculated relative to `current_season`,
+    /// which is independent of the tournaments added (e.g. to produce a historical ranking).
+    pub fn new(config: Config, current_season: i32) -> Self {
+        Self {
+            config,
+            current_season,
+            records: Default::default(),
+            team_records: Default::default(),
+        }
+    }
+
+    /// Start from a previous season's records,
+    /// keeping each counted result with its points multiplied by the config's carry-over factor.
+    ///
+    /// Existing records of the same players and teams are replaced.
+    pub fn with_previous_season(mut self, previous: &Checkpoint) -> Self {
+        let factor = self.config.carry_over;
+        for (pid, rec) in previous.records.iter() {
+            self.records.insert(*pid, rec.carry_over(factor));
+        }
+        for (team, rec) in previous.team_records.iter() {
+            self.team_records
+                .insert(team.clone(), rec.carry_over(factor));
+        }
+        self
+    }
+
+    /// Continue from previously-computed records.
+    ///
+    /// Points in the records are not re-decayed, so this should be used within a single season.
+    pub fn with_records(
+        config: Config,
+        current_season: i32,
+        records: PlayerMap<PlayerRecord>,
+    ) -> Self {
+        Self {
+            config,
+            current_season,
+            records,
+            team_records: Default::default(),
+        }
+    }
+
+    /// Continue from previously-computed team records.
+    pub fn with_team_records(mut self, team_records: HashMap<Team, PlayerRecord>) -> Self {
+        self.team_records = team_records;
+        self
+    }
+
+    pub fn records(&self) -> &PlayerMap<PlayerRecord> {
+        &self.records
+    }
+
+    /// Records of every team seen so far.
+    /// A team record's `id` is the team's lowest player ID.
+    pub fn team_records(&self) -> &HashMap<Team, PlayerRecord> {
+        &self.team_records
+    }
+
+    pub fn team_ratings(&self) -> HashMap<Team, NotNan<f64>> {
+        self.team_records
+            .iter()
+            .map(|(team, rec)| (team.clone(), rec.rating))
+            .collect()
+    }
+
+    pub fn into_records(self) -> PlayerMap<PlayerRecord> {
+        self.records
+    }
+
+    /// Remove results from before the cutoff,
+    /// and any players and teams left with no results.
+    pub fn expire_before(&mut self, cutoff: &DateTime<Utc>) {
+        self.records.retain(|_, rec| {
+            rec.expire_before(cutoff);
+            !rec.history().is_empty()
+        });
+        self.team_records.retain(|_, rec| {
+            rec.expire_before(cutoff);
+            !rec.history().is_empty()
+        });
+    }
+}
+
+impl PointsSystem {
+    /// Add one tournament's points breakdowns to players' and teams' records.
+    fn add_breakdowns(&mut self, breakdowns: PlayerMap<ResultBreakdown>) {
+        for (pid, breakdown) in breakdowns {
+            if let Entrant::Team(team) = &breakdown.entrant {
+                // each player's breakdown is the same, but for the split
+                if team.players()[0] == pid {
+                    let total = breakdown.entrant_points(&self.config);
+                    // not NaN, as the players' shares of it are not
+                    if let Ok(points) = NotNan::new(total) {
+                        let mut team_breakdown = breakdown.clone();
+                        team_breakdown.points = points;
+                        self.team_records
+                            .entry(team.clone())
+                            .or_insert_with(|| PlayerRecord::new(pid, self.config.record_length))
+                            .add_breakdown(team_breakdown);
+                    }
+                }
+            }
+            let record = self
+                .records
+                .entry(pid)
+                .or_insert_with(|| PlayerRecord::new(pid, self.config.record_length));
+            record.add_breakdown(breakdown);
+        }
+    }
+}
+
+impl RatingSystem for PointsSystem {
+    /// Tournaments whose points cannot be calculated (see [PointsError]) are an error.
+    fn add_tournament(
+        &mut self,
+        tournament: &Tournament,
+        ranks: &PlayerMap<u64>,
+    ) -> Result<(), PointsError> {
+        let ratings = match self.config.split {
+            PointSplit::Rating => self.ratings(),
+            PointSplit::Even | PointSplit::Full => HashMap::default(),
+        };
+        let breakdowns = tournament.points_breakdown_with_ratings(
+            self.current_season,
+            ranks,
+            &ratings,
+            &self.config,
+        )?;
+        self.add_breakdowns(breakdowns);
+        Ok(())
+    }
+
+    /// With the `rayon` feature, tournaments' points are calculated in parallel,
+    /// unless points are split by rating (where each tournament changes the next one's split).
+    /// If any tournament's points cannot be calculated, none are added.
+    #[cfg(feature = "rayon")]
+    fn add_simultaneous(
+        &mut self,
+        tournaments: &[&Tournament],
+        ranks: &PlayerMap<u64>,
+    ) -> Result<(), PointsError> {
+        if tournaments.len() < 2 || self.config.split == PointSplit::Rating {
+            for t in tournaments {
+                self.add_tournament(t, ranks)?;
+            }
+            return Ok(());
+        }
+        let ratings = PlayerMap::default();
+        let breakdowns: Vec<_> = tournaments
+            .par_iter()
+            .map(|t| {
+                t.points_breakdown_with_ratings(self.current_season, ranks, &ratings, &self.config)
+            })
+            .collect::<Result<_, _>>()?;
+        for b in breakdowns {
+            self.add_breakdowns(b);
+        }
+        Ok(())
+    }
+
+    fn advance_to(&mut self, datetime: &DateTime<Utc>) {
+        let Some(months) = self.config.window_months else {
+            return;
+        };
+        if let Some(cutoff) = datetime.checked_sub_months(Months::new(months)) {
+            self.expire_before(&cutoff);
+        }
+    }
+
+    fn numbering(&self) -> RankNumbering {
+        self.config.numbering
+    }
+
+    fn direction(&self) -> RankDirection {
+        self.config.direction
+    }
+
+    fn ratings(&self) -> PlayerMap<NotNan<f64>> {
+        self.records
+            .iter()
+            .map(|(pid, rec)| (*pid, rec.rating))
+            .collect()
+    }
+
+    /// As the default, but only looking up the given players' records.
+    fn win_probability(&self, first: &[PlayerId], second: &[PlayerId]) -> f64 {
+        let total = |players: &[PlayerId]| {
+            players
+                .iter()
+                .filter_map(|p| self.records.get(p))
+                .map(|r| r.rating.into_inner())
+                .sum::<f64>()
+        };
+        rating_share(total(first), total(second), self.config.direction)
+    }
+}
+
+impl<R: RatingSystem + ?Sized> RatingSystem for &mut R {
+    fn add_tournament(
+        &mut self,
+        tournament: &Tournament,
+        ranks: &PlayerMap<u64>,
+    ) -> Result<(), PointsError> {
+        (**self).add_tournament(tournament, ranks)
+    }
+
+    fn add_simultaneous(
+        &mut self,
+        tournaments: &[&Tournament],
+        ranks: &PlayerMap<u64>,
+    ) -> Result<(), PointsError> {
+        (**self).add_simultaneous(tournaments, ranks)
+    }
+
+    fn ratings(&self) -> PlayerMap<NotNan<f64>> {
+        (**self).ratings()
+    }
+
+    fn advance_to(&mut self, datetime: &DateTime<Utc>) {
+        (**self).advance_to(datetime)
+    }
+
+    fn numbering(&self) -> RankNumbering {
+        (**self).numbering()
+    }
+
+    fn direction(&self) -> RankDirection {
+        (**self).direction()
+    }
+
+    fn win_probability(&self, first: &[PlayerId], second: &[PlayerId]) -> f64 {
+        (**self).win_probability(first, second)
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("Tournament at {tournament} is earlier than previous tournament at {previous}")]
+pub struct UnorderedTournaments {
+    pub previous: DateTime<Utc>,
+    pub tournament: DateTime<Utc>,
+}
+
+/// Error while adding tournaments to a [RankingEngine].
+#[derive(Debug, Error)]
+pub enum RankingError {
+    #[error(transparent)]
+    Unordered(#[from] UnorderedTournaments),
+    #[error("Could not rate tournament at {datetime}: {source}")]
+    Points {
+        datetime: DateTime<Utc>,
+        source: PointsError,
+    },
+}
+
+/// Error while ranking tournaments as they are read, as by [RankingEngine::add_chunked].
+#[derive(Debug, Error)]
+pub enum ChunkedError {
+    #[error(transparent)]
+    Read(#[from] ResultReadError),
+    #[error(transparent)]
+    Ranking(#[from] RankingError),
+}
+
+/// Ranks players incrementally, as tournaments arrive.
+///
+/// Tournaments must be added in chronological order.
+/// Ranks are updated between tournament dates,
+/// so tournaments on the same date use the same ranks.
+#[derive(Debug, Clone)]
+pub struct RankingEngine<R: RatingSystem = PointsSystem> {
+    system: R,
+    /// Ranks based on all results before `last_datetime`.
+    ranks_before: PlayerMap<u64>,
+    last_datetime: Option<DateTime<Utc>>,
+    /// Ranks used in place of calculated ones for tournaments before a date/time.
+    initial_ranks: Option<(PlayerMap<u64>, DateTime<Utc>)>,
+    /// Keys of the tournaments added at `last_datetime`.
+    added_at_last: Vec<TournamentKey>,
+    /// Keys of tournaments at `last_datetime` which were included in a resumed [Checkpoint],
+    /// and are skipped if added again.
+    resumed: Vec<TournamentKey>,
+}
+
+/// Level and results of a tournament,
+/// by which a [Checkpoint] recognises tournaments it already includes.
+pub type TournamentKey = (Level, Vec<(u64, Entrant)>);
+
+fn tournament_key(tournament: &Tournament) -> TournamentKey {
+    (tournament.level.clone(), tournament.results.clone())
+}
+
+impl<R: RatingSystem> RankingEngine<R> {
+    pub fn new(system: R) -> Self {
+        Self {
+            system,
+            ranks_before: Default::default(),
+            last_datetime: None,
+            initial_ranks: None,
+            added_at_last: Vec::default(),
+            resumed: Vec::default(),
+        }
+    }
+
+    /// Use the given ranks for tournaments before `until`,
+    /// rather than ranks calculated from the results added so far.
+    ///
+    /// This seeds bonus points with a ranking from before the available results,
+    /// e.g. for the first season of results.
+    pub fn with_initial_ranks(mut self, ranks: PlayerMap<u64>, until: DateTime<Utc>) -> Self {
+        self.initial_ranks = Some((ranks, until));
+        self
+    }
+
+    /// Add a tournament, which must not be earlier than those already added.
+    ///
+    /// Returns an error if it cannot be rated (see [RatingSystem::add_tournament]).
+    pub fn add_tournament<T: Borrow<Tournament>>(
+        &mut self,
+        tournament: T,
+    ) -> Result<(), RankingError> {
+        let t = tournament.borrow();
+        self.move_to(&t.datetime)?;
+        if self.skip_resumed(t) {
+            return Ok(());
+        }
+        self.system
+            .add_tournament(t, &self.ranks_before)
+            .map_err(|source| RankingError::Points {
+                datetime: t.datetime,
+                source,
+            })?;
+        self.added_at_last.push(tournament_key(t));
+        Ok(())
+    }
+
+    /// Add tournaments in chronological order.
+    ///
+    /// Consecutive tournaments at the same date/time are passed to the rating system together
+    /// (see [RatingSystem::add_simultaneous]), so that it can process them in parallel.
+    /// Tournaments before an out-of-order one, or one which cannot be rated, are still added.
+    pub fn add_tournaments<T: Borrow<Tournament>>(
+        &mut self,
+        tournaments: &[T],
+    ) -> Result<(), RankingError> {
+        for group in tournaments.chunk_by(|a, b| a.borrow().datetime == b.borrow().datetime) {
+            let datetime = group[0].borrow().datetime;
+            self.move_to(&datetime)?;
+            let group: Vec<&Tournament> = group
+                .iter()
+                .map(Borrow::borrow)
+                .filter(|t| !self.skip_resumed(t))
+                .collect();
+            self.system
+                .add_simultaneous(&group, &self.ranks_before)
+                .map_err(|source| RankingError::Points { datetime, source })?;
+            self.added_at_last
+                .extend(group.into_iter().map(tournament_key));
+        }
+        Ok(())
+    }
+
+    /// Whether the tournament was included in the [Checkpoint] this engine resumed from,
+    /// and so should not be added again.
+    ///
+    /// Each included tournament is only skipped once.
+    fn skip_resumed(&mut self, tournament: &Tournament) -> bool {
+        if self.resumed.is_empty() {
+            return false;
+        }
+        let key = tournament_key(tournament);
+        match self.resumed.iter().position(|k| *k == key) {
+            Some(idx) => {
+                self.added_at_last.push(self.resumed.swap_remove(idx));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Add tournaments from a fallible iterator in chronological order,
+    /// such as [ResultIngester::iter], `chunk_size` at a time.
+    ///
+    /// Each chunk is dropped once it has been added,
+    /// so at most `chunk_size` parsed tournaments are held in memory at once,
+    /// however many there are in total.
+    pub fn add_chunked<I>(&mut self, tournaments: I, chunk_size: usize) -> Result<(), ChunkedError>
+    where
+        I: IntoIterator<Item = Result<Tournament, ResultReadError>>,
+    {
+        let mut chunk = Vec::with_capacity(chunk_size.max(1));
+        for t in tournaments {
+            chunk.push(t?);
+            if chunk.len() >= chunk_size {
+                self.add_tournaments(&chunk)?;
+                chunk.clear();
+            }
+        }
+        self.add_tournaments(&chunk)?;
+        Ok(())
+    }
+
+    /// Update the ranks used for tournaments at the given date/time, if it is a new one.
+    fn move_to(&mut self, datetime: &DateTime<Utc>) -> Result<(), UnorderedTournaments> {
+        match self.last_datetime {
+            Some(prev) if prev > *datetime => {
+                return Err(UnorderedTournaments {
+                    previous: prev,
+                    tournament: *datetime,
+                })
+            }
+            Some(prev) if prev == *datetime => (),
+            _ => {
+                self.system.advance_to(datetime);
+                ratings_to_ranks(
+                    &self.system.ratings(),
+                    &mut self.ranks_before,
+                    self.system.numbering(),
+                    self.system.direction(),
+                );
+                if let Some((ranks, until)) = &self.initial_ranks {
+                    if datetime < until {
+                        self.ranks_before.clone_from(ranks);
+                    }
+                }
+                self.last_datetime = Some(*datetime);
+                self.added_at_last.clear();
+                self.resumed.clear();
+            }
+        }
+        Ok(())
+    }
+
+    /// Advance the current time without adding a tournament,
+    /// e.g. to the time at which ranks are wanted.
+    /// Times before the latest tournament are ignored.
+    pub fn advance_to(&mut self, datetime: &DateTime<Utc>) {
+        if self.last_datetime.is_none_or(|last| last <= *datetime) {
+            self.system.advance_to(datetime);
+        }
+    }
+
+    /// Ranks based on all tournaments added so far.
+    pub fn current_ranks(&self) -> PlayerMap<u64> {
+        let mut ranks = HashMap::default();
+        ratings_to_ranks(
+            &self.system.ratings(),
+            &mut ranks,
+            self.system.numbering(),
+            self.system.direction(),
+        );
+        ranks
+    }
+
+    /// Date/time of the latest tournament added.
+    pub fn last_datetime(&self) -> Option<&DateTime<Utc>> {
+        self.last_datetime.as_ref()
+    }
+
+    pub fn system(&self) -> &R {
+        &self.system
+    }
+
+    pub fn into_system(self) -> R {
+        self.system
+    }
+}
+
+impl RankingEngine<PointsSystem> {
+    /// Records of every player seen so far.
+    pub fn current_records(&self) -> &PlayerMap<PlayerRecord> {
+        self.system.records()
+    }
+
+    /// Ranks of teams based on all tournaments added so far.
+    pub fn current_team_ranks(&self) -> HashMap<Team, u64> {
+        let mut ranks = HashMap::default();
+        ratings_to_ranks(
+            &self.system.team_ratings(),
+            &mut ranks,
+            self.system.numbering(),
+            self.system.direction(),
+        );
+        ranks
+    }
+
+    /// Records of every team seen so far.
+    pub fn current_team_records(&self) -> &HashMap<Team, PlayerRecord> {
+        self.system.team_records()
+    }
+
+    /// Snapshot the ranking state so that it can be resumed later.
+    ///
+    /// Records' histories are trimmed (see [PlayerRecord::trim_history]),
+    /// so that the checkpoint does not grow with every result.
+    pub fn checkpoint(&self) -> Checkpoint {
+        let trimmed = |rec: &PlayerRecord| {
+            let mut rec = rec.clone();
+            rec.trim_history();
+            rec
+        };
+        Checkpoint {
+            records: self
+                .system
+                .records()
+                .iter()
+                .map(|(pid, r)| (*pid, trimmed(r)))
+                .collect(),
+            team_records: self
+                .system
+                .team_records()
+                .iter()
+                .map(|(t, r)| (t.clone(), trimmed(r)))
+                .collect(),
+            ranks: self.current_ranks(),
+            ranks_before: self.ranks_before.clone(),
+            last_datetime: self.last_datetime,
+            seen: self
+                .added_at_last
+                .iter()
+                .chain(&self.resumed)
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// Resume ranking from a checkpoint.
+    ///
+    /// Points in the checkpoint are not re-decayed, so this should be used within a single season.
+    /// Tournaments at the checkpoint's latest date/time which it already includes
+    /// are skipped if they are added again,
+    /// so results can be re-read from that date/time to pick up any added since.
+    pub fn from_checkpoint(checkpoint: Checkpoint, config: Config, current_season: i32) -> Self {
+        Self {
+            system: PointsSystem::with_records(config, current_season, checkpoint.records)
+                .with_team_records(checkpoint.team_records.into_iter().collect()),
+            ranks_before: checkpoint.ranks_before,
+            last_datetime: checkpoint.last_datetime,
+            initial_ranks: None,
+            added_at_last: Vec::default(),
+            resumed: checkpoint.seen,
+        }
+    }
+}
+
+/// Serializable state of a [RankingEngine] using the [PointsSystem].
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub records: PlayerMap<PlayerRecord>,
+    /// Records of teams; a list of pairs, as JSON object keys must be strings.
+    #[serde(default)]
+    pub team_records: Vec<(Team, PlayerRecord)>,
+    /// Ranks based on all tournaments processed.
+    pub ranks: PlayerMap<u64>,
+    /// Ranks based on tournaments before `last_datetime`,
+    /// used for any further tournaments at that date/time.
+    pub ranks_before: PlayerMap<u64>,
+    /// Date/time of the latest tournament processed.
+    pub last_datetime: Option<DateTime<Utc>>,
+    /// Keys of the tournaments processed at `last_datetime`.
+    pub seen: Vec<TournamentKey>,
+}
+
+impl Checkpoint {
+    /// Read a JSON checkpoint.
+    pub fn read<R: Read>(r: R) -> Result<Self, serde_json::Error> {
+        serde_json::from_reader(r)
+    }
+
+    /// Write a JSON checkpoint.
+    pub fn write<W: Write>(&self, w: W) -> Result<(), serde_json::Error> {
+        serde_json::to_writer(w, self)
+    }
+}
+
+/// Feed tournaments into a rating system and return the resulting player ranks.
+///
+/// Tournaments are processed in date order, regardless of their order in the slice.
+/// Ranks are updated between tournament dates,
+/// so tournaments on the same date use the same ranks,
+/// and are added to the system together (see [RatingSystem::add_simultaneous]).
+///
+/// Returns an error if a tournament cannot be rated.
+pub fn rank_with<R: RatingSystem>(
+    system: &mut R,
+    tournaments: &[Tournament],
+) -> Result<PlayerMap<u64>, RankingError> {
+    let mut sorted: Vec<_> = tournaments.iter().collect();
+    sorted.sort_by_key(|t| t.datetime());
+    let mut engine = RankingEngine::new(system);
+    engine.add_tournaments(&sorted)?;
+    Ok(engine.current_ranks())
+}
+
+/// Ratings and ranks of every player seen so far, after all tournaments at a date/time.
+#[derive(Debug, Clone)]
+pub struct TimelinePoint {
+    pub datetime: DateTime<Utc>,
+    pub ratings: PlayerMap<NotNan<f64>>,
+    pub ranks: PlayerMap<u64>,
+}
+
+/// How ratings and ranks changed over time, as returned by [timeline].
+#[derive(Debug, Clone, Default)]
+pub struct Timeline {
+    points: Vec<TimelinePoint>,
+}
+
+impl Timeline {
+    /// Snapshots in chronological order.
+    pub fn points(&self) -> &[TimelinePoint] {
+        &self.points
+    }
+
+    /// A player's rating and rank at each date/time since their first result.
+    pub fn player(&self, id: PlayerId) -> Vec<(DateTime<Utc>, NotNan<f64>, u64)> {
+        self.points
+            .iter()
+            .filter_map(|p| Some((p.datetime, *p.ratings.get(&id)?, *p.ranks.get(&id)?)))
+            .collect()
+    }
+
+    /// The `n` best-ranked players at the end of the timeline, best first.
+    pub fn top(&self, n: usize) -> Vec<PlayerId> {
+        let Some(last) = self.points.last() else {
+            return Vec::default();
+        };
+        let mut ranked: Vec<_> = last.ranks.iter().map(|(pid, r)| (*r, *pid)).collect();
+        ranked.sort_unstable();
+        ranked.into_iter().take(n).map(|(_, pid)| pid).collect()
+    }
+}
+
+/// Feed tournaments into a rating system,
+/// recording ratings and ranks after each tournament date/time.
+///
+/// Tournaments are processed in date order, regardless of their order in the slice.
+/// Returns an error if a tournament cannot be rated.
+pub fn timeline<R: RatingSystem>(
+    system: &mut R,
+    tournaments: &[Tournament],
+) -> Result<Timeline, RankingError> {
+    let mut sorted: Vec<_> = tournaments.iter().collect();
+    sorted.sort_by_key(|t| t.datetime());
+    let mut engine = RankingEngine::new(system);
+    let mut points = Vec::default();
+    for (idx, t) in sorted.iter().enumerate() {
+        engine.add_tournament(*t)?;
+        if sorted
+            .get(idx + 1)
+            .is_some_and(|next| next.datetime() == t.datetime())
+        {
+            continue;
+        }
+        points.push(TimelinePoint {
+            datetime: t.datetime,
+            ratings: engine.system().ratings(),
+            ranks: engine.current_ranks(),
+        });
+    }
+    Ok(Timeline { points })
+}
+
+/// Number of tournaments in which each player took part.
+pub fn event_counts(tournaments: &[Tournament]) -> PlayerMap<usize> {
+    let mut out = HashMap::default();
+    for t in tournaments {
+        for (_, entrant) in t.results() {
+            for p in entrant.players() {
+                *out.entry(*p).or_default() += 1;
+            }
+        }
+    }
+    out
+}
+
+/// Date/time of each player's latest tournament.
+pub fn last_played<T: Borrow<Tournament>>(tournaments: &[T]) -> PlayerMap<DateTime<Utc>> {
+    let mut out: PlayerMap<DateTime<Utc>> = HashMap::default();
+    for t in tournaments.iter().map(Borrow::borrow) {
+        for (_, entrant) in t.results() {
+            for p in entrant.players() {
+                let last = out.entry(*p).or_insert(t.datetime);
+                *last = t.datetime.max(*last);
+            }
+        }
+    }
+    out
+}
+
+/// Rank keys by rating, as [RankingEngine] does; equal ratings share a rank.
+pub fn ranks_from_ratings<K: Hash + Eq + Ord + Clone, S: BuildHasher + Default>(
+    ratings: &HashMap<K, NotNan<f64>, S>,
+    numbering: RankNumbering,
+    direction: RankDirection,
+) -> HashMap<K, u64, S> {
+    let mut ranks = HashMap::default();
+    ratings_to_ranks(ratings, &mut ranks, numbering, direction);
+    ranks
+}
+
+/// A rating relative to the population of ratings it was ranked among.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RelativeRating {
+    /// Percentage of ratings below this one, counting equal ratings (including this one) as half.
+    pub percentile: f64,
+    /// Number of standard deviations above the mean rating, or 0 if every rating is equal.
+    pub z_score: f64,
+}
+
+/// Each player's rating relative to all of the given ratings,
+/// which is comparable between populations with different spreads of ratings
+/// (e.g. seasons with different point inflation).
+pub fn relative_ratings(ratings: &PlayerMap<NotNan<f64>>) -> PlayerMap<RelativeRating> {
+    let mut sorted: Vec<_> = ratings.values().copied().collect();
+    sorted.sort_unstable();
+    let n = sorted.len() as f64;
+    let mean = sorted.iter().map(|r| r.into_inner()).sum::<f64>() / n;
+    let variance = sorted
+        .iter()
+        .map(|r| (r.into_inner() - mean).powi(2))
+        .sum::<f64>()
+        / n;
+    let sd = variance.sqrt();
+    ratings
+        .iter()
+        .map(|(pid, r)| {
+            let below = sorted.partition_point(|x| x < r);
+            let equal = sorted.partition_point(|x| x <= r) - below;
+            let relative = RelativeRating {
+                percentile: 100.0 * (below as f64 + equal as f64 / 2.0) / n,
+                z_score: if sd > 0.0 {
+                    (r.into_inner() - mean) / sd
+                } else {
+                    0.0
+                },
+            };
+            (*pid, relative)
+        })
+        .collect()
+}
+
+/// Rank players using the WFDF points system.
+///
+/// Tournaments need not be sorted.
+/// With the `rayon` feature, points for tournaments at the same date/time are calculated in parallel.
+/// Returns an error if a tournament's points cannot be calculated (see [PointsError]).
+pub fn rank_players(
+    tournaments: &[Tournament],
+    current_season: i32,
+    config: &Config,
+) -> Result<(PlayerMap<u64>, PlayerMap<PlayerRecord>), RankingError> {
+    let mut system = PointsSystem::new(config.clone(), current_season);
+    let ranks = rank_with(&mut system, tournaments)?;
+    Ok((ranks, system.into_records()))
+}
+
+/// Rank players using the WFDF points system,
+/// reading and ranking tournaments `chunk_size` at a time in date order.
+///
+/// Unlike [rank_players] with the results of [ResultIngester::ingest],
+/// parsed tournaments are dropped once they have been ranked,
+/// so memory use is bounded however many results files there are.
+pub fn rank_players_chunked(
+    ingester: &ResultIngester,
+    current_season: i32,
+    config: &Config,
+    chunk_size: usize,
+) -> Result<(PlayerMap<u64>, PlayerMap<PlayerRecord>), ChunkedError> {
+    let mut engine = RankingEngine::new(PointsSystem::new(config.clone(), current_season));
+    engine.add_chunked(ingester, chunk_size)?;
+    let ranks = engine.current_ranks();
+    Ok((ranks, engine.into_system().into_records()))
+}
+
+#[derive(Debug, Error)]
+pub enum ResultReadError {
+    #[error(transparent)]
+    InvalidTournament(#[from] InvalidTournament),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error(transparent)]
+    Toml(#[from] toml::de::Error),
+    #[error("Invalid date: {0}")]
+    InvalidDate(String),
+    #[error("No date in file name or contents")]
+    NoDateInName,
+    #[error("Unknown level: {0}")]
+    UnknownLevel(Level),
+    #[error(transparent)]
+    UnknownZone(#[from] UnknownZone),
+    #[error(transparent)]
+    Zip(#[from] zip::result::ZipError),
+    #[error(transparent)]
+    MalformedRow(#[from] MalformedRow),
+    #[cfg(feature = "http")]
+    #[error(transparent)]
+    Remote(#[from] remote::RemoteError),
+    #[cfg(feature = "cloud")]
+    #[error(transparent)]
+    Cloud(#[from] cloud::CloudError),
+    #[cfg(feature = "sheets")]
+    #[error(transparent)]
+    Sheets(#[from] sheets::SheetsError),
+}
+
+impl ResultReadError {
+    /// Record the file in which a malformed row was found.
+    fn with_path(self, path: &Path) -> Self {
+        match self {
+            Self::MalformedRow(mut m) if m.path.is_none() => {
+                m.path = Some(path.to_owned());
+                Self::MalformedRow(m)
+            }
+            e => e,
+        }
+    }
+}
+
+/// A row of a results TSV which could not be parsed.
+#[derive(Debug, Clone)]
+pub struct MalformedRow {
+    /// File containing the row, if known.
+    pub path: Option<PathBuf>,
+    /// Line number of the row, counting from 1.
+    pub line: u64,
+    /// Content of the offending field, if any.
+    pub field: Option<String>,
+    pub reason: String,
+}
+
+impl Display for MalformedRow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(p) = &self.path {
+            write!(f, "{}, ", p.display())?;
+        }
+        write!(f, "line {}: {}", self.line, self.reason)?;
+        if let Some(field) = &self.field {
+            write!(f, " '{}'", field)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for MalformedRow {}
+
+/// Reads tournaments from a directory of results,
+/// or a zip archive of one (if the root has a `.zip` extension).
+///
+/// With the `http` feature, the root can also be an HTTP(S) URL;
+/// see [remote] for how results are found there.
+/// With the `cloud` feature, it can be the URL of an S3, GCS or Azure bucket
+/// (e.g. `s3://my-bucket/results`); see [cloud].
+/// With the `sheets` feature, it can be the URL of a Google Sheet; see [sheets].
+#[derive(Debug, Clone)]
+pub struct ResultIngester {
+    root: PathBuf,
+    /// Source of files under the root, loaded when first needed.
+    files: Arc<OnceCell<Files>>,
+    /// Levels to ingest, and the name of the directory containing each.
+    levels: HashMap<Level, String>,
+    /// Known levels which are not ingested, having been left out of [Self::levels].
+    ignored_levels: HashSet<Level>,
+    /// Number of player columns in each level's TSVs, if not [TEAM_SIZE].
+    team_sizes: HashMap<Level, usize>,
+    /// Whether malformed TSV rows are an error, rather than skipped.
+    strict: bool,
+    from: DateTime<Utc>,
+    until: DateTime<Utc>,
+    /// If not empty, only files whose path (relative to the root) matches one of these are read.
+    include: Vec<Pattern>,
+    /// Files whose path (relative to the root) matches any of these are not read.
+    exclude: Vec<Pattern>,
+    /// Format of the date in TSVs' file names.
+    filename_date: FilenameDate,
+    /// Columns of TSVs containing the tournament's date or level.
+    columns: TsvColumns,
+    /// Directory in which files downloaded from a URL root are cached.
+    #[cfg(feature = "http")]
+    cache_dir: Option<PathBuf>,
+    /// Cache of parsed TSVs, if any.
+    parse_cache: Option<ParseCache>,
+    /// Index of results files, listed instead of walking the levels' directories.
+    index: Option<Arc<Index>>,
+}
+
+impl ResultIngester {
+    pub fn new<P: Into<PathBuf>>(root: P) -> Self {
+        Self {
+            root: root.into(),
+            files: Arc::default(),
+            levels: Level::defaults()
+                .into_iter()
+                .map(|lvl| {
+                    let dname = lvl.directory_name().to_owned();
+                    (lvl, dname)
+                })
+                .collect(),
+            ignored_levels: HashSet::default(),
+            team_sizes: HashMap::default(),
+            strict: false,
+            from: DateTime::<Utc>::MIN_UTC,
+            until: DateTime::<Utc>::MAX_UTC,
+            include: Vec::default(),
+            exclude: Vec::default(),
+            filename_date: FilenameDate::default(),
+            columns: TsvColumns::default(),
+            #[cfg(feature = "http")]
+            cache_dir: None,
+            parse_cache: None,
+            index: None,
+        }
+    }
+
+    /// Ingest all levels defined in the config, from their configured directories.
+    pub fn from_config<P: Into<PathBuf>>(root: P, config: &Config) -> Self {
+        let mut out = Self::new(root);
+        out.levels = config
+            .levels
+            .keys()
+            .map(|lvl| {
+                let dname = config.get_level_directory(lvl).unwrap().to_owned();
+                (lvl.clone(), dname)
+            })
+            .collect();
+        out.team_sizes = config
+            .levels
+            .iter()
+            .map(|(lvl, c)| (lvl.clone(), c.team_size))
+            .collect();
+        out.filename_date = config.filename_date.clone();
+        out.columns = config.columns;
+        out
+    }
+
+    /// Only ingest these levels.
+    /// Levels whose directories are not already known are read from their default directory.
+    ///
+    /// Tournaments of the levels left out are skipped,
+    /// whereas those of levels which were never known are an error.
+    pub fn levels(mut self, levels: HashSet<Level>) -> Self {
+        let selected: HashMap<_, _> = levels
+            .into_iter()
+            .map(|lvl| {
+                let dname = self
+                    .levels
+                    .remove(&lvl)
+                    .unwrap_or_else(|| lvl.directory_name().to_owned());
+                (lvl, dname)
+            })
+            .collect();
+        self.ignored_levels
+            .extend(std::mem::replace(&mut self.levels, selected).into_keys());
+        self.ignored_levels
+            .retain(|lvl| !self.levels.contains_key(lvl));
+        self
+    }
+
+    /// Read TSVs of this level as having the given number of player columns.
+    pub fn team_size(mut self, level: Level, team_size: usize) -> Self {
+        self.team_sizes.insert(level, team_size);
+        self
+    }
+
+    /// Read the dates in TSVs' file names with this format.
+    pub fn filename_date(mut self, filename_date: FilenameDate) -> Self {
+        self.filename_date = filename_date;
+        self
+    }
+
+    /// Read TSVs' date and level from these columns, if they are not in a header line.
+    pub fn columns(mut self, columns: TsvColumns) -> Self {
+        self.columns = columns;
+        self
+    }
+
+    /// Cache files downloaded from a URL root in this directory,
+    /// and read them from there rather than downloading them again.
+    #[cfg(feature = "http")]
+    pub fn cache_dir(mut self, cache_dir: Option<PathBuf>) -> Self {
+        self.cache_dir = cache_dir;
+        self
+    }
+
+    /// Cache parsed TSVs in this directory (conventionally [parse_cache::DEFAULT_DIR] in the root),
+    /// and read unchanged files from there rather than parsing them again.
+    pub fn parse_cache(mut self, dir: Option<PathBuf>) -> Self {
+        self.parse_cache = dir.map(ParseCache::new);
+        self
+    }
+
+    /// Read only the results files in the index (see [index]), using their indexed dates and levels.
+    pub fn index(mut self, index: Option<Index>) -> Self {
+        self.index = index.map(Arc::new);
+        self
+    }
+
+    /// Whether malformed TSV rows are an error, rather than skipped.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    pub fn from(mut self, from: DateTime<Utc>) -> Self {
+        self.from = from;
+        self
+    }
+
+    /// Only read files whose path relative to the root matches one of these glob patterns
+    /// (e.g. `small/**/2023-*`); if empty, all files are read.
+    ///
+    /// `*` matches across directories, so `*.tsv` matches TSVs at any depth.
+    pub fn include(mut self, patterns: Vec<Pattern>) -> Self {
+        self.include = patterns;
+        self
+    }
+
+    /// Skip files whose path relative to the root matches any of these glob patterns
+    /// (e.g. `*.bak` or `**/drafts/*`), even if they are included.
+    ///
+    /// Skipped files are not validated or reported as unmatched.
+    pub fn exclude(mut self, patterns: Vec<Pattern>) -> Self {
+        self.exclude = patterns;
+        self
+    }
+
+    /// Whether the file is JSON, or a TSV with a date in its name or contents;
+    /// and if so, any TSV contents which were read to find out, so they need not be read again.
+    ///
+    /// TSVs which cannot be read are assumed to be results files, so that the error is reported.
+    fn results_file(&self, path: &Path) -> Option<ResultsFile> {
+        if self.index_entry(path).is_some() {
+            return Some(ResultsFile::Named);
+        }
+        let fname = file_name(path);
+        if fname.ends_with(".json")
+            || fname.ends_with(".ndjson")
+            || self.filename_date.is_match(fname)
+        {
+            return Some(ResultsFile::Named);
+        }
+        if !is_tsv(fname) {
+            return None;
+        }
+        match self.tsv_contents_info(path) {
+            Ok(TsvInfo { datetime: None, .. }) => None,
+            Ok(info) => Some(ResultsFile::Dated(info)),
+            Err(_) => Some(ResultsFile::Named),
+        }
+    }
+
+    /// Whether tournaments of the level are ingested.
+    ///
+    /// Levels which are neither ingested nor left out with [Self::levels] are an error.
+    fn is_ingested(&self, level: &Level) -> Result<bool, ResultReadError> {
+        if self.levels.contains_key(level) {
+            Ok(true)
+        } else if self.ignored_levels.contains(level) {
+            Ok(false)
+        } else {
+            Err(ResultReadError::UnknownLevel(level.clone()))
+        }
+    }
+
+    /// Levels to ingest, in name order,
+    /// so that tournaments at the same date/time are always read in the same order.
+    fn sorted_levels(&self) -> Vec<&Level> {
+        let mut levels: Vec<_> = self.levels.keys().collect();
+        levels.sort_unstable();
+        levels
+    }
+
+    /// Whether the file passes the include and exclude patterns.
+    fn is_selected(&self, path: &Path) -> bool {
+        let rel = path.strip_prefix(&self.root).unwrap_or(path);
+        (self.include.is_empty() || self.include.iter().any(|p| p.matches_path(rel)))
+            && !self.exclude.iter().any(|p| p.matches_path(rel))
+    }
+
+    pub fn until(mut self, until: DateTime<Utc>) -> Self {
+        self.until = until;
+        self
+    }
+
+    /// Source of files under the root: the filesystem, the root's zip archive,
+    /// or (with the `http`, `cloud` or `sheets` features) files downloaded from the root URL.
+    fn files(&self) -> Result<&Files, ResultReadError> {
+        self.files.get_or_try_init(|| {
+            #[cfg(feature = "cloud")]
+            if cloud::is_bucket(&self.root) {
+                return Ok(Files::from_map(cloud::download(&self.root)?));
+            }
+            #[cfg(feature = "sheets")]
+            if sheets::is_sheet(&self.root) {
+                return Ok(Files::from_map(sheets::download(&self.root)?));
+            }
+            #[cfg(feature = "http")]
+            if remote::is_url(&self.root) {
+                let files = remote::download(&self.root, self.cache_dir.as_deref())?;
+                return Ok(Files::from_map(files));
+            }
+            if self.root.extension().is_some_and(|e| e == "zip") && self.root.is_file() {
+                Files::from_zip(&self.root)
+            } else {
+                Ok(Files::default())
+            }
+        })
+    }
+
+    /// Paths of all files in the level's directory, in order.
+    fn level_files(&self, level: &Level) -> Result<Vec<PathBuf>, ResultReadError> {
+        let dname = self
+            .levels
+            .get(level)
+            .map(|d| d.as_str())
+            .unwrap_or(level.directory_name());
+        let mut out = match &self.index {
+            Some(index) => index
+                .under(Path::new(dname))
+                .filter(|(_, e)| e.in_window(&self.from, &self.until))
+                .map(|(p, _)| self.root.join(p))
+                .collect(),
+            None => {
+                let mut d = self.root.clone();
+                d.push(dname);
+                self.files()?.list(&d)?
+            }
+        };
+        out.retain(|p| self.is_selected(p));
+        for p in out.iter().filter(|p| file_name(p).is_empty()) {
+            log::warn!("Ignoring file with non UTF-8 name: {}", p.display());
+        }
+        Ok(out)
+    }
+
+    /// The file's entry in the index, if any.
+    fn index_entry(&self, path: &Path) -> Option<&IndexEntry> {
+        self.index
+            .as_ref()?
+            .get(path.strip_prefix(&self.root).ok()?)
+    }
+
+    /// Index the date and level of every results file in every level's directory,
+    /// regardless of the date window.
+    pub fn build_index(&self) -> Result<Index, ResultReadError> {
+        let unindexed = Self {
+            index: None,
+            ..self.clone()
+        };
+        let mut out = Index::default();
+        for level in unindexed.sorted_levels() {
+            for path in unindexed.level_files(level)? {
+                let Some(kind) = unindexed.results_file(&path) else {
+                    continue;
+                };
+                let fname = file_name(&path);
+                let entry = if fname.ends_with(".json") || fname.ends_with(".ndjson") {
+                    IndexEntry {
+                        datetime: None,
+                        level: None,
+                    }
+                } else {
+                    let Some((dt, lvl)) =
+                        unindexed.tsv_datetime_level(&path, level, kind.into_info())?
+                    else {
+                        continue;
+                    };
+                    IndexEntry {
+                        datetime: Some(dt),
+                        level: Some(lvl),
+                    }
+                };
+                let rel = path.strip_prefix(&self.root).unwrap_or(&path);
+                out.insert(rel.to_owned(), entry);
+            }
+        }
+        Ok(out)
+    }
+
+    /// The date and level given in a TSV's `# date:` and `# level:` header lines,
+    /// or otherwise in its date and level columns, if any.
+    ///
+    /// Only the header lines are read, unless columns are configured.
+    fn tsv_contents_info(&self, path: &Path) -> Result<TsvInfo, ResultReadError> {
+        let mut date_str = None;
+        let mut level = None;
+        let mut zone = None;
+        let mut lines = BufReader::new(self.files()?.open(path)?).lines();
+        let mut first_row = None;
+        for line in lines.by_ref() {
+            let line = line?;
+            let Some(comment) = strip_bom(&line).strip_prefix('#') else {
+                first_row = Some(line);
+                break;
+            };
+            let Some((key, value)) = comment.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+            match key.trim().to_lowercase().as_str() {
+                "date" => date_str = Some(value.to_owned()),
+                "level" => level = Some(Level::new(value)),
+                "timezone" => zone = Some(value.parse::<Zone>()?),
+                _ => (),
+            }
+        }
+        let mut dt = match date_str {
+            Some(s) => Some(
+                json::parse_date_in(&s, zone.as_ref()).ok_or(ResultReadError::InvalidDate(s))?,
+            ),
+            None => None,
+        };
+
+        let date_col = self.columns.date.filter(|_| dt.is_none());
+        let level_col = self.columns.level.filter(|_| level.is_none());
+        if date_col.is_none() && level_col.is_none() {
+            return Ok(TsvInfo {
+                datetime: dt,
+                level,
+                zone,
+            });
+        }
+        let delimiter = first_row
+            .as_ref()
+            .map_or(b'\t', |l| sniff_delimiter(l.as_bytes())) as char;
+        for line in first_row.into_iter().map(Ok).chain(lines) {
+            let line = line?;
+            let fields: Vec<_> = line.split(delimiter).collect();
+            // skip header rows and comments
+            if fields[0].trim().parse::<u64>().is_err() {
+                continue;
+            }
+            let field = |col: Option<usize>| {
+                col.and_then(|c| fields.get(c.checked_sub(1)?))
+                    .map(|f| f.trim())
+                    .filter(|f| !f.is_empty())
+            };
+            if dt.is_none() {
+                dt = field(date_col).and_then(|f| json::parse_date_in(f, zone.as_ref()));
+            }
+            if level.is_none() {
+                level = field(level_col).map(Level::new);
+            }
+            if (date_col.is_none() || dt.is_some()) && (level_col.is_none() || level.is_some()) {
+                break;
+            }
+        }
+        Ok(TsvInfo {
+            datetime: dt,
+            level,
+            zone,
+        })
+    }
+
+    /// The date/time and level of a TSV, from the index, its contents, or its name and directory;
+    /// or `None` if it has no date or is not a TSV.
+    ///
+    /// The contents are only read if they have not been already.
+    fn tsv_datetime_level(
+        &self,
+        path: &Path,
+        level: &Level,
+        info: Option<TsvInfo>,
+    ) -> Result<Option<(DateTime<Utc>, Level)>, ResultReadError> {
+        if let Some(IndexEntry {
+            datetime: Some(dt),
+            level: Some(lvl),
+        }) = self.index_entry(path)
+        {
+            return Ok(Some((*dt, lvl.clone())));
+        }
+        let fname = file_name(path);
+        if !is_tsv(fname) && !self.filename_date.is_match(fname) {
+            return Ok(None);
+        }
+        let info = match info {
+            Some(info) => info,
+            None => self.tsv_contents_info(path)?,
+        };
+        let dt = match (
+            info.datetime,
+            self.filename_date.parse(fname, info.zone.as_ref()),
+        ) {
+            (Some(dt), _) => dt,
+            (None, Some(dt)) => dt?,
+            (None, None) => return Ok(None),
+        };
+        Ok(Some((dt, info.level.unwrap_or_else(|| level.clone()))))
+    }
+
+    /// Find the tournaments in a single file within the window,
+    /// or none if it is not a results file.
+    ///
+    /// Only the start of TSV files is read, to find any date and level given in the file
+    /// (unless already given as `info`); otherwise their date is in the file name.
+    /// Tournaments of unknown levels are an error.
+    fn pending_in_file(
+        &self,
+        path: &Path,
+        level: &Level,
+        info: Option<TsvInfo>,
+    ) -> Result<Vec<PendingTournament>, ResultReadError> {
+        let mut out = Vec::default();
+        let fname = file_name(path);
+        if fname.ends_with(".json") || fname.ends_with(".ndjson") {
+            let rd = BufReader::new(self.files()?.open(path)?);
+            let jts = if fname.ends_with(".json") {
+                vec![json::read_json(rd)?]
+            } else {
+                json::read_ndjson(rd)?
+            };
+            for jt in jts {
+                let dt = jt.datetime()?;
+                if dt < self.from || dt > self.until || !self.is_ingested(jt.level_or(level))? {
+                    continue;
+                }
+                out.push(PendingTournament::Read(jt.into_tournament(level)?));
+            }
+            return Ok(out);
+        }
+        let Some((dt, level)) = self.tsv_datetime_level(path, level, info)? else {
+            return Ok(out);
+        };
+        if dt < self.from || dt > self.until || !self.is_ingested(&level)? {
+            return Ok(out);
+        }
+
+        let files = self.files()?.clone();
+        // archives and downloads are already in memory
+        let cache = self.parse_cache.clone().filter(|_| files.archive.is_none());
+        out.push(PendingTournament::Tsv {
+            path: path.to_owned(),
+            files,
+            datetime: dt,
+            team_size: self.team_sizes.get(&level).copied().unwrap_or(TEAM_SIZE),
+            level,
+            strict: self.strict,
+            cache,
+        });
+        Ok(out)
+    }
+
+    /// Read the tournaments in a single file within the window.
+    fn read_file(
+        &self,
+        path: &Path,
+        level: &Level,
+    ) -> Result<(Vec<Tournament>, IngestionReport), ResultReadError> {
+        let Some(kind) = self.results_file(path) else {
+            let mut report = IngestionReport::default();
+            if !is_sidecar(path, self.files()?) {
+                report.unmatched_files.push(path.to_owned());
+            }
+            return Ok((Vec::default(), report));
+        };
+        self.read_results_file(path, level, kind)
+    }
+
+    /// Read the tournaments within the window in a file known to be a results file.
+    fn read_results_file(
+        &self,
+        path: &Path,
+        level: &Level,
+        kind: ResultsFile,
+    ) -> Result<(Vec<Tournament>, IngestionReport), ResultReadError> {
+        let mut report = IngestionReport::default();
+        let mut out = Vec::default();
+        for p in self.pending_in_file(path, level, kind.into_info())? {
+            let (t, mut skipped) = p.read_with_skipped()?;
+            report.skipped_rows.append(&mut skipped);
+            out.push(t);
+        }
+        if !out.is_empty() {
+            report.files_read.push(path.to_owned());
+        }
+        report.tournaments = out.len();
+        Ok((out, report))
+    }
+
+    fn ingest_level_with_report(
+        &self,
+        level: &Level,
+    ) -> Result<(Vec<Tournament>, IngestionReport), ResultReadError> {
+        let paths = self.level_files(level)?;
+        #[cfg(feature = "rayon")]
+        let paths = paths.par_iter();
+        #[cfg(not(feature = "rayon"))]
+        let paths = paths.iter();
+        let per_file = paths
+            .map(|p| self.read_file(p, level))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut out = Vec::default();
+        let mut report = IngestionReport::default();
+        for (mut ts, r) in per_file {
+            out.append(&mut ts);
+            report.merge(r);
+        }
+        Ok((out, report))
+    }
+
+    /// Read all tournaments of the given level.
+    ///
+    /// With the `rayon` feature, files are read in parallel.
+    pub fn ingest_level(&self, level: &Level) -> Result<Vec<Tournament>, ResultReadError> {
+        self.ingest_level_with_report(level).map(|(ts, _)| ts)
+    }
+
+    /// Read all tournaments of every level, sorted by date/time.
+    ///
+    /// With the `rayon` feature, levels are read in parallel.
+    pub fn ingest(&self) -> Result<Vec<Tournament>, ResultReadError> {
+        self.ingest_with_report().map(|(ts, _)| ts)
+    }
+
+    /// As [ResultIngester::ingest], but also reporting what was read and skipped.
+    pub fn ingest_with_report(
+        &self,
+    ) -> Result<(Vec<Tournament>, IngestionReport), ResultReadError> {
+        let levels = self.sorted_levels();
+        #[cfg(feature = "rayon")]
+        let levels = levels.into_par_iter();
+        #[cfg(not(feature = "rayon"))]
+        let levels = levels.into_iter();
+        let per_level = levels
+            .map(|lvl| self.ingest_level_with_report(lvl))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut out = Vec::default();
+        let mut report = IngestionReport::default();
+        for (mut ts, r) in per_level {
+            out.append(&mut ts);
+            report.merge(r);
+        }
+        out.sort_by(|a, b| a.datetime().cmp(b.datetime()));
+        Ok((out, report))
+    }
+
+    /// Read the tournaments in a local results file which need not be under the root,
+    /// e.g. a hypothetical tournament, as if it were in the given level's directory.
+    ///
+    /// As for other results files, the file's date is in its name or contents,
+    /// and it can give its own level.
+    /// Tournaments outside the window or not of an ingested level are not returned.
+    pub fn read_extra(
+        &self,
+        path: &Path,
+        level: &Level,
+    ) -> Result<Vec<Tournament>, ResultReadError> {
+        let local = Self {
+            files: Arc::default(),
+            parse_cache: None,
+            index: None,
+            ..self.clone()
+        };
+        local.read_file(path, level).map(|(ts, _)| ts)
+    }
+
+    /// Check every file in every level's directory, strictly parsing results files.
+    ///
+    /// TSVs without a date in their name are invalid;
+    /// other files which are not results files are ignored.
+    pub fn validate(&self) -> Vec<(PathBuf, FileCheck)> {
+        let strict = Self {
+            strict: true,
+            ..self.clone()
+        };
+        let mut out = Vec::default();
+        for level in self.levels.keys() {
+            let paths = match self.level_files(level) {
+                Ok(p) => p,
+                Err(e) => {
+                    out.push((self.root.join(&self.levels[level]), FileCheck::Invalid(e)));
+                    continue;
+                }
+            };
+            for path in paths {
+                let check = if let Some(kind) = self.results_file(&path) {
+                    match strict.read_results_file(&path, level, kind) {
+                        Ok((ts, _)) => FileCheck::Valid(ts.len()),
+                        Err(e) => FileCheck::Invalid(e),
+                    }
+                } else if is_tsv(file_name(&path)) {
+                    FileCheck::Invalid(ResultReadError::NoDateInName)
+                } else {
+                    FileCheck::Ignored
+                };
+                out.push((path, check));
+            }
+        }
+        out.sort_by(|a, b| a.0.cmp(&b.0));
+        out
+    }
+
+    /// Lazily read all tournaments of every level, in date order.
+    ///
+    /// TSV files are only read when their tournament is reached,
+    /// but JSON files must be read up front to find their dates.
+    /// Errors encountered while finding tournaments are yielded first.
+    pub fn iter(&self) -> TournamentIter {
+        let mut pending = Vec::default();
+        let mut errors = Vec::default();
+        for level in self.sorted_levels() {
+            let paths = match self.level_files(level) {
+                Ok(p) => p,
+                Err(e) => {
+                    errors.push(e);
+                    continue;
+                }
+            };
+            for path in paths {
+                match self.pending_in_file(&path, level, None) {
+                    Ok(mut p) => pending.append(&mut p),
+                    Err(e) => errors.push(e),
+                }
+            }
+        }
+        pending.sort_by(|a, b| a.datetime().cmp(b.datetime()));
+        TournamentIter {
+            errors: errors.into_iter(),
+            pending: pending.into_iter(),
+        }
+    }
+}
+
+impl IntoIterator for ResultIngester {
+    type Item = Result<Tournament, ResultReadError>;
+    type IntoIter = TournamentIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl IntoIterator for &ResultIngester {
+    type Item = Result<Tournament, ResultReadError>;
+    type IntoIter = TournamentIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Outcome of checking a file with [ResultIngester::validate].
+#[derive(Debug)]
+pub enum FileCheck {
+    /// A results file, containing this many tournaments within the window.
+    Valid(usize),
+    /// Not a results file.
+    Ignored,
+    Invalid(ResultReadError),
+}
+
+/// What was found while ingesting results.
+#[derive(Debug, Clone, Default)]
+pub struct IngestionReport {
+    /// Files from which at least one tournament was read.
+    pub files_read: Vec<PathBuf>,
+    /// Number of tournaments read.
+    pub tournaments: usize,
+    /// TSV rows which were skipped, and why.
+    pub skipped_rows: Vec<MalformedRow>,
+    /// Files which are neither JSON nor TSVs with a date in their name
+    /// (other than TOML metadata sidecars).
+    pub unmatched_files: Vec<PathBuf>,
+}
+
+impl IngestionReport {
+    pub fn merge(&mut self, mut other: Self) {
+        self.files_read.append(&mut other.files_read);
+        self.tournaments += other.tournaments;
+        self.skipped_rows.append(&mut other.skipped_rows);
+        self.unmatched_files.append(&mut other.unmatched_files);
+    }
+}
+
+/// The file's name, or an empty string if it is not UTF-8,
+/// so that it does not match as a results file.
+fn file_name(path: &Path) -> &str {
+    path.file_name()
+        .and_then(|f| f.to_str())
+        .unwrap_or_default()
+}
+
+#[derive(Debug, Error)]
+#[error("Invalid file name date format {format:?}: {reason}")]
+pub struct InvalidDateFormat {
+    pub format: String,
+    pub reason: String,
+}
+
+/// Format of the date in the names of results TSVs, as a [chrono::format::strftime] string.
+///
+/// The specifiers `%Y`, `%y`, `%m`, `%b`, `%d`, `%j`, and `%%` are supported;
+/// the format must include a year, and either a month and day or a day of the year.
+/// The date can be anywhere in the name, followed by `.tsv`.
+/// It can be immediately followed by a time of day, as `T` then the hour and optionally minute
+/// (e.g. `2023-07-10T14-30_open.tsv`), to order tournaments on the same date.
+/// The time can be followed by a UTC offset (`Z`, or e.g. `+02`, `-0530`, or `+05:30`),
+/// in which case the minute must be given; otherwise it is in UTC unless a time zone is given.
+/// Defaults to `%Y-%m-%d`.
+#[derive(Debug, Clone)]
+pub struct FilenameDate {
+    format: String,
+    regex: regex::Regex,
+}
+
+impl FilenameDate {
+    pub fn new<S: Into<String>>(format: S) -> Result<Self, InvalidDateFormat> {
+        let format = format.into();
+        let invalid = |reason: &str| InvalidDateFormat {
+            format: format.clone(),
+            reason: reason.to_owned(),
+        };
+        let mut pattern = String::from("(?P<date>");
+        let mut specifiers = HashSet::new();
+        let mut chars = format.chars();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                pattern.push_str(&regex::escape(&c.to_string()));
+                continue;
+            }
+            let spec = chars.next().ok_or_else(|| invalid("trailing %"))?;
+            pattern.push_str(match spec {
+                'Y' => r"\d{4}",
+                'y' | 'm' | 'd' => r"\d{2}",
+                'j' => r"\d{3}",
+                'b' => r"[A-Za-z]{3}",
+                '%' => "%",
+                _ => return Err(invalid(&format!("unsupported specifier %{}", spec))),
+            });
+            specifiers.insert(spec);
+        }
+        pattern.push_str(
+            r")(?:T(?P<hour>\d\d)(?:[-:h]?(?P<minute>\d\d))?(?P<offset>Z|[+-]\d\d(?::?\d\d)?)?)?.*",
+        );
+        pattern.push_str(RESULTS_EXTENSION);
+
+        let has = |s| specifiers.contains(&s);
+        if !(has('Y') || has('y')) {
+            return Err(invalid("no year"));
+        }
+        if !(has('j') || ((has('m') || has('b')) && has('d'))) {
+            return Err(invalid("no month and day, or day of the year"));
+        }
+        let regex = regex::Regex::new(&pattern).map_err(|e| invalid(&e.to_string()))?;
+        Ok(Self { format, regex })
+    }
+
+    pub fn format(&self) -> &str {
+        &self.format
+    }
+
+    /// The date/time in a TSV's file name (midnight unless a time is given),
+    /// or `None` if it is not the name of a TSV with a date of this format.
+    ///
+    /// The local date/time is in the offset given in the file name,
+    /// otherwise the given zone, otherwise UTC.
+    pub fn parse(
+        &self,
+        file_name: &str,
+        zone: Option<&Zone>,
+    ) -> Option<Result<DateTime<Utc>, ResultReadError>> {
+        let cap = self.regex.captures(file_name)?;
+        let invalid = || ResultReadError::InvalidDate(cap[0].to_owned());
+        let parse = || {
+            let date = NaiveDate::parse_from_str(&cap["date"], &self.format).ok()?;
+            let hour = cap.name("hour").map_or(Ok(0), |h| h.as_str().parse());
+            let minute = cap.name("minute").map_or(Ok(0), |m| m.as_str().parse());
+            let local = date.and_hms_opt(hour.ok()?, minute.ok()?, 0)?;
+            match cap.name("offset") {
+                Some(o) => o.as_str().parse::<Zone>().ok()?.to_utc(&local),
+                None => zone.map_or(Some(Utc.from_utc_datetime(&local)), |z| z.to_utc(&local)),
+            }
+        };
+        Some(parse().ok_or_else(invalid))
+    }
+
+    /// Whether the file name is that of a TSV with a date of this format.
+    pub fn is_match(&self, file_name: &str) -> bool {
+        self.regex.is_match(file_name)
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("Unknown time zone: {0}")]
+pub struct UnknownZone(String);
+
+/// The time zone in which a tournament's local date/time is given:
+/// a fixed UTC offset (`Z`, or e.g. `+02:00`, `-0530`, or `+02`)
+/// or an IANA time zone name (e.g. `Europe/London`), which accounts for daylight saving.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Zone {
+    Fixed(FixedOffset),
+    Named(chrono_tz::Tz),
+}
+
+impl Zone {
+    /// The instant of a local date/time in this zone.
+    ///
+    /// Ambiguous local times (when clocks go back) resolve to the earlier instant;
+    /// times skipped when clocks go forward are `None`.
+    pub fn to_utc(&self, local: &NaiveDateTime) -> Option<DateTime<Utc>> {
+        let dt = match self {
+            Zone::Fixed(offset) => offset.from_local_datetime(local).earliest()?.to_utc(),
+            Zone::Named(tz) => tz.from_local_datetime(local).earliest()?.to_utc(),
+        };
+        Some(dt)
+    }
+}
+
+impl FromStr for Zone {
+    type Err = UnknownZone;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let unknown = || UnknownZone(s.to_owned());
+        if s == "Z" || s.eq_ignore_ascii_case("utc") {
+            return Ok(Zone::Fixed(FixedOffset::east_opt(0).unwrap()));
+        }
+        let Some(digits) = s.strip_prefix('+').or_else(|| s.strip_prefix('-')) else {
+            return s.parse().map(Zone::Named).map_err(|_| unknown());
+        };
+        let digits = digits.replace(':', "");
+        if !(digits.len() == 2 || digits.len() == 4) || !digits.bytes().all(|b| b.is_ascii_digit())
+        {
+            return Err(unknown());
+        }
+        let hours: i32 = digits[..2].parse().unwrap();
+        let minutes: i32 = digits[2..].parse().unwrap_or(0);
+        let seconds = (hours * 60 + minutes) * 60;
+        let seconds = if s.starts_with('-') {
+            -seconds
+        } else {
+            seconds
+        };
+        FixedOffset::east_opt(seconds)
+            .map(Zone::Fixed)
+            .ok_or_else(unknown)
+    }
+}
+
+impl Display for Zone {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Zone::Fixed(offset) => write!(f, "{}", offset),
+            Zone::Named(tz) => write!(f, "{}", tz.name()),
+        }
+    }
+}
+
+impl Serialize for Zone {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Zone {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl Default for FilenameDate {
+    fn default() -> Self {
+        Self::new("%Y-%m-%d").unwrap()
+    }
+}
+
+impl PartialEq for FilenameDate {
+    fn eq(&self, other: &Self) -> bool {
+        self.format == other.format
+    }
+}
+
+impl Serialize for FilenameDate {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.format)
+    }
+}
+
+impl<'de> Deserialize<'de> for FilenameDate {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let format = String::deserialize(deserializer)?;
+        FilenameDate::new(format).map_err(serde::de::Error::custom)
+    }
+}
+
+/// The date/time, level, and time zone given within a TSV.
+struct TsvInfo {
+    datetime: Option<DateTime<Utc>>,
+    level: Option<Level>,
+    zone: Option<Zone>,
+}
+
+/// How a file was recognised as a results file.
+enum ResultsFile {
+    /// By its name, extension, or entry in the index.
+    Named,
+    /// By the date in a TSV's contents, which were read to find it.
+    Dated(TsvInfo),
+}
+
+impl ResultsFile {
+    fn into_info(self) -> Option<TsvInfo> {
+        match self {
+            Self::Named => None,
+            Self::Dated(info) => Some(info),
+        }
+    }
+}
+
+/// Pattern matching the extension of results files with a date in their name.
+#[cfg(not(feature = "xlsx"))]
+const RESULTS_EXTENSION: &str = r"\.(?:tsv|csv)";
+#[cfg(feature = "xlsx")]
+const RESULTS_EXTENSION: &str = r"\.(?:tsv|csv|xlsx)";
+
+/// Extensions of files read as TSVs (including CSVs, whose delimiter is detected).
+#[cfg(not(feature = "xlsx"))]
+const TSV_EXTENSIONS: [&str; 4] = ["tsv", "tsv.gz", "csv", "csv.gz"];
+#[cfg(feature = "xlsx")]
+const TSV_EXTENSIONS: [&str; 5] = ["tsv", "tsv.gz", "csv", "csv.gz", "xlsx"];
+
+/// Whether the file is a TSV or CSV, possibly gzipped,
+/// or (with the `xlsx` feature) a spreadsheet read as one.
+fn is_tsv(file_name: &str) -> bool {
+    TSV_EXTENSIONS.iter().any(|ext| {
+        file_name
+            .strip_suffix(ext)
+            .is_some_and(|s| s.ends_with('.'))
+    })
+}
+
+/// Path of a TSV's TOML metadata, replacing its e.g. `.tsv` or `.tsv.gz` extension.
+fn sidecar_path(results_path: &Path) -> PathBuf {
+    if results_path.extension().is_some_and(|e| e == "gz") {
+        results_path.with_extension("").with_extension("toml")
+    } else {
+        results_path.with_extension("toml")
+    }
+}
+
+/// Whether the file is the TOML metadata of a TSV.
+fn is_sidecar(path: &Path, files: &Files) -> bool {
+    path.extension().is_some_and(|e| e == "toml")
+        && TSV_EXTENSIONS
+            .iter()
+            .any(|ext| files.is_file(&path.with_extension(ext)))
+}
+
+/// Where results files are read from:
+/// the filesystem, a zip archive, or remote files read into memory.
+#[derive(Clone, Default)]
+struct Files {
+    /// Files not on the filesystem, by their path as if the archive were a directory.
+    archive: Option<Arc<Archive>>,
+}
+
+enum Archive {
+    /// Contents of each file.
+    #[cfg_attr(
+        not(any(feature = "http", feature = "cloud", feature = "sheets")),
+        allow(dead_code)
+    )]
+    Memory(BTreeMap<PathBuf, Vec<u8>>),
+    /// Index of each file in the zip archive, whose entries are read as they are opened.
+    Zip {
+        index: BTreeMap<PathBuf, usize>,
+        zip: Mutex<zip::ZipArchive<BufReader<File>>>,
+    },
+}
+
+impl Archive {
+    fn len(&self) -> usize {
+        match self {
+            Self::Memory(files) => files.len(),
+            Self::Zip { index, .. } => index.len(),
+        }
+    }
+
+    fn contains(&self, path: &Path) -> bool {
+        match self {
+            Self::Memory(files) => files.contains_key(path),
+            Self::Zip { index, .. } => index.contains_key(path),
+        }
+    }
+
+    /// Paths of all files in the directory and its subdirectories, in order.
+    fn list(&self, dir: &Path) -> Vec<PathBuf> {
+        let paths: Box<dyn Iterator<Item = &PathBuf>> = match self {
+            Self::Memory(files) => Box::new(files.range(dir.to_owned()..).map(|(p, _)| p)),
+            Self::Zip { index, .. } => Box::new(index.range(dir.to_owned()..).map(|(p, _)| p)),
+        };
+        paths.take_while(|p| p.starts_with(dir)).cloned().collect()
+    }
+
+    fn open(&self, path: &Path) -> io::Result<Box<dyn Read + '_>> {
+        let not_found = || {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} not in archive", path.display()),
+            )
+        };
+        match self {
+            Self::Memory(files) => match files.get(path) {
+                Some(contents) => Ok(Box::new(contents.as_slice())),
+                None => Err(not_found()),
+            },
+            Self::Zip { index, zip } => {
+                let idx = *index.get(path).ok_or_else(not_found)?;
+                let mut zip = zip
+                    .lock()
+                    .map_err(|_| io::Error::other("Zip archive poisoned"))?;
+                // the entry's size in its header is not trusted
+                let mut buf = Vec::default();
+                zip.by_index(idx)?.read_to_end(&mut buf)?;
+                Ok(Box::new(io::Cursor::new(buf)))
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for Files {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Files")
+            .field("archive_len", &self.archive.as_ref().map(|a| a.len()))
+            .finish()
+    }
+}
+
+impl Files {
+    /// Index the files in a zip archive, without reading them.
+    fn from_zip(path: &Path) -> Result<Self, ResultReadError> {
+        let mut zip = zip::ZipArchive::new(BufReader::new(File::open(path)?))?;
+        let mut index = BTreeMap::default();
+        for idx in 0..zip.len() {
+            let f = zip.by_index_raw(idx)?;
+            let Some(name) = f.enclosed_name().filter(|_| f.is_file()) else {
+                continue;
+            };
+            index.insert(path.join(name), idx);
+        }
+        Ok(Self {
+            archive: Some(Arc::new(Archive::Zip {
+                index,
+                zip: Mutex::new(zip),
+            })),
+        })
+    }
+
+    #[cfg_attr(
+        not(any(feature = "http", feature = "cloud", feature = "sheets")),
+        allow(dead_code)
+    )]
+    fn from_map(files: BTreeMap<PathBuf, Vec<u8>>) -> Self {
+        Self {
+            archive: Some(Arc::new(Archive::Memory(files))),
+        }
+    }
+
+    /// Paths of all files in the directory and its subdirectories, in order.
+    fn list(&self, dir: &Path) -> Result<Vec<PathBuf>, ResultReadError> {
+        let Some(archive) = &self.archive else {
+            if !dir.is_dir() {
+                return Ok(Vec::default());
+            }
+            let mut out = Vec::default();
+            for entry in WalkDir::new(dir).follow_links(true).sort_by_file_name() {
+                let e = entry.map_err(|e| {
+                    e.into_io_error()
+                        .unwrap_or(io::Error::other("Error reading directories"))
+                })?;
+                if e.file_type().is_file() {
+                    out.push(e.into_path());
+                }
+            }
+            return Ok(out);
+        };
+        Ok(archive.list(dir))
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        match &self.archive {
+            Some(archive) => archive.contains(path),
+            None => path.is_file(),
+        }
+    }
+
+    /// Open a file, decompressing it if it has a `.gz` extension,
+    /// or (with the `xlsx` feature) converting a spreadsheet's first sheet to TSV.
+    fn open(&self, path: &Path) -> io::Result<Box<dyn Read + '_>> {
+        let rd = self.open_raw(path)?;
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("gz") => Ok(Box::new(GzDecoder::new(rd))),
+            #[cfg(feature = "xlsx")]
+            Some("xlsx") => Ok(Box::new(io::Cursor::new(
+                xlsx::sheet_to_tsv(rd)?.into_bytes(),
+            ))),
+            _ => Ok(rd),
+        }
+    }
+
+    fn open_raw(&self, path: &Path) -> io::Result<Box<dyn Read + '_>> {
+        match &self.archive {
+            Some(archive) => archive.open(path),
+            None => Ok(Box::new(File::open(path)?)),
+        }
+    }
+
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        let mut out = String::new();
+        self.open(path)?.read_to_string(&mut out)?;
+        Ok(out)
+    }
+
+    /// Read a whole file, decompressing or converting it as [Files::open] does.
+    ///
+    /// With the `mmap` feature, large uncompressed files on the filesystem
+    /// are mapped into memory rather than read.
+    fn read(&self, path: &Path) -> io::Result<Contents> {
+        #[cfg(feature = "mmap")]
+        if self.archive.is_none() && path.extension().is_some_and(|e| e == "tsv" || e == "csv") {
+            let file = File::open(path)?;
+            if file.metadata()?.len() >= MMAP_THRESHOLD {
+                // SAFETY: the map is only read, and results files should not change while being read
+                let map = unsafe { memmap2::Mmap::map(&file)? };
+                return Ok(Contents::Mapped(map));
+            }
+        }
+        let mut buf = Vec::default();
+        self.open(path)?.read_to_end(&mut buf)?;
+        Ok(Contents::Read(buf))
+    }
+}
+
+/// Size in bytes from which files are mapped into memory rather than read.
+#[cfg(feature = "mmap")]
+const MMAP_THRESHOLD: u64 = 1 << 20;
+
+/// Contents of a file, read by [Files::read].
+enum Contents {
+    Read(Vec<u8>),
+    #[cfg(feature = "mmap")]
+    Mapped(memmap2::Mmap),
+}
+
+impl std::ops::Deref for Contents {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Self::Read(v) => v,
+            #[cfg(feature = "mmap")]
+            Self::Mapped(m) => m,
+        }
+    }
+}
+
+/// A tournament whose date is known, but which may not have been read yet.
+#[derive(Debug)]
+enum PendingTournament {
+    Tsv {
+        path: PathBuf,
+        files: Files,
+        datetime: DateTime<Utc>,
+        level: Level,
+        team_size: usize,
+        strict: bool,
+        cache: Option<ParseCache>,
+    },
+    Read(Tournament),
+}
+
+impl PendingTournament {
+    fn datetime(&self) -> &DateTime<Utc> {
+        match self {
+            Self::Tsv { datetime, .. } => datetime,
+            Self::Read(t) => t.datetime(),
+        }
+    }
+
+    fn read(self) -> Result<Tournament, ResultReadError> {
+        self.read_with_skipped().map(|(t, _)| t)
+    }
+
+    /// Read the tournament, also returning any TSV rows which were skipped.
+    fn read_with_skipped(self) -> Result<(Tournament, Vec<MalformedRow>), ResultReadError> {
+        match self {
+            Self::Tsv {
+                path,
+                files,
+                datetime,
+                level,
+                team_size,
+                strict,
+                cache,
+            } => {
+                let settings = ReadSettings {
+                    datetime: &datetime,
+                    level: &level,
+                    team_size,
+                    strict,
+                };
+                if let Some(cached) = cache.as_ref().and_then(|c| c.load(&path, settings)) {
+                    return Ok(cached?);
+                }
+                let bytes = files.read(&path)?;
+                let contents = std::str::from_utf8(&bytes)
+                    .map(strip_bom)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+                let metadata = TournamentMetadata::read_sidecar_from(&path, &files)?
+                    .unwrap_or_default()
+                    .or(TournamentMetadata::from_frontmatter(contents));
+                let ParsedResults {
+                    placings,
+                    unplaced,
+                    withdrawn,
+                    matches,
+                    mut skipped,
+                } = parse_results(contents.as_bytes(), team_size, strict)
+                    .map_err(|e| e.with_path(&path))?;
+                for m in skipped.iter_mut() {
+                    m.path = Some(path.clone());
+                }
+                let t = Tournament::new(placings, datetime, level.clone())?
+                    .with_unplaced(unplaced)?
+                    .with_withdrawn(withdrawn)
+                    .with_matches(matches)
+                    .with_metadata(metadata);
+                if let Some(c) = &cache {
+                    c.store(&path, settings, &t, &skipped);
+                }
+                Ok((t, skipped))
+            }
+            Self::Read(t) => Ok((t, Vec::default())),
+        }
+    }
+}
+
+/// Iterator over tournaments in date order, created by [ResultIngester::iter].
+#[derive(Debug)]
+pub struct TournamentIter {
+    errors: std::vec::IntoIter<ResultReadError>,
+    pending: std::vec::IntoIter<PendingTournament>,
+}
+
+impl Iterator for TournamentIter {
+    type Item = Result<Tournament, ResultReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(e) = self.errors.next() {
+            return Some(Err(e));
+        }
+        self.pending.next().map(|p| p.read())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.errors.len() + self.pending.len();
+        (n, Some(n))
+    }
+}
+
+/// Pairs of finishing position and entrant.
+pub type Placings = Vec<(u64, Entrant)>;
+
+/// Columns of a results TSV containing the finishing position and each player, counting from 0.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RankColumns {
+    place: usize,
+    players: Vec<usize>,
+    /// Column marking entrants which withdrew during the tournament.
+    withdrawn: Option<usize>,
+}
+
+impl RankColumns {
+    /// The finishing position, followed by `team_size` player columns.
+    fn positional(team_size: usize) -> Self {
+        Self {
+            place: 0,
+            players: (1..=team_size).collect(),
+            withdrawn: None,
+        }
+    }
+
+    /// Columns named in a header row, if it has a `place` column.
+    ///
+    /// Player columns are named `player` or `p` followed by the player's number within the team
+    /// (e.g. `player1`, `p2`), or just `player` for singles, and are taken in number order.
+    /// A `withdrawn` column marks entrants which withdrew during the tournament.
+    /// Names are case-insensitive; other columns are ignored.
+    fn from_header(record: &StringRecord, team_size: usize) -> Option<Result<Self, String>> {
+        let names: Vec<_> = record.iter().map(|f| f.trim().to_lowercase()).collect();
+        let place = names.iter().position(|n| n == "place")?;
+        let withdrawn = names.iter().position(|n| n == "withdrawn");
+        let players = match numbered_columns(&names, "player", "p", team_size) {
+            Ok(p) => p,
+            Err(e) => return Some(Err(e)),
+        };
+        Some(Ok(Self {
+            place,
+            players,
+            withdrawn,
+        }))
+    }
+}
+
+/// Columns of a results TSV of games, containing the round and each player of the winner and loser.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct MatchColumns {
+    round: usize,
+    winner: Vec<usize>,
+    loser: Vec<usize>,
+    /// Column with the game's score.
+    score: Option<usize>,
+}
+
+impl MatchColumns {
+    /// Columns named in a header row, if it has a `round` column.
+    ///
+    /// Players are in columns named as for [RankColumns::from_header],
+    /// but with `winner`/`w` and `loser`/`l` in place of `player`/`p` (e.g. `winner1`, `l2`).
+    /// A `score` column has the game's score (see [parse_score]).
+    fn from_header(record: &StringRecord, team_size: usize) -> Option<Result<Self, String>> {
+        let names: Vec<_> = record.iter().map(|f| f.trim().to_lowercase()).collect();
+        let round = names.iter().position(|n| n == "round")?;
+        let score = names.iter().position(|n| n == "score");
+        let columns = numbered_columns(&names, "winner", "w", team_size).and_then(|winner| {
+            let loser = numbered_columns(&names, "loser", "l", team_size)?;
+            Ok(Self {
+                round,
+                winner,
+                loser,
+                score,
+            })
+        });
+        Some(columns)
+    }
+}
+
+/// Indices of all columns named with the long or short prefix followed by a number,
+/// in number order; just the long prefix counts as number 1.
+fn all_numbered_columns(names: &[String], long: &str, short: &str) -> Vec<usize> {
+    let mut numbered: Vec<_> = names
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, n)| {
+            let num = n.strip_prefix(long).or_else(|| n.strip_prefix(short))?;
+            if num.is_empty() && n == long {
+                return Some((1, idx));
+            }
+            num.parse::<usize>().ok().map(|num| (num, idx))
+        })
+        .collect();
+    numbered.sort_unstable();
+    numbered.into_iter().map(|(_, idx)| idx).collect()
+}
+
+/// Indices of the first `team_size` columns named with the long or short prefix
+/// followed by a number, in number order; just the long prefix counts as number 1.
+fn numbered_columns(
+    names: &[String],
+    long: &str,
+    short: &str,
+    team_size: usize,
+) -> Result<Vec<usize>, String> {
+    let mut numbered = all_numbered_columns(names, long, short);
+    if numbered.len() < team_size {
+        return Err(format!(
+            "expected {} {} columns, found {}",
+            team_size,
+            long,
+            numbered.len()
+        ));
+    }
+    numbered.truncate(team_size);
+    Ok(numbered)
+}
+
+/// Indices of every player column in a header row, named as in results files (see [parse_results]):
+/// `player` or `p` followed by the player's number within the team, or just `player`;
+/// in number order.
+pub fn player_columns(header: &StringRecord) -> Vec<usize> {
+    let names: Vec<_> = header.iter().map(|f| f.trim().to_lowercase()).collect();
+    all_numbered_columns(&names, "player", "p")
+}
+
+/// Parse a game's score, e.g. `21-15` or `21:15`, with the winner's score first.
+fn parse_score(s: &str) -> Option<(u32, u32)> {
+    let (w, l) = s.split_once(['-', ':'])?;
+    let score = (w.trim().parse().ok()?, l.trim().parse().ok()?);
+    (score.0 >= score.1).then_some(score)
+}
+
+/// The players in the given columns of a row, or the malformed field and why.
+fn row_players(
+    record: &StringRecord,
+    columns: &[usize],
+) -> Result<Vec<PlayerId>, (Option<String>, String)> {
+    let mut players = Vec::with_capacity(columns.len());
+    for (n, idx) in columns.iter().enumerate() {
+        let Some(p_str) = record.get(*idx) else {
+            return Err((None, format!("no player {} field", n + 1)));
+        };
+        let Ok(p) = p_str.parse::<PlayerId>() else {
+            return Err((Some(p_str.to_owned()), "invalid player ID".to_owned()));
+        };
+        players.push(p);
+    }
+    Ok(players)
+}
+
+/// The delimiter of a results file: a comma if its first row (other than comments)
+/// contains a comma but no tab, otherwise a tab.
+fn sniff_delimiter(contents: &[u8]) -> u8 {
+    let first_row = contents
+        .strip_prefix(b"\xEF\xBB\xBF")
+        .unwrap_or(contents)
+        .split(|b| *b == b'\n')
+        .find(|l| !l.starts_with(b"#") && !l.trim_ascii().is_empty());
+    match first_row {
+        Some(l) if l.contains(&b',') && !l.contains(&b'\t') => b',',
+        _ => b'\t',
+    }
+}
+
+/// Whether a player field of a results TSV is a bye: `BYE` (case-insensitive) or the [BYE] ID.
+fn is_bye(field: &str) -> bool {
+    let field = field.trim();
+    field.eq_ignore_ascii_case("bye") || field.parse::<PlayerId>() == Ok(BYE)
+}
+
+/// Renumber the places of entrants below byes at the given places,
+/// as if the byes had not been entered.
+pub(crate) fn close_bye_gaps(placings: &mut Placings, mut byes: Vec<u64>) {
+    byes.sort_unstable();
+    for (place, _) in placings.iter_mut() {
+        *place -= byes.partition_point(|b| b < place) as u64;
+    }
+}
+
+/// The text without any leading UTF-8 byte order mark, as written by e.g. Excel.
+fn strip_bom(s: &str) -> &str {
+    s.strip_prefix('\u{feff}').unwrap_or(s)
+}
+
+/// Read finishing positions and entrants from a TSV.
+///
+/// A leading byte order mark and CRLF line endings (as written by e.g. Excel on Windows) are allowed.
+/// Files whose first row is comma- rather than tab-separated are read as CSVs.
+/// By default, each row has a finishing position followed by `team_size` player columns.
+/// The first row may be a header: if it has a `place` column,
+/// the columns are instead found by name: players are in columns named e.g. `player1` or `p1`
+/// (or just `player`, for singles), so that files can have other columns (e.g. team name or seed)
+/// in any order.
+/// Any other first row which looks like a header (e.g. `rank\tplayer1\tplayer2`) is skipped;
+/// see [is_header].
+/// Malformed rows are skipped, unless `strict`, in which case they are an error.
+///
+/// Rows with a [ResultCode] in place of their finishing position are not included;
+/// see [parse_results].
+/// Rows with a bye (see [BYE]) are removed, and the places of the entrants below them adjusted.
+///
+/// Alternatively, the file can list games: if the header row has a `round` column,
+/// each row is a game between players in columns named e.g. `winner1` or `w1`,
+/// and `loser1` or `l1`, in the named round.
+/// Rounds whose names start with `pool` are pool games, and others are bracket rounds,
+/// in the order that they first appear; see [placings_from_matches] for how finishing positions
+/// are derived.
+/// An optional `score` column has the game's score, e.g. `21-15`, with the winner's score first.
+/// Games against a bye are skipped.
+pub fn parse_ranks<R: Read>(
+    r: R,
+    team_size: usize,
+    strict: bool,
+) -> Result<Placings, ResultReadError> {
+    parse_ranks_with_skipped(r, team_size, strict).map(|(ranks, _)| ranks)
+}
+
+/// As [parse_ranks], but also returning the rows which were skipped.
+pub fn parse_ranks_with_skipped<R: Read>(
+    r: R,
+    team_size: usize,
+    strict: bool,
+) -> Result<(Placings, Vec<MalformedRow>), ResultReadError> {
+    parse_results(r, team_size, strict).map(|p| (p.placings, p.skipped))
+}
+
+/// What was read from a TSV by [parse_results].
+#[derive(Debug, Clone, Default)]
+pub struct ParsedResults {
+    pub placings: Placings,
+    /// Entrants with a [ResultCode] (e.g. `DNF`) in place of their finishing position.
+    pub unplaced: Vec<(ResultCode, Entrant)>,
+    /// Placed entrants which withdrew during the tournament.
+    pub withdrawn: Vec<Entrant>,
+    /// Games between entrants, if the file lists games rather than finishing positions.
+    pub matches: Vec<Match>,
+    /// Rows which were skipped, and why.
+    pub skipped: Vec<MalformedRow>,
+}
+
+/// The finishing position or result code in a row of a results TSV.
+enum RowPlace {
+    Placed(u64),
+    /// A finishing position with a `W` suffix, for an entrant which withdrew during the tournament.
+    Withdrew(u64),
+    Unplaced(ResultCode),
+}
+
+impl FromStr for RowPlace {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(place) = s.parse::<u64>() {
+            return Ok(Self::Placed(place));
+        }
+        if let Some(Ok(place)) = s.strip_suffix(['w', 'W']).map(str::parse::<u64>) {
+            return Ok(Self::Withdrew(place));
+        }
+        s.parse().map(Self::Unplaced).map_err(|_| ())
+    }
+}
+
+/// Names of columns other than players' which are expected in the header rows of results TSVs.
+const HEADER_NAMES: &[&str] = &[
+    "place",
+    "rank",
+    "position",
+    "pos",
+    "team",
+    "name",
+    "seed",
+    "withdrawn",
+    "round",
+    "score",
+];
+
+/// Whether a row looks like a header, i.e. any of its fields is a known column name
+/// (see [HEADER_NAMES]) or a player column name like `player`, `player1`, or `p1`.
+fn is_header(record: &StringRecord) -> bool {
+    record.iter().any(|f| {
+        let name = f.trim().to_lowercase();
+        let numbered = |prefix| {
+            name.strip_prefix(prefix)
+                .is_some_and(|n| n.parse::<usize>().is_ok())
+        };
+        HEADER_NAMES.contains(&name.as_str())
+            || name == "player"
+            || numbered("player")
+            || numbered("p")
+    })
+}
+
+/// Whether a value in the `withdrawn` column marks the entrant as withdrawn:
+/// anything other than an empty value, `0`, `false`, or `no`.
+fn is_marked(field: &str) -> bool {
+    !matches!(
+        field.trim().to_lowercase().as_str(),
+        "" | "0" | "false" | "no"
+    )
+}
+
+/// As [parse_ranks], but also returning entrants with result codes and the rows which were skipped.
+pub fn parse_results<R: Read>(
+    r: R,
+    team_size: usize,
+    strict: bool,
+) -> Result<ParsedResults, ResultReadError> {
+    let mut out = ParsedResults::default();
+    let mut r = BufReader::new(r);
+    let delimiter = sniff_delimiter(r.fill_buf()?);
+    let mut rdr = ReaderBuilder::new()
+        .delimiter(delimiter)
+        .comment(Some(b'#'))
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(r);
+    let mut columns = RankColumns::positional(team_size);
+    let mut match_columns = None;
+    let mut first = true;
+    let mut byes = Vec::default();
+
+    for result in rdr.records() {
+        let record = result.map_err(|e| MalformedRow {
+            path: None,
+            line: e.position().map_or(0, |p| p.line()),
+            field: None,
+            reason: e.to_string(),
+        })?;
+        let line = record.position().map_or(0, |p| p.line());
+        let malformed = |field: Option<&str>, reason: String| MalformedRow {
+            path: None,
+            line,
+            field: field.map(|f| f.to_owned()),
+            reason,
+        };
+        if std::mem::take(&mut first) {
+            match MatchColumns::from_header(&record, team_size) {
+                Some(Ok(c)) => {
+                    match_columns = Some(c);
+                    continue;
+                }
+                Some(Err(reason)) => return Err(malformed(None, reason).into()),
+                None => (),
+            }
+            match RankColumns::from_header(&record, team_size) {
+                Some(Ok(c)) => {
+                    columns = c;
+                    continue;
+                }
+                Some(Err(reason)) => return Err(malformed(None, reason).into()),
+                None if is_header(&record) => continue,
+                None => (),
+            }
+        }
+        if let Some(mc) = &match_columns {
+            let round = record.get(mc.round).unwrap_or_default().trim();
+            if mc
+                .winner
+                .iter()
+                .chain(mc.loser.iter())
+                .filter_map(|idx| record.get(*idx))
+                .any(is_bye)
+            {
+                continue;
+            }
+            let score = match mc.score.and_then(|idx| record.get(idx)).map(str::trim) {
+                None | Some("") => Ok(None),
+                Some(s) => parse_score(s)
+                    .map(Some)
+                    .ok_or_else(|| (Some(s.to_owned()), "invalid score".to_owned())),
+            };
+            let sides = row_players(&record, &mc.winner).and_then(|w| {
+                let l = row_players(&record, &mc.loser)?;
+                Ok((w, l, score?))
+            });
+            match sides {
+                Ok((winner, loser, score)) => out.matches.push(Match {
+                    round: round.to_owned(),
+                    winner: Entrant::from_players(&winner)?,
+                    loser: Entrant::from_players(&loser)?,
+                    score,
+                }),
+                Err((field, reason)) => {
+                    let m = malformed(field.as_deref(), reason);
+                    if strict {
+                        return Err(m.into());
+                    }
+                    debug!("Skipping game on line {}: {}", line, m.reason);
+                    out.skipped.push(m);
+                }
+            }
+            continue;
+        }
+        let Some(rank_str) = record.get(columns.place) else {
+            continue;
+        };
+        // places start at 1
+        let place = match rank_str.parse::<RowPlace>() {
+            Ok(RowPlace::Placed(0) | RowPlace::Withdrew(0)) | Err(()) => {
+                let m = malformed(Some(rank_str), "invalid rank".to_owned());
+                if strict {
+                    return Err(m.into());
+                }
+                debug!("Could not parse '{}' as rank, skipping", rank_str);
+                out.skipped.push(m);
+                continue;
+            }
+            Ok(place) => place,
+        };
+        if columns
+            .players
+            .iter()
+            .filter_map(|idx| record.get(*idx))
+            .any(is_bye)
+        {
+            if let RowPlace::Placed(place) | RowPlace::Withdrew(place) = place {
+                byes.push(place);
+            }
+            continue;
+        }
+        let players = match row_players(&record, &columns.players) {
+            Ok(p) => p,
+            Err((field, reason)) => {
+                let m = malformed(field.as_deref(), reason);
+                if strict {
+                    return Err(m.into());
+                }
+                debug!("Skipping row on line {}: {}", line, m.reason);
+                out.skipped.push(m);
+                continue;
+            }
+        };
+        let entrant = Entrant::from_players(&players)?;
+        let marked = columns
+            .withdrawn
+            .and_then(|idx| record.get(idx))
+            .is_some_and(is_marked);
+        match place {
+            RowPlace::Placed(place) if !marked => out.placings.push((place, entrant)),
+            RowPlace::Placed(place) | RowPlace::Withdrew(place) => {
+                out.withdrawn.push(entrant.clone());
+                out.placings.push((place, entrant));
+            }
+            RowPlace::Unplaced(code) => out.unplaced.push((code, entrant)),
+        }
+    }
+    close_bye_gaps(&mut out.placings, byes);
+    if match_columns.is_some() {
+        out.placings = placings_from_matches(&out.matches);
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    fn data_dir() -> PathBuf {
+        let mut d = PathBuf::from(PathBuf::from(env!("CARGO_MANIFEST_DIR")).parent().unwrap());
+        d.push("example_data");
+        d
+    }
+
+    #[test]
+    fn iter_sorted_by_date() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().to_owned();
+        let mut dir = root.clone();
+        dir.push(Level::SMALL.directory_name());
+        fs::create_dir_all(&dir).unwrap();
+        for (fname, p) in [("2022-06-02.tsv", 3), ("2022-06-01.tsv", 1)] {
+            let mut path = dir.clone();
+            path.push(fname);
+            fs::write(path, format!("place\tp1\tp2\n1\t{}\t{}\n", p, p + 1)).unwrap();
+        }
+        let ingester = ResultIngester::new(&root);
+        let ts: Vec<_> = ingester.iter().collect::<Result<_, _>>().unwrap();
+
+        assert_eq!(ts.len(), 2);
+        assert!(ts[0].datetime() < ts[1].datetime());
+        assert_eq!(ts[0].results()[0].1, Team::new(1, 2).unwrap().into());
+    }
+
+    #[test]
+    fn tsv_metadata() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().to_owned();
+        let mut dir = root.clone();
+        dir.push(Level::SMALL.directory_name());
+        fs::create_dir_all(&dir).unwrap();
+        let mut path = dir.clone();
+        path.push("2022-06-01_open.tsv");
+        fs::write(
+            &path,
+            "# name: Open\n# location: Cambridge\nplace\tp1\tp2\n1\t1\t2\n",
+        )
+        .unwrap();
+        fs::write(path.with_extension("toml"), "location = \"Oxford\"\n").unwrap();
+        let ts = ResultIngester::new(&root).ingest();
+
+        let meta = ts.unwrap()[0].metadata().clone();
+        assert_eq!(meta.name.as_deref(), Some("Open"));
+        assert_eq!(meta.location.as_deref(), Some("Oxford"));
+        assert_eq!(meta.organizer, None);
+    }
+
+    #[test]
+    fn ingestion_report() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().to_owned();
+        let mut dir = root.clone();
+        dir.push(Level::SMALL.directory_name());
+        fs::create_dir_all(&dir).unwrap();
+        let mut path = dir.clone();
+        path.push("2022-06-01.tsv");
+        fs::write(&path, "place\tp1\tp2\n1\t1\t2\nfirst\t3\t4\n").unwrap();
+        fs::write(path.with_extension("toml"), "name = \"Open\"\n").unwrap();
+        let mut notes = dir.clone();
+        notes.push("notes.txt");
+        fs::write(&notes, "").unwrap();
+        let result = ResultIngester::new(&root).ingest_with_report();
+
+        let (ts, report) = result.unwrap();
+        assert_eq!(ts.len(), 1);
+        assert_eq!(report.tournaments, 1);
+        assert_eq!(report.files_read, vec![path.clone()]);
+        assert_eq!(report.unmatched_files, vec![notes]);
+        assert_eq!(report.skipped_rows.len(), 1);
+        assert_eq!(report.skipped_rows[0].path, Some(path));
+        assert_eq!(report.skipped_rows[0].line, 3);
+    }
+
+    #[test]
+    fn extra_file_outside_root() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().to_owned();
+        let extra = root.join("2022-06-01_hypothetical.tsv");
+        fs::write(&extra, "# level: major\nplace\tp1\tp2\n1\t1\t2\n2\t3\t4\n").unwrap();
+        let early = root.join("2021-06-01.tsv");
+        fs::write(&early, "place\tp1\tp2\n1\t1\t2\n").unwrap();
+        let ingester = ResultIngester::new(root.join("missing"))
+            .from(Utc.with_ymd_and_hms(2022, 1, 1, 0, 0, 0).unwrap());
+        let ts = ingester.read_extra(&extra, &Level::SMALL);
+        let outside = ingester.read_extra(&early, &Level::SMALL);
+
+        let ts = ts.unwrap();
+        assert_eq!(ts.len(), 1);
+        assert_eq!(ts[0].level(), &Level::MAJOR);
+        assert_eq!(ts[0].results().len(), 2);
+        assert!(outside.unwrap().is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn non_utf8_file_name() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().to_owned();
+        let mut dir = root.clone();
+        dir.push(Level::SMALL.directory_name());
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("2022-06-01.tsv"), "1\t1\t2\n").unwrap();
+        let weird = dir.join(OsStr::from_bytes(b"2022-07-01\xff.tsv"));
+        fs::write(&weird, "1\t1\t2\n").unwrap();
+        let ingester = ResultIngester::new(&root);
+        let result = ingester.ingest_with_report();
+        let checks = ingester.validate();
+
+        let (ts, report) = result.unwrap();
+        assert_eq!(ts.len(), 1);
+        assert_eq!(report.unmatched_files, vec![weird.clone()]);
+        assert!(checks
+            .iter()
+            .any(|(p, c)| *p == weird && matches!(c, FileCheck::Ignored)));
+    }
+
+    #[test]
+    fn include_exclude() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().to_owned();
+        let mut dir = root.clone();
+        dir.push(Level::SMALL.directory_name());
+        dir.push("drafts");
+        fs::create_dir_all(&dir).unwrap();
+        for path in [
+            "small/2022-06-01.tsv",
+            "small/2022-07-01.tsv.bak",
+            "small/2023-06-01.tsv",
+            "small/drafts/2023-07-01.tsv",
+        ] {
+            fs::write(root.join(path), "1\t1\t2\n").unwrap();
+        }
+        let patterns = |ps: &[&str]| ps.iter().map(|p| Pattern::new(p).unwrap()).collect();
+        let ingester = ResultIngester::new(&root).exclude(patterns(&["*.bak", "**/drafts/*"]));
+        let (excluded, report) = ingester.ingest_with_report().unwrap();
+        let included = ingester.include(patterns(&["*/2023-*"])).ingest();
+
+        assert_eq!(excluded.len(), 2);
+        assert!(report.unmatched_files.is_empty());
+        let included = included.unwrap();
+        assert_eq!(included.len(), 1);
+        assert_eq!(included[0].datetime().year(), 2023);
+    }
+
+    #[test]
+    fn date_from_contents() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().to_owned();
+        let mut dir = root.clone();
+        dir.push(Level::SMALL.directory_name());
+        fs::create_dir_all(&dir).unwrap();
+        for (name, contents) in [
+            ("open.tsv", "# date: 2023-05-01\n# level: major\n1\t1\t2\n"),
+            (
+                "2022-01-01_cup.tsv",
+                "# date: 2023-07-01\n# timezone: +01:00\n1\t3\t4\n",
+            ),
+            ("export.tsv", "place\tp1\tp2\tdate\n1\t5\t6\t2023-06-01\n"),
+            ("undated.tsv", "1\t7\t8\n"),
+        ] {
+            fs::write(dir.join(name), contents).unwrap();
+        }
+        let ingester = ResultIngester::new(&root).columns(TsvColumns {
+            date: Some(4),
+            level: None,
+        });
+        let result = ingester.ingest_with_report();
+        let checks = ingester.validate();
+
+        let (ts, report) = result.unwrap();
+        let date = |t: &Tournament| t.datetime().date_naive().to_string();
+        assert_eq!(ts.len(), 3);
+        assert_eq!(date(&ts[0]), "2023-05-01");
+        assert_eq!(ts[0].level(), &Level::MAJOR);
+        assert_eq!(date(&ts[1]), "2023-06-01");
+        assert_eq!(ts[1].level(), &Level::SMALL);
+        assert_eq!(ts[2].datetime().to_rfc3339(), "2023-06-30T23:00:00+00:00");
+        assert_eq!(report.unmatched_files, vec![dir.join("undated.tsv")]);
+        let invalid: Vec<_> = checks
+            .iter()
+            .filter(|(_, c)| matches!(c, FileCheck::Invalid(_)))
+            .collect();
+        assert_eq!(invalid.len(), 1);
+        assert_eq!(invalid[0].0, dir.join("undated.tsv"));
+    }
+
+    #[test]
+    fn unknown_level_in_contents() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().to_owned();
+        let mut dir = root.clone();
+        dir.push(Level::SMALL.directory_name());
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("2023-05-01.tsv"), "# level: major\n1\t1\t2\n").unwrap();
+        let only_small = ResultIngester::new(&root)
+            .levels(HashSet::from([Level::SMALL]))
+            .ingest_with_report();
+        fs::write(dir.join("2023-06-01.tsv"), "# level: mythical\n1\t3\t4\n").unwrap();
+        let unknown = ResultIngester::new(&root).ingest();
+
+        let (ts, report) = only_small.unwrap();
+        assert!(ts.is_empty());
+        assert!(report.unmatched_files.is_empty());
+        assert!(matches!(
+            unknown,
+            Err(ResultReadError::UnknownLevel(lvl)) if lvl == Level::new("mythical")
+        ));
+    }
+
+    #[test]
+    fn gzipped_tsv() {
+        use flate2::{write::GzEncoder, Compression};
+        use std::io::Write;
+
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().to_owned();
+        let mut dir = root.clone();
+        dir.push(Level::SMALL.directory_name());
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("2023-07-10_open.tsv.gz");
+        let mut gz = GzEncoder::new(File::create(&path).unwrap(), Compression::default());
+        gz.write_all(b"# name: Open\nplace\tp1\tp2\n1\t1\t2\n2\t3\t4\n")
+            .unwrap();
+        gz.finish().unwrap();
+        fs::write(dir.join("2023-07-10_open.toml"), "location = \"Oxford\"\n").unwrap();
+        let result = ResultIngester::new(&root).ingest_with_report();
+
+        let (ts, report) = result.unwrap();
+        assert_eq!(ts.len(), 1);
+        assert_eq!(ts[0].results().len(), 2);
+        assert_eq!(ts[0].metadata().name.as_deref(), Some("Open"));
+        assert_eq!(ts[0].metadata().location.as_deref(), Some("Oxford"));
+        assert_eq!(report.files_read, vec![path]);
+        assert!(report.unmatched_files.is_empty());
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn mapped_tsv() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().to_owned();
+        let dir = root.join(Level::SMALL.directory_name());
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("2023-07-10_open.tsv");
+        let mut tsv = String::from("# name: Open\nplace\tp1\tp2\n");
+        let n_rows = 100_000;
+        for place in 1..=n_rows {
+            tsv.push_str(&format!("{}\t{}\t{}\n", place, place * 2, place * 2 + 1));
+        }
+        fs::write(&path, tsv).unwrap();
+        let mapped = matches!(Files::default().read(&path), Ok(Contents::Mapped(_)));
+        let result = ResultIngester::new(&root).ingest();
+
+        assert!(mapped);
+        let ts = result.unwrap();
+        assert_eq!(ts[0].results().len(), n_rows as usize);
+        assert_eq!(ts[0].metadata().name.as_deref(), Some("Open"));
+    }
+
+    #[test]
+    fn zip_root() {
+        use std::io::Write;
+
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().join("results.zip");
+        let mut zip = zip::ZipWriter::new(File::create(&root).unwrap());
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated);
+        for (name, contents) in [
+            ("small/uk/2023-07-10_open.tsv", "place\tp1\tp2\n1\t1\t2\n"),
+            ("small/uk/2023-07-10_open.toml", "name = \"Open\"\n"),
+            ("small/notes.txt", ""),
+            ("major/2023-08-01.tsv", "place\tp1\tp2\n1\t3\t4\n2\t1\t2\n"),
+        ] {
+            zip.start_file(name, options).unwrap();
+            zip.write_all(contents.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+
+        let ingester = ResultIngester::new(&root);
+        let result = ingester.ingest_with_report();
+        let checks = ingester.validate();
+
+        let (ts, report) = result.unwrap();
+        assert_eq!(ts.len(), 2);
+        assert_eq!(ts[0].metadata().name.as_deref(), Some("Open"));
+        assert_eq!(ts[1].level(), &Level::MAJOR);
+        assert_eq!(ts[1].results().len(), 2);
+        assert_eq!(report.unmatched_files, vec![root.join("small/notes.txt")]);
+        assert!(checks
+            .iter()
+            .all(|(_, c)| !matches!(c, FileCheck::Invalid(_))));
+    }
+
+    #[test]
+    fn validate_place_zero() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().to_owned();
+        let mut dir = root.clone();
+        dir.push(Level::SMALL.directory_name());
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("2023-06-01.tsv"), "0\t1\t2\n2\t3\t4\n").unwrap();
+        fs::write(dir.join("2023-07-01.tsv"), "1\t1\t2\n2\t3\t4\n").unwrap();
+        let checks = ResultIngester::new(&root).validate();
+
+        assert_eq!(checks.len(), 2);
+        assert!(matches!(checks[0].1, FileCheck::Invalid(_)));
+        assert!(matches!(checks[1].1, FileCheck::Valid(1)));
+    }
+
+    #[test]
+    fn same_day_order() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().to_owned();
+        let mut dir = root.clone();
+        dir.push(Level::SMALL.directory_name());
+        fs::create_dir_all(&dir).unwrap();
+        for (name, player) in [
+            ("2023-07-10T15_late.tsv", 1),
+            ("2023-07-10T09-30_early.tsv", 3),
+            ("2023-07-11_b.tsv", 7),
+            ("2023-07-11_a.tsv", 5),
+        ] {
+            fs::write(
+                dir.join(name),
+                format!("place\tp1\tp2\n1\t{}\t{}\n", player, player + 1),
+            )
+            .unwrap();
+        }
+        let ingester = ResultIngester::new(&root);
+        let ts = ingester.ingest();
+        let lazy: Result<Vec<_>, _> = ingester.iter().collect();
+
+        let first_players = |ts: Vec<Tournament>| -> Vec<_> {
+            ts.iter().map(|t| t.results()[0].1.players()[0]).collect()
+        };
+        assert_eq!(first_players(ts.unwrap()), vec![3, 1, 5, 7]);
+        assert_eq!(first_players(lazy.unwrap()), vec![3, 1, 5, 7]);
+    }
+
+    #[test]
+    fn chunked_ranking() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().to_owned();
+        let dir = root.join(Level::SMALL.directory_name());
+        fs::create_dir_all(&dir).unwrap();
+        for (name, first, second) in [
+            ("2023-07-10_a.tsv", 1, 3),
+            ("2023-07-10_b.tsv", 5, 7),
+            ("2023-07-11.tsv", 3, 5),
+            ("2023-08-01.tsv", 7, 1),
+            ("2023-09-01.tsv", 1, 5),
+        ] {
+            fs::write(
+                dir.join(name),
+                format!(
+                    "place\tp1\tp2\n1\t{}\t{}\n2\t{}\t{}\n",
+                    first,
+                    first + 1,
+                    second,
+                    second + 1
+                ),
+            )
+            .unwrap();
+        }
+        let ingester = ResultIngester::new(&root);
+        let config = Config::default();
+        let ts = ingester.ingest();
+        let chunked = rank_players_chunked(&ingester, 2023, &config, 2);
+
+        let (ranks, records) = rank_players(&ts.unwrap(), 2023, &config).unwrap();
+        let (chunked_ranks, chunked_records) = chunked.unwrap();
+        assert_eq!(ranks.len(), 8);
+        assert_eq!(chunked_ranks, ranks);
+        for (pid, rec) in records {
+            assert_eq!(chunked_records[&pid].rating, rec.rating);
+            assert_eq!(chunked_records[&pid].history().len(), rec.history().len());
+        }
+    }
+
+    #[test]
+    fn filename_date() {
+        let date = |y, m, d| Utc.with_ymd_and_hms(y, m, d, 0, 0, 0).unwrap();
+        let default = FilenameDate::default();
+        assert_eq!(
+            default.parse("2023-07-10_open.tsv", None).unwrap().unwrap(),
+            date(2023, 7, 10)
+        );
+        assert!(default.parse("10-07-2023.tsv", None).is_none());
+        assert!(default.parse("2023-13-10.tsv", None).unwrap().is_err());
+        assert_eq!(
+            default
+                .parse("2023-07-10T14-30_open.tsv", None)
+                .unwrap()
+                .unwrap(),
+            date(2023, 7, 10) + Duration::minutes(14 * 60 + 30)
+        );
+        assert_eq!(
+            default.parse("2023-07-10T09.tsv", None).unwrap().unwrap(),
+            date(2023, 7, 10) + Duration::hours(9)
+        );
+        assert!(default.parse("2023-07-10T25.tsv", None).unwrap().is_err());
+        assert_eq!(
+            default
+                .parse("2023-07-10T14-30+02_open.tsv", None)
+                .unwrap()
+                .unwrap(),
+            date(2023, 7, 10) + Duration::minutes(12 * 60 + 30)
+        );
+        assert_eq!(
+            default
+                .parse("2023-07-10T09-00-0530.tsv", None)
+                .unwrap()
+                .unwrap(),
+            date(2023, 7, 10) + Duration::minutes(14 * 60 + 30)
+        );
+        let tokyo: Zone = "Asia/Tokyo".parse().unwrap();
+        assert_eq!(
+            default
+                .parse("2023-07-10.tsv", Some(&tokyo))
+                .unwrap()
+                .unwrap(),
+            date(2023, 7, 9) + Duration::hours(15)
+        );
+        assert_eq!(
+            default
+                .parse("2023-07-10T00-00Z.tsv", Some(&tokyo))
+                .unwrap()
+                .unwrap(),
+            date(2023, 7, 10)
+        );
+
+        let dmy = FilenameDate::new("%d-%m-%Y").unwrap();
+        assert_eq!(
+            dmy.parse("open_10-07-2023.tsv", None).unwrap().unwrap(),
+            date(2023, 7, 10)
+        );
+        let compact = FilenameDate::new("%Y%m%d").unwrap();
+        assert_eq!(
+            compact.parse("20230710.tsv", None).unwrap().unwrap(),
+            date(2023, 7, 10)
+        );
+        let named = FilenameDate::new("%d%b%y").unwrap();
+        assert_eq!(
+            named.parse("10Jul23.tsv", None).unwrap().unwrap(),
+            date(2023, 7, 10)
+        );
+
+        assert!(FilenameDate::new("%m-%d").is_err());
+        assert!(FilenameDate::new("%Y-%m").is_err());
+        assert!(FilenameDate::new("%Y-%m-%d %H").is_err());
+
+        let config = Config::from_toml(
+            "finish_decay = 1.1\nrecord_length = 10\nfilename_date = \"%d.%m.%Y\"\n[levels]\nsmall = 50",
+        )
+        .unwrap();
+        assert_eq!(config.get_filename_date().format(), "%d.%m.%Y");
+        assert!(Config::from_toml(
+            "finish_decay = 1.1\nrecord_length = 10\nfilename_date = \"%Y\"\n[levels]\nsmall = 50",
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn config_deser() {
+        let mut path = data_dir();
+        path.push("default_config.toml");
+        let contents = fs::read_to_string(path).expect("Could not read");
+        let config: Config = toml::from_str(&contents).expect("Could not parse");
+        assert_eq!(config.finish_decay, 1.1);
+        assert_eq!(config.age_decay, 1.1);
+        assert_eq!(config.record_length, 10);
+        assert_eq!(config.levels[&Level::SMALL].points, 50.0);
+        assert_eq!(config.levels[&Level::MEDIUM].points, 125.0);
+        assert_eq!(config.levels[&Level::MAJOR].points, 200.0);
+        assert_eq!(config.levels[&Level::CHAMPIONSHIP].points, 250.0);
+        assert_eq!(config.bonus, default_bonus());
+        assert_eq!(config.algorithm, Algorithm::Points);
+    }
+
+    #[test]
+    fn custom_level_deser() {
+        let config: Config = toml::from_str(
+            r#"
+            finish_decay = 1.1
+            age_decay = 1.1
+            record_length = 10
+
+            [levels]
+            small = 50
+            regional = { points = 80, directory = "regionals" }
+            "#,
+        )
+        .expect("Could not parse");
+        let regional = Level::new("regional");
+        assert_eq!(config.get_level(&regional), Some(80.0));
+        assert_eq!(config.get_level_directory(&regional), Some("regionals"));
+        assert_eq!(config.get_level_directory(&Level::SMALL), Some("small"));
+        assert_eq!(config.get_level(&Level::MAJOR), None);
+    }
+
+    #[test]
+    fn finish_table() {
+        let config: Config = toml::from_str(
+            r#"
+            finish_decay = 2.0
+            age_decay = 1.1
+            record_length = 10
+
+            [levels]
+            small = { points = 100, finish = [1.0, 0.8, 0.65] }
+            "#,
+        )
+        .expect("Could not parse");
+        let small = &config.levels[&Level::SMALL];
+        assert_eq!(small.finish_factor(2.0, 1), 1.0);
+        assert_eq!(small.finish_factor(2.0, 3), 0.65);
+        assert_eq!(small.finish_factor(2.0, 4), 1.0 / 16.0);
+
+        let dt = Utc.with_ymd_and_hms(2022, 6, 1, 0, 0, 0).unwrap();
+        let t = Tournament::new(
+            vec![(1, Entrant::Single(1)), (2, Entrant::Single(2))],
+            dt,
+            Level::SMALL,
+        )
+        .unwrap();
+        let pts = t.points(2022, &HashMap::default(), &config).unwrap();
+        assert_eq!(pts[&1], 100.0);
+        assert_eq!(pts[&2], 80.0);
+    }
+
+    #[test]
+    fn carry_over() {
+        let dt = |y| Utc.with_ymd_and_hms(y, 6, 1, 0, 0, 0).unwrap();
+        let t = |y| {
+            Tournament::new(
+                vec![(1, Team::new(1, 2).unwrap().into())],
+                dt(y),
+                Level::SMALL,
+            )
+            .unwrap()
+        };
+        let config = Config::default().record_length(1);
+        let mut engine = RankingEngine::new(PointsSystem::new(config.clone(), 2021));
+        engine.add_tournament(t(2020)).unwrap();
+        engine.add_tournament(t(2021)).unwrap();
+        let previous = engine.checkpoint();
+        let rating = previous.records[&1].rating;
+
+        let system = PointsSystem::new(config, 2022).with_previous_season(&previous);
+        let rec = &system.records()[&1];
+        // only the counted result is carried over
+        assert_eq!(rec.history().len(), 1);
+        assert_eq!(rec.history()[0].carry_factor, 0.5);
+        assert_eq!(rec.rating, rating * 0.5);
+        assert_eq!(system.team_ratings().len(), 1);
+
+        let no_history = PlayerRecord::new_with_points(3, 2, &[1.0, 2.0, 4.0]);
+        assert_eq!(no_history.carry_over(0.25).rating, 1.5);
+    }
+
+    #[test]
+    fn engine_initial_ranks() {
+        let dt = |m| Utc.with_ymd_and_hms(2022, m, 1, 0, 0, 0).unwrap();
+        let t = |m| {
+            Tournament::new(
+                vec![(1, Entrant::Single(1)), (2, Entrant::Single(2))],
+                dt(m),
+                Level::SMALL,
+            )
+            .unwrap()
+        };
+        let config = Config::default().bonus(vec![(1, 10.0)]);
+        let seed: PlayerMap<_> = [(2, 1)].into_iter().collect();
+        let mut engine =
+            RankingEngine::new(PointsSystem::new(config, 2022)).with_initial_ranks(seed, dt(7));
+        for m in [6, 8] {
+            engine.add_tournament(t(m)).unwrap();
+        }
+        let history = engine.current_records()[&1].history();
+        // seeded rank gives a bonus for finishing above player 2
+        assert_eq!(history[0].bonus, 10.0);
+        // calculated ranks have player 1 first, so no bonus
+        assert_eq!(history[1].bonus, 0.0);
+    }
+
+    #[test]
+    fn engine_rejects_unordered() {
+        let early = Utc.with_ymd_and_hms(2022, 6, 1, 0, 0, 0).unwrap();
+        let late = Utc.with_ymd_and_hms(2022, 7, 1, 0, 0, 0).unwrap();
+        let results = vec![
+            (1, Team::new(1, 2).unwrap().into()),
+            (2, Team::new(3, 4).unwrap().into()),
+        ];
+        let mut engine = RankingEngine::new(PointsSystem::new(Config::default(), 2022));
+        engine
+            .add_tournament(Tournament::new(results.clone(), late, Level::SMALL).unwrap())
+            .unwrap();
+        assert!(engine
+            .add_tournament(Tournament::new(results, early, Level::SMALL).unwrap())
+            .is_err());
+        assert_eq!(engine.current_ranks().len(), 4);
+        assert_eq!(engine.current_records().len(), 4);
+    }
+
+    #[test]
+    fn rank_with_sorts() {
+        let early = Utc.with_ymd_and_hms(2022, 6, 1, 0, 0, 0).unwrap();
+        let late = Utc.with_ymd_and_hms(2022, 6, 2, 0, 0, 0).unwrap();
+        let results = || {
+            vec![
+                (1, Team::new(1, 2).unwrap().into()),
+                (2, Team::new(3, 4).unwrap().into()),
+            ]
+        };
+        let ts = vec![
+            Tournament::new(results(), late, Level::SMALL).unwrap(),
+            Tournament::new(results(), early, Level::SMALL).unwrap(),
+        ];
+        let (ranks, _) = rank_players(&ts, 2022, &Config::default()).unwrap();
+        assert_eq!(ranks.len(), 4);
+    }
+
+    #[test]
+    fn simultaneous_matches_sequential() {
+        let early = Utc.with_ymd_and_hms(2022, 6, 1, 0, 0, 0).unwrap();
+        let late = Utc.with_ymd_and_hms(2022, 6, 2, 0, 0, 0).unwrap();
+        let mut ts = Vec::default();
+        for (offset, dt) in [(0, early), (10, early), (20, early), (0, late), (30, late)] {
+            let results = (0..4)
+                .map(|i| {
+                    let team = Team::new(offset + 2 * i, offset + 2 * i + 1).unwrap();
+                    (i + 1, team.into())
+                })
+                .collect();
+            ts.push(Tournament::new(results, dt, Level::SMALL).unwrap());
+        }
+        for split in [PointSplit::Even, PointSplit::Rating] {
+            let config = Config::default().split(split);
+            let (ranks, records) = rank_players(&ts, 2022, &config).unwrap();
+
+            let mut engine = RankingEngine::new(PointsSystem::new(config, 2022));
+            for t in ts.iter() {
+                engine.add_tournament(t).unwrap();
+            }
+            assert_eq!(ranks, engine.current_ranks());
+            for (pid, rec) in engine.current_records() {
+                assert_eq!(records[pid].rating, rec.rating);
+            }
+        }
+    }
+
+    #[test]
+    fn timeline_snapshots() {
+        let early = Utc.with_ymd_and_hms(2022, 6, 1, 0, 0, 0).unwrap();
+        let late = Utc.with_ymd_and_hms(2022, 6, 2, 0, 0, 0).unwrap();
+        let ts = vec![
+            Tournament::new(
+                vec![
+                    (1, Team::new(5, 6).unwrap().into()),
+                    (2, Team::new(1, 2).unwrap().into()),
+                ],
+                late,
+                Level::SMALL,
+            )
+            .unwrap(),
+            Tournament::new(
+                vec![
+                    (1, Team::new(1, 2).unwrap().into()),
+                    (2, Team::new(3, 4).unwrap().into()),
+                ],
+                early,
+                Level::SMALL,
+            )
+            .unwrap(),
+            Tournament::new(vec![(1, Entrant::Single(7))], early, Level::SMALL).unwrap(),
+        ];
+        let mut system = PointsSystem::new(Config::default(), 2022);
+        let tl = timeline(&mut system, &ts).unwrap();
+        assert_eq!(tl.points().len(), 2);
+        assert_eq!(tl.points()[0].datetime, early);
+        assert_eq!(tl.points()[0].ranks.len(), 5);
+
+        let history = tl.player(1);
+        assert_eq!(history.len(), 2);
+        assert!(history[1].1 > history[0].1);
+        assert_eq!(tl.player(5).len(), 1);
+        assert_eq!(tl.top(3).len(), 3);
+        assert_eq!(tl.points()[1].ratings, system.ratings());
+    }
+
+    #[test]
+    fn points_win_probability() {
+        let dt = Utc.with_ymd_and_hms(2022, 6, 1, 0, 0, 0).unwrap();
+        let results = vec![
+            (1, Team::new(1, 2).unwrap().into()),
+            (2, Team::new(3, 4).unwrap().into()),
+        ];
+        let mut system = PointsSystem::new(Config::default(), 2022);
+        system
+            .add_tournament(
+                &Tournament::new(results, dt, Level::SMALL).unwrap(),
+                &PlayerMap::default(),
+            )
+            .unwrap();
+        let p = system.win_probability(&[1, 2], &[3, 4]);
+        assert!(p > 0.5 && p < 1.0);
+        assert!((system.win_probability(&[3, 4], &[1, 2]) + p - 1.0).abs() < 1e-9);
+        assert_eq!(system.win_probability(&[5], &[6]), 0.5);
+    }
+
+    #[test]
+    fn checkpoint_roundtrip() {
+        let dt = Utc.with_ymd_and_hms(2022, 6, 1, 0, 0, 0).unwrap();
+        let results = vec![
+            (1, Team::new(1, 2).unwrap().into()),
+            (2, Team::new(3, 4).unwrap().into()),
+        ];
+        let mut engine = RankingEngine::new(PointsSystem::new(Config::default(), 2022));
+        engine
+            .add_tournament(Tournament::new(results, dt, Level::SMALL).unwrap())
+            .unwrap();
+
+        let mut buf = Vec::default();
+        engine.checkpoint().write(&mut buf).unwrap();
+        let checkpoint = Checkpoint::read(buf.as_slice()).unwrap();
+        assert_eq!(checkpoint.last_datetime, Some(dt));
+        assert_eq!(checkpoint.ranks, engine.current_ranks());
+
+        let restored = RankingEngine::from_checkpoint(checkpoint, Config::default(), 2022);
+        assert_eq!(restored.current_ranks(), engine.current_ranks());
+        assert_eq!(
+            restored.current_records()[&1].rating,
+            engine.current_records()[&1].rating
+        );
+    }
+
+    #[test]
+    fn resume_at_same_datetime() {
+        let dt = Utc.with_ymd_and_hms(2022, 6, 1, 0, 0, 0).unwrap();
+        let later = Utc.with_ymd_and_hms(2022, 7, 1, 0, 0, 0).unwrap();
+        let tournament = |a, b, c, d, dt| {
+            let results = vec![
+                (1, Team::new(a, b).unwrap().into()),
+                (2, Team::new(c, d).unwrap().into()),
+            ];
+            Tournament::new(results, dt, Level::SMALL).unwrap()
+        };
+        let ts = [
+            tournament(1, 2, 3, 4, dt),
+            tournament(5, 6, 1, 2, dt),
+            tournament(3, 4, 5, 6, later),
+        ];
+        let mut whole = RankingEngine::new(PointsSystem::new(Config::default(), 2022));
+        whole.add_tournaments(&ts).unwrap();
+
+        let mut first = RankingEngine::new(PointsSystem::new(Config::default(), 2022));
+        first.add_tournament(&ts[0]).unwrap();
+        let checkpoint = first.checkpoint();
+        assert_eq!(checkpoint.seen.len(), 1);
+        let mut resumed = RankingEngine::from_checkpoint(checkpoint, Config::default(), 2022);
+        // re-reading from the checkpoint's date/time picks up the second tournament only
+        resumed.add_tournaments(&ts[..2]).unwrap();
+        assert_eq!(resumed.checkpoint().seen.len(), 2);
+        resumed.add_tournament(&ts[2]).unwrap();
+
+        assert_eq!(resumed.current_ranks(), whole.current_ranks());
+        for (pid, record) in whole.current_records() {
+            assert_eq!(resumed.current_records()[pid].rating, record.rating);
+            assert_eq!(resumed.current_records()[pid].events(), record.events());
+        }
+    }
+
+    #[test]
+    fn counted_history() {
+        let mut engine = RankingEngine::new(PointsSystem::new(Config::default(), 2022));
+        for day in 1..=(RECORD_LENGTH as u32 + 1) {
+            let dt = Utc.with_ymd_and_hms(2022, 6, day, 0, 0, 0).unwrap();
+            let mut teams = vec![Team::new(1, 2).unwrap(), Team::new(3, 4).unwrap()];
+            if day == 1 {
+                teams.reverse();
+            }
+            let results = teams
+                .into_iter()
+                .enumerate()
+                .map(|(i, t)| (i as u64 + 1, t.into()))
+                .collect();
+            engine
+                .add_tournament(Tournament::new(results, dt, Level::SMALL).unwrap())
+                .unwrap();
+        }
+        let history = engine.current_records()[&1].counted_history();
+        assert_eq!(history.len(), RECORD_LENGTH + 1);
+        assert_eq!(history[0].0.place, 2);
+        assert!(!history[0].1);
+        assert!(history[1..]
+            .iter()
+            .all(|(b, counted)| b.place == 1 && *counted));
+    }
+
+    #[test]
+    fn checkpoint_trims_history() {
+        let config = Config::default().record_length(2);
+        let mut engine = RankingEngine::new(PointsSystem::new(config.clone(), 2022));
+        for day in 1..=4 {
+            let dt = Utc.with_ymd_and_hms(2022, 6, day, 0, 0, 0).unwrap();
+            let mut teams = vec![Team::new(1, 2).unwrap(), Team::new(3, 4).unwrap()];
+            if day % 2 == 0 {
+                teams.reverse();
+            }
+            let results = teams
+                .into_iter()
+                .enumerate()
+                .map(|(i, t)| (i as u64 + 1, t.into()))
+                .collect();
+            engine
+                .add_tournament(Tournament::new(results, dt, Level::SMALL).unwrap())
+                .unwrap();
+        }
+        let checkpoint = engine.checkpoint();
+        let trimmed = &checkpoint.records[&1];
+        let days: Vec<_> = trimmed.history().iter().map(|b| b.datetime.day()).collect();
+        // the counted wins, and the latest result
+        assert_eq!(days, vec![1, 3, 4]);
+        assert_eq!(trimmed.events(), 4);
+        assert_eq!(trimmed.rating, engine.current_records()[&1].rating);
+
+        let resumed = RankingEngine::from_checkpoint(checkpoint, config, 2022);
+        let rec = &resumed.current_records()[&1];
+        assert_eq!(rec.events(), 4);
+        assert_eq!(rec.counted_history().iter().filter(|(_, c)| *c).count(), 2);
+    }
+
+    #[test]
+    fn event_counts_and_ranks() {
+        let dt = Utc.with_ymd_and_hms(2022, 6, 1, 0, 0, 0).unwrap();
+        let results = || {
+            vec![
+                (1, Team::new(1, 2).unwrap().into()),
+                (2, Entrant::Single(3)),
+            ]
+        };
+        let ts = vec![
+            Tournament::new(results(), dt, Level::SMALL).unwrap(),
+            Tournament::new(vec![(1, Entrant::Single(1))], dt, Level::SMALL).unwrap(),
+        ];
+        let counts = event_counts(&ts);
+        assert_eq!(counts[&1], 2);
+        assert_eq!(counts[&3], 1);
+
+        let (ranks, records) = rank_players(&ts, 2022, &Config::default()).unwrap();
+        let ratings: PlayerMap<_> = records.iter().map(|(p, r)| (*p, r.rating)).collect();
+        assert_eq!(
+            ranks_from_ratings(&ratings, RankNumbering::Standard, RankDirection::Descending),
+            ranks
+        );
+    }
+
+    #[test]
+    fn relative_to_population() {
+        let ratings: PlayerMap<_> = [(1, 10.0), (2, 20.0), (3, 20.0), (4, 30.0)]
+            .into_iter()
+            .map(|(p, r)| (p, NotNan::new(r).unwrap()))
+            .collect();
+        let relative = relative_ratings(&ratings);
+        assert_eq!(relative[&1].percentile, 12.5);
+        assert_eq!(relative[&2].percentile, 50.0);
+        assert_eq!(relative[&4].percentile, 87.5);
+        assert_eq!(relative[&2].z_score, 0.0);
+        assert!((relative[&4].z_score - 2.0_f64.sqrt()).abs() < 1e-9);
+        assert!((relative[&1].z_score + relative[&4].z_score).abs() < 1e-9);
+
+        let equal: PlayerMap<_> = [(1, NotNan::new(5.0).unwrap())].into_iter().collect();
+        assert_eq!(relative_ratings(&equal)[&1].z_score, 0.0);
+        assert_eq!(relative_ratings(&equal)[&1].percentile, 50.0);
+    }
+
+    #[test]
+    fn inactivity_decay() {
+        let config = InactivityConfig {
+            days: 100,
+            factor: 0.5,
+        };
+        assert_eq!(config.decay(80.0, 0.0, Duration::days(99)), 80.0);
+        assert_eq!(config.decay(80.0, 0.0, Duration::days(250)), 20.0);
+        assert_eq!(config.decay(1600.0, 1500.0, Duration::days(100)), 1550.0);
+
+        let early = Utc.with_ymd_and_hms(2022, 1, 1, 0, 0, 0).unwrap();
+        let late = Utc.with_ymd_and_hms(2022, 6, 1, 0, 0, 0).unwrap();
+        let ts = vec![
+            Tournament::new(
+                vec![(1, Team::new(1, 2).unwrap().into())],
+                early,
+                Level::SMALL,
+            )
+            .unwrap(),
+            Tournament::new(vec![(1, Entrant::Single(1))], late, Level::SMALL).unwrap(),
+        ];
+        let last = last_played(&ts);
+        assert_eq!(last[&1], late);
+        assert_eq!(last[&2], early);
+
+        let mut ratings = HashMap::default();
+        ratings.insert(1, NotNan::new(10.0).unwrap());
+        ratings.insert(2, NotNan::new(10.0).unwrap());
+        config
+            .apply(&mut ratings, &last, 0.0, late + Duration::days(50))
+            .unwrap();
+        assert_eq!(ratings[&1], 10.0);
+        assert_eq!(ratings[&2], 2.5);
+    }
+
+    #[test]
+    fn rolling_window() {
+        let config = Config::default().window_months(Some(6));
+        let mut engine = RankingEngine::new(PointsSystem::new(config, 2022));
+        for month in [1, 5, 9] {
+            let dt = Utc.with_ymd_and_hms(2022, month, 1, 0, 0, 0).unwrap();
+            let mut results = vec![(1, Entrant::Single(1))];
+            if month == 1 {
+                results.push((2, Entrant::Single(2)));
+            }
+            engine
+                .add_tournament(Tournament::new(results, dt, Level::SMALL).unwrap())
+                .unwrap();
+        }
+        let records = engine.current_records();
+        assert_eq!(records[&1].history().len(), 2);
+        assert!(!records.contains_key(&2));
+        let rating = records[&1].rating;
+
+        engine.advance_to(&Utc.with_ymd_and_hms(2022, 12, 1, 0, 0, 0).unwrap());
+        let records = engine.current_records();
+        assert_eq!(records[&1].history().len(), 1);
+        assert!(records[&1].rating < rating);
+        assert_eq!(records[&1].rating, records[&1].history()[0].points);
+    }
+
+    #[test]
+    fn seasons() {
+        let dt = |y, m, d| Utc.with_ymd_and_hms(y, m, d, 0, 0, 0).unwrap();
+        let calendar = SeasonConfig::default();
+        assert_eq!(calendar.season_of(&dt(2022, 1, 1)), 2022);
+        assert_eq!(calendar.season_of(&dt(2022, 12, 31)), 2022);
+
+        let autumn: SeasonConfig = toml::from_str("month = 9\nday = 1").unwrap();
+        assert_eq!(autumn.season_of(&dt(2022, 8, 31)), 2021);
+        assert_eq!(autumn.season_of(&dt(2022, 9, 1)), 2022);
+
+        let explicit: SeasonConfig =
+            toml::from_str(r#"starts = ["2022-03-01", "2023-02-15"]"#).unwrap();
+        assert_eq!(explicit.season_of(&dt(2022, 2, 1)), 2021);
+        assert_eq!(explicit.season_of(&dt(2023, 2, 14)), 2022);
+        assert_eq!(explicit.season_of(&dt(2024, 1, 1)), 2023);
+        assert_eq!(
+            explicit.start_of(2023),
+            NaiveDate::from_ymd_opt(2023, 2, 15)
+        );
+        assert_eq!(explicit.start_of(2024), None);
+        assert_eq!(autumn.start_of(2024), NaiveDate::from_ymd_opt(2024, 9, 1));
+
+        // a result from the previous season is decayed
+        let config = Config::default().seasons(autumn);
+        let t =
+            Tournament::new(vec![(1, Entrant::Single(1))], dt(2022, 8, 1), Level::SMALL).unwrap();
+        let b = &t
+            .points_breakdown(2022, &HashMap::default(), &config)
+            .unwrap()[&1];
+        assert!(b.age_factor < 1.0);
+    }
+
+    #[test]
+    fn config_validation() {
+        assert!(Config::default().validate().is_ok());
+        let key = |config: Config| config.validate().unwrap_err().key;
+        assert_eq!(key(Config::default().finish_decay(0.9)), "finish_decay");
+        assert_eq!(key(Config::default().age_decay(f64::NAN)), "age_decay");
+        assert_eq!(key(Config::default().record_length(0)), "record_length");
+        assert_eq!(
+            key(Config::default().window_months(Some(0))),
+            "window_months"
+        );
+
+        let base = "finish_decay = 1.1\nage_decay = 1.1\nrecord_length = 10\n";
+        let err = Config::from_toml(&format!("{}[levels]\nsmall = -50", base)).unwrap_err();
+        match err {
+            ConfigError::Invalid(e) => assert_eq!(e.key, "levels.small.points"),
+            e => panic!("Unexpected error: {}", e),
+        }
+        assert!(matches!(
+            Config::from_toml(&format!("{}levels = {{}}", base)),
+            Err(ConfigError::Invalid(_))
+        ));
+        assert!(Config::from_toml(&format!("{}[levels]\nsmall = 50", base)).is_ok());
+
+        for (table, field, value) in [
+            ("elo", "k", "0.0"),
+            ("elo", "scale", "0.0"),
+            ("glicko", "tau", "0.0"),
+            ("glicko", "period_days", "0.0"),
+            ("glicko", "initial_rd", "-1.0"),
+            ("trueskill", "sigma", "0.0"),
+            ("trueskill", "beta", "-1.0"),
+            ("trueskill", "kappa", "0.0"),
+            ("inactivity", "days", "0\nfactor = 0.5"),
+        ] {
+            let toml = format!(
+                "{}[levels]\nsmall = 50\n[{}]\n{} = {}",
+                base, table, field, value
+            );
+            match Config::from_toml(&toml).unwrap_err() {
+                ConfigError::Invalid(e) => assert_eq!(e.key, format!("{}.{}", table, field)),
+                e => panic!("Unexpected error: {}", e),
+            }
+        }
+    }
+
+    #[test]
+    fn config_toml_roundtrip() {
+        let config = Config::default()
+            .inactivity(Some(InactivityConfig {
+                days: 365,
+                factor: 0.5,
+            }))
+            .ties(TiePolicy::Average);
+        let s = config.to_toml().unwrap();
+        let parsed = Config::from_toml(&s).unwrap();
+        assert_eq!(parsed.to_toml().unwrap(), s);
+        assert_eq!(parsed.levels, config.levels);
+        assert_eq!(parsed.inactivity, config.inactivity);
+        assert_eq!(parsed.ties, TiePolicy::Average);
+    }
+
+    #[test]
+    fn config_overrides() {
+        let config = Config::default()
+            .with_overrides([
+                ("DDCRATE_RECORD_LENGTH", "12"),
+                ("DDCRATE_TIES", "average"),
+                ("DDCRATE_ELO__K", "24"),
+                ("DDCRATE_INACTIVITY", "{ days = 365, factor = 0.5 }"),
+                ("DDCRATE_LEVELS__SMALL", "60"),
+                ("HOME", "/root"),
+            ])
+            .unwrap();
+        assert_eq!(config.record_length, 12);
+        assert_eq!(config.ties, TiePolicy::Average);
+        assert_eq!(config.elo.k, 24.0);
+        assert_eq!(config.inactivity.unwrap().days, 365);
+        assert_eq!(config.levels[&Level::SMALL].points, 60.0);
+        assert_eq!(config.levels[&Level::MAJOR].points, 200.0);
+
+        let unknown = Config::default()
+            .with_overrides([("DDCRATE_HOME", "/root"), ("DDCRATE_RECORD_LENGTH", "12")])
+            .unwrap();
+        assert_eq!(unknown.record_length, 12);
+        let invalid = Config::default().with_overrides([("DDCRATE_RECORD_LENGTH", "0")]);
+        assert!(matches!(invalid, Err(ConfigError::Invalid(_))));
+        let wrong_type = Config::default().with_overrides([("DDCRATE_RECORD_LENGTH", "many")]);
+        assert!(matches!(wrong_type, Err(ConfigError::Toml(_))));
+    }
+
+    #[test]
+    fn config_json_schema() {
+        let schema = serde_json::to_value(Config::json_schema()).unwrap();
+        let required = schema["required"].as_array().unwrap();
+        for key in ["finish_decay", "record_length", "levels"] {
+            assert!(required.contains(&key.into()), "{key} not required");
+        }
+        assert!(!required.contains(&"age_decay".into()));
+        let props = &schema["properties"];
+        assert!(props["ties"].is_object());
+        assert!(props["elo"].is_object());
+        let level = &schema["definitions"]["LevelConfig"];
+        assert_eq!(level["anyOf"].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn config_formats() {
+        let yaml = Config::from_yaml(
+            "finish_decay: 1.1\nage_decay: 1.2\nrecord_length: 8\nlevels:\n  small: 50\n  regional: {points: 80, directory: regionals}\n",
+        )
+        .unwrap();
+        let json = Config::from_json(
+            r#"{"finish_decay": 1.1, "age_decay": 1.2, "record_length": 8,
+            "levels": {"small": 50, "regional": {"points": 80, "directory": "regionals"}}}"#,
+        )
+        .unwrap();
+        for config in [yaml, json] {
+            assert_eq!(config.record_length, 8);
+            assert_eq!(config.get_level(&Level::SMALL), Some(50.0));
+            assert_eq!(
+                config.get_level_directory(&Level::new("regional")),
+                Some("regionals")
+            );
+        }
+        assert!(matches!(
+            Config::from_yaml("finish_decay: [1.1]"),
+            Err(ConfigError::Yaml(_))
+        ));
+    }
+
+    #[test]
+    fn age_half_life() {
+        let config: Config = toml::from_str(
+            r#"
+            finish_decay = 1.1
+            age_half_life_months = 18
+            record_length = 10
+
+            [levels]
+            small = 50
+            "#,
+        )
+        .expect("Could not parse");
+        assert!((1.0 / config.get_age_decay().powf(1.5) - 0.5).abs() < 1e-12);
+        assert_eq!(Config::default().get_age_decay(), AGE_DECAY);
+    }
+
+    #[test]
+    fn age_in_days() {
+        let dt = |y, m, d| Utc.with_ymd_and_hms(y, m, d, 0, 0, 0).unwrap();
+        let seasons = Config::default();
+        let days = Config::default().age_unit(AgeUnit::Days);
+        assert_eq!(seasons.age_of(2023, &dt(2022, 12, 1)), 1.0);
+        assert_eq!(seasons.age_of(2023, &dt(2022, 1, 1)), 1.0);
+        assert_eq!(days.age_of(2023, &dt(2023, 6, 1)), 0.0);
+        assert!((days.age_of(2023, &dt(2022, 12, 1)) - 31.0 / 365.25).abs() < 1e-12);
+        assert!((days.age_of(2023, &dt(2022, 1, 1)) - 365.0 / 365.25).abs() < 1e-12);
+
+        // no known start of the current season
+        let explicit: SeasonConfig = toml::from_str(r#"starts = ["2022-03-01"]"#).unwrap();
+        let days = days.seasons(explicit);
+        assert_eq!(days.age_of(2023, &dt(2022, 6, 1)), 1.0);
+    }
+
+    #[test]
+    fn tie_policies() {
+        let dt = Utc.with_ymd_and_hms(2022, 6, 1, 0, 0, 0).unwrap();
+        let t = Tournament::new(
+            vec![
+                (1, Entrant::Single(1)),
+                (2, Entrant::Single(2)),
+                (2, Entrant::Single(3)),
+                (4, Entrant::Single(4)),
+            ],
+            dt,
+            Level::SMALL,
+        )
+        .unwrap();
+        let factor = |ties, pid| {
+            let config = Config::default().ties(ties);
+            t.points_breakdown(2022, &HashMap::default(), &config)
+                .unwrap()[&pid]
+                .finish_factor
+        };
+        let decay = Config::default().finish_decay;
+        assert_eq!(factor(TiePolicy::Best, 2), 1.0 / decay.powi(2));
+        assert_eq!(factor(TiePolicy::Worst, 3), 1.0 / decay.powi(3));
+        let avg = factor(TiePolicy::Average, 2);
+        assert!((avg - (1.0 / decay.powi(2) + 1.0 / decay.powi(3)) / 2.0).abs() < 1e-12);
+        assert_eq!(factor(TiePolicy::Average, 4), 1.0 / decay.powi(4));
+    }
+
+    #[test]
+    fn strength_of_schedule() {
+        let dt = Utc.with_ymd_and_hms(2022, 6, 1, 0, 0, 0).unwrap();
+        let t = Tournament::new(
+            vec![
+                (1, Team::new(1, 2).unwrap().into()),
+                (2, Team::new(3, 4).unwrap().into()),
+                (3, Team::new(5, 6).unwrap().into()),
+            ],
+            dt,
+            Level::SMALL,
+        )
+        .unwrap();
+        let ranks: PlayerMap<_> = [(1, 1), (3, 2), (4, 4), (5, 6)].into_iter().collect();
+        let b = t
+            .points_breakdown(2022, &ranks, &Config::default())
+            .unwrap();
+        assert_eq!(b[&1].mean_opponent_rank, Some(4.0));
+        assert_eq!(b[&3].mean_opponent_rank, Some(3.5));
+
+        let solo = Tournament::new(vec![(1, Entrant::Single(1))], dt, Level::SMALL).unwrap();
+        let b_solo = solo
+            .points_breakdown(2022, &ranks, &Config::default())
+            .unwrap();
+        assert_eq!(b_solo[&1].mean_opponent_rank, None);
+
+        let mut rec = PlayerRecord::new(1, 2);
+        rec.add_breakdown(b[&1].clone());
+        rec.add_breakdown(b_solo[&1].clone());
+        assert_eq!(rec.strength_of_schedule(), Some(4.0));
+        assert_eq!(PlayerRecord::new(2, 2).strength_of_schedule(), None);
+    }
+
+    #[test]
+    fn size_scaling() {
+        let linear: SizeScalingConfig = toml::from_str("reference = 8\nmax = 2.0").unwrap();
+        assert_eq!(linear.factor(4), 0.5);
+        assert_eq!(linear.factor(8), 1.0);
+        assert_eq!(linear.factor(64), 2.0);
+
+        let log: SizeScalingConfig = toml::from_str("scale = \"log\"\nreference = 7").unwrap();
+        assert_eq!(log.factor(7), 1.0);
+        assert!((log.factor(63) - 2.0).abs() < 1e-12);
+
+        let dt = Utc.with_ymd_and_hms(2022, 6, 1, 0, 0, 0).unwrap();
+        let t = Tournament::new(
+            vec![(1, Entrant::Single(1)), (2, Entrant::Single(2))],
+            dt,
+            Level::SMALL,
+        )
+        .unwrap();
+        let config = Config::default().size_scaling(Some(linear));
+        let b = t
+            .points_breakdown(2022, &HashMap::default(), &config)
+            .unwrap();
+        assert_eq!(b[&1].point_base, config.levels[&Level::SMALL].points / 4.0);
+    }
+
+    #[test]
+    fn field_strength() {
+        let dt = Utc.with_ymd_and_hms(2022, 6, 1, 0, 0, 0).unwrap();
+        let t = Tournament::new(
+            vec![(1, Entrant::Single(1)), (2, Entrant::Single(2))],
+            dt,
+            Level::SMALL,
+        )
+        .unwrap();
+        let fs: FieldStrengthConfig = toml::from_str("top = 10\nper_player = 0.25").unwrap();
+        let config = Config::default().field_strength(Some(fs));
+        let ranks: PlayerMap<_> = [(1, 3), (2, 11)].into_iter().collect();
+        let weak = t
+            .points_breakdown(2022, &HashMap::default(), &config)
+            .unwrap();
+        let strong = t.points_breakdown(2022, &ranks, &config).unwrap();
+        assert_eq!(weak[&2].field_factor, 1.0);
+        assert_eq!(strong[&2].field_factor, 1.25);
+        assert_eq!(strong[&2].points, weak[&2].points * 1.25);
+
+        let capped = FieldStrengthConfig {
+            max: Some(1.1),
+            ..fs
+        };
+        assert_eq!(capped.factor(&[1, 2], &ranks), 1.1);
+    }
+
+    #[test]
+    fn rank_numbering() {
+        let ratings: PlayerMap<_> = [(1, 3.0), (2, 2.0), (3, 2.0), (4, 1.0)]
+            .into_iter()
+            .map(|(p, r)| (p, NotNan::new(r).unwrap()))
+            .collect();
+        let ranks = |numbering, direction| {
+            let ranks = ranks_from_ratings(&ratings, numbering, direction);
+            (1..=4).map(|p| ranks[&p]).collect::<Vec<_>>()
+        };
+        let desc = RankDirection::Descending;
+        assert_eq!(ranks(RankNumbering::Standard, desc), vec![1, 2, 2, 4]);
+        assert_eq!(ranks(RankNumbering::Dense, desc), vec![1, 2, 2, 3]);
+        assert_eq!(ranks(RankNumbering::Ordinal, desc), vec![1, 2, 3, 4]);
+        let asc = RankDirection::Ascending;
+        assert_eq!(ranks(RankNumbering::Standard, asc), vec![4, 2, 2, 1]);
+        assert_eq!(ranks(RankNumbering::Ordinal, asc), vec![4, 2, 3, 1]);
+    }
+
+    #[test]
+    fn team_ratings() {
+        let mut engine = RankingEngine::new(PointsSystem::new(Config::default(), 2022));
+        for day in 1..=2 {
+            let dt = Utc.with_ymd_and_hms(2022, 6, day, 0, 0, 0).unwrap();
+            let results = vec![
+                (1, Team::new(1, 2).unwrap().into()),
+                (2, Team::new(3, 5).unwrap().into()),
+                (3, Entrant::Single(4)),
+            ];
+            engine
+                .add_tournament(Tournament::new(results, dt, Level::SMALL).unwrap())
+                .unwrap();
+        }
+        let team = Team::new(1, 2).unwrap();
+        let records = engine.current_team_records();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[&team].history().len(), 2);
+        assert_eq!(
+            records[&team].rating,
+            engine.current_records()[&1].rating * 2.0
+        );
+        let ranks = engine.current_team_ranks();
+        assert_eq!(ranks.len(), 2);
+
+        let mut buf = Vec::default();
+        engine.checkpoint().write(&mut buf).unwrap();
+        let restored = RankingEngine::from_checkpoint(
+            Checkpoint::read(buf.as_slice()).unwrap(),
+            Config::default(),
+            2022,
+        );
+        assert_eq!(restored.current_team_ranks(), ranks);
+    }
+
+    #[test]
+    fn withdrawn_team_ratings() {
+        let dt = Utc.with_ymd_and_hms(2022, 6, 1, 0, 0, 0).unwrap();
+        let team = Team::new(3, 4).unwrap();
+        let results = vec![
+            (1, Team::new(1, 2).unwrap().into()),
+            (2, team.clone().into()),
+        ];
+        let t = Tournament::new(results, dt, Level::SMALL)
+            .unwrap()
+            .with_withdrawn(vec![team.clone().into()]);
+        let rating = |policy| {
+            let config = Config::default().withdrawn(WithdrawnConfig {
+                policy,
+                factor: 0.25,
+            });
+            let mut engine = RankingEngine::new(PointsSystem::new(config, 2022));
+            engine.add_tournament(t.clone()).unwrap();
+            let player = engine.current_records()[&3].rating.into_inner();
+            let team = engine.current_team_records()[&team].rating.into_inner();
+            (player, team)
+        };
+        let (kept_player, kept_team) = rating(WithdrawnPolicy::Keep);
+        let (player, team) = rating(WithdrawnPolicy::Reduced);
+        assert!((team - kept_team * 0.25).abs() < 1e-9);
+        assert!((team - player * 2.0).abs() < 1e-9);
+        assert!((kept_team - kept_player * 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn partner_stats() {
+        let mut engine = RankingEngine::new(PointsSystem::new(Config::default(), 2022));
+        let entrants = [
+            Team::new(1, 2).unwrap(),
+            Team::new(1, 2).unwrap(),
+            Team::new(1, 3).unwrap(),
+        ];
+        for (idx, team) in entrants.into_iter().enumerate() {
+            let dt = Utc
+                .with_ymd_and_hms(2022, 6, idx as u32 + 1, 0, 0, 0)
+                .unwrap();
+            let results = vec![
+                (idx as u64 % 2 + 1, team.into()),
+                (2 - idx as u64 % 2, Entrant::Single(9)),
+            ];
+            engine
+                .add_tournament(Tournament::new(results, dt, Level::SMALL).unwrap())
+                .unwrap();
+        }
+        let record = &engine.current_records()[&1];
+        let stats = record.partner_stats();
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[0].partner, 2);
+        assert_eq!(stats[0].events, 2);
+        assert_eq!(stats[0].mean_place, 1.5);
+        assert_eq!(stats[1].partner, 3);
+        let total: f64 = record.history().iter().map(|b| b.points.into_inner()).sum();
+        assert!((stats.iter().map(|s| s.points).sum::<f64>() - total).abs() < 1e-9);
+    }
+
+    #[test]
+    fn singles_points_not_split() {
+        let ranks = parse_ranks("place\tplayer\n1\t1\n2\t2\n".as_bytes(), 1, false).unwrap();
+        assert_eq!(ranks[0].1, Entrant::Single(1));
+        let mut results = ranks;
+        results.push((3, Team::new(3, 4).unwrap().into()));
+        let dt = Utc.with_ymd_and_hms(2022, 6, 1, 0, 0, 0).unwrap();
+        let t = Tournament::new(results, dt, Level::SMALL).unwrap();
+        let config = Config::default();
+        let points = t.points(2022, &HashMap::default(), &config).unwrap();
+        let base = config.get_level(&Level::SMALL).unwrap();
+        assert!((points[&1].into_inner() - base / 1.1).abs() < 1e-9);
+        assert!((points[&3].into_inner() - base / 1.1f64.powi(3) / 2.0).abs() < 1e-9);
+        assert_eq!(points[&3], points[&4]);
+    }
+
+    #[test]
+    fn teams_of_three() {
+        let ranks = parse_ranks(
+            "place\tp1\tp2\tp3\n1\t3\t1\t2\n2\t4\t5\tx\n".as_bytes(),
+            3,
+            false,
+        )
+        .unwrap();
+        assert_eq!(ranks.len(), 1);
+        assert_eq!(ranks[0].1.players(), &[1, 2, 3]);
+        assert!(parse_ranks("place\tp1\tp2\tp3\n1\t1\t2\t1\n".as_bytes(), 3, false).is_err());
+
+        let dt = Utc.with_ymd_and_hms(2022, 6, 1, 0, 0, 0).unwrap();
+        let t = Tournament::new(ranks, dt, Level::SMALL).unwrap();
+        let even = t
+            .points(2022, &HashMap::default(), &Config::default())
+            .unwrap();
+        let full = t
+            .points(
+                2022,
+                &HashMap::default(),
+                &Config::default().split(PointSplit::Full),
+            )
+            .unwrap();
+        assert!((full[&1].into_inner() - even[&1].into_inner() * 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn rating_split() {
+        let ratings: PlayerMap<_> = [(1, 30.0), (2, 10.0), (3, -5.0)]
+            .into_iter()
+            .map(|(p, r)| (p, NotNan::new(r).unwrap()))
+            .collect();
+        let split = PointSplit::Rating;
+        assert_eq!(split.share(&1, &[1, 2], &ratings), 0.75);
+        assert_eq!(split.share(&2, &[1, 2], &ratings), 0.25);
+        assert_eq!(split.share(&2, &[2, 4], &ratings), 1.0);
+        assert_eq!(split.share(&3, &[3, 4], &ratings), 0.5);
+
+        let dt = Utc.with_ymd_and_hms(2022, 6, 1, 0, 0, 0).unwrap();
+        let t =
+            Tournament::new(vec![(1, Team::new(1, 2).unwrap().into())], dt, Level::SMALL).unwrap();
+        let config = Config::default().split(split);
+        let b = t
+            .points_breakdown_with_ratings(2022, &HashMap::default(), &ratings, &config)
+            .unwrap();
+        let total = b[&1].points + b[&2].points;
+        assert_eq!(b[&1].points, total * 0.75);
+    }
+
+    #[test]
+    fn record_length_honoured() {
+        let record = PlayerRecord::new_with_points(1, 2, &[1.0, 3.0, 2.0]);
+        assert_eq!(record.rating, 5.0);
+    }
+
+    #[test]
+    fn named_columns() {
+        let tsv = "# a comment\nseed\tP2\tteam\tplace\tplayer1\n3\t2\tFoo\t1\t1\n1\t4\tBar\t2\t3\n";
+        let ranks = parse_ranks(tsv.as_bytes(), 2, true).unwrap();
+        assert_eq!(ranks.len(), 2);
+        assert_eq!(ranks[0], (1, Team::new(1, 2).unwrap().into()));
+        assert_eq!(ranks[1], (2, Team::new(3, 4).unwrap().into()));
+
+        let ranks = parse_ranks("1\t1\t2\n2\t3\t4\n".as_bytes(), 2, true).unwrap();
+        assert_eq!(ranks.len(), 2);
+
+        let Err(ResultReadError::MalformedRow(m)) =
+            parse_ranks("place\tp1\tname\n1\t1\tFoo\n".as_bytes(), 2, false)
+        else {
+            panic!("Expected malformed header");
+        };
+        assert_eq!(m.line, 1);
+    }
+
+    #[test]
+    fn comma_separated() {
+        assert_eq!(
+            sniff_delimiter(b"# date: 2023-07-10\n\nplace,p1,p2\n"),
+            b','
+        );
+        assert_eq!(sniff_delimiter(b"1\tSmith, J\t2\n"), b'\t');
+
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().to_owned();
+        let mut dir = root.clone();
+        dir.push(Level::SMALL.directory_name());
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("2023-07-10_open.csv"),
+            "place,team,p1,p2\n1,\"Foo, Bar\",1,2\n2,Baz,3,4\n",
+        )
+        .unwrap();
+        let result = ResultIngester::new(&root).ingest();
+
+        let ts = result.unwrap();
+        assert_eq!(ts.len(), 1);
+        assert_eq!(ts[0].results()[1], (2, Team::new(3, 4).unwrap().into()));
+    }
+
+    #[test]
+    fn bom_and_crlf() {
+        let expected = vec![
+            (1, Team::new(1, 2).unwrap().into()),
+            (2, Team::new(3, 4).unwrap().into()),
+        ];
+        for tsv in [
+            "\u{feff}1\t1\t2\r\n2\t3\t4\r\n",
+            "\u{feff}place\tp1\tp2\r\n1\t1\t2\r\n2\t3\t4",
+            "\u{feff}# name: Open\r\nplace,p1,p2\r\n1,1,2\r\n2,3,4\r\n",
+        ] {
+            assert_eq!(parse_ranks(tsv.as_bytes(), 2, true).unwrap(), expected);
+        }
+
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().to_owned();
+        let mut dir = root.clone();
+        dir.push(Level::SMALL.directory_name());
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("open.tsv"),
+            "\u{feff}# date: 2023-07-10\r\n# name: Open\r\n1\t1\t2\r\n2\t3\t4\r\n",
+        )
+        .unwrap();
+        let result = ResultIngester::new(&root).ingest();
+
+        let ts = result.unwrap();
+        assert_eq!(ts.len(), 1);
+        assert_eq!(ts[0].datetime().day(), 10);
+        assert_eq!(ts[0].metadata().name.as_deref(), Some("Open"));
+        assert_eq!(ts[0].results(), expected.as_slice());
+    }
+
+    #[test]
+    fn result_codes() {
+        let tsv = "place\tp1\tp2\n1\t1\t2\n2\t3\t4\nDNF\t5\t6\ndq\t7\t8\nWD\t9\t10\n";
+        let parsed = parse_results(tsv.as_bytes(), 2, true).unwrap();
+        assert_eq!(parsed.placings.len(), 2);
+        let codes: Vec<_> = parsed.unplaced.iter().map(|(c, _)| *c).collect();
+        assert_eq!(codes, vec![ResultCode::Dnf, ResultCode::Dq, ResultCode::Wd]);
+
+        let dt = Utc.with_ymd_and_hms(2022, 6, 1, 0, 0, 0).unwrap();
+        let t = Tournament::new(parsed.placings, dt, Level::SMALL)
+            .unwrap()
+            .with_unplaced(parsed.unplaced)
+            .unwrap();
+        let config = Config::default();
+        let b = t
+            .points_breakdown(2022, &HashMap::default(), &config)
+            .unwrap();
+        assert_eq!(b.len(), 8);
+        assert_eq!((b[&5].place, b[&5].code), (3, Some(ResultCode::Dnf)));
+        let base = config.get_level(&Level::SMALL).unwrap();
+        assert!((b[&5].points.into_inner() - base / 1.1f64.powi(3) / 2.0).abs() < 1e-9);
+        assert_eq!(
+            (b[&7].code, b[&7].points.into_inner()),
+            (Some(ResultCode::Dq), 0.0)
+        );
+        assert!(!b.contains_key(&9));
+
+        let omit = CodePolicies {
+            dnf: CodePolicy::Omit,
+            dq: CodePolicy::Omit,
+            wd: CodePolicy::Omit,
+        };
+        let b = t
+            .points_breakdown(2022, &HashMap::default(), &config.codes(omit))
+            .unwrap();
+        assert_eq!(b.len(), 4);
+
+        let repeated = vec![(ResultCode::Wd, Team::new(1, 11).unwrap().into())];
+        assert!(
+            Tournament::new(vec![(1, Team::new(1, 2).unwrap().into())], dt, Level::SMALL)
+                .unwrap()
+                .with_unplaced(repeated)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn byes() {
+        let tsv =
+            "place\tp1\tp2\n1\t1\t2\n2\tBYE\tbye\n3\t3\t4\n3\t5\t6\n3\t0\t\n6\t7\t8\nDNF\tBYE\t\n";
+        let parsed = parse_results(tsv.as_bytes(), 2, true).unwrap();
+        let places: Vec<_> = parsed.placings.iter().map(|(p, _)| *p).collect();
+        assert_eq!(places, vec![1, 2, 2, 4]);
+        assert_eq!(parsed.placings[3].1, Team::new(7, 8).unwrap().into());
+        assert!(parsed.unplaced.is_empty());
+
+        let dt = Utc.with_ymd_and_hms(2022, 6, 1, 0, 0, 0).unwrap();
+        let t = Tournament::new(parsed.placings, dt, Level::SMALL).unwrap();
+        let (ranks, _) = rank_players(&[t], 2022, &Config::default()).unwrap();
+        assert_eq!(ranks.len(), 8);
+        assert!(!ranks.contains_key(&BYE));
+    }
+
+    #[test]
+    fn withdrawn_entrants() {
+        let tsv = "place\tp1\tp2\twithdrawn\n1\t1\t2\t\n2\t3\t4\tyes\n3W\t5\t6\tno\n4\t7\t8\t0\n";
+        let parsed = parse_results(tsv.as_bytes(), 2, true).unwrap();
+        assert_eq!(parsed.placings.len(), 4);
+        assert_eq!(
+            parsed.withdrawn,
+            vec![
+                Team::new(3, 4).unwrap().into(),
+                Team::new(5, 6).unwrap().into()
+            ]
+        );
+
+        let dt = Utc.with_ymd_and_hms(2022, 6, 1, 0, 0, 0).unwrap();
+        let t = Tournament::new(parsed.placings, dt, Level::SMALL)
+            .unwrap()
+            .with_withdrawn(parsed.withdrawn);
+        let points = |policy| {
+            let config = Config::default().withdrawn(WithdrawnConfig {
+                policy,
+                factor: 0.25,
+            });
+            t.points(2022, &HashMap::default(), &config).unwrap()
+        };
+        let keep = points(WithdrawnPolicy::Keep);
+        let reduced = points(WithdrawnPolicy::Reduced);
+        let zero = points(WithdrawnPolicy::Zero);
+        assert_eq!(reduced[&1], keep[&1]);
+        assert_eq!(reduced[&7], keep[&7]);
+        assert_eq!(reduced[&3].into_inner(), keep[&3].into_inner() * 0.25);
+        assert_eq!(zero[&5].into_inner(), 0.0);
+        assert_eq!(zero.len(), 8);
+    }
+
+    #[test]
+    fn match_results() {
+        let tsv = "round\tw1\tw2\tl1\tl2\tcourt\n\
+            pool A\t1\t2\t3\t4\t1\npool A\t1\t2\t5\t6\t1\npool A\t3\t4\t5\t6\t1\n\
+            pool B\t7\t8\t9\t10\t2\npool B\t9\t10\t11\t12\t2\npool B\t11\t12\t7\t8\t2\n\
+            pool B\t7\t8\tBYE\tBYE\t2\n\
+            semi\t1\t2\t9\t10\t1\nsemi\t7\t8\t3\t4\t2\nfinal\t1\t2\t7\t8\t1\n";
+        let parsed = parse_results(tsv.as_bytes(), 2, true).unwrap();
+        assert_eq!(parsed.matches.len(), 9);
+        assert!(parsed.matches[0].is_pool());
+        assert!(!parsed.matches[8].is_pool());
+        let team = |a, b| Entrant::from(Team::new(a, b).unwrap());
+        assert_eq!(
+            parsed.placings,
+            vec![
+                (1, team(1, 2)),
+                (2, team(7, 8)),
+                (3, team(3, 4)),
+                (3, team(9, 10)),
+                (5, team(11, 12)),
+                (6, team(5, 6)),
+            ]
+        );
+
+        let header = "round\twinner\tloser\n";
+        assert!(parse_results(header.as_bytes(), 2, true).is_err());
+        let malformed = format!("{}final\t1\tone\n", header);
+        let parsed = parse_results(malformed.as_bytes(), 1, false).unwrap();
+        assert_eq!(parsed.skipped.len(), 1);
+        assert!(parsed.placings.is_empty());
+    }
+
+    #[test]
+    fn player_columns_in_number_order() {
+        let header = StringRecord::from(vec!["Team", "p2", "Player1", "seed", "p3", "pace"]);
+        assert_eq!(player_columns(&header), vec![2, 1, 4]);
+        let singles = StringRecord::from(vec!["place", "player"]);
+        assert_eq!(player_columns(&singles), vec![1]);
+    }
+
+    #[test]
+    fn third_place_game() {
+        let game = |round: &str, winner, loser| Match {
+            round: round.to_owned(),
+            winner: Entrant::Single(winner),
+            loser: Entrant::Single(loser),
+            score: None,
+        };
+        let expected = vec![
+            (1, Entrant::Single(1)),
+            (2, Entrant::Single(2)),
+            (3, Entrant::Single(4)),
+            (4, Entrant::Single(3)),
+        ];
+        let semis = [game("semi", 1, 3), game("semi", 2, 4)];
+        // listed after the final, or before it
+        for last in [
+            [game("final", 1, 2), game("3rd place", 4, 3)],
+            [game("3rd place", 4, 3), game("final", 1, 2)],
+        ] {
+            let matches: Vec<_> = semis.iter().chain(&last).cloned().collect();
+            assert_eq!(placings_from_matches(&matches), expected);
+        }
+    }
+
+    #[test]
+    fn match_scores() {
+        let tsv =
+            "round\twinner\tloser\tscore\nsemi\t1\t2\t21-15\nsemi\t3\t4\t\nfinal\t1\t3\t22:20\n";
+        let parsed = parse_results(tsv.as_bytes(), 1, true).unwrap();
+        let scores: Vec<_> = parsed.matches.iter().map(|m| m.score).collect();
+        assert_eq!(scores, vec![Some((21, 15)), None, Some((22, 20))]);
+        assert_eq!(parsed.matches[2].margin(), Some(2));
+
+        for score in ["15-21", "21", "a-b"] {
+            let tsv = format!("round\twinner\tloser\tscore\nfinal\t1\t2\t{}\n", score);
+            let parsed = parse_results(tsv.as_bytes(), 1, false).unwrap();
+            assert_eq!(parsed.skipped[0].reason, "invalid score");
+        }
+        assert!(Config::default()
+            .elo(EloConfig {
+                margin_cap: 0,
+                ..Default::default()
+            })
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn strict_parsing() {
+        let tsv = "place\tp1\tp2\n1\t1\t2\n2\t3\tthree\n";
+        assert_eq!(parse_ranks(tsv.as_bytes(), 2, false).unwrap().len(), 1);
+        let Err(ResultReadError::MalformedRow(m)) = parse_ranks(tsv.as_bytes(), 2, true) else {
+            panic!("Expected malformed row");
+        };
+        assert_eq!(m.line, 3);
+        assert_eq!(m.field.as_deref(), Some("three"));
+
+        let zero = "0\t1\t2\n2\t3\t4\n";
+        let Err(ResultReadError::MalformedRow(m)) = parse_ranks(zero.as_bytes(), 2, true) else {
+            panic!("Expected malformed row");
+        };
+        assert_eq!(m.line, 1);
+        assert_eq!(m.field.as_deref(), Some("0"));
+
+        let headed = parse_ranks("Rank\tPlayer1\tPlayer2\n1\t1\t2\n".as_bytes(), 2, true).unwrap();
+        assert_eq!(headed.len(), 1);
+        let malformed_first = "x\t1\t2\n1\t3\t4\n";
+        let (ranks, skipped) =
+            parse_ranks_with_skipped(malformed_first.as_bytes(), 2, false).unwrap();
+        assert_eq!(ranks.len(), 1);
+        assert_eq!(skipped[0].line, 1);
+        assert!(parse_ranks(malformed_first.as_bytes(), 2, true).is_err());
+    }
+
+    #[test]
+    fn rank_consistency() {
+        let dt = Utc.with_ymd_and_hms(2022, 6, 1, 0, 0, 0).unwrap();
+        let tournament = |places: &[u64]| {
+            let results = places
+                .iter()
+                .enumerate()
+                .map(|(i, p)| (*p, Entrant::Single(i as PlayerId)))
+                .collect();
+            Tournament::new(results, dt, Level::SMALL)
+        };
+        assert!(tournament(&[1, 2, 2, 4, 5]).is_ok());
+        assert!(tournament(&[1, 1, 1, 4]).is_ok());
+        assert!(tournament(&[1, 1, 2]).is_err());
+        assert!(tournament(&[2, 3]).is_err());
+        assert!(tournament(&[1, 2, 2, 3]).is_err());
+        // not tied with the initial previous place
+        assert!(tournament(&[0, 2, 3]).is_err());
+        assert!(tournament(&[0]).is_err());
+    }
+
+    #[test]
+    fn bonus_lookup() {
+        let table = vec![(20, 5.0), (5, 10.0)];
+        assert_eq!(bonus_points(1, &table), 10.0);
+        assert_eq!(bonus_points(6, &table), 5.0);
+        assert_eq!(bonus_points(21, &table), 0.0);
+    }
+
+    #[test]
+    fn bonus_modes() {
+        let dt = Utc.with_ymd_and_hms(2022, 6, 1, 0, 0, 0).unwrap();
+        let t = Tournament::new(
+            vec![
+                (1, Team::new(1, 2).unwrap().into()),
+                (2, Team::new(3, 4).unwrap().into()),
+                (2, Team::new(5, 6).unwrap().into()),
+            ],
+            dt,
+            Level::SMALL,
+        )
+        .unwrap();
+        let ranks: PlayerMap<_> = [(3, 1), (4, 10), (5, 1)].into_iter().collect();
+        let config = Config::default().bonus(vec![(20, 5.0), (5, 10.0)]);
+
+        let per_player = t.points_breakdown(2022, &ranks, &config).unwrap();
+        assert_eq!(per_player[&1].bonus, 25.0);
+        // tied entrants do not receive bonuses for each other
+        assert_eq!(per_player[&3].bonus, 0.0);
+        assert_eq!(per_player[&5].bonus, 0.0);
+
+        let per_team = t
+            .points_breakdown(2022, &ranks, &config.bonus_mode(BonusMode::Team))
+            .unwrap();
+        assert_eq!(per_team[&1].bonus, 20.0);
+    }
+
+    #[test]
+    fn points_use_config_decay() {
+        let dt = Utc.with_ymd_and_hms(2022, 6, 1, 0, 0, 0).unwrap();
+        let t =
+            Tournament::new(vec![(1, Team::new(1, 2).unwrap().into())], dt, Level::SMALL).unwrap();
+        let ranks = HashMap::default();
+        let default_pts = t.points(2023, &ranks, &Config::default()).unwrap();
+        let config = Config::default().finish_decay(2.0).age_decay(1.0);
+        let pts = t.points(2023, &ranks, &config).unwrap();
+        assert_eq!(default_pts[&1], 50.0 / 1.1 / 1.1 / 2.0);
+        assert_eq!(pts[&1], 50.0 / 2.0 / 2.0);
+    }
+
+    #[test]
+    fn extreme_points_are_capped() {
+        let dt = Utc.with_ymd_and_hms(2022, 6, 1, 0, 0, 0).unwrap();
+        let t = Tournament::new_unchecked(
+            vec![
+                (1, Team::new(1, 2).unwrap().into()),
+                (u64::MAX, Team::new(3, 4).unwrap().into()),
+            ],
+            dt,
+            Level::SMALL,
+        );
+        // every finish factor is infinite
+        let config = Config::default().finish_decay(0.0).max_points(1000.0);
+        let pts = t.points(2022, &HashMap::default(), &config).unwrap();
+        assert_eq!(pts[&1], 500.0);
+        assert_eq!(pts[&3], 500.0);
+
+        let mut system = PointsSystem::new(config.clone(), 2022);
+        system.add_tournament(&t, &HashMap::default()).unwrap();
+        let team = Team::new(1, 2).unwrap();
+        assert_eq!(system.team_records()[&team].rating, 1000.0);
+
+        // infinite finish factor times zero point base
+        let zero_base = config.level(Level::SMALL, 0.0);
+        assert!(matches!(
+            t.points(2022, &HashMap::default(), &zero_base),
+            Err(PointsError::NaN(_))
+        ));
+    }
+
+    #[test]
+    fn unknown_level_is_error() {
+        let dt = Utc.with_ymd_and_hms(2022, 6, 1, 0, 0, 0).unwrap();
+        let results = |a, b| vec![(1, Team::new(a, b).unwrap().into())];
+        let unknown = Tournament::new(results(1, 2), dt, Level::new("regional")).unwrap();
+        assert!(matches!(
+            unknown.points(2022, &HashMap::default(), &Config::default()),
+            Err(PointsError::UnknownLevel(l)) if l.name() == "regional"
+        ));
+
+        let known = Tournament::new(results(3, 4), dt, Level::SMALL).unwrap();
+        assert!(matches!(
+            rank_players(&[unknown.clone(), known.clone()], 2022, &Config::default()),
+            Err(RankingError::Points {
+                source: PointsError::UnknownLevel(_),
+                ..
+            })
+        ));
+
+        let mut engine = RankingEngine::new(PointsSystem::new(Config::default(), 2022));
+        engine.add_tournament(&known).unwrap();
+        assert!(engine.add_tournament(&unknown).is_err());
+        assert_eq!(engine.current_records().len(), 2);
     }
 }
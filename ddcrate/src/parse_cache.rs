@@ -0,0 +1,265 @@
+//! Cache of parsed results files, so that unchanged files are not parsed again on later runs.
+//!
+//! Each TSV read from the filesystem is saved (with bincode) in the cache directory,
+//! under a hash of its path, size and modification time, those of its TOML sidecar,
+//! and the date, level, and settings it was read with.
+//! Changing any of these misses the cache, so entries are never stale;
+//! old entries are not removed, but the directory can be deleted at any time.
+//!
+//! Files read from an archive or URL, and JSON results, are not cached.
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    sidecar_path, Entrant, InvalidTournament, Level, MalformedRow, Match, PlayerId, ResultCode,
+    Tournament, TournamentMetadata,
+};
+
+/// Name of the cache directory, when it is in the results directory.
+pub const DEFAULT_DIR: &str = ".ddcrate-cache";
+
+/// Changed whenever the format of entries changes, so that old entries are ignored.
+const VERSION: u32 = 6;
+
+/// What was parsed from a results file.
+#[derive(Debug, Serialize, Deserialize)]
+struct Entry {
+    results: Vec<(u64, Vec<PlayerId>)>,
+    unplaced: Vec<(ResultCode, Vec<PlayerId>)>,
+    withdrawn: Vec<Vec<PlayerId>>,
+    matches: Vec<EntryMatch>,
+    metadata: TournamentMetadata,
+    /// Line, field, and reason for each skipped row.
+    skipped: Vec<(u64, Option<String>, String)>,
+}
+
+/// A game, with the players of each entrant.
+#[derive(Debug, Serialize, Deserialize)]
+struct EntryMatch {
+    round: String,
+    winner: Vec<PlayerId>,
+    loser: Vec<PlayerId>,
+    score: Option<(u32, u32)>,
+}
+
+/// Settings with which a results file is read, which affect what is parsed.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ReadSettings<'a> {
+    pub datetime: &'a DateTime<Utc>,
+    pub level: &'a Level,
+    pub team_size: usize,
+    pub strict: bool,
+}
+
+/// Key of a file's entry, or `None` if it cannot be cached (e.g. it is not on the filesystem).
+fn key(path: &Path, settings: ReadSettings) -> Option<u64> {
+    let mut hasher = DefaultHasher::new();
+    VERSION.hash(&mut hasher);
+    path.hash(&mut hasher);
+    let meta = fs::metadata(path).ok()?;
+    (meta.len(), meta.modified().ok()?).hash(&mut hasher);
+    fs::metadata(sidecar_path(path))
+        .ok()
+        .map(|m| (m.len(), m.modified().ok()))
+        .hash(&mut hasher);
+    settings.datetime.hash(&mut hasher);
+    settings.level.hash(&mut hasher);
+    settings.team_size.hash(&mut hasher);
+    settings.strict.hash(&mut hasher);
+    Some(hasher.finish())
+}
+
+/// A cache of parsed results files in a directory.
+#[derive(Debug, Clone)]
+pub(crate) struct ParseCache {
+    dir: PathBuf,
+}
+
+impl ParseCache {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    fn entry_path(&self, key: u64) -> PathBuf {
+        self.dir.join(format!("{:016x}.bin", key))
+    }
+
+    /// The tournament in the file and its skipped rows, if it is cached.
+    pub fn load(
+        &self,
+        path: &Path,
+        settings: ReadSettings,
+    ) -> Option<Result<(Tournament, Vec<MalformedRow>), InvalidTournament>> {
+        let bytes = fs::read(self.entry_path(key(path, settings)?)).ok()?;
+        let entry: Entry = match bincode::deserialize(&bytes) {
+            Ok(e) => e,
+            Err(e) => {
+                warn!(
+                    "Ignoring unreadable cache entry for {}: {}",
+                    path.display(),
+                    e
+                );
+                return None;
+            }
+        };
+        debug!("Read {} from cache", path.display());
+        Some(entry.into_tournament(path, settings))
+    }
+
+    /// Save the tournament in the file and its skipped rows.
+    ///
+    /// Failure to save is logged, but otherwise ignored.
+    pub fn store(
+        &self,
+        path: &Path,
+        settings: ReadSettings,
+        tournament: &Tournament,
+        skipped: &[MalformedRow],
+    ) {
+        let Some(key) = key(path, settings) else {
+            return;
+        };
+        let entry = Entry {
+            results: tournament
+                .results()
+                .iter()
+                .map(|(place, e)| (*place, e.players().to_vec()))
+                .collect(),
+            unplaced: tournament
+                .unplaced()
+                .iter()
+                .map(|(code, e)| (*code, e.players().to_vec()))
+                .collect(),
+            withdrawn: tournament
+                .withdrawn()
+                .iter()
+                .map(|e| e.players().to_vec())
+                .collect(),
+            matches: tournament
+                .matches()
+                .iter()
+                .map(|m| EntryMatch {
+                    round: m.round.clone(),
+                    winner: m.winner.players().to_vec(),
+                    loser: m.loser.players().to_vec(),
+                    score: m.score,
+                })
+                .collect(),
+            metadata: tournament.metadata().clone(),
+            skipped: skipped
+                .iter()
+                .map(|m| (m.line, m.field.clone(), m.reason.clone()))
+                .collect(),
+        };
+        let entry_path = self.entry_path(key);
+        // write to a temporary file first, so that other processes never read a partial entry
+        let tmp = entry_path.with_extension(format!("tmp{}", std::process::id()));
+        let result = bincode::serialize(&entry)
+            .map_err(|e| e.to_string())
+            .and_then(|bytes| {
+                fs::create_dir_all(&self.dir)
+                    .and_then(|_| fs::write(&tmp, bytes))
+                    .and_then(|_| fs::rename(&tmp, &entry_path))
+                    .map_err(|e| e.to_string())
+            });
+        if let Err(e) = result {
+            warn!("Could not cache {}: {}", path.display(), e);
+        }
+    }
+}
+
+impl Entry {
+    fn into_tournament(
+        self,
+        path: &Path,
+        settings: ReadSettings,
+    ) -> Result<(Tournament, Vec<MalformedRow>), InvalidTournament> {
+        let results = self
+            .results
+            .into_iter()
+            .map(|(place, players)| Ok((place, Entrant::from_players(&players)?)))
+            .collect::<Result<Vec<_>, InvalidTournament>>()?;
+        let unplaced = self
+            .unplaced
+            .into_iter()
+            .map(|(code, players)| Ok((code, Entrant::from_players(&players)?)))
+            .collect::<Result<Vec<_>, InvalidTournament>>()?;
+        let withdrawn = self
+            .withdrawn
+            .iter()
+            .map(|players| Entrant::from_players(players))
+            .collect::<Result<Vec<_>, InvalidTournament>>()?;
+        let matches = self
+            .matches
+            .into_iter()
+            .map(|m| {
+                Ok(Match {
+                    round: m.round,
+                    winner: Entrant::from_players(&m.winner)?,
+                    loser: Entrant::from_players(&m.loser)?,
+                    score: m.score,
+                })
+            })
+            .collect::<Result<Vec<_>, InvalidTournament>>()?;
+        let t = Tournament::new(results, *settings.datetime, settings.level.clone())?
+            .with_unplaced(unplaced)?
+            .with_withdrawn(withdrawn)
+            .with_matches(matches)
+            .with_metadata(self.metadata);
+        let skipped = self
+            .skipped
+            .into_iter()
+            .map(|(line, field, reason)| MalformedRow {
+                path: Some(path.to_owned()),
+                line,
+                field,
+                reason,
+            })
+            .collect();
+        Ok((t, skipped))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::{Level, ResultIngester};
+
+    #[test]
+    fn reuses_unchanged_files() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().to_owned();
+        let dir = root.join(Level::SMALL.directory_name());
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("2023-07-10_open.tsv");
+        fs::write(
+            &path,
+            "# name: Open\nplace\tp1\tp2\n1\t1\t2\nx\t3\t4\n2\t5\t6\n",
+        )
+        .unwrap();
+        let cache = root.join(super::DEFAULT_DIR);
+        let ingest = ResultIngester::new(&root).parse_cache(Some(cache.clone()));
+
+        let (first, first_report) = ingest.ingest_with_report().unwrap();
+        let entries = fs::read_dir(&cache).unwrap().count();
+        let (second, second_report) = ingest.ingest_with_report().unwrap();
+        // a changed file is parsed again
+        fs::write(&path, "place\tp1\tp2\n1\t1\t2\n").unwrap();
+        let third = ingest.ingest().unwrap();
+
+        assert_eq!(entries, 1);
+        assert_eq!(first[0].results(), second[0].results());
+        assert_eq!(second[0].metadata().name.as_deref(), Some("Open"));
+        assert_eq!(first_report.skipped_rows.len(), 1);
+        assert_eq!(second_report.skipped_rows[0].line, 4);
+        assert_eq!(second_report.skipped_rows[0].path.as_ref(), Some(&path));
+        assert_eq!(third[0].results().len(), 1);
+    }
+}
@@ -0,0 +1,292 @@
+//! Reading results published over HTTP(S).
+//!
+//! A results root ending in `/` is a directory listing, such as those served by nginx or Apache,
+//! whose links are followed into subdirectories (but never above the root).
+//! Any other root is a manifest: a text file listing one results file per line,
+//! as a URL or a path relative to the manifest, with `#` comments and blank lines ignored.
+//!
+//! Either way, each file's path relative to the root (or the manifest's directory)
+//! is used as if it were in a results directory, so it should start with the level's directory
+//! (e.g. `small/2023-07-10_open.tsv`).
+//! Files listed elsewhere are placed by their path on their server.
+//!
+//! If a cache directory is given, results files are saved there when downloaded
+//! and read from there on later runs rather than downloaded again,
+//! on the assumption that published results do not change.
+//! Listings and manifests are always downloaded.
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use log::debug;
+use once_cell::sync::Lazy;
+use percent_encoding::percent_decode_str;
+use regex::Regex;
+use thiserror::Error;
+use url::Url;
+
+#[derive(Debug, Error)]
+pub enum RemoteError {
+    #[error(transparent)]
+    Http(#[from] Box<ureq::Error>),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("Invalid URL {url:?}: {source}")]
+    Url {
+        url: String,
+        source: url::ParseError,
+    },
+}
+
+/// Whether the results root is an HTTP(S) URL.
+pub fn is_url(root: &Path) -> bool {
+    root.to_str()
+        .is_some_and(|s| s.starts_with("http://") || s.starts_with("https://"))
+}
+
+fn parse_url(s: &str) -> Result<Url, RemoteError> {
+    Url::parse(s).map_err(|source| RemoteError::Url {
+        url: s.to_owned(),
+        source,
+    })
+}
+
+fn join_url(base: &Url, s: &str) -> Result<Url, RemoteError> {
+    base.join(s).map_err(|source| RemoteError::Url {
+        url: s.to_owned(),
+        source,
+    })
+}
+
+pub(crate) fn get(url: &Url) -> Result<Vec<u8>, RemoteError> {
+    debug!("Downloading {}", url);
+    let resp = ureq::get(url.as_str()).call().map_err(Box::new)?;
+    let mut buf = Vec::default();
+    resp.into_reader().read_to_end(&mut buf)?;
+    Ok(buf)
+}
+
+/// Links in an HTML page, resolved against its URL.
+pub fn links(html: &str, page: &Url) -> Vec<Url> {
+    static HREF: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r#"(?i)href\s*=\s*["']([^"'#?]+)["']"#).unwrap());
+    HREF.captures_iter(html)
+        .filter_map(|cap| page.join(&cap[1]).ok())
+        .collect()
+}
+
+/// URLs of the files listed in a manifest, resolved against its URL.
+pub fn manifest_entries(text: &str, manifest: &Url) -> Result<Vec<Url>, RemoteError> {
+    text.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(|l| join_url(manifest, l))
+        .collect()
+}
+
+/// Path of a file relative to the base URL,
+/// or its path on its server if it is not under the base.
+fn relative_path(url: &Url, base: &Url) -> PathBuf {
+    let rel = url
+        .as_str()
+        .strip_prefix(base.as_str())
+        .unwrap_or(url.path());
+    decode_path(rel)
+}
+
+/// A percent-encoded URL path as a relative file path.
+fn decode_path(path: &str) -> PathBuf {
+    path.split('/')
+        .map(|s| percent_decode_str(s).decode_utf8_lossy().into_owned())
+        .filter(|s| !s.is_empty() && s != "." && s != "..")
+        .collect()
+}
+
+/// URLs of all files in a directory listing and the listings below it.
+fn crawl(root: &Url) -> Result<Vec<Url>, RemoteError> {
+    let mut out = Vec::default();
+    let mut seen = HashSet::new();
+    let mut pending = vec![root.clone()];
+    while let Some(dir) = pending.pop() {
+        if !seen.insert(dir.clone()) {
+            continue;
+        }
+        let html = String::from_utf8_lossy(&get(&dir)?).into_owned();
+        for link in links(&html, &dir) {
+            // skip parent directories, sorting links, and other sites
+            if !link.as_str().starts_with(dir.as_str()) || link == dir {
+                continue;
+            }
+            if link.path().ends_with('/') {
+                pending.push(link);
+            } else {
+                out.push(link);
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// Where a downloaded file is cached: under its scheme, then its host (and any non-default port),
+/// so that files from different servers do not collide.
+fn cache_path(cache: &Path, url: &Url) -> PathBuf {
+    let host = url.host_str().unwrap_or_default();
+    let server = match url.port() {
+        Some(port) => format!("{}_{}", host, port),
+        None => host.to_owned(),
+    };
+    cache
+        .join(url.scheme())
+        .join(server)
+        .join(decode_path(url.path()))
+}
+
+/// Download every results file under the root,
+/// keyed by its path as if the root were a results directory.
+pub(crate) fn download(
+    root: &Path,
+    cache: Option<&Path>,
+) -> Result<BTreeMap<PathBuf, Vec<u8>>, RemoteError> {
+    let root_str = root.to_string_lossy();
+    let root_url = parse_url(&root_str)?;
+    let (base, urls) = if root_str.ends_with('/') {
+        let urls = crawl(&root_url)?;
+        (root_url, urls)
+    } else {
+        let text = String::from_utf8_lossy(&get(&root_url)?).into_owned();
+        let urls = manifest_entries(&text, &root_url)?;
+        (join_url(&root_url, ".")?, urls)
+    };
+
+    let mut out = BTreeMap::default();
+    for url in urls {
+        let contents = match cache.map(|c| cache_path(c, &url)) {
+            Some(p) if p.is_file() => fs::read(p)?,
+            Some(p) => {
+                let contents = get(&url)?;
+                if let Some(dir) = p.parent() {
+                    fs::create_dir_all(dir)?;
+                }
+                fs::write(p, &contents)?;
+                contents
+            }
+            None => get(&url)?,
+        };
+        out.insert(root.join(relative_path(&url, &base)), contents);
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+
+    use super::*;
+
+    #[test]
+    fn listing_links() {
+        let page = Url::parse("http://example.com/results/small/").unwrap();
+        let html = r#"<a href="../">Parent</a><a href="?C=N;O=D">Name</a>
+            <a href="uk/">uk/</a><a href='2023-07-10_open.tsv'>open</a>
+            <a href="https://other.com/x.tsv">x</a>"#;
+        let links: Vec<_> = links(html, &page)
+            .into_iter()
+            .map(|u| u.to_string())
+            .collect();
+        assert_eq!(
+            links,
+            vec![
+                "http://example.com/results/",
+                "http://example.com/results/small/uk/",
+                "http://example.com/results/small/2023-07-10_open.tsv",
+                "https://other.com/x.tsv",
+            ]
+        );
+    }
+
+    #[test]
+    fn paths_relative_to_base() {
+        let base = Url::parse("http://example.com/results/").unwrap();
+        let url = |s| Url::parse(s).unwrap();
+        assert_eq!(
+            relative_path(&url("http://example.com/results/small/a%20b.tsv"), &base),
+            PathBuf::from("small/a b.tsv")
+        );
+        assert_eq!(
+            relative_path(&url("http://other.com/major/2023-01-01.tsv"), &base),
+            PathBuf::from("major/2023-01-01.tsv")
+        );
+    }
+
+    #[test]
+    fn cache_paths_by_server() {
+        let cache = Path::new("cache");
+        let path = |s| cache_path(cache, &Url::parse(s).unwrap());
+        assert_eq!(
+            path("https://example.com/results/a%20b.tsv"),
+            PathBuf::from("cache/https/example.com/results/a b.tsv")
+        );
+        assert_eq!(
+            path("http://example.com:8080/results/a.tsv"),
+            PathBuf::from("cache/http/example.com_8080/results/a.tsv")
+        );
+        assert_ne!(
+            path("http://example.com/results/a.tsv"),
+            path("https://example.com/results/a.tsv")
+        );
+    }
+
+    #[test]
+    fn ingest_manifest() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = std::thread::spawn(move || {
+            for stream in listener.incoming().take(3) {
+                let mut stream = stream.unwrap();
+                let mut lines = BufReader::new(&stream).lines();
+                let request = lines.next().unwrap().unwrap();
+                // read the rest of the headers before responding
+                for line in lines.by_ref() {
+                    if line.unwrap().is_empty() {
+                        break;
+                    }
+                }
+                let body = match request.split_whitespace().nth(1).unwrap() {
+                    "/results/manifest.txt" => {
+                        "# results\nsmall/2023-07-10_open.tsv\nmajor/2023-08-01.tsv\n"
+                    }
+                    "/results/small/2023-07-10_open.tsv" => "place\tp1\tp2\n1\t1\t2\n",
+                    "/results/major/2023-08-01.tsv" => "place\tp1\tp2\n1\t3\t4\n2\t1\t2\n",
+                    _ => "",
+                };
+                write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                )
+                .unwrap();
+            }
+        });
+
+        let tmp = tempfile::tempdir().unwrap();
+        let cache = tmp.path().join("cache");
+        let root = format!("http://{}/results/manifest.txt", addr);
+        let result = crate::ResultIngester::new(&root)
+            .cache_dir(Some(cache.clone()))
+            .ingest();
+        server.join().unwrap();
+        let cached = cache
+            .join(format!("http/127.0.0.1_{}", addr.port()))
+            .join("results/major/2023-08-01.tsv")
+            .is_file();
+
+        let ts = result.unwrap();
+        assert_eq!(ts.len(), 2);
+        assert_eq!(ts[1].level(), &crate::Level::MAJOR);
+        assert_eq!(ts[1].results().len(), 2);
+        assert!(cached);
+    }
+}
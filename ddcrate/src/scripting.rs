@@ -0,0 +1,184 @@
+//! Custom point formulae, written as [Rhai](https://rhai.rs) scripts.
+//!
+//! A formula is evaluated for each entrant of a tournament, with the variables
+//!
+//! - `place`: finishing position (integer)
+//! - `base`: point base of the tournament's level, after any size scaling (float)
+//! - `age`: age of the result (float; a whole number of seasons unless `age_unit = "days"`)
+//! - `field_size`: number of entrants (integer)
+//!
+//! Its value (the last expression in the script) replaces
+//! the default `base / finish_decay ^ place / age_decay ^ age`;
+//! field strength multipliers and bonuses are applied as usual.
+//!
+//! Scripts are limited in how long they can run and how deeply they can nest,
+//! so that e.g. `loop {}` is an error rather than hanging.
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use rhai::{Dynamic, Engine, EvalAltResult, ParseError, Scope, AST};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use thiserror::Error;
+
+/// Most operations a formula may perform for one result.
+const MAX_OPERATIONS: u64 = 100_000;
+/// Deepest nesting of expressions in a formula, at the top level and within functions.
+const MAX_EXPR_DEPTHS: (usize, usize) = (64, 32);
+/// Deepest nesting of function calls in a formula.
+const MAX_CALL_LEVELS: usize = 16;
+
+#[derive(Debug, Error)]
+pub enum FormulaError {
+    #[error("Could not parse point formula: {0}")]
+    Parse(#[from] ParseError),
+    #[error("Could not evaluate point formula: {0}")]
+    Eval(#[from] Box<EvalAltResult>),
+    #[error("Point formula returned {0}, not a finite number")]
+    NotANumber(String),
+}
+
+/// A compiled point formula.
+#[derive(Clone)]
+pub struct PointFormula {
+    source: String,
+    engine: Arc<Engine>,
+    ast: AST,
+}
+
+impl Debug for PointFormula {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PointFormula")
+            .field("source", &self.source)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PointFormula {
+    pub fn new<S: Into<String>>(source: S) -> Result<Self, FormulaError> {
+        let source = source.into();
+        let mut engine = Engine::new();
+        engine
+            .set_max_operations(MAX_OPERATIONS)
+            .set_max_expr_depths(MAX_EXPR_DEPTHS.0, MAX_EXPR_DEPTHS.1)
+            .set_max_call_levels(MAX_CALL_LEVELS);
+        let ast = engine.compile(&source)?;
+        Ok(Self {
+            source,
+            engine: Arc::new(engine),
+            ast,
+        })
+    }
+
+    /// The script from which the formula was compiled.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Evaluate the formula for one result.
+    pub fn points(
+        &self,
+        place: u64,
+        base: f64,
+        age: f64,
+        field_size: usize,
+    ) -> Result<f64, FormulaError> {
+        let mut scope = Scope::new();
+        scope.push("place", place as i64);
+        scope.push("base", base);
+        scope.push("age", age);
+        scope.push("field_size", field_size as i64);
+        let value: Dynamic = self.engine.eval_ast_with_scope(&mut scope, &self.ast)?;
+        let points = match value.as_float() {
+            Ok(f) => f,
+            Err(_) => value
+                .as_int()
+                .map_err(|_| FormulaError::NotANumber(value.to_string()))?
+                as f64,
+        };
+        if !points.is_finite() {
+            return Err(FormulaError::NotANumber(points.to_string()));
+        }
+        Ok(points)
+    }
+}
+
+impl Serialize for PointFormula {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.source)
+    }
+}
+
+impl<'de> Deserialize<'de> for PointFormula {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let source = String::deserialize(deserializer)?;
+        PointFormula::new(source).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use chrono::{TimeZone, Utc};
+
+    use super::*;
+    use crate::{Config, Entrant, Level, PointsError, Tournament};
+
+    #[test]
+    fn evaluate() {
+        let f =
+            PointFormula::new("base * (field_size - place + 1) / field_size / (age + 1)").unwrap();
+        assert_eq!(f.points(1, 50.0, 0.0, 4).unwrap(), 50.0);
+        assert_eq!(f.points(4, 50.0, 1.0, 4).unwrap(), 6.25);
+
+        let int = PointFormula::new("10 - place").unwrap();
+        assert_eq!(int.points(3, 50.0, 0.0, 4).unwrap(), 7.0);
+
+        assert!(PointFormula::new("base *").is_err());
+        assert!(PointFormula::new("\"text\"")
+            .unwrap()
+            .points(1, 50.0, 0.0, 4)
+            .is_err());
+        assert!(PointFormula::new("base / 0.0")
+            .unwrap()
+            .points(1, 50.0, 0.0, 4)
+            .is_err());
+        assert!(PointFormula::new("loop {}")
+            .unwrap()
+            .points(1, 50.0, 0.0, 4)
+            .is_err());
+        assert!(PointFormula::new("fn f(x) { f(x) } f(1)")
+            .unwrap()
+            .points(1, 50.0, 0.0, 4)
+            .is_err());
+    }
+
+    #[test]
+    fn formula_in_config() {
+        let config_str = |formula| {
+            format!(
+                "finish_decay = 1.1\nage_decay = 1.1\nrecord_length = 10\nformula = {:?}\n[levels]\nsmall = 50",
+                formula
+            )
+        };
+        let config: Config = toml::from_str(&config_str("base / place")).unwrap();
+        let dt = Utc.with_ymd_and_hms(2022, 6, 1, 0, 0, 0).unwrap();
+        let t = Tournament::new(
+            vec![(1, Entrant::Single(1)), (2, Entrant::Single(2))],
+            dt,
+            Level::SMALL,
+        )
+        .unwrap();
+        let pts = t.points(2022, &HashMap::default(), &config).unwrap();
+        assert_eq!(pts[&2], pts[&1] / 2.0);
+
+        assert!(toml::from_str::<Config>(&config_str("base *")).is_err());
+
+        let config: Config =
+            toml::from_str(&config_str("if place > 1 { \"last\" } else { base }")).unwrap();
+        assert!(matches!(
+            t.points(2022, &HashMap::default(), &config),
+            Err(PointsError::Formula(_))
+        ));
+    }
+}
@@ -0,0 +1,91 @@
+//! Seeding a tournament's entrants by their players' current ratings.
+//!
+//! An entrant's seeding rating is the sum of its players' ratings,
+//! so that a team of two strong players is seeded above a strong player with a weak partner.
+//! Players without a rating count as having the rating of a player with no results.
+use std::cmp::Reverse;
+
+use crate::{Entrant, NotNan, PlayerMap};
+
+/// An entrant's position in a seeded entry list.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Seeded {
+    /// Seed, from 1 for the highest-rated entrant.
+    pub seed: u64,
+    /// Combined rating of the entrant's players.
+    pub rating: f64,
+    pub entrant: Entrant,
+    /// Position of the entrant in the entry list, from 0.
+    pub entry: usize,
+    /// Whether any of the entrant's players had no rating.
+    pub unrated: bool,
+}
+
+/// Order entrants by the combined rating of their players, highest first.
+///
+/// `unrated` is the rating of players missing from `ratings`.
+/// Every entrant gets a distinct seed; entrants with equal ratings keep their order in the entry list.
+pub fn seed_entrants(
+    entrants: Vec<Entrant>,
+    ratings: &PlayerMap<NotNan<f64>>,
+    unrated: f64,
+) -> Vec<Seeded> {
+    let mut rated: Vec<_> = entrants
+        .into_iter()
+        .enumerate()
+        .map(|(entry, entrant)| {
+            let mut missing = false;
+            let rating = entrant
+                .players()
+                .iter()
+                .map(|p| match ratings.get(p) {
+                    Some(r) => r.into_inner(),
+                    None => {
+                        missing = true;
+                        unrated
+                    }
+                })
+                .sum::<f64>();
+            Seeded {
+                seed: 0,
+                rating,
+                entrant,
+                entry,
+                unrated: missing,
+            }
+        })
+        .collect();
+    rated.sort_by_key(|s| Reverse(NotNan::new(s.rating).unwrap_or_default()));
+    for (idx, s) in rated.iter_mut().enumerate() {
+        s.seed = idx as u64 + 1;
+    }
+    rated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PlayerHasher;
+
+    #[test]
+    fn combined_ratings() {
+        let mut ratings = PlayerMap::with_capacity_and_hasher(4, PlayerHasher::default());
+        for (p, r) in [(1, 10.0), (2, 5.0), (3, 12.0), (4, 1.0)] {
+            ratings.insert(p, NotNan::new(r).unwrap());
+        }
+        let entrants = vec![
+            Entrant::from_players(&[3, 4]).unwrap(),
+            Entrant::from_players(&[1, 2]).unwrap(),
+            Entrant::from_players(&[5, 6]).unwrap(),
+            Entrant::from_players(&[2, 7]).unwrap(),
+        ];
+        let seeded = seed_entrants(entrants, &ratings, 0.0);
+        let order: Vec<_> = seeded.iter().map(|s| s.entrant.players()[0]).collect();
+        assert_eq!(order, vec![1, 3, 2, 5]);
+        assert_eq!(seeded[0].rating, 15.0);
+        assert!(!seeded[1].unrated);
+        assert!(seeded[2].unrated);
+        assert_eq!(seeded[3].entry, 2);
+        assert_eq!(seeded[3].seed, 4);
+    }
+}
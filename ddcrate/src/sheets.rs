@@ -0,0 +1,228 @@
+//! Reading results from a Google Sheet.
+//!
+//! The results root is the URL of a spreadsheet which is either published to the web
+//! (`https://docs.google.com/spreadsheets/d/e/.../pubhtml`)
+//! or shared with anyone who has the link (`https://docs.google.com/spreadsheets/d/.../edit`).
+//! Each tab is read as if it were a results TSV.
+//!
+//! If the URL selects a tab (with `gid=...`), that tab is a manifest:
+//! each row gives a results path (e.g. `small/2023-07-10_open.tsv`)
+//! followed by the name or ID of the tab containing that tournament's results,
+//! with `#` comments, blank rows, and a `path` header row ignored.
+//! Otherwise, every tab whose name is a results path without the extension
+//! (e.g. `small/2023-07-10_open`) is read.
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use log::debug;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use thiserror::Error;
+use url::Url;
+
+use crate::remote::{self, RemoteError};
+
+static SHEET_URL: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^https://docs\.google\.com/spreadsheets/d/(e/)?([\w-]+)").unwrap());
+
+#[derive(Debug, Error)]
+pub enum SheetsError {
+    #[error(transparent)]
+    Remote(#[from] RemoteError),
+    #[error("Not a Google Sheets URL: {0:?}")]
+    NotASheet(String),
+    #[error("No tab {0:?} in spreadsheet")]
+    UnknownTab(String),
+}
+
+/// Whether the results root is the URL of a Google Sheet.
+pub fn is_sheet(root: &Path) -> bool {
+    root.to_str().is_some_and(|s| SHEET_URL.is_match(s))
+}
+
+/// A spreadsheet, and the manifest tab if one is selected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Sheet {
+    /// URL of the spreadsheet, ending in `/`.
+    base: String,
+    /// Whether the spreadsheet is published to the web, rather than shared by link.
+    published: bool,
+    manifest: Option<String>,
+}
+
+impl Sheet {
+    fn parse(url: &str) -> Result<Self, SheetsError> {
+        let cap = SHEET_URL
+            .captures(url)
+            .ok_or_else(|| SheetsError::NotASheet(url.to_owned()))?;
+        static GID: Lazy<Regex> = Lazy::new(|| Regex::new(r"[?#&]gid=(\d+)").unwrap());
+        Ok(Self {
+            base: cap[0].to_owned() + "/",
+            published: cap.get(1).is_some(),
+            manifest: GID.captures(url).map(|c| c[1].to_owned()),
+        })
+    }
+
+    /// URL of a page listing the spreadsheet's tabs.
+    fn listing_url(&self) -> String {
+        let page = if self.published {
+            "pubhtml"
+        } else {
+            "htmlview"
+        };
+        format!("{}{}", self.base, page)
+    }
+
+    /// URL of a tab's contents as TSV.
+    fn tsv_url(&self, gid: &str) -> String {
+        let page = if self.published {
+            "pub?output=tsv&gid="
+        } else {
+            "export?format=tsv&gid="
+        };
+        format!("{}{}{}", self.base, page, gid)
+    }
+}
+
+/// Names and IDs of the tabs listed in a spreadsheet's HTML view.
+fn tabs(html: &str) -> Vec<(String, String)> {
+    static TAB: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r#"id="sheet-button-(\d+)"[^>]*>\s*<a[^>]*>([^<]*)</a>"#).unwrap());
+    TAB.captures_iter(html)
+        .map(|cap| (unescape(&cap[2]), cap[1].to_owned()))
+        .collect()
+}
+
+/// Undo HTML escaping of a tab name.
+fn unescape(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Results paths and the names or IDs of their tabs, from a manifest tab's TSV.
+fn manifest_entries(tsv: &str) -> Vec<(PathBuf, String)> {
+    tsv.lines()
+        .filter_map(|line| {
+            let mut cells = line.split('\t').map(str::trim);
+            let path = cells.next()?;
+            let tab = cells.next()?;
+            if path.is_empty() || path.starts_with('#') || path.eq_ignore_ascii_case("path") {
+                return None;
+            }
+            Some((PathBuf::from(path), tab.to_owned()))
+        })
+        .collect()
+}
+
+fn get(url: &str) -> Result<String, SheetsError> {
+    let url = Url::parse(url).map_err(|source| RemoteError::Url {
+        url: url.to_owned(),
+        source,
+    })?;
+    Ok(String::from_utf8_lossy(&remote::get(&url)?).into_owned())
+}
+
+/// Download every results tab of the root spreadsheet,
+/// keyed by its path as if the root were a results directory.
+pub(crate) fn download(root: &Path) -> Result<BTreeMap<PathBuf, Vec<u8>>, SheetsError> {
+    let sheet = Sheet::parse(&root.to_string_lossy())?;
+    let tabs = tabs(&get(&sheet.listing_url())?);
+    debug!("Found {} tabs in {}", tabs.len(), sheet.base);
+
+    let entries = match &sheet.manifest {
+        Some(gid) => manifest_entries(&get(&sheet.tsv_url(gid))?)
+            .into_iter()
+            .map(|(path, tab)| {
+                if tab.chars().all(|c| c.is_ascii_digit()) {
+                    return Ok((path, tab));
+                }
+                tabs.iter()
+                    .find(|(name, _)| *name == tab)
+                    .map(|(_, gid)| (path, gid.clone()))
+                    .ok_or(SheetsError::UnknownTab(tab))
+            })
+            .collect::<Result<Vec<_>, _>>()?,
+        None => tabs
+            .into_iter()
+            .filter(|(name, _)| name.contains('/'))
+            .map(|(name, gid)| (PathBuf::from(name + ".tsv"), gid))
+            .collect(),
+    };
+
+    let mut out = BTreeMap::default();
+    for (path, gid) in entries {
+        let tsv = get(&sheet.tsv_url(&gid))?;
+        out.insert(root.join(path), tsv.into_bytes());
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sheet_urls() {
+        assert!(is_sheet(Path::new(
+            "https://docs.google.com/spreadsheets/d/abc-123_X/edit#gid=0"
+        )));
+        assert!(!is_sheet(Path::new("https://example.com/results/")));
+
+        let published =
+            Sheet::parse("https://docs.google.com/spreadsheets/d/e/2PACX-1vQ/pubhtml").unwrap();
+        assert_eq!(published.manifest, None);
+        assert_eq!(
+            published.tsv_url("42"),
+            "https://docs.google.com/spreadsheets/d/e/2PACX-1vQ/pub?output=tsv&gid=42"
+        );
+
+        let shared =
+            Sheet::parse("https://docs.google.com/spreadsheets/d/abc-123/edit?usp=sharing#gid=7")
+                .unwrap();
+        assert_eq!(shared.manifest.as_deref(), Some("7"));
+        assert_eq!(
+            shared.listing_url(),
+            "https://docs.google.com/spreadsheets/d/abc-123/htmlview"
+        );
+        assert_eq!(
+            shared.tsv_url("7"),
+            "https://docs.google.com/spreadsheets/d/abc-123/export?format=tsv&gid=7"
+        );
+    }
+
+    #[test]
+    fn listed_tabs() {
+        let html = r##"<ul id="sheet-menu">
+            <li id="sheet-button-0" class="switcherItem"><a href="#">Manifest</a></li>
+            <li id="sheet-button-123456"><a href="#" onclick="switchToSheet('123456')">small/2023-07-10_Cup &amp; Plate</a></li>
+            </ul>"##;
+        assert_eq!(
+            tabs(html),
+            vec![
+                ("Manifest".to_owned(), "0".to_owned()),
+                (
+                    "small/2023-07-10_Cup & Plate".to_owned(),
+                    "123456".to_owned()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn manifest_rows() {
+        let tsv = "path\ttab\n# comment\t\nsmall/2023-07-10_open.tsv\tOpen 2023\n\t\nmajor/2023-08-01.tsv\t98765\n";
+        assert_eq!(
+            manifest_entries(tsv),
+            vec![
+                (
+                    PathBuf::from("small/2023-07-10_open.tsv"),
+                    "Open 2023".to_owned()
+                ),
+                (PathBuf::from("major/2023-08-01.tsv"), "98765".to_owned()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,284 @@
+//! Storage of tournaments and rankings in a SQLite database.
+//!
+//! Tournaments are stored in the `tournaments` and `results` tables,
+//! the latter with a row for each player of each entrant.
+//! Each ranking run is recorded in `ranking_runs`,
+//! with its players' ranks and ratings in `rankings`.
+use std::collections::HashSet;
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use ordered_float::NotNan;
+use rusqlite::{params, Connection};
+use thiserror::Error;
+
+use crate::{
+    Entrant, InvalidTournament, Level, PlayerId, PlayerMap, ResultIngester, ResultReadError,
+    Tournament, TournamentMetadata,
+};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS tournaments (
+    id INTEGER PRIMARY KEY,
+    datetime TEXT NOT NULL,
+    level TEXT NOT NULL,
+    name TEXT,
+    location TEXT,
+    organizer TEXT
+);
+CREATE TABLE IF NOT EXISTS results (
+    tournament_id INTEGER NOT NULL REFERENCES tournaments(id),
+    -- index of the entrant within the tournament
+    entrant INTEGER NOT NULL,
+    place INTEGER NOT NULL,
+    player INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS results_tournament ON results(tournament_id);
+CREATE TABLE IF NOT EXISTS ranking_runs (
+    id INTEGER PRIMARY KEY,
+    created TEXT NOT NULL,
+    algorithm TEXT NOT NULL,
+    season INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS rankings (
+    run_id INTEGER NOT NULL REFERENCES ranking_runs(id),
+    player_id INTEGER NOT NULL,
+    rank INTEGER NOT NULL,
+    rating REAL NOT NULL
+);
+CREATE INDEX IF NOT EXISTS rankings_run ON rankings(run_id);
+";
+
+#[derive(Debug, Error)]
+pub enum StoreError {
+    #[error(transparent)]
+    Sqlite(#[from] rusqlite::Error),
+    #[error(transparent)]
+    ResultRead(#[from] ResultReadError),
+    #[error(transparent)]
+    InvalidTournament(#[from] InvalidTournament),
+    #[error("Invalid stored datetime: {0}")]
+    InvalidDatetime(String),
+}
+
+pub struct Store {
+    conn: Connection,
+}
+
+impl Store {
+    /// Open (or create) a database file, creating any missing tables.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, StoreError> {
+        Self::from_connection(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> Result<Self, StoreError> {
+        Self::from_connection(Connection::open_in_memory()?)
+    }
+
+    fn from_connection(conn: Connection) -> Result<Self, StoreError> {
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self { conn })
+    }
+
+    /// Insert a tournament, returning its ID.
+    pub fn insert_tournament(&mut self, tournament: &Tournament) -> Result<i64, StoreError> {
+        let tx = self.conn.transaction()?;
+        let id = insert_tournament(&tx, tournament)?;
+        tx.commit()?;
+        Ok(id)
+    }
+
+    /// Read every tournament from an ingester into the database, returning the number imported.
+    ///
+    /// Tournaments are appended; importing the same results twice will duplicate them.
+    pub fn import(&mut self, ingester: &ResultIngester) -> Result<usize, StoreError> {
+        let tournaments = ingester.ingest()?;
+        let tx = self.conn.transaction()?;
+        for t in tournaments.iter() {
+            insert_tournament(&tx, t)?;
+        }
+        tx.commit()?;
+        Ok(tournaments.len())
+    }
+
+    /// Read tournaments from the database, sorted by date/time.
+    ///
+    /// If `levels` is given, only tournaments of those levels are returned.
+    pub fn tournaments(
+        &self,
+        levels: Option<&HashSet<Level>>,
+        from: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> Result<Vec<Tournament>, StoreError> {
+        let mut t_stmt = self
+            .conn
+            .prepare(
+            "SELECT id, datetime, level, name, location, organizer FROM tournaments ORDER BY datetime, id",
+        )?;
+        let mut r_stmt = self.conn.prepare(
+            "SELECT entrant, place, player FROM results WHERE tournament_id = ?1 ORDER BY entrant",
+        )?;
+        let rows = t_stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                TournamentMetadata {
+                    name: row.get(3)?,
+                    location: row.get(4)?,
+                    organizer: row.get(5)?,
+                },
+            ))
+        })?;
+
+        let mut out = Vec::default();
+        for row in rows {
+            let (id, dt_str, level_str, metadata) = row?;
+            let dt = DateTime::parse_from_rfc3339(&dt_str)
+                .map_err(|_| StoreError::InvalidDatetime(dt_str.clone()))?
+                .with_timezone(&Utc);
+            let level = Level::new(level_str);
+            if dt < from || dt > until || levels.is_some_and(|ls| !ls.contains(&level)) {
+                continue;
+            }
+            let rows = r_stmt.query_map([id], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, i64>(1)? as u64,
+                    row.get::<_, i64>(2)? as PlayerId,
+                ))
+            })?;
+            // (entrant index, place, players)
+            let mut entrants: Vec<(i64, u64, Vec<PlayerId>)> = Vec::default();
+            for row in rows {
+                let (idx, place, player) = row?;
+                match entrants.last_mut() {
+                    Some((last, _, players)) if *last == idx => players.push(player),
+                    _ => entrants.push((idx, place, vec![player])),
+                }
+            }
+            let results = entrants
+                .into_iter()
+                .map(|(_, place, players)| Ok((place, Entrant::from_players(&players)?)))
+                .collect::<Result<Vec<_>, StoreError>>()?;
+            out.push(Tournament::new(results, dt, level)?.with_metadata(metadata));
+        }
+        Ok(out)
+    }
+
+    /// Record the result of a ranking run, returning the run's ID.
+    pub fn write_ranking(
+        &mut self,
+        algorithm: &str,
+        season: i32,
+        ranks: &PlayerMap<u64>,
+        ratings: &PlayerMap<NotNan<f64>>,
+    ) -> Result<i64, StoreError> {
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "INSERT INTO ranking_runs (created, algorithm, season) VALUES (?1, ?2, ?3)",
+            params![Utc::now().to_rfc3339(), algorithm, season],
+        )?;
+        let run_id = tx.last_insert_rowid();
+        {
+            let mut stmt = tx.prepare(
+                "INSERT INTO rankings (run_id, player_id, rank, rating) VALUES (?1, ?2, ?3, ?4)",
+            )?;
+            for (pid, rank) in ranks.iter() {
+                let rating = ratings.get(pid).map_or(0.0, |r| r.into_inner());
+                stmt.execute(params![run_id, *pid as i64, *rank as i64, rating])?;
+            }
+        }
+        tx.commit()?;
+        Ok(run_id)
+    }
+
+    /// Ranks and ratings recorded by a ranking run.
+    pub fn ranking(&self, run_id: i64) -> Result<PlayerMap<(u64, f64)>, StoreError> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT player_id, rank, rating FROM rankings WHERE run_id = ?1")?;
+        let rows = stmt.query_map([run_id], |row| {
+            Ok((
+                row.get::<_, i64>(0)? as PlayerId,
+                (row.get::<_, i64>(1)? as u64, row.get::<_, f64>(2)?),
+            ))
+        })?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+}
+
+fn insert_tournament(conn: &Connection, tournament: &Tournament) -> Result<i64, StoreError> {
+    conn.execute(
+        "INSERT INTO tournaments (datetime, level, name, location, organizer) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            tournament.datetime().to_rfc3339(),
+            tournament.level().name(),
+            tournament.metadata().name,
+            tournament.metadata().location,
+            tournament.metadata().organizer,
+        ],
+    )?;
+    let id = conn.last_insert_rowid();
+    let mut stmt = conn.prepare_cached(
+        "INSERT INTO results (tournament_id, entrant, place, player) VALUES (?1, ?2, ?3, ?4)",
+    )?;
+    for (idx, (place, entrant)) in tournament.results().iter().enumerate() {
+        for player in entrant.players() {
+            stmt.execute(params![id, idx as i64, *place as i64, *player as i64])?;
+        }
+    }
+    Ok(id)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::{rank_players, Config, Team};
+
+    #[test]
+    fn roundtrip() {
+        let mut store = Store::open_in_memory().unwrap();
+        let dt = Utc.with_ymd_and_hms(2022, 6, 1, 0, 0, 0).unwrap();
+        let t = Tournament::new(
+            vec![
+                (1, Team::from_players(vec![1, 2, 5]).unwrap().into()),
+                (2, Entrant::Single(3)),
+            ],
+            dt,
+            Level::MAJOR,
+        )
+        .unwrap();
+        store.insert_tournament(&t).unwrap();
+
+        let ts = store
+            .tournaments(None, DateTime::<Utc>::MIN_UTC, DateTime::<Utc>::MAX_UTC)
+            .unwrap();
+        assert_eq!(ts.len(), 1);
+        assert_eq!(ts[0].results(), t.results());
+        assert_eq!(ts[0].datetime(), &dt);
+        assert_eq!(ts[0].level(), &Level::MAJOR);
+
+        let mut small = HashSet::default();
+        small.insert(Level::SMALL);
+        let ts_small = store
+            .tournaments(
+                Some(&small),
+                DateTime::<Utc>::MIN_UTC,
+                DateTime::<Utc>::MAX_UTC,
+            )
+            .unwrap();
+        assert!(ts_small.is_empty());
+
+        let (ranks, records) = rank_players(&ts, 2022, &Config::default()).unwrap();
+        let ratings = records.iter().map(|(p, r)| (*p, r.rating)).collect();
+        let run = store
+            .write_ranking("points", 2022, &ranks, &ratings)
+            .unwrap();
+        let stored = store.ranking(run).unwrap();
+        assert_eq!(stored.len(), 4);
+        assert_eq!(stored[&1].0, ranks[&1]);
+    }
+}
@@ -0,0 +1,129 @@
+//! Summary statistics of the distribution of ratings,
+//! e.g. to spot ratings drifting after a change of config.
+
+/// Value at the given quantile of sorted values, interpolating linearly between them.
+pub(crate) fn quantile(sorted: &[f64], q: f64) -> f64 {
+    let pos = q * (sorted.len() - 1) as f64;
+    let below = pos.floor() as usize;
+    let above = pos.ceil() as usize;
+    sorted[below] + (sorted[above] - sorted[below]) * (pos - below as f64)
+}
+
+/// Summary of a set of ratings.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Distribution {
+    pub count: usize,
+    pub mean: f64,
+    pub min: f64,
+    pub lower_quartile: f64,
+    pub median: f64,
+    pub upper_quartile: f64,
+    pub max: f64,
+}
+
+impl Distribution {
+    /// Summarise the ratings, interpolating linearly between them for the quartiles.
+    ///
+    /// Returns `None` if there are no ratings.
+    pub fn new<I: IntoIterator<Item = f64>>(ratings: I) -> Option<Self> {
+        let mut sorted: Vec<_> = ratings.into_iter().collect();
+        if sorted.is_empty() {
+            return None;
+        }
+        sorted.sort_unstable_by(f64::total_cmp);
+        Some(Self {
+            count: sorted.len(),
+            mean: sorted.iter().sum::<f64>() / sorted.len() as f64,
+            min: sorted[0],
+            lower_quartile: quantile(&sorted, 0.25),
+            median: quantile(&sorted, 0.5),
+            upper_quartile: quantile(&sorted, 0.75),
+            max: sorted[sorted.len() - 1],
+        })
+    }
+}
+
+/// Number of ratings from `lower` up to but not including `upper`,
+/// or including `upper` for the highest bin.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bin {
+    pub lower: f64,
+    pub upper: f64,
+    pub count: usize,
+}
+
+/// Count the ratings in equally wide bins from the lowest rating to the highest.
+///
+/// If every rating is equal, there is a single bin.
+/// Returns no bins if there are no ratings or `bins` is 0.
+pub fn histogram(ratings: &[f64], bins: usize) -> Vec<Bin> {
+    let Some(dist) = Distribution::new(ratings.iter().copied()) else {
+        return Vec::default();
+    };
+    if bins == 0 {
+        return Vec::default();
+    }
+    let width = (dist.max - dist.min) / bins as f64;
+    if width <= 0.0 {
+        return vec![Bin {
+            lower: dist.min,
+            upper: dist.max,
+            count: ratings.len(),
+        }];
+    }
+    let mut out: Vec<_> = (0..bins)
+        .map(|idx| Bin {
+            lower: dist.min + width * idx as f64,
+            upper: dist.min + width * (idx + 1) as f64,
+            count: 0,
+        })
+        .collect();
+    out[bins - 1].upper = dist.max;
+    for r in ratings {
+        let idx = (((r - dist.min) / width) as usize).min(bins - 1);
+        out[idx].count += 1;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quantiles_interpolate() {
+        let values = [1.0, 2.0, 4.0];
+        assert_eq!(quantile(&values, 0.0), 1.0);
+        assert_eq!(quantile(&values, 0.25), 1.5);
+        assert_eq!(quantile(&values, 0.75), 3.0);
+        assert_eq!(quantile(&values, 1.0), 4.0);
+    }
+
+    #[test]
+    fn summary() {
+        let dist = Distribution::new([4.0, 1.0, 3.0, 2.0, 5.0]).unwrap();
+        assert_eq!(dist.count, 5);
+        assert_eq!(dist.mean, 3.0);
+        assert_eq!(dist.min, 1.0);
+        assert_eq!(dist.lower_quartile, 2.0);
+        assert_eq!(dist.median, 3.0);
+        assert_eq!(dist.upper_quartile, 4.0);
+        assert_eq!(dist.max, 5.0);
+        assert_eq!(Distribution::new([]), None);
+    }
+
+    #[test]
+    fn bins() {
+        let counts: Vec<_> = histogram(&[0.0, 1.0, 2.5, 3.0, 10.0], 4)
+            .into_iter()
+            .map(|b| b.count)
+            .collect();
+        assert_eq!(counts, vec![2, 2, 0, 1]);
+
+        let equal = histogram(&[2.0, 2.0], 4);
+        assert_eq!(equal.len(), 1);
+        assert_eq!(equal[0].count, 2);
+        assert!(histogram(&[], 4).is_empty());
+        assert!(histogram(&[1.0], 0).is_empty());
+    }
+}
@@ -0,0 +1,227 @@
+//! Generating random but realistic seasons of results, for benchmarking and testing.
+//!
+//! Every player in the pool has a fixed skill, drawn from a standard normal distribution.
+//! In each tournament, a random selection of players is split into teams,
+//! each of which performs at its players' mean skill plus normally-distributed noise,
+//! and teams finish in order of performance.
+//! The same config (including the seed) always generates the same tournaments.
+use std::f64::consts::TAU;
+use std::fs;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Datelike, Days, Utc};
+use rand::rngs::StdRng;
+use rand::seq::index::sample;
+use rand::{Rng, SeedableRng};
+
+use crate::{Entrant, Level, PlayerId, Tournament, TEAM_SIZE};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeneratorConfig {
+    /// Number of players in the pool, with IDs from 1.
+    pub players: u64,
+    /// Tournaments are on weekends from this date...
+    pub from: DateTime<Utc>,
+    /// ... until this one.
+    pub until: DateTime<Utc>,
+    /// Mean number of tournaments each weekend.
+    pub events_per_week: f64,
+    /// Levels of tournaments, with their relative frequencies.
+    pub levels: Vec<(Level, f64)>,
+    /// Number of players in each entrant.
+    pub team_size: usize,
+    /// Fewest entrants in a tournament.
+    pub min_entrants: usize,
+    /// Most entrants in a tournament.
+    pub max_entrants: usize,
+    /// Standard deviation of a team's performance in a tournament around its mean skill;
+    /// larger values make results less predictable.
+    pub noise: f64,
+    pub seed: u64,
+}
+
+impl Default for GeneratorConfig {
+    fn default() -> Self {
+        Self {
+            players: 500,
+            from: "2023-01-01T00:00:00Z".parse().unwrap(),
+            until: "2023-12-31T00:00:00Z".parse().unwrap(),
+            events_per_week: 2.0,
+            levels: vec![
+                (Level::SMALL, 10.0),
+                (Level::MEDIUM, 4.0),
+                (Level::MAJOR, 1.0),
+                (Level::CHAMPIONSHIP, 0.2),
+            ],
+            team_size: TEAM_SIZE,
+            min_entrants: 8,
+            max_entrants: 32,
+            noise: 0.5,
+            seed: 0,
+        }
+    }
+}
+
+/// A sample from the standard normal distribution (Box-Muller).
+fn normal<R: Rng>(rng: &mut R) -> f64 {
+    let u1: f64 = 1.0 - rng.gen::<f64>();
+    let u2: f64 = rng.gen();
+    (-2.0 * u1.ln()).sqrt() * (TAU * u2).cos()
+}
+
+/// A sample from the Poisson distribution with the given mean (Knuth's algorithm).
+fn poisson<R: Rng>(rng: &mut R, mean: f64) -> usize {
+    let limit = (-mean).exp();
+    let mut n = 0;
+    let mut p = rng.gen::<f64>();
+    while p > limit {
+        n += 1;
+        p *= rng.gen::<f64>();
+    }
+    n
+}
+
+/// A level chosen according to the levels' frequencies.
+fn choose_level<'a, R: Rng>(rng: &mut R, levels: &'a [(Level, f64)]) -> Option<&'a Level> {
+    let total: f64 = levels.iter().map(|(_, w)| w).sum();
+    let mut x = rng.gen::<f64>() * total;
+    for (level, weight) in levels {
+        if x < *weight {
+            return Some(level);
+        }
+        x -= weight;
+    }
+    levels.last().map(|(l, _)| l)
+}
+
+/// Generate tournaments according to the config, in date order.
+pub fn generate(config: &GeneratorConfig) -> Vec<Tournament> {
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let skills: Vec<f64> = (0..config.players).map(|_| normal(&mut rng)).collect();
+    let team_size = config.team_size.max(1);
+    let max_entrants = config.max_entrants.min(skills.len() / team_size);
+    let min_entrants = config.min_entrants.max(2).min(max_entrants);
+
+    let mut out = Vec::default();
+    if max_entrants < 2 {
+        return out;
+    }
+    let (from, until) = (config.from.date_naive(), config.until.date_naive());
+    // the Saturday of the first weekend
+    let mut saturday = from - Days::new((from.weekday().num_days_from_monday() as u64 + 2) % 7);
+    while saturday <= until {
+        for _ in 0..poisson(&mut rng, config.events_per_week) {
+            let Some(level) = choose_level(&mut rng, &config.levels) else {
+                return out;
+            };
+            let entrants = rng.gen_range(min_entrants..=max_entrants);
+            let players = sample(&mut rng, skills.len(), entrants * team_size).into_vec();
+            let mut teams: Vec<(f64, Vec<PlayerId>)> = players
+                .chunks(team_size)
+                .map(|idxs| {
+                    let skill = idxs.iter().map(|i| skills[*i]).sum::<f64>() / team_size as f64;
+                    let ids = idxs.iter().map(|i| *i as PlayerId + 1).collect();
+                    (skill + config.noise * normal(&mut rng), ids)
+                })
+                .collect();
+            teams.sort_by(|a, b| b.0.total_cmp(&a.0));
+            let results = teams
+                .into_iter()
+                .enumerate()
+                .map(|(idx, (_, ids))| {
+                    let entrant = Entrant::from_players(&ids).expect("Players are distinct");
+                    (idx as u64 + 1, entrant)
+                })
+                .collect();
+            let day = saturday + Days::new(rng.gen_range(0..2));
+            if day < from || day > until {
+                continue;
+            }
+            let datetime = day.and_hms_opt(0, 0, 0).unwrap().and_utc();
+            let t = Tournament::new(results, datetime, level.clone()).expect("Places are valid");
+            out.push(t);
+        }
+        saturday = saturday + Days::new(7);
+    }
+    out.sort_by_key(|t| *t.datetime());
+    out
+}
+
+/// Write tournaments as a tree of results TSVs under the root,
+/// in a directory for each level named e.g. `2023-07-08_3.tsv`.
+///
+/// Entrants must all have the same number of players,
+/// which must match the levels' team sizes when the tree is read.
+pub fn write_tree(tournaments: &[Tournament], root: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut out = Vec::with_capacity(tournaments.len());
+    for (idx, t) in tournaments.iter().enumerate() {
+        let dir = root.join(t.level().directory_name());
+        fs::create_dir_all(&dir)?;
+        let path = dir.join(format!("{}_{}.tsv", t.datetime().format("%Y-%m-%d"), idx));
+        let mut w = BufWriter::new(fs::File::create(&path)?);
+        let team_size = t
+            .results()
+            .first()
+            .map_or(TEAM_SIZE, |(_, e)| e.players().len());
+        write!(w, "place")?;
+        for n in 1..=team_size {
+            write!(w, "\tp{}", n)?;
+        }
+        writeln!(w)?;
+        for (place, entrant) in t.results() {
+            write!(w, "{}", place)?;
+            for p in entrant.players() {
+                write!(w, "\t{}", p)?;
+            }
+            writeln!(w)?;
+        }
+        w.flush()?;
+        out.push(path);
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{rank_players, Config, ResultIngester};
+
+    #[test]
+    fn generated_season() {
+        let config = GeneratorConfig {
+            players: 100,
+            seed: 42,
+            ..Default::default()
+        };
+        let ts = generate(&config);
+        assert!(ts.len() > 50);
+        assert_eq!(
+            generate(&config)
+                .iter()
+                .map(|t| t.results().to_vec())
+                .collect::<Vec<_>>(),
+            ts.iter().map(|t| t.results().to_vec()).collect::<Vec<_>>()
+        );
+        for t in ts.iter() {
+            assert!(matches!(t.datetime().weekday().number_from_monday(), 6 | 7));
+            assert!((8..=32).contains(&t.results().len()));
+        }
+
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().to_owned();
+        let paths = write_tree(&ts, &root).unwrap();
+        let read = ResultIngester::new(&root).ingest();
+        assert_eq!(paths.len(), ts.len());
+        assert_eq!(read.unwrap().len(), ts.len());
+
+        // the most skilled players are ranked above the least
+        let (ranks, _) = rank_players(&ts, 2023, &Config::default()).unwrap();
+        let mut rng = StdRng::seed_from_u64(42);
+        let skills: Vec<f64> = (0..100).map(|_| normal(&mut rng)).collect();
+        let mut by_skill: Vec<_> = (1..=100).collect();
+        by_skill.sort_by(|a, b| skills[*b as usize - 1].total_cmp(&skills[*a as usize - 1]));
+        let mean_rank = |ids: &[PlayerId]| ids.iter().map(|p| ranks[p]).sum::<u64>() / 10;
+        assert!(mean_rank(&by_skill[..10]) < mean_rank(&by_skill[90..]));
+    }
+}
@@ -0,0 +1,299 @@
+//! TrueSkill-style team rating, modelling each player's skill as a normal distribution.
+//!
+//! A team's performance is the sum of its players' skills,
+//! so both partners are updated from every team finish
+//! in proportion to their share of the team's uncertainty.
+//! Updates use the Bayesian approximation of Weng & Lin (2011)
+//! with full Thurstone-Mosteller pairing of teams.
+use std::f64::consts::{PI, SQRT_2};
+
+use ordered_float::NotNan;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{collect_ratings, PlayerId, PlayerMap, PointsError, RatingSystem, Tournament};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct TrueSkillConfig {
+    /// Mean skill of a player with no results.
+    pub mu: f64,
+    /// Standard deviation of the skill of a player with no results.
+    pub sigma: f64,
+    /// Standard deviation of a player's performance around their skill.
+    pub beta: f64,
+    /// Performance margin within which teams are considered to have drawn.
+    pub draw_margin: f64,
+    /// Minimum proportion of a player's variance retained after an update.
+    pub kappa: f64,
+    /// Number of standard deviations subtracted from the mean to give a conservative rating.
+    pub confidence: f64,
+}
+
+impl Default for TrueSkillConfig {
+    fn default() -> Self {
+        let mu = 25.0;
+        let sigma = mu / 3.0;
+        Self {
+            mu,
+            sigma,
+            beta: sigma / 2.0,
+            draw_margin: 0.1,
+            kappa: 0.0001,
+            confidence: 3.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrueSkillRecord {
+    /// Mean of the skill distribution.
+    pub mu: f64,
+    /// Standard deviation of the skill distribution.
+    pub sigma: f64,
+}
+
+impl TrueSkillRecord {
+    /// Skill which the player very probably exceeds: `mu - confidence * sigma`.
+    pub fn conservative(&self, confidence: f64) -> f64 {
+        self.mu - confidence * self.sigma
+    }
+}
+
+fn pdf(x: f64) -> f64 {
+    (-x * x / 2.0).exp() / (2.0 * PI).sqrt()
+}
+
+/// Complementary error function, with fractional error below 1.2e-7.
+fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let r = t
+        * (-z * z - 1.26551223
+            + t * (1.00002368
+                + t * (0.37409196
+                    + t * (0.09678418
+                        + t * (-0.18628806
+                            + t * (0.27886807
+                                + t * (-1.13520398
+                                    + t * (1.48851587 + t * (-0.82215223 + t * 0.17087277)))))))))
+            .exp();
+    if x >= 0.0 {
+        r
+    } else {
+        2.0 - r
+    }
+}
+
+fn cdf(x: f64) -> f64 {
+    0.5 * erfc(-x / SQRT_2)
+}
+
+/// Additive correction for a win.
+fn v_win(x: f64, t: f64) -> f64 {
+    let xt = x - t;
+    let denom = cdf(xt);
+    if denom < f64::EPSILON {
+        return -xt;
+    }
+    pdf(xt) / denom
+}
+
+/// Multiplicative correction for a win.
+fn w_win(x: f64, t: f64) -> f64 {
+    let v = v_win(x, t);
+    v * (v + x - t)
+}
+
+/// Additive correction for a draw.
+fn v_draw(x: f64, t: f64) -> f64 {
+    let denom = cdf(t - x) - cdf(-t - x);
+    if denom < f64::EPSILON {
+        return if x < 0.0 { -x - t } else { -x + t };
+    }
+    (pdf(-t - x) - pdf(t - x)) / denom
+}
+
+/// Multiplicative correction for a draw.
+fn w_draw(x: f64, t: f64) -> f64 {
+    let denom = cdf(t - x) - cdf(-t - x);
+    if denom < f64::EPSILON {
+        return 1.0;
+    }
+    let v = v_draw(x, t);
+    v * v + ((t - x) * pdf(t - x) + (t + x) * pdf(-t - x)) / denom
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TrueSkill {
+    config: TrueSkillConfig,
+    records: PlayerMap<TrueSkillRecord>,
+}
+
+impl TrueSkill {
+    pub fn new(config: TrueSkillConfig) -> Self {
+        Self {
+            config,
+            records: Default::default(),
+        }
+    }
+
+    pub fn config(&self) -> &TrueSkillConfig {
+        &self.config
+    }
+
+    pub fn records(&self) -> &PlayerMap<TrueSkillRecord> {
+        &self.records
+    }
+
+    pub fn into_records(self) -> PlayerMap<TrueSkillRecord> {
+        self.records
+    }
+
+    /// Player's skill distribution, or the prior if they have no results.
+    pub fn record(&self, player: &PlayerId) -> TrueSkillRecord {
+        self.records
+            .get(player)
+            .copied()
+            .unwrap_or(TrueSkillRecord {
+                mu: self.config.mu,
+                sigma: self.config.sigma,
+            })
+    }
+}
+
+impl RatingSystem for TrueSkill {
+    fn add_tournament(
+        &mut self,
+        tournament: &Tournament,
+        _ranks: &PlayerMap<u64>,
+    ) -> Result<(), PointsError> {
+        let results = tournament.results();
+        if results.len() < 2 {
+            return Ok(());
+        }
+        let team_records: Vec<_> = results
+            .iter()
+            .map(|(_, entrant)| {
+                entrant
+                    .players()
+                    .iter()
+                    .map(|p| self.record(p))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        // (mu, sigma^2) of each team's performance
+        let teams: Vec<_> = team_records
+            .iter()
+            .map(|recs| {
+                let mu: f64 = recs.iter().map(|r| r.mu).sum();
+                let var: f64 = recs.iter().map(|r| r.sigma.powi(2)).sum();
+                (mu, var)
+            })
+            .collect();
+        let beta_sq = self.config.beta.powi(2);
+
+        for (i, (place_i, entrant)) in results.iter().enumerate() {
+            let (mu_i, var_i) = teams[i];
+            let mut omega = 0.0;
+            let mut delta = 0.0;
+            for (q, (place_q, _)) in results.iter().enumerate() {
+                if q == i {
+                    continue;
+                }
+                let (mu_q, var_q) = teams[q];
+                let c = (var_i + var_q + 2.0 * beta_sq).sqrt();
+                let t = self.config.draw_margin / c;
+                let gamma = var_i.sqrt() / c;
+                let (v, w) = match place_i.cmp(place_q) {
+                    std::cmp::Ordering::Less => {
+                        let x = (mu_i - mu_q) / c;
+                        (v_win(x, t), w_win(x, t))
+                    }
+                    std::cmp::Ordering::Greater => {
+                        let x = (mu_q - mu_i) / c;
+                        (-v_win(x, t), w_win(x, t))
+                    }
+                    std::cmp::Ordering::Equal => {
+                        let x = (mu_i - mu_q) / c;
+                        (v_draw(x, t), w_draw(x, t))
+                    }
+                };
+                omega += var_i / c * v;
+                delta += gamma * var_i / c.powi(2) * w;
+            }
+            for (player, rec) in entrant.players().iter().zip(team_records[i].iter()) {
+                let var = rec.sigma.powi(2);
+                let share = var / var_i;
+                let mu = rec.mu + share * omega;
+                let sigma = (var * (1.0 - share * delta).max(self.config.kappa)).sqrt();
+                self.records.insert(*player, TrueSkillRecord { mu, sigma });
+            }
+        }
+        Ok(())
+    }
+
+    /// The conservative estimate of each player's skill.
+    fn ratings(&self) -> PlayerMap<NotNan<f64>> {
+        collect_ratings(
+            self.records
+                .iter()
+                .map(|(pid, rec)| (*pid, rec.conservative(self.config.confidence))),
+        )
+    }
+
+    /// Probability that the first team's performance exceeds the second's,
+    /// from the players' full skill distributions rather than their conservative ratings.
+    fn win_probability(&self, first: &[PlayerId], second: &[PlayerId]) -> f64 {
+        let mut mu = 0.0;
+        let mut var = 0.0;
+        for (players, sign) in [(first, 1.0), (second, -1.0)] {
+            for rec in players.iter().map(|p| self.record(p)) {
+                mu += sign * rec.mu;
+                var += rec.sigma.powi(2) + self.config.beta.powi(2);
+            }
+        }
+        // averaged over both orders so that evenly matched teams are exactly even,
+        // despite the approximate error function
+        let z = mu / var.sqrt();
+        (cdf(z) + 1.0 - cdf(-z)) / 2.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::*;
+    use crate::{Level, Team};
+
+    #[test]
+    fn both_partners_updated() {
+        let dt = Utc.with_ymd_and_hms(2022, 6, 1, 0, 0, 0).unwrap();
+        let t = Tournament::new(
+            vec![
+                (1, Team::new(1, 2).unwrap().into()),
+                (2, Team::new(3, 4).unwrap().into()),
+                (2, Team::new(5, 6).unwrap().into()),
+            ],
+            dt,
+            Level::SMALL,
+        )
+        .unwrap();
+        let mut ts = TrueSkill::default();
+        ts.add_tournament(&t, &PlayerMap::default()).unwrap();
+        let prior = TrueSkillConfig::default();
+        for winner in [1, 2] {
+            let rec = ts.records()[&winner];
+            assert!(rec.mu > prior.mu);
+            assert!(rec.sigma < prior.sigma);
+        }
+        assert!(ts.records()[&3].mu < prior.mu);
+        assert_eq!(ts.records()[&3], ts.records()[&6]);
+
+        let p = ts.win_probability(&[1, 2], &[3, 4]);
+        assert!(p > 0.5);
+        assert!((p + ts.win_probability(&[3, 4], &[1, 2]) - 1.0).abs() < 1e-6);
+        assert!((ts.win_probability(&[3, 4], &[5, 6]) - 0.5).abs() < 1e-6);
+    }
+}
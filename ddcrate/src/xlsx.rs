@@ -0,0 +1,151 @@
+//! Reading results from Excel (`.xlsx`) spreadsheets.
+//!
+//! The first sheet of a spreadsheet is read as if it were a results TSV:
+//! each row becomes a line of tab-separated cells,
+//! so that it is parsed exactly as a TSV would be
+//! (including `# key: value` header lines in the first column).
+use std::io::{self, Cursor, Read};
+
+use calamine::{Data, Reader, Xlsx};
+
+/// Read the first sheet of a spreadsheet as the contents of a results TSV.
+///
+/// Whole numbers are written without a decimal point, so that they parse as ranks and IDs,
+/// and dates as ISO-8601 dates (or datetimes, if they have a time of day).
+/// Empty cells at the end of each row are dropped.
+pub fn sheet_to_tsv<R: Read>(mut r: R) -> io::Result<String> {
+    let mut buf = Vec::default();
+    r.read_to_end(&mut buf)?;
+    let mut workbook = Xlsx::new(Cursor::new(buf)).map_err(io::Error::other)?;
+    let Some(range) = workbook.worksheet_range_at(0) else {
+        return Ok(String::default());
+    };
+    let range = range.map_err(io::Error::other)?;
+
+    let mut out = String::default();
+    for row in range.rows() {
+        let len = row
+            .iter()
+            .rposition(|c| {
+                !matches!(c, Data::Empty) && !matches!(c, Data::String(s) if s.is_empty())
+            })
+            .map_or(0, |idx| idx + 1);
+        let cells: Vec<_> = row[..len].iter().map(cell_to_string).collect();
+        out.push_str(&cells.join("\t"));
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+fn cell_to_string(cell: &Data) -> String {
+    match cell {
+        Data::Float(f) if f.fract() == 0.0 && f.abs() < i64::MAX as f64 => {
+            format!("{}", *f as i64)
+        }
+        Data::DateTime(dt) => match dt.as_datetime() {
+            Some(ndt) if ndt.time() == chrono::NaiveTime::MIN => ndt.format("%Y-%m-%d").to_string(),
+            Some(ndt) => ndt.format("%Y-%m-%dT%H:%M:%S").to_string(),
+            None => cell.to_string(),
+        },
+        // keep each cell on its own line and in its own column
+        Data::String(s) => s.replace(['\t', '\n', '\r'], " "),
+        _ => cell.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    /// A minimal spreadsheet with one sheet of the given rows,
+    /// whose cells are numbers if they parse as such, otherwise inline strings.
+    fn workbook(rows: &[&[&str]]) -> Vec<u8> {
+        let mut sheet = String::from(
+            r#"<?xml version="1.0" encoding="UTF-8"?><worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><sheetData>"#,
+        );
+        for (r, row) in rows.iter().enumerate() {
+            sheet.push_str(&format!(r#"<row r="{}">"#, r + 1));
+            for (c, cell) in row.iter().enumerate() {
+                let reference = format!("{}{}", (b'A' + c as u8) as char, r + 1);
+                if cell.parse::<f64>().is_ok() {
+                    sheet.push_str(&format!(r#"<c r="{}"><v>{}</v></c>"#, reference, cell));
+                } else {
+                    sheet.push_str(&format!(
+                        r#"<c r="{}" t="inlineStr"><is><t>{}</t></is></c>"#,
+                        reference, cell
+                    ));
+                }
+            }
+            sheet.push_str("</row>");
+        }
+        sheet.push_str("</sheetData></worksheet>");
+
+        let files = [
+            (
+                "[Content_Types].xml",
+                r#"<?xml version="1.0" encoding="UTF-8"?><Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/xl/workbook.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/><Override PartName="/xl/worksheets/sheet1.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/></Types>"#.to_owned(),
+            ),
+            (
+                "_rels/.rels",
+                r#"<?xml version="1.0" encoding="UTF-8"?><Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="xl/workbook.xml"/></Relationships>"#.to_owned(),
+            ),
+            (
+                "xl/workbook.xml",
+                r#"<?xml version="1.0" encoding="UTF-8"?><workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"><sheets><sheet name="Results" sheetId="1" r:id="rId1"/></sheets></workbook>"#.to_owned(),
+            ),
+            (
+                "xl/_rels/workbook.xml.rels",
+                r#"<?xml version="1.0" encoding="UTF-8"?><Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet1.xml"/></Relationships>"#.to_owned(),
+            ),
+            ("xl/worksheets/sheet1.xml", sheet),
+        ];
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::default()));
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated);
+        for (name, contents) in files {
+            zip.start_file(name, options).unwrap();
+            zip.write_all(contents.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn first_sheet_as_tsv() {
+        let bytes = workbook(&[
+            &["# name: Open"],
+            &["place", "player1", "player2"],
+            &["1", "235476", "529052"],
+            &["2", "23342", "4235211978", ""],
+        ]);
+        let tsv = sheet_to_tsv(bytes.as_slice()).unwrap();
+        assert_eq!(
+            tsv,
+            "# name: Open\nplace\tplayer1\tplayer2\n1\t235476\t529052\n2\t23342\t4235211978\n"
+        );
+        let ranks = crate::parse_ranks(tsv.as_bytes(), 2, true).unwrap();
+        assert_eq!(ranks.len(), 2);
+    }
+
+    #[test]
+    fn ingest_xlsx() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().to_owned();
+        let mut dir = root.clone();
+        dir.push(crate::Level::SMALL.directory_name());
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("2023-07-10_open.xlsx");
+        let rows: &[&[&str]] = &[&["place", "p1", "p2"], &["1", "1", "2"], &["2", "3", "4"]];
+        std::fs::write(&path, workbook(rows)).unwrap();
+        std::fs::write(dir.join("2023-07-10_open.toml"), "name = \"Open\"\n").unwrap();
+        let result = crate::ResultIngester::new(&root).ingest_with_report();
+
+        let (ts, report) = result.unwrap();
+        assert_eq!(ts.len(), 1);
+        assert_eq!(ts[0].results().len(), 2);
+        assert_eq!(ts[0].metadata().name.as_deref(), Some("Open"));
+        assert_eq!(report.files_read, vec![path]);
+        assert!(report.unmatched_files.is_empty());
+    }
+}